@@ -0,0 +1,91 @@
+//! Messages per second framed and decoded from a packet of tick-by-tick or market depth messages,
+//! with read_msg, read_fields and Decoder::interpret, which allocate a String per message and per
+//! field, and with read_msg_slice and Decoder::interpret_message, which borrow fields from the
+//! packet the way the reader does.  Both go through the same decoding code, so only the cost of
+//! framing and allocating is compared, not this version against earlier ones.
+use std::sync::mpsc::channel;
+use std::sync::{Arc, Mutex};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+use twsapi::core::client::ConnStatus;
+use twsapi::core::decoder::Decoder;
+use twsapi::core::messages::{make_message, read_fields, read_msg, read_msg_slice};
+use twsapi::examples::defaults::DefaultWrapper;
+
+const MESSAGES: usize = 1000;
+
+//==================================================================================================
+fn packet(messages: &[&str]) -> Vec<u8> {
+    let mut packet = Vec::new();
+    for msg in messages.iter().cycle().take(MESSAGES) {
+        packet.extend(make_message(msg).unwrap());
+    }
+    packet
+}
+
+//==================================================================================================
+fn decoder() -> Decoder<DefaultWrapper> {
+    let (_tx, rx) = channel::<String>();
+    Decoder::with_handler(
+        DefaultWrapper::new(),
+        rx,
+        151,
+        Arc::new(Mutex::new(ConnStatus::CONNECTED)),
+    )
+}
+
+//==================================================================================================
+fn decode_owned(decoder: &mut Decoder<DefaultWrapper>, packet: &[u8]) {
+    let mut remaining = packet.to_vec();
+    while !remaining.is_empty() {
+        let (_size, msg, rest) = read_msg(&remaining).unwrap();
+        decoder.interpret(&read_fields(&msg)).unwrap();
+        remaining = rest;
+    }
+}
+
+//==================================================================================================
+fn decode_borrowed(decoder: &mut Decoder<DefaultWrapper>, packet: &[u8]) {
+    let mut remaining = packet;
+    while let Some((msg, rest)) = read_msg_slice(remaining).unwrap() {
+        decoder.interpret_message(msg).unwrap();
+        remaining = rest;
+    }
+}
+
+//==================================================================================================
+fn bench_decoding(c: &mut Criterion) {
+    let feeds = [
+        (
+            "tick_by_tick",
+            packet(&[
+                "99\04001\01\01600000000\0101.25\0100\00\0ISLAND\0\0",
+                "99\04001\03\01600000000\0101.2\0101.3\0300\0200\00\0",
+            ]),
+        ),
+        (
+            "market_depth",
+            packet(&[
+                "13\01\04002\00\0NSDQ\01\00\0101.2\0300\01\0",
+                "13\01\04002\01\0ARCA\01\01\0101.3\0200\01\0",
+            ]),
+        ),
+    ];
+
+    let mut group = c.benchmark_group("decoding");
+    group.throughput(Throughput::Elements(MESSAGES as u64));
+    for (feed, packet) in feeds.iter() {
+        let mut decoder = decoder();
+        group.bench_with_input(BenchmarkId::new("owned", feed), packet, |b, packet| {
+            b.iter(|| decode_owned(&mut decoder, packet))
+        });
+        group.bench_with_input(BenchmarkId::new("borrowed", feed), packet, |b, packet| {
+            b.iter(|| decode_borrowed(&mut decoder, packet))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_decoding);
+criterion_main!(benches);
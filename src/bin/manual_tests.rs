@@ -15,16 +15,7 @@ use twsapi::examples::test_helpers::TestWrapper;
 /// start_requests function inn TestWrapper which is called by next_valid_id
 //==================================================================================================
 pub fn main() -> Result<(), IBKRApiLibError> {
-    match log4rs::init_file("./log_config.yml", Default::default()) {
-        Ok(_) => (),
-        Err(_) => {
-            return Err(IBKRApiLibError::ApiError(TwsApiReportableError::new(
-                -1,
-                "-1".to_string(),
-                "Failed to create logger!!".to_string(),
-            )))
-        }
-    };
+    init_logging()?;
 
     let wrapper = Arc::new(Mutex::new(TestWrapper::<TcpStreamer>::new()));
     let app = Arc::new(Mutex::new(EClient::new(wrapper.clone())));
@@ -42,3 +33,43 @@ pub fn main() -> Result<(), IBKRApiLibError> {
 
     Ok(())
 }
+
+//==================================================================================================
+/// Logs with log4rs, configured by log_config.yml
+#[cfg(not(feature = "tracing"))]
+fn init_logging() -> Result<(), IBKRApiLibError> {
+    match log4rs::init_file("./log_config.yml", Default::default()) {
+        Ok(_) => (),
+        Err(_) => {
+            return Err(IBKRApiLibError::ApiError(TwsApiReportableError::new(
+                -1,
+                "-1".to_string(),
+                "Failed to create logger!!".to_string(),
+            )))
+        }
+    };
+    Ok(())
+}
+
+//==================================================================================================
+/// Sends request spans, and the log records emitted inside them, to a tracing subscriber.  The
+/// level is set with RUST_LOG, e.g. RUST_LOG=twsapi=debug to see every callback of each request.
+#[cfg(feature = "tracing")]
+fn init_logging() -> Result<(), IBKRApiLibError> {
+    use tracing_subscriber::fmt::format::FmtSpan;
+    use tracing_subscriber::EnvFilter;
+
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
+        )
+        .with_span_events(FmtSpan::CLOSE)
+        .try_init()
+        .map_err(|err| {
+            IBKRApiLibError::ApiError(TwsApiReportableError::new(
+                -1,
+                "-1".to_string(),
+                format!("Failed to create logger!! {}", err),
+            ))
+        })
+}
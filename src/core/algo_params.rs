@@ -1,4 +1,7 @@
 //! Utility functions that illustrate setting fields related to algo parameters
+//!
+//! These push the parameters unchecked; [crate::core::algo_strategy::AlgoStrategy] validates them
+//! and can also read them back from an order.
 use crate::core::common::TagValue;
 use crate::core::order::Order;
 
@@ -0,0 +1,1028 @@
+//! Typed and validated IB algo strategies and their conversion to and from Order::algo_params
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{NaiveDateTime, NaiveTime};
+use serde::{Deserialize, Serialize};
+
+use crate::core::common::TagValue;
+use crate::core::errors::{IBKRApiLibError, TwsApiReportableError, TwsError};
+use crate::core::order::Order;
+
+/// Smallest and largest participation rate IB accepts for maxPctVol, pctVol and similar rates
+pub const MIN_PCT_VOL: f64 = 0.1;
+pub const MAX_PCT_VOL: f64 = 0.5;
+
+//==================================================================================================
+fn algo_error(description: String) -> IBKRApiLibError {
+    IBKRApiLibError::ApiError(TwsApiReportableError::new(
+        -1,
+        TwsError::BadMessage.code().to_string(),
+        description,
+    ))
+}
+
+//==================================================================================================
+fn check_range(
+    strategy: &str,
+    name: &str,
+    value: f64,
+    min: f64,
+    max: f64,
+) -> Result<(), IBKRApiLibError> {
+    if value < min || value > max || value.is_nan() {
+        return Err(algo_error(format!(
+            "{}: {} must be between {} and {}, got {}",
+            strategy, name, min, max, value
+        )));
+    }
+    Ok(())
+}
+
+//==================================================================================================
+fn check_non_negative(strategy: &str, name: &str, value: f64) -> Result<(), IBKRApiLibError> {
+    check_range(strategy, name, value, 0.0, f64::MAX)
+}
+
+//==================================================================================================
+fn check_positive(strategy: &str, name: &str, value: i32) -> Result<(), IBKRApiLibError> {
+    if value <= 0 {
+        return Err(algo_error(format!(
+            "{}: {} must be positive, got {}",
+            strategy, name, value
+        )));
+    }
+    Ok(())
+}
+
+//==================================================================================================
+/// Algo start and end times are empty (start now / end at the close), "hh:mm:ss" or
+/// "yyyymmdd-hh:mm:ss", optionally followed by a time zone such as "US/Eastern" or "CET"
+fn check_time(strategy: &str, name: &str, value: &str) -> Result<(), IBKRApiLibError> {
+    if value.is_empty() {
+        return Ok(());
+    }
+    let mut parts = value.splitn(2, ' ');
+    let time = parts.next().unwrap_or("");
+    let time_zone = parts.next().unwrap_or("");
+    let valid_time = NaiveTime::parse_from_str(time, "%H:%M:%S").is_ok()
+        || NaiveDateTime::parse_from_str(time, "%Y%m%d-%H:%M:%S").is_ok();
+    if !valid_time || time_zone.contains(char::is_whitespace) {
+        return Err(algo_error(format!(
+            "{}: {} '{}' is not hh:mm:ss or yyyymmdd-hh:mm:ss with an optional time zone",
+            strategy, name, value
+        )));
+    }
+    Ok(())
+}
+
+//==================================================================================================
+fn check_pct_vol(strategy: &str, name: &str, value: f64) -> Result<(), IBKRApiLibError> {
+    check_range(strategy, name, value, MIN_PCT_VOL, MAX_PCT_VOL)
+}
+
+//==================================================================================================
+/// Builds the algo_params of a strategy
+#[derive(Default)]
+struct ParamWriter(Vec<TagValue>);
+
+impl ParamWriter {
+    fn value<T: ToString>(mut self, tag: &str, value: T) -> Self {
+        self.0
+            .push(TagValue::new(tag.to_string(), value.to_string()));
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn flag(self, tag: &str, value: bool) -> Self {
+        self.value(tag, value as i32)
+    }
+}
+
+//==================================================================================================
+/// Reads the algo_params of a strategy.  Tags that are missing read as the default value.
+struct ParamReader<'a> {
+    strategy: &'a str,
+    params: &'a [TagValue],
+}
+
+impl<'a> ParamReader<'a> {
+    fn text(&self, tag: &str) -> String {
+        self.params
+            .iter()
+            .find(|param| param.tag == tag)
+            .map(|param| param.value.clone())
+            .unwrap_or_default()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn number<T: FromStr + Default>(&self, tag: &str) -> Result<T, IBKRApiLibError> {
+        let text = self.text(tag);
+        if text.is_empty() {
+            return Ok(T::default());
+        }
+        text.parse::<T>().map_err(|_| {
+            algo_error(format!(
+                "{}: invalid value '{}' for {}",
+                self.strategy, text, tag
+            ))
+        })
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn flag(&self, tag: &str) -> Result<bool, IBKRApiLibError> {
+        match self.text(tag).to_lowercase().as_str() {
+            "1" | "true" => Ok(true),
+            "0" | "false" | "" => Ok(false),
+            text => Err(algo_error(format!(
+                "{}: invalid flag '{}' for {}",
+                self.strategy, text, tag
+            ))),
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn choice<T, F: Fn(&str) -> Option<T>>(
+        &self,
+        tag: &str,
+        from_name: F,
+    ) -> Result<T, IBKRApiLibError> {
+        let text = self.text(tag);
+        from_name(text.as_str()).ok_or_else(|| {
+            algo_error(format!(
+                "{}: invalid value '{}' for {}",
+                self.strategy, text, tag
+            ))
+        })
+    }
+}
+
+//==================================================================================================
+/// riskAversion of the arrival price, balance impact risk and close price algos
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RiskAversion {
+    GetDone,
+    Aggressive,
+    Neutral,
+    Passive,
+}
+
+impl RiskAversion {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            RiskAversion::GetDone => "Get Done",
+            RiskAversion::Aggressive => "Aggressive",
+            RiskAversion::Neutral => "Neutral",
+            RiskAversion::Passive => "Passive",
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "Get Done" | "Get_Done" => Some(RiskAversion::GetDone),
+            "Aggressive" => Some(RiskAversion::Aggressive),
+            "Neutral" => Some(RiskAversion::Neutral),
+            "Passive" => Some(RiskAversion::Passive),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for RiskAversion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+//==================================================================================================
+/// adaptivePriority of the adaptive algo
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AdaptivePriority {
+    Urgent,
+    Normal,
+    Patient,
+}
+
+impl AdaptivePriority {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            AdaptivePriority::Urgent => "Urgent",
+            AdaptivePriority::Normal => "Normal",
+            AdaptivePriority::Patient => "Patient",
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "Urgent" => Some(AdaptivePriority::Urgent),
+            "Normal" => Some(AdaptivePriority::Normal),
+            "Patient" => Some(AdaptivePriority::Patient),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for AdaptivePriority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+//==================================================================================================
+/// strategyType of the TWAP algo
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TwapStrategyType {
+    Marketable,
+    MatchingMidpoint,
+    MatchingSameSide,
+    MatchingLast,
+}
+
+impl TwapStrategyType {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            TwapStrategyType::Marketable => "Marketable",
+            TwapStrategyType::MatchingMidpoint => "Matching Midpoint",
+            TwapStrategyType::MatchingSameSide => "Matching Same Side",
+            TwapStrategyType::MatchingLast => "Matching Last",
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "Marketable" => Some(TwapStrategyType::Marketable),
+            "Matching Midpoint" => Some(TwapStrategyType::MatchingMidpoint),
+            "Matching Same Side" => Some(TwapStrategyType::MatchingSameSide),
+            "Matching Last" => Some(TwapStrategyType::MatchingLast),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for TwapStrategyType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+//==================================================================================================
+/// An IB algo with its parameters.
+///
+/// [AlgoStrategy::apply] validates the parameters and writes Order::algo_strategy and
+/// Order::algo_params; [AlgoStrategy::from_order] reads them back, e.g. from an order passed to
+/// Wrapper::open_order.  The Jefferies, CSFB and QB algos must be routed to JEFFALGO, CSFBALGO
+/// and QBALGO respectively.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum AlgoStrategy {
+    Adaptive {
+        priority: AdaptivePriority,
+    },
+    ArrivalPx {
+        max_pct_vol: f64,
+        risk_aversion: RiskAversion,
+        start_time: String,
+        end_time: String,
+        force_completion: bool,
+        allow_past_end_time: bool,
+        monetary_value: f64,
+    },
+    DarkIce {
+        display_size: i32,
+        start_time: String,
+        end_time: String,
+        allow_past_end_time: bool,
+        monetary_value: f64,
+    },
+    PctVol {
+        pct_vol: f64,
+        start_time: String,
+        end_time: String,
+        no_take_liq: bool,
+        monetary_value: f64,
+    },
+    /// Price variant percentage of volume
+    PctVolPx {
+        pct_vol: f64,
+        delta_pct_vol: f64,
+        min_pct_vol_4px: f64,
+        max_pct_vol_4px: f64,
+        start_time: String,
+        end_time: String,
+        no_take_liq: bool,
+        monetary_value: f64,
+    },
+    /// Size variant percentage of volume
+    PctVolSz {
+        start_pct_vol: f64,
+        end_pct_vol: f64,
+        start_time: String,
+        end_time: String,
+        no_take_liq: bool,
+        monetary_value: f64,
+    },
+    /// Time variant percentage of volume
+    PctVolTm {
+        start_pct_vol: f64,
+        end_pct_vol: f64,
+        start_time: String,
+        end_time: String,
+        no_take_liq: bool,
+        monetary_value: f64,
+    },
+    Twap {
+        strategy_type: TwapStrategyType,
+        start_time: String,
+        end_time: String,
+        allow_past_end_time: bool,
+        monetary_value: f64,
+    },
+    Vwap {
+        max_pct_vol: f64,
+        start_time: String,
+        end_time: String,
+        allow_past_end_time: bool,
+        no_take_liq: bool,
+        monetary_value: f64,
+    },
+    /// Accumulate/distribute, sent as "AD"
+    AccumulateDistribute {
+        component_size: i32,
+        time_between_orders: i32,
+        randomize_time_20: bool,
+        randomize_size_55: bool,
+        give_up: i32,
+        catch_up: bool,
+        wait_for_fill: bool,
+        active_time_start: String,
+        active_time_end: String,
+    },
+    BalanceImpactRisk {
+        max_pct_vol: f64,
+        risk_aversion: RiskAversion,
+        force_completion: bool,
+    },
+    MinImpact {
+        max_pct_vol: f64,
+    },
+    ClosePx {
+        max_pct_vol: f64,
+        risk_aversion: RiskAversion,
+        start_time: String,
+        force_completion: bool,
+        monetary_value: f64,
+    },
+    /// Jefferies VWAP, sent as "VWAP"
+    JefferiesVwap {
+        start_time: String,
+        end_time: String,
+        relative_limit: f64,
+        max_volume_rate: f64,
+        exclude_auctions: String,
+        trigger_price: f64,
+        wow_price: f64,
+        min_fill_size: i32,
+        wow_order_pct: f64,
+        wow_mode: String,
+        is_buy_back: bool,
+        wow_reference: String,
+    },
+    /// CSFB Inline, sent as "INLINE"
+    CsfbInline {
+        start_time: String,
+        end_time: String,
+        exec_style: String,
+        min_percent: i32,
+        max_percent: i32,
+        display_size: i32,
+        auction: String,
+        block_finder: bool,
+        block_price: f64,
+        min_block_size: i32,
+        max_block_size: i32,
+        i_would_price: f64,
+    },
+    /// QB Strobe, sent as "STROBE"
+    QbStrobe {
+        start_time: String,
+        end_time: String,
+        benchmark: String,
+        percent_volume: f64,
+        no_clean_up: bool,
+    },
+}
+
+impl AlgoStrategy {
+    /// The name sent in Order::algo_strategy
+    pub fn name(&self) -> &'static str {
+        match self {
+            AlgoStrategy::Adaptive { .. } => "Adaptive",
+            AlgoStrategy::ArrivalPx { .. } => "ArrivalPx",
+            AlgoStrategy::DarkIce { .. } => "DarkIce",
+            AlgoStrategy::PctVol { .. } => "PctVol",
+            AlgoStrategy::PctVolPx { .. } => "PctVolPx",
+            AlgoStrategy::PctVolSz { .. } => "PctVolSz",
+            AlgoStrategy::PctVolTm { .. } => "PctVolTm",
+            AlgoStrategy::Twap { .. } => "Twap",
+            AlgoStrategy::Vwap { .. } => "Vwap",
+            AlgoStrategy::AccumulateDistribute { .. } => "AD",
+            AlgoStrategy::BalanceImpactRisk { .. } => "BalanceImpactRisk",
+            AlgoStrategy::MinImpact { .. } => "MinImpact",
+            AlgoStrategy::ClosePx { .. } => "ClosePx",
+            AlgoStrategy::JefferiesVwap { .. } => "VWAP",
+            AlgoStrategy::CsfbInline { .. } => "INLINE",
+            AlgoStrategy::QbStrobe { .. } => "STROBE",
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Checks the parameters against the ranges and formats IB accepts
+    pub fn validate(&self) -> Result<(), IBKRApiLibError> {
+        let name = self.name();
+        match self {
+            AlgoStrategy::Adaptive { .. } => Ok(()),
+            AlgoStrategy::ArrivalPx {
+                max_pct_vol,
+                start_time,
+                end_time,
+                monetary_value,
+                ..
+            } => {
+                check_pct_vol(name, "max_pct_vol", *max_pct_vol)?;
+                check_time(name, "start_time", start_time)?;
+                check_time(name, "end_time", end_time)?;
+                check_non_negative(name, "monetary_value", *monetary_value)
+            }
+            AlgoStrategy::DarkIce {
+                display_size,
+                start_time,
+                end_time,
+                monetary_value,
+                ..
+            } => {
+                check_positive(name, "display_size", *display_size)?;
+                check_time(name, "start_time", start_time)?;
+                check_time(name, "end_time", end_time)?;
+                check_non_negative(name, "monetary_value", *monetary_value)
+            }
+            AlgoStrategy::PctVol {
+                pct_vol,
+                start_time,
+                end_time,
+                monetary_value,
+                ..
+            } => {
+                check_pct_vol(name, "pct_vol", *pct_vol)?;
+                check_time(name, "start_time", start_time)?;
+                check_time(name, "end_time", end_time)?;
+                check_non_negative(name, "monetary_value", *monetary_value)
+            }
+            AlgoStrategy::PctVolPx {
+                pct_vol,
+                delta_pct_vol,
+                min_pct_vol_4px,
+                max_pct_vol_4px,
+                start_time,
+                end_time,
+                monetary_value,
+                ..
+            } => {
+                check_pct_vol(name, "pct_vol", *pct_vol)?;
+                check_range(name, "delta_pct_vol", *delta_pct_vol, 0.0, MAX_PCT_VOL)?;
+                check_range(name, "min_pct_vol_4px", *min_pct_vol_4px, 0.0, MAX_PCT_VOL)?;
+                check_range(
+                    name,
+                    "max_pct_vol_4px",
+                    *max_pct_vol_4px,
+                    *min_pct_vol_4px,
+                    MAX_PCT_VOL,
+                )?;
+                check_time(name, "start_time", start_time)?;
+                check_time(name, "end_time", end_time)?;
+                check_non_negative(name, "monetary_value", *monetary_value)
+            }
+            AlgoStrategy::PctVolSz {
+                start_pct_vol,
+                end_pct_vol,
+                start_time,
+                end_time,
+                monetary_value,
+                ..
+            }
+            | AlgoStrategy::PctVolTm {
+                start_pct_vol,
+                end_pct_vol,
+                start_time,
+                end_time,
+                monetary_value,
+                ..
+            } => {
+                check_pct_vol(name, "start_pct_vol", *start_pct_vol)?;
+                check_pct_vol(name, "end_pct_vol", *end_pct_vol)?;
+                check_time(name, "start_time", start_time)?;
+                check_time(name, "end_time", end_time)?;
+                check_non_negative(name, "monetary_value", *monetary_value)
+            }
+            AlgoStrategy::Twap {
+                start_time,
+                end_time,
+                monetary_value,
+                ..
+            } => {
+                check_time(name, "start_time", start_time)?;
+                check_time(name, "end_time", end_time)?;
+                check_non_negative(name, "monetary_value", *monetary_value)
+            }
+            AlgoStrategy::Vwap {
+                max_pct_vol,
+                start_time,
+                end_time,
+                monetary_value,
+                ..
+            } => {
+                check_pct_vol(name, "max_pct_vol", *max_pct_vol)?;
+                check_time(name, "start_time", start_time)?;
+                check_time(name, "end_time", end_time)?;
+                check_non_negative(name, "monetary_value", *monetary_value)
+            }
+            AlgoStrategy::AccumulateDistribute {
+                component_size,
+                time_between_orders,
+                give_up,
+                active_time_start,
+                active_time_end,
+                ..
+            } => {
+                check_positive(name, "component_size", *component_size)?;
+                check_positive(name, "time_between_orders", *time_between_orders)?;
+                check_non_negative(name, "give_up", *give_up as f64)?;
+                check_time(name, "active_time_start", active_time_start)?;
+                check_time(name, "active_time_end", active_time_end)
+            }
+            AlgoStrategy::BalanceImpactRisk { max_pct_vol, .. }
+            | AlgoStrategy::MinImpact { max_pct_vol } => {
+                check_pct_vol(name, "max_pct_vol", *max_pct_vol)
+            }
+            AlgoStrategy::ClosePx {
+                max_pct_vol,
+                start_time,
+                monetary_value,
+                ..
+            } => {
+                check_pct_vol(name, "max_pct_vol", *max_pct_vol)?;
+                check_time(name, "start_time", start_time)?;
+                check_non_negative(name, "monetary_value", *monetary_value)
+            }
+            AlgoStrategy::JefferiesVwap {
+                start_time,
+                end_time,
+                max_volume_rate,
+                min_fill_size,
+                wow_order_pct,
+                ..
+            } => {
+                check_time(name, "start_time", start_time)?;
+                check_time(name, "end_time", end_time)?;
+                check_range(name, "max_volume_rate", *max_volume_rate, 0.0, 100.0)?;
+                check_non_negative(name, "min_fill_size", *min_fill_size as f64)?;
+                check_range(name, "wow_order_pct", *wow_order_pct, 0.0, 100.0)
+            }
+            AlgoStrategy::CsfbInline {
+                start_time,
+                end_time,
+                min_percent,
+                max_percent,
+                min_block_size,
+                max_block_size,
+                ..
+            } => {
+                check_time(name, "start_time", start_time)?;
+                check_time(name, "end_time", end_time)?;
+                check_range(name, "min_percent", *min_percent as f64, 0.0, 100.0)?;
+                check_range(
+                    name,
+                    "max_percent",
+                    *max_percent as f64,
+                    *min_percent as f64,
+                    100.0,
+                )?;
+                check_range(
+                    name,
+                    "max_block_size",
+                    *max_block_size as f64,
+                    *min_block_size as f64,
+                    f64::MAX,
+                )
+            }
+            AlgoStrategy::QbStrobe {
+                start_time,
+                end_time,
+                percent_volume,
+                ..
+            } => {
+                check_time(name, "start_time", start_time)?;
+                check_time(name, "end_time", end_time)?;
+                check_range(name, "percent_volume", *percent_volume, 0.0, 1.0)
+            }
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The parameters as sent in Order::algo_params, without validation
+    pub fn to_params(&self) -> Vec<TagValue> {
+        let params = ParamWriter::default();
+        let params = match self {
+            AlgoStrategy::Adaptive { priority } => params.value("adaptivePriority", priority),
+            AlgoStrategy::ArrivalPx {
+                max_pct_vol,
+                risk_aversion,
+                start_time,
+                end_time,
+                force_completion,
+                allow_past_end_time,
+                monetary_value,
+            } => params
+                .value("maxPctVol", max_pct_vol)
+                .value("riskAversion", risk_aversion)
+                .value("startTime", start_time)
+                .value("endTime", end_time)
+                .flag("forceCompletion", *force_completion)
+                .flag("allowPastEndTime", *allow_past_end_time)
+                .value("monetaryValue", monetary_value),
+            AlgoStrategy::DarkIce {
+                display_size,
+                start_time,
+                end_time,
+                allow_past_end_time,
+                monetary_value,
+            } => params
+                .value("displaySize", display_size)
+                .value("startTime", start_time)
+                .value("endTime", end_time)
+                .flag("allowPastEndTime", *allow_past_end_time)
+                .value("monetaryValue", monetary_value),
+            AlgoStrategy::PctVol {
+                pct_vol,
+                start_time,
+                end_time,
+                no_take_liq,
+                monetary_value,
+            } => params
+                .value("pctVol", pct_vol)
+                .value("startTime", start_time)
+                .value("endTime", end_time)
+                .flag("noTakeLiq", *no_take_liq)
+                .value("monetaryValue", monetary_value),
+            AlgoStrategy::PctVolPx {
+                pct_vol,
+                delta_pct_vol,
+                min_pct_vol_4px,
+                max_pct_vol_4px,
+                start_time,
+                end_time,
+                no_take_liq,
+                monetary_value,
+            } => params
+                .value("pctVol", pct_vol)
+                .value("deltaPctVol", delta_pct_vol)
+                .value("minPctVol4Px", min_pct_vol_4px)
+                .value("maxPctVol4Px", max_pct_vol_4px)
+                .value("startTime", start_time)
+                .value("endTime", end_time)
+                .flag("noTakeLiq", *no_take_liq)
+                .value("monetaryValue", monetary_value),
+            AlgoStrategy::PctVolSz {
+                start_pct_vol,
+                end_pct_vol,
+                start_time,
+                end_time,
+                no_take_liq,
+                monetary_value,
+            }
+            | AlgoStrategy::PctVolTm {
+                start_pct_vol,
+                end_pct_vol,
+                start_time,
+                end_time,
+                no_take_liq,
+                monetary_value,
+            } => params
+                .value("startPctVol", start_pct_vol)
+                .value("endPctVol", end_pct_vol)
+                .value("startTime", start_time)
+                .value("endTime", end_time)
+                .flag("noTakeLiq", *no_take_liq)
+                .value("monetaryValue", monetary_value),
+            AlgoStrategy::Twap {
+                strategy_type,
+                start_time,
+                end_time,
+                allow_past_end_time,
+                monetary_value,
+            } => params
+                .value("strategyType", strategy_type)
+                .value("startTime", start_time)
+                .value("endTime", end_time)
+                .flag("allowPastEndTime", *allow_past_end_time)
+                .value("monetaryValue", monetary_value),
+            AlgoStrategy::Vwap {
+                max_pct_vol,
+                start_time,
+                end_time,
+                allow_past_end_time,
+                no_take_liq,
+                monetary_value,
+            } => params
+                .value("maxPctVol", max_pct_vol)
+                .value("startTime", start_time)
+                .value("endTime", end_time)
+                .flag("allowPastEndTime", *allow_past_end_time)
+                .flag("noTakeLiq", *no_take_liq)
+                .value("monetaryValue", monetary_value),
+            AlgoStrategy::AccumulateDistribute {
+                component_size,
+                time_between_orders,
+                randomize_time_20,
+                randomize_size_55,
+                give_up,
+                catch_up,
+                wait_for_fill,
+                active_time_start,
+                active_time_end,
+            } => params
+                .value("ComponentSize", component_size)
+                .value("TimeBetweenOrders", time_between_orders)
+                .flag("RandomizeTime20", *randomize_time_20)
+                .flag("RandomizeSize55", *randomize_size_55)
+                .value("GiveUp", give_up)
+                .flag("CatchUp", *catch_up)
+                .flag("WaitForFill", *wait_for_fill)
+                .value("activeTimeStart", active_time_start)
+                .value("activeTimeEnd", active_time_end),
+            AlgoStrategy::BalanceImpactRisk {
+                max_pct_vol,
+                risk_aversion,
+                force_completion,
+            } => params
+                .value("maxPctVol", max_pct_vol)
+                .value("riskAversion", risk_aversion)
+                .flag("forceCompletion", *force_completion),
+            AlgoStrategy::MinImpact { max_pct_vol } => params.value("maxPctVol", max_pct_vol),
+            AlgoStrategy::ClosePx {
+                max_pct_vol,
+                risk_aversion,
+                start_time,
+                force_completion,
+                monetary_value,
+            } => params
+                .value("maxPctVol", max_pct_vol)
+                .value("riskAversion", risk_aversion)
+                .value("startTime", start_time)
+                .flag("forceCompletion", *force_completion)
+                .value("monetaryValue", monetary_value),
+            AlgoStrategy::JefferiesVwap {
+                start_time,
+                end_time,
+                relative_limit,
+                max_volume_rate,
+                exclude_auctions,
+                trigger_price,
+                wow_price,
+                min_fill_size,
+                wow_order_pct,
+                wow_mode,
+                is_buy_back,
+                wow_reference,
+            } => params
+                .value("startTime", start_time)
+                .value("endTime", end_time)
+                .value("relativeLimit", relative_limit)
+                .value("maxVolumeRate", max_volume_rate)
+                .value("excludeAuctions", exclude_auctions)
+                .value("triggerPrice", trigger_price)
+                .value("wowPrice", wow_price)
+                .value("minFillSize", min_fill_size)
+                .value("wowOrderPct", wow_order_pct)
+                .value("wowMode", wow_mode)
+                .flag("isBuyBack", *is_buy_back)
+                .value("wowReference", wow_reference),
+            AlgoStrategy::CsfbInline {
+                start_time,
+                end_time,
+                exec_style,
+                min_percent,
+                max_percent,
+                display_size,
+                auction,
+                block_finder,
+                block_price,
+                min_block_size,
+                max_block_size,
+                i_would_price,
+            } => params
+                .value("StartTime", start_time)
+                .value("EndTime", end_time)
+                .value("ExecStyle", exec_style)
+                .value("MinPercent", min_percent)
+                .value("MaxPercent", max_percent)
+                .value("DisplaySize", display_size)
+                .value("Auction", auction)
+                .flag("BlockFinder", *block_finder)
+                .value("BlockPrice", block_price)
+                .value("MinBlockSize", min_block_size)
+                .value("MaxBlockSize", max_block_size)
+                .value("IWouldPrice", i_would_price),
+            AlgoStrategy::QbStrobe {
+                start_time,
+                end_time,
+                benchmark,
+                percent_volume,
+                no_clean_up,
+            } => params
+                .value("startTime", start_time)
+                .value("endTime", end_time)
+                .value("benchmark", benchmark)
+                .value("percentVolume", percent_volume)
+                .flag("noCleanUp", *no_clean_up),
+        };
+        params.0
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Reads a strategy from its name and algo_params.  Missing parameters take default values;
+    /// the values are not validated since they come from TWS.
+    pub fn from_params(name: &str, params: &[TagValue]) -> Result<Self, IBKRApiLibError> {
+        let params = ParamReader {
+            strategy: name,
+            params,
+        };
+        Ok(match name {
+            "Adaptive" => AlgoStrategy::Adaptive {
+                priority: params.choice("adaptivePriority", AdaptivePriority::from_name)?,
+            },
+            "ArrivalPx" => AlgoStrategy::ArrivalPx {
+                max_pct_vol: params.number("maxPctVol")?,
+                risk_aversion: params.choice("riskAversion", RiskAversion::from_name)?,
+                start_time: params.text("startTime"),
+                end_time: params.text("endTime"),
+                force_completion: params.flag("forceCompletion")?,
+                allow_past_end_time: params.flag("allowPastEndTime")?,
+                monetary_value: params.number("monetaryValue")?,
+            },
+            "DarkIce" => AlgoStrategy::DarkIce {
+                display_size: params.number("displaySize")?,
+                start_time: params.text("startTime"),
+                end_time: params.text("endTime"),
+                allow_past_end_time: params.flag("allowPastEndTime")?,
+                monetary_value: params.number("monetaryValue")?,
+            },
+            "PctVol" => AlgoStrategy::PctVol {
+                pct_vol: params.number("pctVol")?,
+                start_time: params.text("startTime"),
+                end_time: params.text("endTime"),
+                no_take_liq: params.flag("noTakeLiq")?,
+                monetary_value: params.number("monetaryValue")?,
+            },
+            "PctVolPx" => AlgoStrategy::PctVolPx {
+                pct_vol: params.number("pctVol")?,
+                delta_pct_vol: params.number("deltaPctVol")?,
+                min_pct_vol_4px: params.number("minPctVol4Px")?,
+                max_pct_vol_4px: params.number("maxPctVol4Px")?,
+                start_time: params.text("startTime"),
+                end_time: params.text("endTime"),
+                no_take_liq: params.flag("noTakeLiq")?,
+                monetary_value: params.number("monetaryValue")?,
+            },
+            "PctVolSz" => AlgoStrategy::PctVolSz {
+                start_pct_vol: params.number("startPctVol")?,
+                end_pct_vol: params.number("endPctVol")?,
+                start_time: params.text("startTime"),
+                end_time: params.text("endTime"),
+                no_take_liq: params.flag("noTakeLiq")?,
+                monetary_value: params.number("monetaryValue")?,
+            },
+            "PctVolTm" => AlgoStrategy::PctVolTm {
+                start_pct_vol: params.number("startPctVol")?,
+                end_pct_vol: params.number("endPctVol")?,
+                start_time: params.text("startTime"),
+                end_time: params.text("endTime"),
+                no_take_liq: params.flag("noTakeLiq")?,
+                monetary_value: params.number("monetaryValue")?,
+            },
+            "Twap" => AlgoStrategy::Twap {
+                strategy_type: params.choice("strategyType", TwapStrategyType::from_name)?,
+                start_time: params.text("startTime"),
+                end_time: params.text("endTime"),
+                allow_past_end_time: params.flag("allowPastEndTime")?,
+                monetary_value: params.number("monetaryValue")?,
+            },
+            "Vwap" => AlgoStrategy::Vwap {
+                max_pct_vol: params.number("maxPctVol")?,
+                start_time: params.text("startTime"),
+                end_time: params.text("endTime"),
+                allow_past_end_time: params.flag("allowPastEndTime")?,
+                no_take_liq: params.flag("noTakeLiq")?,
+                monetary_value: params.number("monetaryValue")?,
+            },
+            "AD" => AlgoStrategy::AccumulateDistribute {
+                component_size: params.number("ComponentSize")?,
+                time_between_orders: params.number("TimeBetweenOrders")?,
+                randomize_time_20: params.flag("RandomizeTime20")?,
+                randomize_size_55: params.flag("RandomizeSize55")?,
+                give_up: params.number("GiveUp")?,
+                catch_up: params.flag("CatchUp")?,
+                wait_for_fill: params.flag("WaitForFill")?,
+                active_time_start: params.text("activeTimeStart"),
+                active_time_end: params.text("activeTimeEnd"),
+            },
+            "BalanceImpactRisk" => AlgoStrategy::BalanceImpactRisk {
+                max_pct_vol: params.number("maxPctVol")?,
+                risk_aversion: params.choice("riskAversion", RiskAversion::from_name)?,
+                force_completion: params.flag("forceCompletion")?,
+            },
+            "MinImpact" => AlgoStrategy::MinImpact {
+                max_pct_vol: params.number("maxPctVol")?,
+            },
+            "ClosePx" => AlgoStrategy::ClosePx {
+                max_pct_vol: params.number("maxPctVol")?,
+                risk_aversion: params.choice("riskAversion", RiskAversion::from_name)?,
+                start_time: params.text("startTime"),
+                force_completion: params.flag("forceCompletion")?,
+                monetary_value: params.number("monetaryValue")?,
+            },
+            "VWAP" => AlgoStrategy::JefferiesVwap {
+                start_time: params.text("startTime"),
+                end_time: params.text("endTime"),
+                relative_limit: params.number("relativeLimit")?,
+                max_volume_rate: params.number("maxVolumeRate")?,
+                exclude_auctions: params.text("excludeAuctions"),
+                trigger_price: params.number("triggerPrice")?,
+                wow_price: params.number("wowPrice")?,
+                min_fill_size: params.number("minFillSize")?,
+                wow_order_pct: params.number("wowOrderPct")?,
+                wow_mode: params.text("wowMode"),
+                is_buy_back: params.flag("isBuyBack")?,
+                wow_reference: params.text("wowReference"),
+            },
+            "INLINE" => AlgoStrategy::CsfbInline {
+                start_time: params.text("StartTime"),
+                end_time: params.text("EndTime"),
+                exec_style: params.text("ExecStyle"),
+                min_percent: params.number("MinPercent")?,
+                max_percent: params.number("MaxPercent")?,
+                display_size: params.number("DisplaySize")?,
+                auction: params.text("Auction"),
+                block_finder: params.flag("BlockFinder")?,
+                block_price: params.number("BlockPrice")?,
+                min_block_size: params.number("MinBlockSize")?,
+                max_block_size: params.number("MaxBlockSize")?,
+                i_would_price: params.number("IWouldPrice")?,
+            },
+            "STROBE" => AlgoStrategy::QbStrobe {
+                start_time: params.text("startTime"),
+                end_time: params.text("endTime"),
+                benchmark: params.text("benchmark"),
+                percent_volume: params.number("percentVolume")?,
+                no_clean_up: params.flag("noCleanUp")?,
+            },
+            _ => return Err(algo_error(format!("Unknown algo strategy '{}'", name))),
+        })
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The strategy of an order, None if it has no algo_strategy
+    pub fn from_order(order: &Order) -> Result<Option<Self>, IBKRApiLibError> {
+        if order.algo_strategy.is_empty() {
+            return Ok(None);
+        }
+        AlgoStrategy::from_params(order.algo_strategy.as_str(), order.algo_params.as_slice())
+            .map(Some)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Validates the parameters and sets algo_strategy and algo_params of the order, replacing
+    /// any parameters it already had
+    pub fn apply(&self, order: &mut Order) -> Result<(), IBKRApiLibError> {
+        self.validate()?;
+        order.algo_strategy = self.name().to_string();
+        order.algo_params = self.to_params();
+        Ok(())
+    }
+}
+
+impl fmt::Display for AlgoStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: [{}]",
+            self.name(),
+            self.to_params()
+                .iter()
+                .map(|param| format!("{}={}", param.tag, param.value))
+                .collect::<Vec<String>>()
+                .join(", ")
+        )
+    }
+}
@@ -0,0 +1,580 @@
+//! Replays historical bars and ticks into a Wrapper and fills its orders with a SimBroker
+use std::fmt;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use serde::de::DeserializeOwned;
+
+use crate::core::common::{
+    BarData, HistoricalTickBidAsk, HistoricalTickLast, RealTimeBar, TickAttrib, TickByTickType,
+    TickType,
+};
+use crate::core::contract::Contract;
+use crate::core::errors::{IBKRApiLibError, TwsApiReportableError, TwsError};
+use crate::core::order::Order;
+use crate::core::sim_broker::{SimBroker, SimFill};
+use crate::core::wrapper::Wrapper;
+
+//==================================================================================================
+fn backtest_error(description: String) -> IBKRApiLibError {
+    IBKRApiLibError::ApiError(TwsApiReportableError::new(
+        -1,
+        TwsError::BadMessage.code().to_string(),
+        description,
+    ))
+}
+
+//==================================================================================================
+/// Seconds since the epoch of a BarData::date: epoch seconds, "yyyymmdd" or
+/// "yyyymmdd  hh:mm:ss".  A trailing time zone is ignored and the time is taken as UTC.
+pub fn bar_time(date: &str) -> Result<i64, IBKRApiLibError> {
+    let mut tokens = date.split_whitespace();
+    let day = tokens.next().unwrap_or("");
+    let invalid = || backtest_error(format!("Invalid bar date '{}'", date));
+    if day.len() != 8 && !day.is_empty() && day.chars().all(|c| c.is_ascii_digit()) {
+        return day.parse::<i64>().map_err(|_| invalid());
+    }
+    let day = NaiveDate::parse_from_str(day, "%Y%m%d").map_err(|_| invalid())?;
+    let date_time = match tokens.next() {
+        Some(time) => NaiveDateTime::parse_from_str(
+            format!("{} {}", day.format("%Y%m%d"), time).as_str(),
+            "%Y%m%d %H:%M:%S",
+        )
+        .map_err(|_| invalid())?,
+        None => day.and_hms_opt(0, 0, 0).unwrap(),
+    };
+    Ok(date_time.and_utc().timestamp())
+}
+
+//==================================================================================================
+/// Formats epoch seconds like the time of an execution
+fn execution_time(time: i64) -> String {
+    DateTime::from_timestamp(time, 0)
+        .map(|date_time| date_time.format("%Y%m%d  %H:%M:%S").to_string())
+        .unwrap_or_default()
+}
+
+//==================================================================================================
+/// Reads a JSON array of BarData, HistoricalTickLast or HistoricalTickBidAsk
+pub fn load_series<T: DeserializeOwned, P: AsRef<Path>>(
+    path: P,
+) -> Result<Vec<T>, IBKRApiLibError> {
+    let path = path.as_ref();
+    let reader = BufReader::new(File::open(path)?);
+    serde_json::from_reader(reader)
+        .map_err(|err| backtest_error(format!("Invalid series {}: {}", path.display(), err)))
+}
+
+//==================================================================================================
+/// The callbacks bars are delivered as
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BarDelivery {
+    HistoricalDataUpdate,
+    RealtimeBar,
+    /// tick_price with the close as the last price
+    TickPrice,
+}
+
+//==================================================================================================
+/// The callbacks ticks are delivered as
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TickDelivery {
+    /// tick_by_tick_all_last or tick_by_tick_bid_ask
+    TickByTick,
+    /// tick_price of the last price, or of the bid and the ask
+    TickPrice,
+}
+
+//==================================================================================================
+#[derive(Clone, Debug)]
+enum SeriesData {
+    Bars(Vec<BarData>, BarDelivery),
+    Last(Vec<HistoricalTickLast>, TickDelivery),
+    BidAsk(Vec<HistoricalTickBidAsk>, TickDelivery),
+}
+
+#[derive(Clone, Debug)]
+struct Series {
+    req_id: i32,
+    con_id: i32,
+    data: SeriesData,
+}
+
+//==================================================================================================
+#[derive(Clone, Debug)]
+enum SimRequest {
+    PlaceOrder(i32, Box<Contract>, Box<Order>),
+    CancelOrder(i32),
+    GlobalCancel,
+    CurrentTime,
+}
+
+//==================================================================================================
+/// Handle a strategy uses instead of EClient during a backtest.
+///
+/// Requests are queued and carried out by the [Backtester] when the callback that made them
+/// returns, so they can be made from inside Wrapper callbacks.
+#[derive(Clone, Debug, Default)]
+pub struct SimClient {
+    requests: Arc<Mutex<Vec<SimRequest>>>,
+    clock: Arc<AtomicI64>,
+}
+
+impl SimClient {
+    pub fn place_order(
+        &self,
+        order_id: i32,
+        contract: &Contract,
+        order: &Order,
+    ) -> Result<(), IBKRApiLibError> {
+        self.push(SimRequest::PlaceOrder(
+            order_id,
+            Box::new(contract.clone()),
+            Box::new(order.clone()),
+        ));
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn cancel_order(&self, order_id: i32) -> Result<(), IBKRApiLibError> {
+        self.push(SimRequest::CancelOrder(order_id));
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn req_global_cancel(&self) -> Result<(), IBKRApiLibError> {
+        self.push(SimRequest::GlobalCancel);
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Answered with Wrapper::current_time at the simulated time
+    pub fn req_current_time(&self) -> Result<(), IBKRApiLibError> {
+        self.push(SimRequest::CurrentTime);
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Simulated time in seconds since the epoch
+    pub fn current_time(&self) -> i64 {
+        self.clock.load(Ordering::SeqCst)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn push(&self, request: SimRequest) {
+        self.requests.lock().unwrap().push(request);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn take(&self) -> Vec<SimRequest> {
+        std::mem::take(&mut *self.requests.lock().unwrap())
+    }
+}
+
+//==================================================================================================
+/// Equity curve and trade statistics of a backtest.  A trade is a fill that reduces a position.
+#[derive(Clone, Debug, Default)]
+pub struct BacktestReport {
+    pub starting_capital: f64,
+    pub final_equity: f64,
+    /// Time in seconds since the epoch and equity after each time step
+    pub equity_curve: Vec<(i64, f64)>,
+    /// Largest fall from a peak of equity, as a fraction of the peak
+    pub max_drawdown: f64,
+    pub trades: usize,
+    pub winning_trades: usize,
+    pub losing_trades: usize,
+    pub gross_profit: f64,
+    pub gross_loss: f64,
+    pub total_commission: f64,
+    pub fills: Vec<SimFill>,
+}
+
+impl BacktestReport {
+    fn new(starting_capital: f64, equity_curve: Vec<(i64, f64)>, fills: Vec<SimFill>) -> Self {
+        let mut report = BacktestReport {
+            starting_capital,
+            final_equity: equity_curve
+                .last()
+                .map_or(starting_capital, |(_, equity)| *equity),
+            ..Default::default()
+        };
+        let mut peak = starting_capital;
+        for (_, equity) in equity_curve.iter() {
+            peak = peak.max(*equity);
+            if peak > 0.0 {
+                report.max_drawdown = report.max_drawdown.max((peak - equity) / peak);
+            }
+        }
+        for fill in fills.iter() {
+            report.total_commission += fill.commission;
+            if let Some(pnl) = fill.realized_pnl {
+                report.trades += 1;
+                if pnl > 0.0 {
+                    report.winning_trades += 1;
+                    report.gross_profit += pnl;
+                } else {
+                    report.losing_trades += 1;
+                    report.gross_loss -= pnl;
+                }
+            }
+        }
+        report.equity_curve = equity_curve;
+        report.fills = fills;
+        report
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Return as a fraction of the starting capital
+    pub fn total_return(&self) -> f64 {
+        if self.starting_capital == 0.0 {
+            return 0.0;
+        }
+        self.final_equity / self.starting_capital - 1.0
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn win_rate(&self) -> f64 {
+        if self.trades == 0 {
+            return 0.0;
+        }
+        self.winning_trades as f64 / self.trades as f64
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Gross profit over gross loss, infinite without losses
+    pub fn profit_factor(&self) -> f64 {
+        if self.gross_loss == 0.0 {
+            return if self.gross_profit > 0.0 {
+                f64::INFINITY
+            } else {
+                0.0
+            };
+        }
+        self.gross_profit / self.gross_loss
+    }
+}
+
+impl fmt::Display for BacktestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "starting capital: {:.2}", self.starting_capital)?;
+        writeln!(f, "final equity: {:.2}", self.final_equity)?;
+        writeln!(f, "total return: {:.2}%", self.total_return() * 100.0)?;
+        writeln!(f, "max drawdown: {:.2}%", self.max_drawdown * 100.0)?;
+        writeln!(
+            f,
+            "trades: {} ({} won, {} lost, win rate {:.2}%)",
+            self.trades,
+            self.winning_trades,
+            self.losing_trades,
+            self.win_rate() * 100.0
+        )?;
+        writeln!(
+            f,
+            "gross profit: {:.2}, gross loss: {:.2}, profit factor: {:.2}",
+            self.gross_profit,
+            self.gross_loss,
+            self.profit_factor()
+        )?;
+        write!(f, "commission: {:.2}", self.total_commission)
+    }
+}
+
+//==================================================================================================
+/// Replays series of bars and ticks in time order into a Wrapper.
+///
+/// At each time step the [SimBroker] first matches working orders against the new prices, then
+/// the wrapper receives the data and the requests it made through the [SimClient] are carried
+/// out.  Orders placed in a callback therefore fill at the current price at the earliest.  Bid
+/// ask ticks are matched at the midpoint.  Series with equal times are replayed in the order
+/// they were added.
+pub struct Backtester<T: Wrapper> {
+    wrapper: Arc<Mutex<T>>,
+    broker: SimBroker<T>,
+    client: SimClient,
+    series: Vec<Series>,
+    starting_capital: f64,
+}
+
+impl<T: Wrapper> Backtester<T> {
+    pub fn new(wrapper: Arc<Mutex<T>>, account: &str, starting_capital: f64) -> Self {
+        Backtester {
+            broker: SimBroker::new(wrapper.clone(), account),
+            wrapper,
+            client: SimClient::default(),
+            series: vec![],
+            starting_capital,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The handle the strategy sends orders through
+    pub fn client(&self) -> SimClient {
+        self.client.clone()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The broker, to set the commission, fill model and slippage
+    pub fn broker_mut(&mut self) -> &mut SimBroker<T> {
+        &mut self.broker
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn add_bars(
+        &mut self,
+        req_id: i32,
+        contract: &Contract,
+        bars: Vec<BarData>,
+        delivery: BarDelivery,
+    ) {
+        self.add_series(req_id, contract, SeriesData::Bars(bars, delivery));
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn add_last_ticks(
+        &mut self,
+        req_id: i32,
+        contract: &Contract,
+        ticks: Vec<HistoricalTickLast>,
+        delivery: TickDelivery,
+    ) {
+        self.add_series(req_id, contract, SeriesData::Last(ticks, delivery));
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn add_bid_ask_ticks(
+        &mut self,
+        req_id: i32,
+        contract: &Contract,
+        ticks: Vec<HistoricalTickBidAsk>,
+        delivery: TickDelivery,
+    ) {
+        self.add_series(req_id, contract, SeriesData::BidAsk(ticks, delivery));
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Adds bars from a JSON file, see [load_series]
+    pub fn load_bars<P: AsRef<Path>>(
+        &mut self,
+        req_id: i32,
+        contract: &Contract,
+        path: P,
+        delivery: BarDelivery,
+    ) -> Result<(), IBKRApiLibError> {
+        self.add_bars(req_id, contract, load_series(path)?, delivery);
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn load_last_ticks<P: AsRef<Path>>(
+        &mut self,
+        req_id: i32,
+        contract: &Contract,
+        path: P,
+        delivery: TickDelivery,
+    ) -> Result<(), IBKRApiLibError> {
+        self.add_last_ticks(req_id, contract, load_series(path)?, delivery);
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn load_bid_ask_ticks<P: AsRef<Path>>(
+        &mut self,
+        req_id: i32,
+        contract: &Contract,
+        path: P,
+        delivery: TickDelivery,
+    ) -> Result<(), IBKRApiLibError> {
+        self.add_bid_ask_ticks(req_id, contract, load_series(path)?, delivery);
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn add_series(&mut self, req_id: i32, contract: &Contract, data: SeriesData) {
+        self.series.push(Series {
+            req_id,
+            con_id: contract.con_id,
+            data,
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Times of all bars and ticks, sorted, with the series and position they belong to
+    fn events(&self) -> Result<Vec<(i64, usize, usize)>, IBKRApiLibError> {
+        let mut events = vec![];
+        for (series_index, series) in self.series.iter().enumerate() {
+            match &series.data {
+                SeriesData::Bars(bars, _) => {
+                    for (index, bar) in bars.iter().enumerate() {
+                        events.push((bar_time(bar.date.as_str())?, series_index, index));
+                    }
+                }
+                SeriesData::Last(ticks, _) => {
+                    for (index, tick) in ticks.iter().enumerate() {
+                        events.push((tick.time as i64, series_index, index));
+                    }
+                }
+                SeriesData::BidAsk(ticks, _) => {
+                    for (index, tick) in ticks.iter().enumerate() {
+                        events.push((tick.time as i64, series_index, index));
+                    }
+                }
+            }
+        }
+        events.sort_by_key(|(time, series_index, index)| (*time, *series_index, *index));
+        Ok(events)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Replays all series and reports the results
+    pub fn run(&mut self) -> Result<BacktestReport, IBKRApiLibError> {
+        let events = self.events()?;
+        let mut equity_curve: Vec<(i64, f64)> = vec![];
+        self.process_requests()?;
+        for (time, series_index, index) in events {
+            self.client.clock.store(time, Ordering::SeqCst);
+            self.broker.set_time(execution_time(time).as_str());
+            self.replay(series_index, index)?;
+            self.process_requests()?;
+
+            let equity = self.starting_capital + self.broker.total_pnl();
+            match equity_curve.last_mut() {
+                Some(last) if last.0 == time => last.1 = equity,
+                _ => equity_curve.push((time, equity)),
+            }
+        }
+        Ok(BacktestReport::new(
+            self.starting_capital,
+            equity_curve,
+            self.broker.fills().to_vec(),
+        ))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn replay(&mut self, series_index: usize, index: usize) -> Result<(), IBKRApiLibError> {
+        let series = &self.series[series_index];
+        let (req_id, con_id) = (series.req_id, series.con_id);
+        match &series.data {
+            SeriesData::Bars(bars, delivery) => {
+                let bar = bars[index].clone();
+                let delivery = *delivery;
+                self.broker.on_bar(con_id, &bar);
+                let mut wrapper = self.wrapper.lock().unwrap();
+                match delivery {
+                    BarDelivery::HistoricalDataUpdate => {
+                        wrapper.historical_data_update(req_id, bar)
+                    }
+                    BarDelivery::RealtimeBar => wrapper.realtime_bar(
+                        req_id,
+                        RealTimeBar {
+                            date_time: bar_time(bar.date.as_str())?.to_string(),
+                            open: bar.open,
+                            high: bar.high,
+                            low: bar.low,
+                            close: bar.close,
+                            volume: bar.volume,
+                            wap: bar.average,
+                            count: bar.bar_count,
+                        },
+                    ),
+                    BarDelivery::TickPrice => {
+                        wrapper.tick_price(req_id, TickType::Last, bar.close, TickAttrib::default())
+                    }
+                }
+            }
+            SeriesData::Last(ticks, delivery) => {
+                let tick = ticks[index].clone();
+                let delivery = *delivery;
+                self.broker.on_tick(con_id, tick.price);
+                let mut wrapper = self.wrapper.lock().unwrap();
+                match delivery {
+                    TickDelivery::TickByTick => wrapper.tick_by_tick_all_last(
+                        req_id,
+                        TickByTickType::Last,
+                        tick.time as i64,
+                        tick.price,
+                        tick.size,
+                        tick.tick_attrib_last,
+                        tick.exchange.as_str(),
+                        tick.special_conditions.as_str(),
+                    ),
+                    TickDelivery::TickPrice => wrapper.tick_price(
+                        req_id,
+                        TickType::Last,
+                        tick.price,
+                        TickAttrib::default(),
+                    ),
+                }
+            }
+            SeriesData::BidAsk(ticks, delivery) => {
+                let tick = ticks[index].clone();
+                let delivery = *delivery;
+                self.broker
+                    .on_tick(con_id, (tick.price_bid + tick.price_ask) / 2.0);
+                let mut wrapper = self.wrapper.lock().unwrap();
+                match delivery {
+                    TickDelivery::TickByTick => wrapper.tick_by_tick_bid_ask(
+                        req_id,
+                        tick.time as i64,
+                        tick.price_bid,
+                        tick.price_ask,
+                        tick.size_bid,
+                        tick.size_ask,
+                        tick.tick_attrib_bid_ask,
+                    ),
+                    TickDelivery::TickPrice => {
+                        wrapper.tick_price(
+                            req_id,
+                            TickType::Bid,
+                            tick.price_bid,
+                            TickAttrib::default(),
+                        );
+                        wrapper.tick_price(
+                            req_id,
+                            TickType::Ask,
+                            tick.price_ask,
+                            TickAttrib::default(),
+                        );
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Carries out the queued requests, including the ones made by the callbacks they cause
+    fn process_requests(&mut self) -> Result<(), IBKRApiLibError> {
+        loop {
+            let requests = self.client.take();
+            if requests.is_empty() {
+                return Ok(());
+            }
+            for request in requests {
+                match request {
+                    // rejected orders are reported like TWS does instead of ending the backtest
+                    SimRequest::PlaceOrder(order_id, contract, order) => {
+                        if let Err(err) = self.broker.place_order(order_id, &contract, &order) {
+                            self.wrapper.lock().unwrap().error(
+                                order_id,
+                                TwsError::BadMessage.code(),
+                                err.to_string().as_str(),
+                            );
+                        }
+                    }
+                    SimRequest::CancelOrder(order_id) => self.broker.cancel_order(order_id)?,
+                    SimRequest::GlobalCancel => self.broker.req_global_cancel()?,
+                    SimRequest::CurrentTime => self
+                        .wrapper
+                        .lock()
+                        .unwrap()
+                        .current_time(self.client.current_time()),
+                }
+            }
+        }
+    }
+}
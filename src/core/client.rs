@@ -5,8 +5,9 @@ use std::net::Shutdown;
 use std::net::TcpStream;
 use std::ops::Deref;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::mpsc::channel;
-use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{channel, Sender};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
 use std::{fmt::Debug, thread};
 
 use from_ascii::FromAscii;
@@ -20,18 +21,25 @@ use crate::core::contract::Contract;
 use crate::core::decoder::Decoder;
 use crate::core::errors::{IBKRApiLibError, TwsApiReportableError, TwsError};
 use crate::core::execution::ExecutionFilter;
-use crate::core::messages::make_field;
+use crate::core::messages::{error_message, make_field};
 use crate::core::messages::{make_field_handle_empty, read_msg};
 use crate::core::messages::{make_message, read_fields, OutgoingMessageIds};
+use crate::core::metrics::{MetricsSink, SubscriptionKind};
 use crate::core::order::Order;
 use crate::core::order_condition::Condition;
 use crate::core::reader::Reader;
+#[cfg(feature = "tracing")]
+use crate::core::request_spans::RequestSpans;
+use crate::core::risk_gate::RiskGate;
 use crate::core::scanner::ScannerSubscription;
 use crate::core::server_versions::*;
 use crate::core::wrapper::Wrapper;
 
 pub(crate) static POISONED_MUTEX: &str = "Mutex was poisoned";
 
+/// How long connect waits for the decoder of the previous connection to give back the handler
+const HANDLER_RETURN_TIMEOUT: Duration = Duration::from_secs(10);
+
 //==================================================================================================
 /// Connection status
 #[repr(i32)]
@@ -43,6 +51,15 @@ pub enum ConnStatus {
     REDIRECT,
 }
 
+//==================================================================================================
+/// Where the decoder gets the wrapper from
+enum WrapperSlot<T> {
+    /// Locked by the decoder for every callback
+    Shared(Arc<Mutex<T>>),
+    /// Owned by the decoder while connected, and parked here in between connections
+    Exclusive(Arc<(Mutex<Option<T>>, Condvar)>),
+}
+
 //==================================================================================================
 /// Struct for sending requests
 //#[derive(Debug)]
@@ -50,8 +67,10 @@ pub struct EClient<T>
 where
     T: Wrapper,
 {
-    wrapper: Arc<Mutex<T>>,
+    wrapper: WrapperSlot<T>,
     pub(crate) stream: Option<Box<dyn Streamer>>,
+    /// Queue of the decoder of the current connection
+    pub(crate) msg_tx: Option<Sender<String>>,
     host: String,
     port: u32,
     extra_auth: bool,
@@ -61,6 +80,10 @@ where
     pub conn_state: Arc<Mutex<ConnStatus>>,
     opt_capab: String,
     disconnect_requested: Arc<AtomicBool>,
+    risk_gate: Option<Arc<RiskGate>>,
+    metrics: Option<Arc<dyn MetricsSink>>,
+    #[cfg(feature = "tracing")]
+    request_spans: Arc<RequestSpans>,
 }
 
 impl<T> EClient<T>
@@ -68,9 +91,26 @@ where
     T: Wrapper + Send + Sync + 'static,
 {
     pub fn new(wrapper: Arc<Mutex<T>>) -> Self {
+        EClient::with_slot(WrapperSlot::Shared(wrapper))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Creates a client whose decoder owns the handler while connected, so that callbacks are made
+    /// without locking.  The handler makes requests through a
+    /// [ClientHandle](crate::core::client_handle::ClientHandle) rather than through the client.
+    pub fn with_handler(handler: T) -> Self {
+        EClient::with_slot(WrapperSlot::Exclusive(Arc::new((
+            Mutex::new(Some(handler)),
+            Condvar::new(),
+        ))))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn with_slot(wrapper: WrapperSlot<T>) -> Self {
         EClient {
-            wrapper: wrapper,
+            wrapper,
             stream: None,
+            msg_tx: None,
             host: "".to_string(),
             port: 0,
             extra_auth: false,
@@ -80,22 +120,140 @@ where
             conn_state: Arc::new(Mutex::new(ConnStatus::DISCONNECTED)),
             opt_capab: "".to_string(),
             disconnect_requested: Arc::new(AtomicBool::new(false)),
+            risk_gate: None,
+            metrics: None,
+            #[cfg(feature = "tracing")]
+            request_spans: Arc::new(RequestSpans::new()),
         }
     }
     fn send_request(&mut self, request: &str) -> Result<(), IBKRApiLibError> {
         let bytes = make_message(request)?;
         self.send_bytes(bytes.as_slice())?;
+        if let Some(metrics) = &self.metrics {
+            // the message id is the first field of every request
+            if let Some(Ok(msg_id)) = request.split('\0').next().map(str::parse) {
+                metrics.message_sent(msg_id);
+            }
+        }
         Ok(())
     }
 
     fn send_bytes(&mut self, bytes: &[u8]) -> Result<usize, IBKRApiLibError> {
         let return_val = self.stream.as_mut().unwrap().write(bytes)?;
+        if let Some(metrics) = &self.metrics {
+            metrics.bytes_sent(return_val);
+        }
         Ok(return_val)
     }
 
+    //----------------------------------------------------------------------------------------------
+    fn track_subscription(&self, kind: SubscriptionKind, req_id: i32, subscribed: bool) {
+        if let Some(metrics) = &self.metrics {
+            if subscribed {
+                metrics.subscribed(kind, req_id);
+            } else {
+                metrics.unsubscribed(kind, req_id);
+            }
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Opens the tracing span of a request, and returns true if a new one was opened rather than
+    /// an order's span being reused.  Does nothing without the `tracing` feature.
+    #[allow(unused_variables)]
+    fn open_span(&self, id: i32, message: &str, contract: Option<&Contract>) -> bool {
+        #[cfg(feature = "tracing")]
+        return self
+            .request_spans
+            .open(id, message, contract, self.client_id);
+        #[cfg(not(feature = "tracing"))]
+        false
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Sends a request whose span was opened before it, so that no answer arrives outside of it.
+    /// A span opened for the request is closed again if the request can't be sent.
+    fn send_in_span(
+        &mut self,
+        id: i32,
+        opened: bool,
+        request: &str,
+    ) -> Result<(), IBKRApiLibError> {
+        let result = self.send_request(request);
+        if result.is_err() && opened {
+            self.close_span(id, "request not sent");
+        }
+        result
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Opens the tracing span of a request and sends it
+    fn send_traced(
+        &mut self,
+        id: i32,
+        message: &str,
+        contract: Option<&Contract>,
+        request: &str,
+    ) -> Result<(), IBKRApiLibError> {
+        let opened = self.open_span(id, message, contract);
+        self.send_in_span(id, opened, request)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Keeps the tracing span of a historical data request open after its initial bars.  Does
+    /// nothing without the `tracing` feature.
+    #[allow(unused_variables)]
+    fn keep_span_open(&self, id: i32) {
+        #[cfg(feature = "tracing")]
+        self.request_spans.keep_open(id);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Records a call on the tracing span of a request.  Does nothing without the `tracing`
+    /// feature.
+    #[allow(unused_variables)]
+    fn record_span(&self, id: i32, message: &str) {
+        #[cfg(feature = "tracing")]
+        self.request_spans.record(id, message);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Closes the tracing span of a cancelled request.  Does nothing without the `tracing` feature.
+    #[allow(unused_variables)]
+    fn close_span(&self, id: i32, message: &str) {
+        #[cfg(feature = "tracing")]
+        {
+            self.request_spans.record(id, message);
+            self.request_spans.close(id);
+        }
+    }
+
     pub(crate) fn set_streamer(&mut self, streamer: Option<Box<dyn Streamer>>) {
         self.stream = streamer;
     }
+
+    //----------------------------------------------------------------------------------------------
+    /// Gives the handler of a decoder that did not start back to the client
+    fn park_handler(&self, decoder: Decoder<T>) {
+        if let (WrapperSlot::Exclusive(slot), Some(handler)) =
+            (&self.wrapper, decoder.into_handler())
+        {
+            park_handler(slot, handler);
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Hands an error to the wrapper through the decoder, as if TWS had reported it.  Used for
+    /// requests made through a ClientHandle, whose callers do not get the result.
+    pub(crate) fn report_error(&self, req_id: i32, err: &IBKRApiLibError) {
+        let delivered = match (error_message(req_id, err), &self.msg_tx) {
+            (Ok(message), Some(msg_tx)) => msg_tx.send(message).is_ok(),
+            _ => false,
+        };
+        if !delivered {
+            error!("Request {} failed: {}", req_id, err);
+        }
+    }
     //----------------------------------------------------------------------------------------------
     /// Establishes a connection to TWS or IB Gateway
     pub fn connect(
@@ -127,8 +285,7 @@ where
             tx.clone(),
             self.disconnect_requested.clone(),
         );
-
-        let mut fields: Vec<String> = Vec::new();
+        reader.set_metrics(self.metrics.clone());
 
         let v_100_prefix = "API\0";
         let v_100_version = format!("v{}..{}", MIN_CLIENT_VER, MAX_CLIENT_VER);
@@ -141,30 +298,34 @@ where
 
         self.send_bytes(bytearray.as_slice())?;
 
-        let mut decoder = Decoder::new(
-            self.wrapper.clone(),
-            rx,
-            self.server_version,
-            self.conn_state.clone(),
-        );
-
-        //An Interactive Broker's developer's note: "sometimes I get news before the server version, thus the loop"
-        while fields.len() != 2 {
-            if fields.len() > 0 {
-                decoder.interpret(fields.as_slice())?;
-            }
-
-            let buf = reader.recv_packet()?;
-
-            if buf.len() > 0 {
-                let (_size, msg, _remaining_messages) = read_msg(buf.as_slice())?;
-
-                fields.clear();
-                fields.extend_from_slice(read_fields(msg.as_ref()).as_slice());
-            } else {
-                fields.clear();
+        let mut decoder = match &self.wrapper {
+            WrapperSlot::Shared(wrapper) => Decoder::new(
+                wrapper.clone(),
+                rx,
+                self.server_version,
+                self.conn_state.clone(),
+            ),
+            WrapperSlot::Exclusive(slot) => Decoder::with_handler(
+                take_handler(slot)?,
+                rx,
+                self.server_version,
+                self.conn_state.clone(),
+            ),
+        };
+        decoder.set_metrics(self.metrics.clone());
+        #[cfg(feature = "tracing")]
+        decoder.set_request_spans(Some(self.request_spans.clone()));
+        let (spare_tx, spare_rx) = channel::<String>();
+        decoder.set_spare_buffers(Some(spare_tx));
+        reader.set_spare_buffers(Some(spare_rx));
+
+        let fields = match read_server_version(&mut decoder, &mut reader) {
+            Ok(fields) => fields,
+            Err(err) => {
+                self.park_handler(decoder);
+                return Err(err);
             }
-        }
+        };
 
         self.server_version = i32::from_ascii(fields.get(0).unwrap().as_bytes()).unwrap();
 
@@ -172,18 +333,30 @@ where
 
         self.conn_time = fields.get(1).unwrap().to_string();
         decoder.server_version = self.server_version;
+        self.msg_tx = Some(tx);
 
         thread::spawn(move || {
             reader.run();
         });
 
+        let slot = match &self.wrapper {
+            WrapperSlot::Shared(_) => None,
+            WrapperSlot::Exclusive(slot) => Some(slot.clone()),
+        };
         thread::spawn(move || {
-            if decoder.run().is_err() {
+            let result = decoder.run();
+            if let (Some(slot), Some(handler)) = (slot, decoder.into_handler()) {
+                park_handler(&slot, handler);
+            }
+            if result.is_err() {
                 panic!("decoder.run() failed!!");
             }
         });
         *self.conn_state.lock().expect(POISONED_MUTEX) = ConnStatus::CONNECTED;
         info!("Connected");
+        if let Some(metrics) = &self.metrics {
+            metrics.connected();
+        }
         self.start_api()?;
         Ok(())
     }
@@ -208,6 +381,37 @@ where
         self.server_version
     }
 
+    //----------------------------------------------------------------------------------------------
+    /// Installs or removes the pre-trade checks that place_order runs before sending an order.
+    /// The gate is shared so that limits and market state can be updated from other threads.
+    pub fn set_risk_gate(&mut self, risk_gate: Option<Arc<RiskGate>>) {
+        self.risk_gate = risk_gate;
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn risk_gate(&self) -> Option<&Arc<RiskGate>> {
+        self.risk_gate.as_ref()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Installs or removes the sink that receives message, byte, error and subscription counts.
+    /// Takes effect for the reader and decoder at the next connect.
+    pub fn set_metrics(&mut self, metrics: Option<Arc<dyn MetricsSink>>) {
+        self.metrics = metrics;
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn metrics(&self) -> Option<&Arc<dyn MetricsSink>> {
+        self.metrics.as_ref()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Spans of the requests still in flight, shared with the decoder
+    #[cfg(feature = "tracing")]
+    pub fn request_spans(&self) -> &Arc<RequestSpans> {
+        &self.request_spans
+    }
+
     //----------------------------------------------------------------------------------------------
     /// Sets server logging level
     pub fn set_server_log_level(&mut self, log_evel: i32) -> Result<(), IBKRApiLibError> {
@@ -451,7 +655,10 @@ where
             msg.push_str(&make_field(&mkt_data_options_str)?);
         }
 
-        self.send_request(msg.as_str())?;
+        self.send_traced(req_id, "req_mkt_data", Some(contract), msg.as_str())?;
+        if !snapshot && !regulatory_snapshot {
+            self.track_subscription(SubscriptionKind::MktData, req_id, true);
+        }
         Ok(())
     }
 
@@ -472,6 +679,8 @@ where
         msg.push_str(&make_field(&req_id)?);
 
         self.send_request(msg.as_str())?;
+        self.close_span(req_id, "cancel_mkt_data");
+        self.track_subscription(SubscriptionKind::MktData, req_id, false);
         Ok(())
     }
 
@@ -553,7 +762,7 @@ where
         msg.push_str(&make_field(&req_id)?);
         msg.push_str(&make_field(&String::from(bbo_exchange))?);
 
-        self.send_request(msg.as_str())?;
+        self.send_traced(req_id, "req_smart_components", None, msg.as_str())?;
         Ok(())
     }
 
@@ -668,7 +877,13 @@ where
             msg.push_str(&make_field(&ignore_size)?);
         }
 
-        self.send_request(msg.as_str())?;
+        self.send_traced(
+            req_id,
+            "req_tick_by_tick_data",
+            Some(contract),
+            msg.as_str(),
+        )?;
+        self.track_subscription(SubscriptionKind::TickByTick, req_id, true);
         Ok(())
     }
 
@@ -702,6 +917,8 @@ where
         msg.push_str(&make_field(&req_id)?);
 
         self.send_request(msg.as_str())?;
+        self.close_span(req_id, "cancel_tick_by_tick_data");
+        self.track_subscription(SubscriptionKind::TickByTick, req_id, false);
         Ok(())
     }
 
@@ -802,7 +1019,12 @@ where
         }
         error!("sending calculate_implied_volatility");
         error!("{}", msg);
-        self.send_request(msg.as_str())?;
+        self.send_traced(
+            req_id,
+            "calculate_implied_volatility",
+            Some(contract),
+            msg.as_str(),
+        )?;
         Ok(())
     }
 
@@ -897,7 +1119,12 @@ where
                 msg.push_str(&make_field(&opt_prc_opt_str)?);
             }
         }
-        self.send_request(msg.as_str())?;
+        self.send_traced(
+            req_id,
+            "calculate_option_price",
+            Some(contract),
+            msg.as_str(),
+        )?;
         Ok(())
     }
 
@@ -935,6 +1162,7 @@ where
         msg.push_str(&make_field(&req_id)?);
 
         self.send_request(msg.as_str())?;
+        self.close_span(req_id, "cancel_calculate_option_price");
         Ok(())
     }
 
@@ -974,6 +1202,7 @@ where
         msg.push_str(&make_field(&req_id)?);
 
         self.send_request(msg.as_str())?;
+        self.close_span(req_id, "cancel_calculate_implied_volatility");
         Ok(())
     }
 
@@ -1082,6 +1311,9 @@ where
         contract: &Contract,
         order: &Order,
     ) -> Result<(), IBKRApiLibError> {
+        if let Some(risk_gate) = &self.risk_gate {
+            risk_gate.check(order_id, contract, order)?;
+        }
         self.check_connected(NO_VALID_ID)?;
 
         if self.server_version() < MIN_SERVER_VER_DELTA_NEUTRAL {
@@ -1945,7 +2177,10 @@ where
             msg.push_str(&make_field_handle_empty(&order.use_price_mgmt_algo)?);
         }
 
-        self.send_request(msg.as_str())?;
+        self.send_traced(order_id, "place_order", Some(contract), msg.as_str())?;
+        if let Some(risk_gate) = &self.risk_gate {
+            risk_gate.record_order(order_id, contract, order);
+        }
         Ok(())
     }
 
@@ -1967,6 +2202,7 @@ where
         msg.push_str(&make_field(&order_id)?);
 
         self.send_request(msg.as_str())?;
+        self.record_span(order_id, "cancel_order");
         Ok(())
     }
 
@@ -2134,6 +2370,7 @@ where
         msg.push_str(&make_field(&String::from(acct_code))?); // srv v9 and above, the account code.This will only be used for FA clients
 
         self.send_request(msg.as_str())?;
+        self.track_subscription(SubscriptionKind::AccountUpdates, NO_VALID_ID, subscribe);
 
         Ok(())
     }
@@ -2172,7 +2409,8 @@ where
         msg.push_str(&make_field(&String::from(group_name))?);
         msg.push_str(&make_field(&String::from(tags))?);
 
-        self.send_request(msg.as_str())?;
+        self.send_traced(req_id, "req_account_summary", None, msg.as_str())?;
+        self.track_subscription(SubscriptionKind::AccountSummary, req_id, true);
         Ok(())
     }
 
@@ -2192,6 +2430,8 @@ where
         msg.push_str(&make_field(&req_id)?);
 
         self.send_request(msg.as_str())?;
+        self.close_span(req_id, "cancel_account_summary");
+        self.track_subscription(SubscriptionKind::AccountSummary, req_id, false);
 
         Ok(())
     }
@@ -2223,6 +2463,7 @@ where
         msg.push_str(&make_field(&version)?);
 
         self.send_request(msg.as_str())?;
+        self.track_subscription(SubscriptionKind::Positions, NO_VALID_ID, true);
 
         Ok(())
     }
@@ -2253,6 +2494,7 @@ where
         msg.push_str(&make_field(&message_id)?);
         msg.push_str(&make_field(&version)?);
         self.send_request(msg.as_str())?;
+        self.track_subscription(SubscriptionKind::Positions, NO_VALID_ID, false);
 
         Ok(())
     }
@@ -2300,7 +2542,8 @@ where
         msg.push_str(&make_field(&String::from(mut_account))?);
         msg.push_str(&make_field(&String::from(mut_model_code))?);
 
-        self.send_request(msg.as_str())?;
+        self.send_traced(req_id, "req_positions_multi", None, msg.as_str())?;
+        self.track_subscription(SubscriptionKind::PositionsMulti, req_id, true);
 
         Ok(())
     }
@@ -2336,6 +2579,8 @@ where
         msg.push_str(&make_field(&mut_req_id)?);
 
         self.send_request(msg.as_str())?;
+        self.close_span(req_id, "cancel_positions_multi");
+        self.track_subscription(SubscriptionKind::PositionsMulti, req_id, false);
         Ok(())
     }
 
@@ -2386,7 +2631,8 @@ where
         msg.push_str(&make_field(&String::from(mut_model_code))?);
         msg.push_str(&make_field(&mut_ledger_and_nlv)?);
 
-        self.send_request(msg.as_str())?;
+        self.send_traced(req_id, "req_account_updates_multi", None, msg.as_str())?;
+        self.track_subscription(SubscriptionKind::AccountUpdatesMulti, req_id, true);
 
         Ok(())
     }
@@ -2421,7 +2667,10 @@ where
         msg.push_str(&make_field(&version)?);
         msg.push_str(&make_field(&mut_req_id)?);
 
-        self.send_request(msg.as_str())
+        self.send_request(msg.as_str())?;
+        self.close_span(req_id, "cancel_account_updates_multi");
+        self.track_subscription(SubscriptionKind::AccountUpdatesMulti, req_id, false);
+        Ok(())
     }
 
     //#########################################################################
@@ -2462,7 +2711,9 @@ where
         msg.push_str(&make_field(&String::from(account))?);
         msg.push_str(&make_field(&String::from(model_code))?);
 
-        self.send_request(msg.as_str())
+        self.send_traced(req_id, "req_pnl", None, msg.as_str())?;
+        self.track_subscription(SubscriptionKind::Pnl, req_id, true);
+        Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
@@ -2492,7 +2743,10 @@ where
         msg.push_str(&make_field(&message_id)?);
         msg.push_str(&make_field(&req_id)?);
 
-        self.send_request(msg.as_str())
+        self.send_request(msg.as_str())?;
+        self.close_span(req_id, "cancel_pnl");
+        self.track_subscription(SubscriptionKind::Pnl, req_id, false);
+        Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
@@ -2534,7 +2788,9 @@ where
         msg.push_str(&make_field(&String::from(model_code))?);
         msg.push_str(&make_field(&con_id)?);
 
-        self.send_request(msg.as_str())
+        self.send_traced(req_id, "req_pnl_single", None, msg.as_str())?;
+        self.track_subscription(SubscriptionKind::PnlSingle, req_id, true);
+        Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
@@ -2564,7 +2820,10 @@ where
         msg.push_str(&make_field(&message_id)?);
         msg.push_str(&make_field(&req_id)?);
 
-        self.send_request(msg.as_str())
+        self.send_request(msg.as_str())?;
+        self.close_span(req_id, "cancel_pnl_single");
+        self.track_subscription(SubscriptionKind::PnlSingle, req_id, false);
+        Ok(())
     }
 
     //#########################################################################
@@ -2608,7 +2867,7 @@ where
         msg.push_str(&make_field(&exec_filter.exchange)?);
         msg.push_str(&make_field(&exec_filter.side)?);
 
-        self.send_request(msg.as_str())
+        self.send_traced(req_id, "req_executions", None, msg.as_str())
     }
 
     //#########################################################################
@@ -2728,7 +2987,7 @@ where
             msg.push_str(&make_field(&contract.sec_id)?);
         }
 
-        self.send_request(msg.as_str())
+        self.send_traced(req_id, "req_contract_details", Some(contract), msg.as_str())
     }
 
     //#########################################################################
@@ -2891,7 +3150,9 @@ where
             let mkt_data_options_str = "";
             msg.push_str(&make_field(&mkt_data_options_str)?);
         }
-        self.send_request(msg.as_str())
+        self.send_traced(req_id, "req_mkt_depth", Some(contract), msg.as_str())?;
+        self.track_subscription(SubscriptionKind::MktDepth, req_id, true);
+        Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
@@ -2935,7 +3196,10 @@ where
             msg.push_str(&make_field(&is_smart_depth)?);
         }
 
-        self.send_request(msg.as_str())
+        self.send_request(msg.as_str())?;
+        self.close_span(req_id, "cancel_mkt_depth");
+        self.track_subscription(SubscriptionKind::MktDepth, req_id, false);
+        Ok(())
     }
 
     //#########################################################################
@@ -2960,6 +3224,7 @@ where
         msg.push_str(&make_field(&all_msgs)?);
 
         self.send_request(msg.as_str())?;
+        self.track_subscription(SubscriptionKind::NewsBulletins, NO_VALID_ID, true);
         Ok(())
     }
 
@@ -2975,6 +3240,7 @@ where
         msg.push_str(&make_field(&message_id)?);
         msg.push_str(&make_field(&version)?);
         self.send_request(msg.as_str())?;
+        self.track_subscription(SubscriptionKind::NewsBulletins, NO_VALID_ID, false);
         Ok(())
     }
 
@@ -3191,7 +3457,14 @@ where
             msg.push_str(&make_field(&chart_options_str)?);
         }
 
-        self.send_request(msg.as_str())?;
+        let opened = self.open_span(req_id, "req_historical_data", Some(contract));
+        if keep_up_to_date {
+            self.keep_span_open(req_id);
+        }
+        self.send_in_span(req_id, opened, msg.as_str())?;
+        if keep_up_to_date {
+            self.track_subscription(SubscriptionKind::HistoricalData, req_id, true);
+        }
         Ok(())
     }
 
@@ -3215,6 +3488,8 @@ where
         msg.push_str(&make_field(&req_id)?);
 
         self.send_request(msg.as_str())?;
+        self.close_span(req_id, "cancel_historical_data");
+        self.track_subscription(SubscriptionKind::HistoricalData, req_id, false);
 
         Ok(())
     }
@@ -3277,7 +3552,7 @@ where
         msg.push_str(&make_field(&String::from(what_to_show))?);
         msg.push_str(&make_field(&format_date)?);
 
-        self.send_request(msg.as_str())?;
+        self.send_traced(req_id, "req_head_time_stamp", Some(contract), msg.as_str())?;
         Ok(())
     }
 
@@ -3310,6 +3585,7 @@ where
         msg.push_str(&make_field(&req_id)?);
 
         self.send_request(msg.as_str())?;
+        self.close_span(req_id, "cancel_head_time_stamp");
         Ok(())
     }
 
@@ -3365,7 +3641,12 @@ where
         msg.push_str(&make_field(&use_rth)?);
         msg.push_str(&make_field(&String::from(time_period))?);
 
-        self.send_request(msg.as_str())?;
+        self.send_traced(
+            ticker_id,
+            "req_histogram_data",
+            Some(contract),
+            msg.as_str(),
+        )?;
         Ok(())
     }
 
@@ -3397,6 +3678,7 @@ where
         msg.push_str(&make_field(&ticker_id)?);
 
         self.send_request(msg.as_str())?;
+        self.close_span(ticker_id, "cancel_histogram_data");
         Ok(())
     }
 
@@ -3473,7 +3755,7 @@ where
 
         msg.push_str(&make_field(&misc_options_string)?);
 
-        self.send_request(msg.as_str())?;
+        self.send_traced(req_id, "req_historical_ticks", Some(contract), msg.as_str())?;
         Ok(())
     }
 
@@ -3582,7 +3864,8 @@ where
         }
         error!("req_scanner_subscription");
         error!("{}", msg);
-        self.send_request(msg.as_str())?;
+        self.send_traced(req_id, "req_scanner_subscription", None, msg.as_str())?;
+        self.track_subscription(SubscriptionKind::ScannerSubscription, req_id, true);
         Ok(())
     }
 
@@ -3606,6 +3889,8 @@ where
         msg.push_str(&make_field(&req_id)?);
 
         self.send_request(msg.as_str())?;
+        self.close_span(req_id, "cancel_scanner_subscription");
+        self.track_subscription(SubscriptionKind::ScannerSubscription, req_id, false);
         Ok(())
     }
 
@@ -3704,7 +3989,8 @@ where
             msg.push_str(&make_field(&real_time_bars_options_str)?);
         }
 
-        self.send_request(msg.as_str())?;
+        self.send_traced(req_id, "req_real_time_bars", Some(contract), msg.as_str())?;
+        self.track_subscription(SubscriptionKind::RealTimeBars, req_id, true);
         Ok(())
     }
 
@@ -3727,6 +4013,8 @@ where
         msg.push_str(&make_field(&req_id)?);
 
         self.send_request(msg.as_str())?;
+        self.close_span(req_id, "cancel_real_time_bars");
+        self.track_subscription(SubscriptionKind::RealTimeBars, req_id, false);
         Ok(())
     }
 
@@ -3824,7 +4112,7 @@ where
             msg.push_str(&make_field(&fund_data_opt_str)?);
         }
 
-        self.send_request(msg.as_str())?;
+        self.send_traced(req_id, "req_fundamental_data", Some(contract), msg.as_str())?;
         Ok(())
     }
 
@@ -3860,6 +4148,7 @@ where
         msg.push_str(&make_field(&req_id)?);
 
         self.send_request(msg.as_str())?;
+        self.close_span(req_id, "cancel_fundamental_data");
         Ok(())
     }
 
@@ -3942,7 +4231,7 @@ where
             msg.push_str(&make_field(&news_article_options_str)?);
         }
 
-        self.send_request(msg.as_str())?;
+        self.send_traced(req_id, "req_news_article", None, msg.as_str())?;
         Ok(())
     }
 
@@ -4004,7 +4293,7 @@ where
             msg.push_str(&make_field(&historical_news_options_str)?);
         }
 
-        self.send_request(msg.as_str())?;
+        self.send_traced(req_id, "req_historical_news", None, msg.as_str())?;
         Ok(())
     }
 
@@ -4385,7 +4674,7 @@ where
         msg.push_str(&make_field(&String::from(underlying_sec_type))?);
         msg.push_str(&make_field(&underlying_con_id)?);
 
-        self.send_request(msg.as_str())?;
+        self.send_traced(req_id, "req_sec_def_opt_params", None, msg.as_str())?;
         Ok(())
     }
 
@@ -4405,7 +4694,7 @@ where
 
         msg.push_str(&make_field(&req_id)?);
 
-        self.send_request(msg.as_str())?;
+        self.send_traced(req_id, "req_soft_dollar_tiers", None, msg.as_str())?;
         Ok(())
     }
 
@@ -4470,7 +4759,7 @@ where
         msg.push_str(&make_field(&req_id)?);
         msg.push_str(&make_field(&String::from(pattern))?);
 
-        self.send_request(msg.as_str())?;
+        self.send_traced(req_id, "req_matching_symbols", None, msg.as_str())?;
         Ok(())
     }
 
@@ -4509,3 +4798,59 @@ where
         }
     }
 }
+
+//==================================================================================================
+/// Reads messages until the one carrying the server version and connection time
+fn read_server_version<T: Wrapper + Sync>(
+    decoder: &mut Decoder<T>,
+    reader: &mut Reader,
+) -> Result<Vec<String>, IBKRApiLibError> {
+    let mut fields: Vec<String> = Vec::new();
+
+    //An Interactive Broker's developer's note: "sometimes I get news before the server version, thus the loop"
+    while fields.len() != 2 {
+        if !fields.is_empty() {
+            decoder.interpret(fields.as_slice())?;
+        }
+
+        let buf = reader.recv_packet()?;
+
+        if !buf.is_empty() {
+            let (_size, msg, _remaining_messages) = read_msg(buf.as_slice())?;
+
+            fields.clear();
+            fields.extend_from_slice(read_fields(msg.as_ref()).as_slice());
+        } else {
+            fields.clear();
+        }
+    }
+    Ok(fields)
+}
+
+//==================================================================================================
+/// Takes the handler out of its slot, waiting for the decoder of the previous connection to
+/// give it back
+fn take_handler<T>(slot: &(Mutex<Option<T>>, Condvar)) -> Result<T, IBKRApiLibError> {
+    let (handler, returned) = slot;
+    let (mut handler, _) = returned
+        .wait_timeout_while(
+            handler.lock().expect(POISONED_MUTEX),
+            HANDLER_RETURN_TIMEOUT,
+            |handler| handler.is_none(),
+        )
+        .expect(POISONED_MUTEX);
+    handler.take().ok_or_else(|| {
+        IBKRApiLibError::ApiError(TwsApiReportableError::new(
+            NO_VALID_ID,
+            TwsError::AlreadyConnected.code().to_string(),
+            "The handler is still in use by the previous connection".to_string(),
+        ))
+    })
+}
+
+//==================================================================================================
+fn park_handler<T>(slot: &(Mutex<Option<T>>, Condvar), handler: T) {
+    let (parked, returned) = slot;
+    *parked.lock().expect(POISONED_MUTEX) = Some(handler);
+    returned.notify_all();
+}
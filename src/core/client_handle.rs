@@ -0,0 +1,202 @@
+//! Cloneable, non-blocking handle for making requests from callbacks
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex, Weak};
+use std::thread;
+use std::thread::JoinHandle;
+
+use crate::core::client::{EClient, POISONED_MUTEX};
+use crate::core::common::{TagValue, TickByTickType, NO_VALID_ID};
+use crate::core::contract::Contract;
+use crate::core::errors::{IBKRApiLibError, TwsApiReportableError, TwsError};
+use crate::core::order::Order;
+use crate::core::wrapper::Wrapper;
+
+type Command<T> = Box<dyn FnOnce(&mut EClient<T>) -> Result<(), IBKRApiLibError> + Send>;
+
+//==================================================================================================
+/// Request queued by a [ClientHandle]
+pub struct ClientCommand<T: Wrapper> {
+    /// Request or order id the command is about; errors are reported with it
+    id: i32,
+    run: Command<T>,
+}
+
+//==================================================================================================
+/// Queues requests for an EClient without blocking, so that they can be made from inside
+/// callbacks while the decoder is dispatching.  Requests run in the order they were queued, by
+/// [CommandQueue::spawn] or [CommandQueue::run_pending].  Since callers do not get the result, a
+/// request that fails is reported to the wrapper's error callback, with the request or order id.
+pub struct ClientHandle<T: Wrapper> {
+    commands: Sender<ClientCommand<T>>,
+}
+
+impl<T: Wrapper> Clone for ClientHandle<T> {
+    fn clone(&self) -> Self {
+        ClientHandle {
+            commands: self.commands.clone(),
+        }
+    }
+}
+
+impl<T> ClientHandle<T>
+where
+    T: Wrapper + Send + Sync + 'static,
+{
+    /// Creates a handle and the queue its requests end up in.  The handle can be given to the
+    /// handler before the client exists.
+    pub fn channel() -> (ClientHandle<T>, CommandQueue<T>) {
+        let (commands, queue) = channel();
+        (ClientHandle { commands }, CommandQueue { commands: queue })
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Queues any request.  Fails only if the queue is gone.
+    pub fn execute<F>(&self, id: i32, run: F) -> Result<(), IBKRApiLibError>
+    where
+        F: FnOnce(&mut EClient<T>) -> Result<(), IBKRApiLibError> + Send + 'static,
+    {
+        self.commands
+            .send(ClientCommand {
+                id,
+                run: Box::new(run),
+            })
+            .map_err(|_| {
+                IBKRApiLibError::ApiError(TwsApiReportableError::new(
+                    id,
+                    TwsError::NotConnected.code().to_string(),
+                    "The client of this handle is gone".to_string(),
+                ))
+            })
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn place_order(
+        &self,
+        order_id: i32,
+        contract: &Contract,
+        order: &Order,
+    ) -> Result<(), IBKRApiLibError> {
+        let contract = contract.clone();
+        let order = order.clone();
+        self.execute(order_id, move |client| {
+            client.place_order(order_id, &contract, &order)
+        })
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn cancel_order(&self, order_id: i32) -> Result<(), IBKRApiLibError> {
+        self.execute(order_id, move |client| client.cancel_order(order_id))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn req_global_cancel(&self) -> Result<(), IBKRApiLibError> {
+        self.execute(NO_VALID_ID, |client| client.req_global_cancel())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn req_ids(&self, num_ids: i32) -> Result<(), IBKRApiLibError> {
+        self.execute(NO_VALID_ID, move |client| client.req_ids(num_ids))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn req_current_time(&self) -> Result<(), IBKRApiLibError> {
+        self.execute(NO_VALID_ID, |client| client.req_current_time())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn req_mkt_data(
+        &self,
+        req_id: i32,
+        contract: &Contract,
+        generic_tick_list: &str,
+        snapshot: bool,
+        regulatory_snapshot: bool,
+        mkt_data_options: Vec<TagValue>,
+    ) -> Result<(), IBKRApiLibError> {
+        let contract = contract.clone();
+        let generic_tick_list = generic_tick_list.to_string();
+        self.execute(req_id, move |client| {
+            client.req_mkt_data(
+                req_id,
+                &contract,
+                &generic_tick_list,
+                snapshot,
+                regulatory_snapshot,
+                mkt_data_options,
+            )
+        })
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn cancel_mkt_data(&self, req_id: i32) -> Result<(), IBKRApiLibError> {
+        self.execute(req_id, move |client| client.cancel_mkt_data(req_id))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn req_tick_by_tick_data(
+        &self,
+        req_id: i32,
+        contract: &Contract,
+        tick_type: TickByTickType,
+        number_of_ticks: i32,
+        ignore_size: bool,
+    ) -> Result<(), IBKRApiLibError> {
+        let contract = contract.clone();
+        self.execute(req_id, move |client| {
+            client.req_tick_by_tick_data(req_id, &contract, tick_type, number_of_ticks, ignore_size)
+        })
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn cancel_tick_by_tick_data(&self, req_id: i32) -> Result<(), IBKRApiLibError> {
+        self.execute(req_id, move |client| {
+            client.cancel_tick_by_tick_data(req_id)
+        })
+    }
+}
+
+//==================================================================================================
+/// Receiving end of the requests queued by [ClientHandle]s
+pub struct CommandQueue<T: Wrapper> {
+    commands: Receiver<ClientCommand<T>>,
+}
+
+impl<T> CommandQueue<T>
+where
+    T: Wrapper + Send + Sync + 'static,
+{
+    /// Runs the requests queued so far on the client, and returns how many ran
+    pub fn run_pending(&self, client: &mut EClient<T>) -> usize {
+        let mut count = 0;
+        while let Ok(command) = self.commands.try_recv() {
+            run(client, command);
+            count += 1;
+        }
+        count
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Runs requests on the client as they are queued, until all handles or the client are
+    /// dropped.  Only a weak reference to the client is kept, so the thread does not keep it alive.
+    pub fn spawn(self, client: &Arc<Mutex<EClient<T>>>) -> JoinHandle<()> {
+        let client: Weak<Mutex<EClient<T>>> = Arc::downgrade(client);
+        thread::spawn(move || {
+            for command in self.commands.iter() {
+                match client.upgrade() {
+                    Some(client) => run(&mut client.lock().expect(POISONED_MUTEX), command),
+                    None => break,
+                }
+            }
+        })
+    }
+}
+
+//==================================================================================================
+fn run<T>(client: &mut EClient<T>, command: ClientCommand<T>)
+where
+    T: Wrapper + Send + Sync + 'static,
+{
+    if let Err(err) = (command.run)(client) {
+        client.report_error(command.id, &err);
+    }
+}
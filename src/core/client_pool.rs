@@ -0,0 +1,367 @@
+//! Several connections to one TWS or Gateway, with requests routed to them by category
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::{Arc, Mutex};
+
+use log::*;
+
+use crate::core::client::{EClient, POISONED_MUTEX};
+use crate::core::common::NO_VALID_ID;
+use crate::core::errors::{IBKRApiLibError, TwsApiReportableError, TwsError};
+use crate::core::event_bus::{Event, EventBus, EventKind};
+
+/// Most clients TWS accepts at once
+pub const MAX_POOL_CONNECTIONS: usize = 32;
+
+/// Only this client id can bind orders entered in TWS with req_auto_open_orders
+const AUTO_OPEN_ORDERS_CLIENT_ID: i32 = 0;
+
+/// Error codes that report the state of TWS's own connections, which every client is told about
+const CONNECTION_STATUS_CODES: [std::ops::RangeInclusive<i32>; 2] = [1100..=1102, 2100..=2199];
+
+//==================================================================================================
+/// Kind of request, used to pick the connection it is sent on
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum RequestCategory {
+    /// Quotes, ticks, depth, bars and historical data
+    MarketData,
+    /// Placing, modifying and cancelling orders, and executions
+    Orders,
+    /// Account values, positions, portfolio and PnL
+    Account,
+}
+
+impl RequestCategory {
+    pub const ALL: [RequestCategory; 3] = [
+        RequestCategory::MarketData,
+        RequestCategory::Orders,
+        RequestCategory::Account,
+    ];
+}
+
+//==================================================================================================
+/// One connection of a [ClientPool] and the requests it takes
+#[derive(Clone, Debug)]
+pub struct PoolConnection {
+    pub client_id: i32,
+    pub categories: Vec<RequestCategory>,
+}
+
+//==================================================================================================
+/// Connections of a [ClientPool].  Every category must be taken by exactly one connection.
+#[derive(Clone, Debug)]
+pub struct ClientPoolConfig {
+    pub host: String,
+    pub port: u32,
+    pub connections: Vec<PoolConnection>,
+    /// Client id set as "Master API client ID" in the TWS API settings.  Its connection receives
+    /// the orders of all clients, so open orders are requested on it.
+    pub master_client_id: Option<i32>,
+    /// Calls req_auto_open_orders on client id 0 after connecting, so that orders entered in TWS
+    /// are bound to it.  Client id 0 must then take the Orders category.
+    pub bind_manual_orders: bool,
+}
+
+impl ClientPoolConfig {
+    pub fn new(host: &str, port: u32) -> Self {
+        ClientPoolConfig {
+            host: host.to_string(),
+            port,
+            connections: vec![],
+            master_client_id: None,
+            bind_manual_orders: false,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn connection(mut self, client_id: i32, categories: &[RequestCategory]) -> Self {
+        self.connections.push(PoolConnection {
+            client_id,
+            categories: categories.to_vec(),
+        });
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn master_client_id(mut self, master_client_id: i32) -> Self {
+        self.master_client_id = Some(master_client_id);
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn bind_manual_orders(mut self, bind_manual_orders: bool) -> Self {
+        self.bind_manual_orders = bind_manual_orders;
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Index of the connection of each category
+    fn routes(&self) -> Result<HashMap<RequestCategory, usize>, IBKRApiLibError> {
+        if self.connections.is_empty() || self.connections.len() > MAX_POOL_CONNECTIONS {
+            return Err(config_error(format!(
+                "A pool needs 1 to {} connections, not {}",
+                MAX_POOL_CONNECTIONS,
+                self.connections.len()
+            )));
+        }
+        let mut client_ids = HashSet::new();
+        let mut routes = HashMap::new();
+        for (index, connection) in self.connections.iter().enumerate() {
+            if !client_ids.insert(connection.client_id) {
+                return Err(config_error(format!(
+                    "Client id {} is used by more than one connection",
+                    connection.client_id
+                )));
+            }
+            for category in connection.categories.iter() {
+                if routes.insert(*category, index).is_some() {
+                    return Err(config_error(format!(
+                        "{:?} requests are taken by more than one connection",
+                        category
+                    )));
+                }
+            }
+        }
+        if let Some(category) = RequestCategory::ALL
+            .iter()
+            .find(|category| !routes.contains_key(category))
+        {
+            return Err(config_error(format!(
+                "No connection takes {:?} requests",
+                category
+            )));
+        }
+        if let Some(master_client_id) = self.master_client_id {
+            if !client_ids.contains(&master_client_id) {
+                return Err(config_error(format!(
+                    "Master client id {} is not one of the connections",
+                    master_client_id
+                )));
+            }
+        }
+        if self.bind_manual_orders
+            && self.connections[routes[&RequestCategory::Orders]].client_id
+                != AUTO_OPEN_ORDERS_CLIENT_ID
+        {
+            return Err(config_error(format!(
+                "Binding manual orders needs client id {} to take Orders requests",
+                AUTO_OPEN_ORDERS_CLIENT_ID
+            )));
+        }
+        Ok(routes)
+    }
+}
+
+//==================================================================================================
+fn config_error(description: String) -> IBKRApiLibError {
+    IBKRApiLibError::ApiError(TwsApiReportableError::new(
+        NO_VALID_ID,
+        TwsError::BadMessage.code().to_string(),
+        format!("Invalid client pool: {}", description),
+    ))
+}
+
+//==================================================================================================
+/// Wrapper of the connection at `index`, see [ClientPool::connection_events]
+fn connection_events(
+    events: &EventBus,
+    config: &ClientPoolConfig,
+    routes: &HashMap<RequestCategory, usize>,
+    index: usize,
+) -> EventBus {
+    let orders = routes[&RequestCategory::Orders] == index;
+    let open_orders = match config.master_client_id {
+        Some(client_id) => config.connections[index].client_id == client_id,
+        None => orders,
+    };
+    events.filtered(move |event| match event.kind() {
+        EventKind::NextValidId | EventKind::ManagedAccounts => orders,
+        EventKind::OpenOrder | EventKind::OpenOrderEnd | EventKind::OrderStatus => open_orders,
+        EventKind::Error => orders || !is_connection_status(event),
+        _ => true,
+    })
+}
+
+//==================================================================================================
+fn is_connection_status(event: &Event) -> bool {
+    match event {
+        Event::Error {
+            req_id, error_code, ..
+        } => {
+            *req_id == NO_VALID_ID
+                && CONNECTION_STATUS_CODES
+                    .iter()
+                    .any(|codes| codes.contains(error_code))
+        }
+        _ => false,
+    }
+}
+
+//==================================================================================================
+/// Connections to one TWS or Gateway with distinct client ids, so that a burst of messages for
+/// one kind of request does not hold up the others.  Requests are routed to a connection by
+/// [RequestCategory], and the events of all connections are published on one [EventBus].
+///
+/// Request ids only need to be unique per connection, but events do not say which connection
+/// they came from, so take them from [ClientPool::next_req_id].  Callbacks that every
+/// connection gets are published once; see [ClientPool::connection_events].
+pub struct ClientPool {
+    config: ClientPoolConfig,
+    events: EventBus,
+    clients: Vec<Arc<Mutex<EClient<EventBus>>>>,
+    routes: HashMap<RequestCategory, usize>,
+    next_req_id: AtomicI32,
+}
+
+impl ClientPool {
+    /// Creates the clients of the connections, without connecting them
+    pub fn new(config: ClientPoolConfig) -> Result<Self, IBKRApiLibError> {
+        let routes = config.routes()?;
+        let events = EventBus::new();
+        let clients = (0..config.connections.len())
+            .map(|index| {
+                let handler = connection_events(&events, &config, &routes, index);
+                Arc::new(Mutex::new(EClient::with_handler(handler)))
+            })
+            .collect();
+        Ok(ClientPool {
+            config,
+            events,
+            clients,
+            routes,
+            next_req_id: AtomicI32::new(1),
+        })
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Connects every client that is not connected yet.  If one fails, the others are
+    /// disconnected again.
+    pub fn connect(&self) -> Result<(), IBKRApiLibError> {
+        for (connection, client) in self.config.connections.iter().zip(self.clients.iter()) {
+            let mut client = client.lock().expect(POISONED_MUTEX);
+            if client.is_connected() {
+                continue;
+            }
+            info!(
+                "Connecting client {} for {:?}",
+                connection.client_id, connection.categories
+            );
+            if let Err(err) =
+                client.connect(&self.config.host, self.config.port, connection.client_id)
+            {
+                drop(client);
+                self.disconnect()?;
+                return Err(err);
+            }
+        }
+        self.bind_manual_orders()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Asks TWS to bind orders entered in it to client id 0, if the config says so
+    pub(crate) fn bind_manual_orders(&self) -> Result<(), IBKRApiLibError> {
+        if !self.config.bind_manual_orders {
+            return Ok(());
+        }
+        self.execute(RequestCategory::Orders, |client| {
+            client.req_auto_open_orders(true)
+        })
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn disconnect(&self) -> Result<(), IBKRApiLibError> {
+        for client in self.clients.iter() {
+            client.lock().expect(POISONED_MUTEX).disconnect()?;
+        }
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn is_connected(&self) -> bool {
+        self.clients
+            .iter()
+            .all(|client| client.lock().expect(POISONED_MUTEX).is_connected())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Events of all connections
+    pub fn events(&self) -> &EventBus {
+        &self.events
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Wrapper the client of a connection publishes its events with.  next_valid_id,
+    /// managed_accounts and errors about the state of TWS's own connections are only published
+    /// for the Orders connection, and open_order, open_order_end and order_status only for
+    /// [ClientPool::open_orders_client], since every connection receives them.
+    pub fn connection_events(&self, client_id: i32) -> Option<EventBus> {
+        let index = self
+            .config
+            .connections
+            .iter()
+            .position(|connection| connection.client_id == client_id)?;
+        Some(connection_events(
+            &self.events,
+            &self.config,
+            &self.routes,
+            index,
+        ))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Request id that no other request made through the pool has
+    pub fn next_req_id(&self) -> i32 {
+        self.next_req_id.fetch_add(1, Ordering::SeqCst)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn config(&self) -> &ClientPoolConfig {
+        &self.config
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Client that requests of a category are sent on
+    pub fn client(&self, category: RequestCategory) -> &Arc<Mutex<EClient<EventBus>>> {
+        &self.clients[self.routes[&category]]
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn client_by_id(&self, client_id: i32) -> Option<&Arc<Mutex<EClient<EventBus>>>> {
+        self.config
+            .connections
+            .iter()
+            .position(|connection| connection.client_id == client_id)
+            .map(|index| &self.clients[index])
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Client that sees the orders of all clients: the master client if there is one, otherwise
+    /// the one taking Orders requests
+    pub fn open_orders_client(&self) -> &Arc<Mutex<EClient<EventBus>>> {
+        self.config
+            .master_client_id
+            .and_then(|client_id| self.client_by_id(client_id))
+            .unwrap_or_else(|| self.client(RequestCategory::Orders))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Makes a request on the client of a category
+    pub fn execute<R, F>(&self, category: RequestCategory, f: F) -> Result<R, IBKRApiLibError>
+    where
+        F: FnOnce(&mut EClient<EventBus>) -> Result<R, IBKRApiLibError>,
+    {
+        f(&mut self.client(category).lock().expect(POISONED_MUTEX))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Requests the open orders of all clients on the master client, or the open orders of the
+    /// Orders client if there is no master client
+    pub fn req_open_orders(&self) -> Result<(), IBKRApiLibError> {
+        let mut client = self.open_orders_client().lock().expect(POISONED_MUTEX);
+        if self.config.master_client_id.is_some() {
+            client.req_all_open_orders()
+        } else {
+            client.req_open_orders()
+        }
+    }
+}
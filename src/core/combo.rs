@@ -0,0 +1,496 @@
+//! Builder for combo (BAG) contracts with con_id resolution of the legs
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::core::client::EClient;
+use crate::core::common::{Right, UNSET_DOUBLE};
+use crate::core::contract::{ComboLeg, Contract, ContractDetails, PositionType};
+use crate::core::contract_cache::ContractCache;
+use crate::core::errors::{IBKRApiLibError, TwsApiReportableError, TwsError};
+use crate::core::order::{Order, OrderComboLeg};
+use crate::core::wrapper::Wrapper;
+
+//==================================================================================================
+fn combo_error(req_id: i32, description: String) -> IBKRApiLibError {
+    IBKRApiLibError::ApiError(TwsApiReportableError::new(
+        req_id,
+        TwsError::BadMessage.code().to_string(),
+        description,
+    ))
+}
+
+//==================================================================================================
+fn lists_exchange(details: &ContractDetails, exchange: &str) -> bool {
+    details.valid_exchanges.is_empty()
+        || details
+            .valid_exchanges
+            .split(',')
+            .any(|valid| valid.trim().eq_ignore_ascii_case(exchange))
+}
+
+//==================================================================================================
+/// Option contract for a leg of a template: the template with expiry, strike and right set
+fn option_leg(template: &Contract, expiry: &str, strike: f64, right: Right) -> Contract {
+    let mut contract = template.clone();
+    contract.con_id = 0;
+    if contract.sec_type.is_empty() {
+        contract.sec_type = "OPT".to_string();
+    }
+    contract.last_trade_date_or_contract_month = expiry.to_string();
+    contract.strike = strike;
+    contract.right = right.to_string();
+    contract.local_symbol = "".to_string();
+    contract
+}
+
+//==================================================================================================
+/// One leg of a combo.  The contract can be fully specified with a con_id or be a spec that is
+/// resolved through contract details.
+#[derive(Clone, Debug)]
+pub struct ComboLegSpec {
+    pub contract: Contract,
+    pub action: String,
+    pub ratio: f64,
+    /// Per leg limit price sent in Order::order_combo_legs, UNSET_DOUBLE if not used
+    pub price: f64,
+}
+
+impl ComboLegSpec {
+    pub fn new(contract: Contract, action: &str, ratio: f64) -> Self {
+        ComboLegSpec {
+            contract,
+            action: action.to_string(),
+            ratio,
+            price: UNSET_DOUBLE,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn price(mut self, price: f64) -> Self {
+        self.price = price;
+        self
+    }
+}
+
+impl fmt::Display for ComboLegSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let contract = &self.contract;
+        write!(
+            f,
+            "{} {} {} {}",
+            self.action, self.ratio, contract.symbol, contract.sec_type
+        )?;
+        if !contract.last_trade_date_or_contract_month.is_empty() {
+            write!(f, " {}", contract.last_trade_date_or_contract_month)?;
+        }
+        if contract.strike != 0.0 && contract.strike != UNSET_DOUBLE {
+            write!(f, " {}", contract.strike)?;
+        }
+        write!(f, "{}", contract.right)
+    }
+}
+
+//==================================================================================================
+/// Builds a BAG contract from leg specs.
+///
+/// Legs without a con_id are resolved through contract details, either from a [ContractCache]
+/// with [ComboBuilder::resolve_from_cache] or by sending requests with
+/// [ComboBuilder::resolve_legs] and forwarding contract_details and contract_details_end.
+/// Request ids are allocated sequentially starting at the id passed to [ComboBuilder::new].
+/// [ComboBuilder::build] checks that the legs share a currency and can be routed to the combo's
+/// exchange.  When no exchange is set the legs' common exchange is used, or SMART if they differ.
+#[derive(Clone, Debug)]
+pub struct ComboBuilder {
+    legs: Vec<ComboLegSpec>,
+    details: Vec<Option<ContractDetails>>,
+    symbol: String,
+    exchange: String,
+    next_req_id: i32,
+    pending: HashMap<i32, (usize, Vec<ContractDetails>)>,
+}
+
+impl ComboBuilder {
+    pub fn new(first_req_id: i32) -> Self {
+        ComboBuilder {
+            legs: vec![],
+            details: vec![],
+            symbol: "".to_string(),
+            exchange: "".to_string(),
+            next_req_id: first_req_id,
+            pending: HashMap::new(),
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn leg(mut self, leg: ComboLegSpec) -> Self {
+        self.legs.push(leg);
+        self.details.push(None);
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn buy(self, contract: &Contract, ratio: f64) -> Self {
+        self.leg(ComboLegSpec::new(contract.clone(), "BUY", ratio))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn sell(self, contract: &Contract, ratio: f64) -> Self {
+        self.leg(ComboLegSpec::new(contract.clone(), "SELL", ratio))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Symbol of the combo, by default the symbols of the legs separated by commas
+    pub fn symbol(mut self, symbol: &str) -> Self {
+        self.symbol = symbol.to_string();
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Exchange the combo is routed to
+    pub fn exchange(mut self, exchange: &str) -> Self {
+        self.exchange = exchange.to_string();
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Buys `long_strike` and sells `short_strike` with the same expiry and right
+    pub fn vertical(
+        self,
+        template: &Contract,
+        expiry: &str,
+        right: Right,
+        long_strike: f64,
+        short_strike: f64,
+    ) -> Self {
+        self.buy(&option_leg(template, expiry, long_strike, right), 1.0)
+            .sell(&option_leg(template, expiry, short_strike, right), 1.0)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Sells the near expiry and buys the far expiry at the same strike
+    pub fn calendar(
+        self,
+        template: &Contract,
+        right: Right,
+        strike: f64,
+        near_expiry: &str,
+        far_expiry: &str,
+    ) -> Self {
+        self.sell(&option_leg(template, near_expiry, strike, right), 1.0)
+            .buy(&option_leg(template, far_expiry, strike, right), 1.0)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Buys a call and a put at the same strike and expiry
+    pub fn straddle(self, template: &Contract, expiry: &str, strike: f64) -> Self {
+        self.buy(&option_leg(template, expiry, strike, Right::Call), 1.0)
+            .buy(&option_leg(template, expiry, strike, Right::Put), 1.0)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Buys a put and a call at different strikes with the same expiry
+    pub fn strangle(
+        self,
+        template: &Contract,
+        expiry: &str,
+        put_strike: f64,
+        call_strike: f64,
+    ) -> Self {
+        self.buy(&option_leg(template, expiry, put_strike, Right::Put), 1.0)
+            .buy(&option_leg(template, expiry, call_strike, Right::Call), 1.0)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Buys one `lower`, sells two `middle` and buys one `upper`
+    pub fn butterfly(
+        self,
+        template: &Contract,
+        expiry: &str,
+        right: Right,
+        lower: f64,
+        middle: f64,
+        upper: f64,
+    ) -> Self {
+        self.buy(&option_leg(template, expiry, lower, right), 1.0)
+            .sell(&option_leg(template, expiry, middle, right), 2.0)
+            .buy(&option_leg(template, expiry, upper, right), 1.0)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Buys `stock_ratio` shares against one option, e.g. a buy-write with a SELL call or a
+    /// protective put with a BUY put
+    pub fn stock_vs_option(
+        self,
+        stock: &Contract,
+        stock_ratio: f64,
+        option: &Contract,
+        option_action: &str,
+    ) -> Self {
+        self.buy(stock, stock_ratio)
+            .leg(ComboLegSpec::new(option.clone(), option_action, 1.0))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn legs(&self) -> &[ComboLegSpec] {
+        self.legs.as_slice()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Sets the per leg limit price of a leg
+    pub fn set_leg_price(&mut self, index: usize, price: f64) {
+        if let Some(leg) = self.legs.get_mut(index) {
+            leg.price = price;
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Resolves a leg with known contract details
+    pub fn resolve_leg(&mut self, index: usize, details: &ContractDetails) {
+        if let Some(resolved) = self.details.get_mut(index) {
+            *resolved = Some(details.clone());
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn is_resolved(&self) -> bool {
+        self.details.iter().all(Option::is_some)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Resolves the legs that are in the cache.  A leg whose spec matches several contracts
+    /// is an error.
+    pub fn resolve_from_cache(&mut self, cache: &ContractCache) -> Result<(), IBKRApiLibError> {
+        for (leg, resolved) in self.legs.iter().zip(self.details.iter_mut()) {
+            if resolved.is_none() {
+                *resolved = cache.lookup(&leg.contract)?;
+            }
+        }
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Allocates a contract details request for every unresolved leg that has none yet and
+    /// returns the requests to send
+    pub fn contract_detail_requests(&mut self) -> Vec<(i32, Contract)> {
+        let mut requests = vec![];
+        for (index, leg) in self.legs.iter().enumerate() {
+            if self.details[index].is_some()
+                || self.pending.values().any(|(pending, _)| *pending == index)
+            {
+                continue;
+            }
+            let req_id = self.next_req_id;
+            self.next_req_id += 1;
+            self.pending.insert(req_id, (index, vec![]));
+            requests.push((req_id, leg.contract.clone()));
+        }
+        requests
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Requests contract details for the unresolved legs
+    pub fn resolve_legs<T: Wrapper + Send + Sync + 'static>(
+        &mut self,
+        client: &mut EClient<T>,
+    ) -> Result<(), IBKRApiLibError> {
+        for (req_id, contract) in self.contract_detail_requests() {
+            client.req_contract_details(req_id, &contract)?;
+        }
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn owns_request(&self, req_id: i32) -> bool {
+        self.pending.contains_key(&req_id)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Forwarded from Wrapper::contract_details
+    pub fn contract_details(&mut self, req_id: i32, contract_details: &ContractDetails) -> bool {
+        match self.pending.get_mut(&req_id) {
+            Some((_, details)) => {
+                details.push(contract_details.clone());
+                true
+            }
+            None => false,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Forwarded from Wrapper::contract_details_end.  It is an error if the leg's spec matched
+    /// no contract or more than one.
+    pub fn contract_details_end(&mut self, req_id: i32) -> Result<bool, IBKRApiLibError> {
+        let (index, mut details) = match self.pending.remove(&req_id) {
+            Some(pending) => pending,
+            None => return Ok(false),
+        };
+        match details.len() {
+            1 => {
+                self.details[index] = details.pop();
+                Ok(true)
+            }
+            0 => Err(combo_error(
+                req_id,
+                format!("No contract found for combo leg {}", self.legs[index]),
+            )),
+            _ => Err(combo_error(
+                req_id,
+                format!(
+                    "Ambiguous combo leg {}: matches con_ids {}",
+                    self.legs[index],
+                    details
+                        .iter()
+                        .map(|details| details.contract.con_id.to_string())
+                        .collect::<Vec<String>>()
+                        .join(",")
+                ),
+            )),
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Builds the BAG contract once every leg is resolved
+    pub fn build(&self) -> Result<Contract, IBKRApiLibError> {
+        if self.legs.len() < 2 {
+            return Err(combo_error(
+                -1,
+                "A combo needs at least two legs".to_string(),
+            ));
+        }
+        let mut resolved = vec![];
+        for (leg, details) in self.legs.iter().zip(self.details.iter()) {
+            let details = details
+                .as_ref()
+                .ok_or_else(|| combo_error(-1, format!("Combo leg {} is not resolved", leg)))?;
+            if leg.ratio <= 0.0 {
+                return Err(combo_error(
+                    -1,
+                    format!("Combo leg {} has a ratio that is not positive", leg),
+                ));
+            }
+            if !["BUY", "SELL", "SSHORT"].contains(&leg.action.to_uppercase().as_str()) {
+                return Err(combo_error(
+                    -1,
+                    format!("Combo leg {} has an invalid action", leg),
+                ));
+            }
+            if resolved
+                .iter()
+                .any(|(_, other): &(&ComboLegSpec, &ContractDetails)| {
+                    other.contract.con_id == details.contract.con_id
+                })
+            {
+                return Err(combo_error(
+                    -1,
+                    format!(
+                        "Combo leg {} uses con_id {} more than once",
+                        leg, details.contract.con_id
+                    ),
+                ));
+            }
+            resolved.push((leg, details));
+        }
+
+        let currency = resolved[0].1.contract.currency.clone();
+        if let Some((leg, details)) = resolved
+            .iter()
+            .find(|(_, details)| details.contract.currency != currency)
+        {
+            return Err(combo_error(
+                -1,
+                format!(
+                    "Combo leg {} trades in {} but the combo in {}",
+                    leg, details.contract.currency, currency
+                ),
+            ));
+        }
+
+        let leg_exchange = |leg: &ComboLegSpec, details: &ContractDetails| {
+            if leg.contract.exchange.is_empty() {
+                details.contract.exchange.clone()
+            } else {
+                leg.contract.exchange.clone()
+            }
+        };
+        let exchange = if !self.exchange.is_empty() {
+            self.exchange.clone()
+        } else {
+            let first = leg_exchange(resolved[0].0, resolved[0].1);
+            if resolved
+                .iter()
+                .all(|(leg, details)| leg_exchange(leg, details) == first)
+            {
+                first
+            } else {
+                "SMART".to_string()
+            }
+        };
+        if let Some((leg, _)) = resolved
+            .iter()
+            .find(|(_, details)| !lists_exchange(details, exchange.as_str()))
+        {
+            return Err(combo_error(
+                -1,
+                format!("Combo leg {} cannot be routed to {}", leg, exchange),
+            ));
+        }
+
+        let symbol = if self.symbol.is_empty() {
+            let mut symbols: Vec<String> = vec![];
+            for (_, details) in resolved.iter() {
+                if !symbols.contains(&details.contract.symbol) {
+                    symbols.push(details.contract.symbol.clone());
+                }
+            }
+            symbols.join(",")
+        } else {
+            self.symbol.clone()
+        };
+
+        let combo_legs = resolved
+            .iter()
+            .map(|(leg, details)| {
+                let routed = leg_exchange(leg, details);
+                ComboLeg {
+                    con_id: details.contract.con_id,
+                    ratio: leg.ratio,
+                    action: leg.action.to_uppercase(),
+                    exchange: if exchange == "SMART" && !routed.is_empty() {
+                        routed
+                    } else {
+                        exchange.clone()
+                    },
+                    open_close: PositionType::SamePos,
+                    ..Default::default()
+                }
+            })
+            .collect();
+
+        Ok(Contract {
+            symbol,
+            sec_type: "BAG".to_string(),
+            currency,
+            exchange,
+            combo_legs,
+            ..Default::default()
+        })
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Per leg prices for Order::order_combo_legs, empty if no leg has a price
+    pub fn order_combo_legs(&self) -> Vec<OrderComboLeg> {
+        if self.legs.iter().all(|leg| leg.price == UNSET_DOUBLE) {
+            return vec![];
+        }
+        self.legs
+            .iter()
+            .map(|leg| OrderComboLeg::new(leg.price))
+            .collect()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Sets Order::order_combo_legs from the leg prices
+    pub fn apply_leg_prices(&self, order: &mut Order) {
+        order.order_combo_legs = self.order_combo_legs();
+    }
+}
@@ -15,6 +15,11 @@ pub const UNSET_INTEGER: i32 = std::i32::MAX;
 pub const UNSET_DOUBLE: f64 = 1.7976931348623157E308_f64;
 pub const UNSET_LONG: i64 = std::i64::MAX;
 
+/// Order types whose aux_price is a trigger price rather than an offset
+pub const TRIGGER_ORDER_TYPES: [&str; 4] = ["STP", "STP LMT", "MIT", "LIT"];
+/// Order statuses after which TWS sends no further updates for an order
+pub const TERMINAL_ORDER_STATUSES: [&str; 4] = ["Filled", "Cancelled", "ApiCancelled", "Inactive"];
+
 //==================================================================================================
 /// Tick types
 #[repr(i32)]
@@ -849,7 +854,7 @@ impl Display for HedgeType {
 
 //==================================================================================================
 #[repr(i32)]
-#[derive(Serialize, Deserialize, Clone, FromPrimitive, Debug)]
+#[derive(Serialize, Deserialize, Clone, Copy, FromPrimitive, Debug, PartialEq, Eq, Hash)]
 pub enum Right {
     None,
     Put,
@@ -866,6 +871,17 @@ impl Display for Right {
     }
 }
 
+impl Right {
+    /// Converts the right string used in Contract (P, PUT, C or CALL) to a Right
+    pub fn from_contract_right(right: &str) -> Self {
+        match right.to_uppercase().as_str() {
+            "P" | "PUT" => Right::Put,
+            "C" | "CALL" => Right::Call,
+            _ => Right::None,
+        }
+    }
+}
+
 //==================================================================================================
 #[repr(i32)]
 #[derive(Serialize, Deserialize, Clone, FromPrimitive, Debug)]
@@ -1364,7 +1380,7 @@ impl Display for MarketDataTypeEnum {
 
 //==================================================================================================
 #[repr(i32)]
-#[derive(Serialize, Deserialize, Clone, FromPrimitive, Debug)]
+#[derive(Serialize, Deserialize, Clone, Copy, FromPrimitive, Debug, PartialEq, Eq)]
 pub enum Method {
     None,
     EqualQuantity,
@@ -1385,6 +1401,21 @@ impl Display for Method {
     }
 }
 
+impl Method {
+    /// Converts the method name used in FA group XML (e.g. EqualQuantity) to a Method.
+    /// An empty name is Method::None.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim() {
+            "" => Some(Method::None),
+            "EqualQuantity" => Some(Method::EqualQuantity),
+            "AvailableEquity" => Some(Method::AvailableEquity),
+            "NetLiq" => Some(Method::NetLiq),
+            "PctChange" => Some(Method::PctChange),
+            _ => None,
+        }
+    }
+}
+
 //==================================================================================================
 #[repr(i32)]
 #[derive(Serialize, Deserialize, Clone, FromPrimitive, Debug)]
@@ -0,0 +1,388 @@
+//! Text expressions for order conditions.
+//!
+//! Conditions are written one after the other, joined by `and` or `or`:
+//!
+//! ```text
+//! price(265598@SMART, last) >= 150 and time >= 20261020 14:00:00 or margin <= 30
+//! ```
+//!
+//! The supported conditions are
+//! * `price(con_id@exchange[, trigger_method]) op price`
+//! * `time op yyyymmdd hh:mm:ss [time zone]`
+//! * `margin op percent`
+//! * `volume(con_id@exchange) op volume`
+//! * `percent_change(con_id@exchange) op percent`
+//! * `execution(symbol@exchange, sec_type)`
+//!
+//! where op is `>=` or `<=`; `>` and `<` are accepted as the same operators.  The trigger
+//! method is one of the [TriggerMethod] names in snake case and is left out when it is the
+//! default.  Rendering writes the operators as `>=` and `<=`, so rendering parsed text gives
+//! back the same text when it was written in that form.
+use num_traits::FromPrimitive;
+
+use crate::core::errors::{IBKRApiLibError, TwsApiReportableError, TwsError};
+use crate::core::order_condition::{
+    ExecutionCondition, MarginCondition, OperatorCondition, OrderCondition, OrderConditionEnum,
+    PercentChangeCondition, PriceCondition, TimeCondition, TriggerMethod, VolumeCondition,
+};
+
+//==================================================================================================
+fn expression_error(description: String) -> IBKRApiLibError {
+    IBKRApiLibError::ApiError(TwsApiReportableError::new(
+        -1,
+        TwsError::BadMessage.code().to_string(),
+        description,
+    ))
+}
+
+//==================================================================================================
+fn trigger_method_name(trigger_method: TriggerMethod) -> &'static str {
+    match trigger_method {
+        TriggerMethod::Default => "default",
+        TriggerMethod::DoubleBidAsk => "double_bid_ask",
+        TriggerMethod::Last => "last",
+        TriggerMethod::DoubleLast => "double_last",
+        TriggerMethod::BidAsk => "bid_ask",
+        TriggerMethod::NA1 => "na1",
+        TriggerMethod::NA2 => "na2",
+        TriggerMethod::LastBidAsk => "last_bid_ask",
+        TriggerMethod::MidPoint => "mid_point",
+    }
+}
+
+//==================================================================================================
+fn parse_trigger_method(name: &str) -> Option<TriggerMethod> {
+    (0..9)
+        .filter_map(TriggerMethod::from_i32)
+        .find(|trigger_method| trigger_method_name(*trigger_method) == name.to_lowercase())
+}
+
+//==================================================================================================
+fn order_condition(condition: &OrderConditionEnum) -> &OrderCondition {
+    match condition {
+        OrderConditionEnum::Price(price) => {
+            &price.contract_condition.operator_condition.order_condition
+        }
+        OrderConditionEnum::Time(time) => &time.operator_condition.order_condition,
+        OrderConditionEnum::Margin(margin) => &margin.operator_condition.order_condition,
+        OrderConditionEnum::Execution(execution) => &execution.order_condition,
+        OrderConditionEnum::Volume(volume) => {
+            &volume.contract_condition.operator_condition.order_condition
+        }
+        OrderConditionEnum::PercentChange(change) => {
+            &change.contract_condition.operator_condition.order_condition
+        }
+    }
+}
+
+//==================================================================================================
+fn order_condition_mut(condition: &mut OrderConditionEnum) -> &mut OrderCondition {
+    match condition {
+        OrderConditionEnum::Price(price) => {
+            &mut price.contract_condition.operator_condition.order_condition
+        }
+        OrderConditionEnum::Time(time) => &mut time.operator_condition.order_condition,
+        OrderConditionEnum::Margin(margin) => &mut margin.operator_condition.order_condition,
+        OrderConditionEnum::Execution(execution) => &mut execution.order_condition,
+        OrderConditionEnum::Volume(volume) => {
+            &mut volume.contract_condition.operator_condition.order_condition
+        }
+        OrderConditionEnum::PercentChange(change) => {
+            &mut change.contract_condition.operator_condition.order_condition
+        }
+    }
+}
+
+//==================================================================================================
+/// Splits "name(arguments)" into the lower cased name and the arguments
+fn split_call(text: &str) -> Result<(String, Vec<&str>), IBKRApiLibError> {
+    match text.find('(') {
+        None => Ok((text.trim().to_lowercase(), vec![])),
+        Some(open) => {
+            let arguments = text[open + 1..]
+                .trim_end()
+                .strip_suffix(')')
+                .ok_or_else(|| expression_error(format!("Missing ')' in condition '{}'", text)))?;
+            Ok((
+                text[..open].trim().to_lowercase(),
+                arguments.split(',').map(str::trim).collect(),
+            ))
+        }
+    }
+}
+
+//==================================================================================================
+/// Parses "con_id@exchange"
+fn parse_contract(text: &str, condition: &str) -> Result<(i32, String), IBKRApiLibError> {
+    let invalid = || {
+        expression_error(format!(
+            "Expected con_id@exchange instead of '{}' in condition '{}'",
+            text, condition
+        ))
+    };
+    let (con_id, exchange) = text.split_once('@').ok_or_else(invalid)?;
+    let con_id = con_id.trim().parse::<i32>().map_err(|_| invalid())?;
+    if exchange.trim().is_empty() {
+        return Err(invalid());
+    }
+    Ok((con_id, exchange.trim().to_string()))
+}
+
+//==================================================================================================
+fn parse_number<T: std::str::FromStr>(text: &str, condition: &str) -> Result<T, IBKRApiLibError> {
+    text.parse::<T>().map_err(|_| {
+        expression_error(format!(
+            "Invalid value '{}' in condition '{}'",
+            text, condition
+        ))
+    })
+}
+
+//==================================================================================================
+/// Position and length of the first comparison operator outside parentheses
+fn find_operator(text: &str) -> Option<(usize, usize)> {
+    let mut depth = 0;
+    let bytes = text.as_bytes();
+    for (index, byte) in bytes.iter().enumerate() {
+        match byte {
+            b'(' => depth += 1,
+            b')' => depth -= 1,
+            b'>' | b'<' if depth == 0 => {
+                let length = if bytes.get(index + 1) == Some(&b'=') {
+                    2
+                } else {
+                    1
+                };
+                return Some((index, length));
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+//==================================================================================================
+/// Parses a single condition, without a connector
+pub fn parse_condition(text: &str) -> Result<OrderConditionEnum, IBKRApiLibError> {
+    let text = text.trim();
+    let (head, operator, value) = match find_operator(text) {
+        Some((index, length)) => (
+            &text[..index],
+            Some(&text[index..index + length]),
+            text[index + length..].trim(),
+        ),
+        None => (text, None, ""),
+    };
+    let (name, arguments) = split_call(head)?;
+    let wrong_arguments = || {
+        expression_error(format!(
+            "Wrong arguments for {} in condition '{}'",
+            name, text
+        ))
+    };
+
+    if name == "execution" {
+        if operator.is_some() || arguments.len() != 2 {
+            return Err(wrong_arguments());
+        }
+        let (symbol, exchange) = arguments[0].split_once('@').ok_or_else(wrong_arguments)?;
+        return Ok(OrderConditionEnum::Execution(ExecutionCondition::new(
+            arguments[1].to_string(),
+            exchange.trim().to_string(),
+            symbol.trim().to_string(),
+        )));
+    }
+
+    let mut operator_condition = OperatorCondition::default();
+    operator_condition
+        .set_value_from_string(operator.ok_or_else(|| {
+            expression_error(format!("Missing operator in condition '{}'", text))
+        })?)?;
+    let is_more = operator_condition.is_more;
+    if value.is_empty() {
+        return Err(expression_error(format!(
+            "Missing value in condition '{}'",
+            text
+        )));
+    }
+
+    match name.as_str() {
+        "price" => {
+            if arguments.is_empty() || arguments.len() > 2 {
+                return Err(wrong_arguments());
+            }
+            let (con_id, exchange) = parse_contract(arguments[0], text)?;
+            let trigger_method = match arguments.get(1) {
+                Some(method) => parse_trigger_method(method).ok_or_else(|| {
+                    expression_error(format!(
+                        "Unknown trigger method '{}' in condition '{}'",
+                        method, text
+                    ))
+                })?,
+                None => TriggerMethod::Default,
+            };
+            Ok(OrderConditionEnum::Price(PriceCondition::new(
+                trigger_method,
+                con_id,
+                exchange.as_str(),
+                is_more,
+                parse_number(value, text)?,
+            )))
+        }
+        "volume" | "percent_change" => {
+            if arguments.len() != 1 {
+                return Err(wrong_arguments());
+            }
+            let (con_id, exchange) = parse_contract(arguments[0], text)?;
+            if name == "volume" {
+                Ok(OrderConditionEnum::Volume(VolumeCondition::new(
+                    con_id,
+                    exchange.as_str(),
+                    is_more,
+                    parse_number(value, text)?,
+                )))
+            } else {
+                Ok(OrderConditionEnum::PercentChange(
+                    PercentChangeCondition::new(
+                        con_id,
+                        exchange,
+                        is_more,
+                        parse_number(value, text)?,
+                    ),
+                ))
+            }
+        }
+        "time" | "margin" => {
+            if !arguments.is_empty() {
+                return Err(wrong_arguments());
+            }
+            if name == "time" {
+                Ok(OrderConditionEnum::Time(TimeCondition::new(
+                    is_more,
+                    value.split_whitespace().collect::<Vec<&str>>().join(" "),
+                )))
+            } else {
+                Ok(OrderConditionEnum::Margin(MarginCondition::new(
+                    is_more,
+                    parse_number(value, text)?,
+                )))
+            }
+        }
+        _ => Err(expression_error(format!(
+            "Unknown condition '{}' in '{}'",
+            name, text
+        ))),
+    }
+}
+
+//==================================================================================================
+/// Parses conditions joined by `and` / `or`.  The connector after each condition is stored in
+/// its is_conjunction_connection, the last condition is left as `or`.
+pub fn parse_conditions(text: &str) -> Result<Vec<OrderConditionEnum>, IBKRApiLibError> {
+    let mut conditions = vec![];
+    let mut words: Vec<&str> = vec![];
+    let mut depth = 0;
+    for word in text.split_whitespace() {
+        let is_connector = word.eq_ignore_ascii_case("and") || word.eq_ignore_ascii_case("or");
+        if depth == 0 && is_connector {
+            if words.is_empty() {
+                return Err(expression_error(format!(
+                    "Missing condition before '{}' in '{}'",
+                    word, text
+                )));
+            }
+            let mut condition = parse_condition(words.join(" ").as_str())?;
+            order_condition_mut(&mut condition).is_conjunction_connection =
+                word.eq_ignore_ascii_case("and");
+            conditions.push(condition);
+            words.clear();
+            continue;
+        }
+        depth += word.matches('(').count() as i32 - word.matches(')').count() as i32;
+        words.push(word);
+    }
+    if words.is_empty() {
+        if conditions.is_empty() {
+            return Ok(conditions);
+        }
+        return Err(expression_error(format!(
+            "Missing condition at the end of '{}'",
+            text
+        )));
+    }
+    conditions.push(parse_condition(words.join(" ").as_str())?);
+    Ok(conditions)
+}
+
+//==================================================================================================
+/// Renders a single condition, without a connector
+pub fn condition_to_string(condition: &OrderConditionEnum) -> String {
+    match condition {
+        OrderConditionEnum::Price(price) => {
+            let contract = &price.contract_condition;
+            let trigger_method = match price.trigger_method {
+                TriggerMethod::Default => "".to_string(),
+                method => format!(", {}", trigger_method_name(method)),
+            };
+            format!(
+                "price({}@{}{}) {} {}",
+                contract.con_id,
+                contract.exchange,
+                trigger_method,
+                contract.operator_condition.value_to_string(),
+                price.price
+            )
+        }
+        OrderConditionEnum::Time(time) => format!(
+            "time {} {}",
+            time.operator_condition.value_to_string(),
+            time.time
+        ),
+        OrderConditionEnum::Margin(margin) => format!(
+            "margin {} {}",
+            margin.operator_condition.value_to_string(),
+            margin.percent
+        ),
+        OrderConditionEnum::Execution(execution) => format!(
+            "execution({}@{}, {})",
+            execution.symbol, execution.exchange, execution.sec_type
+        ),
+        OrderConditionEnum::Volume(volume) => format!(
+            "volume({}@{}) {} {}",
+            volume.contract_condition.con_id,
+            volume.contract_condition.exchange,
+            volume
+                .contract_condition
+                .operator_condition
+                .value_to_string(),
+            volume.volume
+        ),
+        OrderConditionEnum::PercentChange(change) => format!(
+            "percent_change({}@{}) {} {}",
+            change.contract_condition.con_id,
+            change.contract_condition.exchange,
+            change
+                .contract_condition
+                .operator_condition
+                .value_to_string(),
+            change.change_percent
+        ),
+    }
+}
+
+//==================================================================================================
+/// Renders conditions as an expression that [parse_conditions] reads back
+pub fn conditions_to_string(conditions: &[OrderConditionEnum]) -> String {
+    let mut text = String::new();
+    for (index, condition) in conditions.iter().enumerate() {
+        if index > 0 {
+            if order_condition(&conditions[index - 1]).is_conjunction_connection {
+                text.push_str(" and ");
+            } else {
+                text.push_str(" or ");
+            }
+        }
+        text.push_str(condition_to_string(condition).as_str());
+    }
+    text
+}
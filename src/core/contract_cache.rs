@@ -0,0 +1,343 @@
+//! Cache of contract details with expiry and on-disk persistence
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use log::*;
+use serde::{Deserialize, Serialize};
+
+use crate::core::client::EClient;
+use crate::core::common::UNSET_DOUBLE;
+use crate::core::contract::{Contract, ContractDetails};
+use crate::core::errors::{IBKRApiLibError, TwsApiReportableError, TwsError};
+use crate::core::wrapper::Wrapper;
+
+/// Version of the cache file layout, bumped when it changes incompatibly
+const CACHE_FILE_VERSION: i32 = 1;
+
+//==================================================================================================
+fn now_secs() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+//==================================================================================================
+/// Normalized key for the fields of a contract that identify it when no con_id is known:
+/// symbol, sec_type, exchange, currency and expiry, plus strike, right, multiplier, trading
+/// class and local symbol when they are set.  Text is trimmed and upper cased.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ContractSpec(String);
+
+impl ContractSpec {
+    pub fn from_contract(contract: &Contract) -> Self {
+        let normalize = |value: &str| value.trim().to_uppercase();
+        let strike = if contract.strike == 0.0 || contract.strike == UNSET_DOUBLE {
+            "".to_string()
+        } else {
+            format!("{}", contract.strike)
+        };
+        ContractSpec(
+            [
+                normalize(contract.symbol.as_str()),
+                normalize(contract.sec_type.as_str()),
+                normalize(contract.exchange.as_str()),
+                normalize(contract.currency.as_str()),
+                normalize(contract.last_trade_date_or_contract_month.as_str()),
+                strike,
+                normalize(contract.right.as_str()),
+                normalize(contract.multiplier.as_str()),
+                normalize(contract.trading_class.as_str()),
+                normalize(contract.local_symbol.as_str()),
+            ]
+            .join("|"),
+        )
+    }
+}
+
+impl fmt::Display for ContractSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+//==================================================================================================
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct CachedDetails {
+    details: ContractDetails,
+    fetched_at: i64,
+}
+
+//==================================================================================================
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct CachedSpec {
+    con_ids: Vec<i32>,
+    fetched_at: i64,
+}
+
+//==================================================================================================
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct CacheFile {
+    version: i32,
+    details: Vec<CachedDetails>,
+    specs: Vec<(ContractSpec, CachedSpec)>,
+}
+
+//==================================================================================================
+/// Cache of ContractDetails keyed by con_id and by [ContractSpec].
+///
+/// Entries older than the time to live are ignored.  Use [ContractCache::req_contract_details]
+/// instead of calling the client directly and forward contract_details, contract_details_end and
+/// error so the results of requests that went to TWS are added to the cache.
+/// [ContractCache::lookup] reports a spec that resolved to several contracts as an error rather
+/// than picking one.
+#[derive(Debug)]
+pub struct ContractCache {
+    ttl: Duration,
+    details: HashMap<i32, CachedDetails>,
+    specs: HashMap<ContractSpec, CachedSpec>,
+    pending: HashMap<i32, (Contract, Vec<ContractDetails>)>,
+}
+
+impl ContractCache {
+    pub fn new(ttl: Duration) -> Self {
+        ContractCache {
+            ttl,
+            details: HashMap::new(),
+            specs: HashMap::new(),
+            pending: HashMap::new(),
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Loads a cache written by [ContractCache::save].  A missing file gives an empty cache.
+    pub fn load<P: AsRef<Path>>(path: P, ttl: Duration) -> Result<Self, IBKRApiLibError> {
+        let mut cache = ContractCache::new(ttl);
+        let contents = match fs::read_to_string(path.as_ref()) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(cache),
+            Err(err) => return Err(err.into()),
+        };
+        let file: CacheFile = serde_json::from_str(contents.as_str()).map_err(io::Error::from)?;
+        if file.version != CACHE_FILE_VERSION {
+            warn!(
+                "Ignoring contract cache {} with version {}, expected {}",
+                path.as_ref().display(),
+                file.version,
+                CACHE_FILE_VERSION
+            );
+            return Ok(cache);
+        }
+        for cached in file.details {
+            cache.details.insert(cached.details.contract.con_id, cached);
+        }
+        cache.specs = file.specs.into_iter().collect();
+        Ok(cache)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Writes the cache to a file, replacing it atomically
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), IBKRApiLibError> {
+        let file = CacheFile {
+            version: CACHE_FILE_VERSION,
+            details: self.details.values().cloned().collect(),
+            specs: self
+                .specs
+                .iter()
+                .map(|(spec, cached)| (spec.clone(), cached.clone()))
+                .collect(),
+        };
+        let contents = serde_json::to_string(&file).map_err(io::Error::from)?;
+        let tmp_path = path.as_ref().with_extension("tmp");
+        fs::write(&tmp_path, contents)?;
+        fs::rename(&tmp_path, path.as_ref())?;
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn set_ttl(&mut self, ttl: Duration) {
+        self.ttl = ttl;
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn is_fresh(&self, fetched_at: i64) -> bool {
+        now_secs() - fetched_at < self.ttl.as_secs() as i64
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Fresh details for a con_id
+    pub fn get(&self, con_id: i32) -> Option<&ContractDetails> {
+        self.details
+            .get(&con_id)
+            .filter(|cached| self.is_fresh(cached.fetched_at))
+            .map(|cached| &cached.details)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Fresh details for every contract a request for `contract` returned, looked up by con_id
+    /// when it is set and by spec otherwise.  None if nothing fresh is cached.
+    pub fn lookup_all(&self, contract: &Contract) -> Option<Vec<ContractDetails>> {
+        if contract.con_id != 0 {
+            return self
+                .get(contract.con_id)
+                .map(|details| vec![details.clone()]);
+        }
+        let cached = self
+            .specs
+            .get(&ContractSpec::from_contract(contract))
+            .filter(|cached| self.is_fresh(cached.fetched_at))?;
+        cached
+            .con_ids
+            .iter()
+            .map(|con_id| self.get(*con_id).cloned())
+            .collect()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Fresh details for a single contract.  It is an error if the contract's spec matched more
+    /// than one contract; add fields such as exchange, expiry or trading class to narrow it down.
+    pub fn lookup(&self, contract: &Contract) -> Result<Option<ContractDetails>, IBKRApiLibError> {
+        match self.lookup_all(contract) {
+            None => Ok(None),
+            Some(mut matches) if matches.len() == 1 => Ok(matches.pop()),
+            Some(matches) => Err(IBKRApiLibError::ApiError(TwsApiReportableError::new(
+                -1,
+                TwsError::BadMessage.code().to_string(),
+                format!(
+                    "Ambiguous contract {}: matches con_ids {}",
+                    ContractSpec::from_contract(contract),
+                    matches
+                        .iter()
+                        .map(|details| details.contract.con_id.to_string())
+                        .collect::<Vec<String>>()
+                        .join(",")
+                ),
+            ))),
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Adds the details returned for a request for `contract`
+    pub fn insert(&mut self, contract: &Contract, details: Vec<ContractDetails>) {
+        let fetched_at = now_secs();
+        let con_ids = details
+            .iter()
+            .map(|details| details.contract.con_id)
+            .collect::<Vec<i32>>();
+        for details in details {
+            self.details.insert(
+                details.contract.con_id,
+                CachedDetails {
+                    details,
+                    fetched_at,
+                },
+            );
+        }
+        if contract.con_id == 0 && !con_ids.is_empty() {
+            self.specs.insert(
+                ContractSpec::from_contract(contract),
+                CachedSpec {
+                    con_ids,
+                    fetched_at,
+                },
+            );
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Drops expired entries
+    pub fn purge_expired(&mut self) {
+        let now = now_secs();
+        let ttl = self.ttl.as_secs() as i64;
+        self.details
+            .retain(|_, cached| now - cached.fetched_at < ttl);
+        self.specs.retain(|_, cached| now - cached.fetched_at < ttl);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn len(&self) -> usize {
+        self.details.len()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn is_empty(&self) -> bool {
+        self.details.is_empty()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Drop-in replacement for EClient::req_contract_details.  Fresh details are delivered to
+    /// `wrapper` right away, with contract_details followed by contract_details_end, the way TWS
+    /// would answer; otherwise the request is sent and its results are cached when
+    /// contract_details_end is forwarded.  A cached spec that matched several contracts delivers
+    /// all of them, as TWS did.
+    pub fn req_contract_details<T, W>(
+        &mut self,
+        client: &mut EClient<T>,
+        wrapper: &mut W,
+        req_id: i32,
+        contract: &Contract,
+    ) -> Result<(), IBKRApiLibError>
+    where
+        T: Wrapper + Send + Sync + 'static,
+        W: Wrapper + ?Sized,
+    {
+        if let Some(matches) = self.lookup_all(contract) {
+            for details in matches {
+                wrapper.contract_details(req_id, details);
+            }
+            wrapper.contract_details_end(req_id);
+            return Ok(());
+        }
+        self.track(req_id, contract);
+        let result = client.req_contract_details(req_id, contract);
+        if result.is_err() {
+            self.pending.remove(&req_id);
+        }
+        result
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Caches the results of a request that was sent directly through the client
+    pub fn track(&mut self, req_id: i32, contract: &Contract) {
+        self.pending.insert(req_id, (contract.clone(), vec![]));
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Forwarded from Wrapper::contract_details
+    pub fn contract_details(&mut self, req_id: i32, contract_details: &ContractDetails) -> bool {
+        match self.pending.get_mut(&req_id) {
+            Some((_, details)) => {
+                details.push(contract_details.clone());
+                true
+            }
+            None => false,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Forwarded from Wrapper::contract_details_end
+    pub fn contract_details_end(&mut self, req_id: i32) -> bool {
+        match self.pending.remove(&req_id) {
+            Some((contract, details)) => {
+                self.insert(&contract, details);
+                true
+            }
+            None => false,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Forwarded from Wrapper::error.  A request that failed is no longer waited for.
+    pub fn error(&mut self, req_id: i32) -> bool {
+        self.pending.remove(&req_id).is_some()
+    }
+}
@@ -3,11 +3,13 @@ use std::collections::HashSet;
 
 use std::marker::Sync;
 use std::ops::Deref;
+use std::ops::DerefMut;
 use std::slice::Iter;
 use std::str::FromStr;
 use std::string::ToString;
-use std::sync::mpsc::Receiver;
-use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{Receiver, Sender};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Instant;
 
 use bigdecimal::BigDecimal;
 use float_cmp::*;
@@ -25,9 +27,12 @@ use crate::core::common::{
 use crate::core::contract::{Contract, ContractDescription, ContractDetails, DeltaNeutralContract};
 use crate::core::errors::{IBKRApiLibError, TwsError};
 use crate::core::execution::Execution;
-use crate::core::messages::{read_fields, IncomingMessageIds};
+use crate::core::messages::{with_fields, IncomingMessageIds};
+use crate::core::metrics::MetricsSink;
 use crate::core::order::{Order, OrderState, SoftDollarTier};
 use crate::core::order_decoder::OrderDecoder;
+#[cfg(feature = "tracing")]
+use crate::core::request_spans::RequestSpans;
 use crate::core::scanner::ScanData;
 use crate::core::server_versions::{
     MIN_SERVER_VER_AGG_GROUP, MIN_SERVER_VER_FRACTIONAL_POSITIONS, MIN_SERVER_VER_LAST_LIQUIDITY,
@@ -39,71 +44,163 @@ use crate::core::server_versions::{
     MIN_SERVER_VER_SYNT_REALTIME_BARS, MIN_SERVER_VER_UNDERLYING_INFO,
     MIN_SERVER_VER_UNREALIZED_PNL,
 };
+use crate::core::tick_parsers::{DividendInfo, FundamentalRatios, RtVolume};
 use crate::core::wrapper::Wrapper;
 
 const WRAPPER_POISONED_MUTEX: &str = "Wrapper mutex was poisoned";
 //==================================================================================================
-pub fn decode_i32(iter: &mut Iter<String>) -> Result<i32, IBKRApiLibError> {
+/// Fields can be decoded from borrowed slices of a message, or from owned Strings
+pub fn decode_i32<S: AsRef<str>>(
+    iter: &mut impl Iterator<Item = S>,
+) -> Result<i32, IBKRApiLibError> {
     let next = iter.next();
 
-    let val: i32 = next.unwrap().parse().unwrap_or(0);
+    let val: i32 = next.unwrap().as_ref().parse().unwrap_or(0);
     Ok(val)
 }
 
 //==================================================================================================
-pub fn decode_i32_show_unset(iter: &mut Iter<String>) -> Result<i32, IBKRApiLibError> {
+pub fn decode_i32_show_unset<S: AsRef<str>>(
+    iter: &mut impl Iterator<Item = S>,
+) -> Result<i32, IBKRApiLibError> {
     let next = iter.next();
     //info!("{:?}", next);
-    let retval: i32 = next.unwrap().parse().unwrap_or(0);
+    let retval: i32 = next.unwrap().as_ref().parse().unwrap_or(0);
     Ok(if retval == 0 { UNSET_INTEGER } else { retval })
 }
 
 //==================================================================================================
-pub fn decode_i64(iter: &mut Iter<String>) -> Result<i64, IBKRApiLibError> {
+pub fn decode_i64<S: AsRef<str>>(
+    iter: &mut impl Iterator<Item = S>,
+) -> Result<i64, IBKRApiLibError> {
     let next = iter.next();
     //info!("{:?}", next);
-    let val: i64 = next.unwrap().parse().unwrap_or(0);
+    let val: i64 = next.unwrap().as_ref().parse().unwrap_or(0);
     Ok(val)
 }
 
 //==================================================================================================
-pub fn decode_f64(iter: &mut Iter<String>) -> Result<f64, IBKRApiLibError> {
+pub fn decode_f64<S: AsRef<str>>(
+    iter: &mut impl Iterator<Item = S>,
+) -> Result<f64, IBKRApiLibError> {
     let next = iter.next();
     //info!("{:?}", next);
-    let val = next.unwrap().parse().unwrap_or(0.0);
+    let val = next.unwrap().as_ref().parse().unwrap_or(0.0);
     Ok(val)
 }
 
 //==================================================================================================
-pub fn decode_f64_show_unset(iter: &mut Iter<String>) -> Result<f64, IBKRApiLibError> {
+pub fn decode_f64_show_unset<S: AsRef<str>>(
+    iter: &mut impl Iterator<Item = S>,
+) -> Result<f64, IBKRApiLibError> {
     let next = iter.next();
     //info!("{:?}", next);
-    let retval: f64 = next.unwrap().parse().unwrap_or(0.0);
+    let retval: f64 = next.unwrap().as_ref().parse().unwrap_or(0.0);
     Ok(if retval == 0.0 { UNSET_DOUBLE } else { retval })
 }
 
 //==================================================================================================
-pub fn decode_string(iter: &mut Iter<String>) -> Result<String, IBKRApiLibError> {
+pub fn decode_string<S: AsRef<str>>(
+    iter: &mut impl Iterator<Item = S>,
+) -> Result<String, IBKRApiLibError> {
     let next = iter.next();
     //info!("{:?}", next);
-    let val = next.unwrap().parse().unwrap_or("".to_string());
+    let val = next.unwrap().as_ref().to_string();
     Ok(val)
 }
 
 //==================================================================================================
-pub fn decode_bool(iter: &mut Iter<String>) -> Result<bool, IBKRApiLibError> {
+/// Borrows a string field rather than copying it, for fields that are only passed on to the wrapper
+pub fn decode_str<'a>(
+    iter: &mut impl Iterator<Item = &'a &'a str>,
+) -> Result<&'a str, IBKRApiLibError> {
+    let next = iter.next();
+    Ok(next.unwrap())
+}
+
+//==================================================================================================
+/// Runs a decode method that reads borrowed fields, such as Condition::decode or
+/// OrderDecoder::decode_open, on owned fields, and advances `fields_iter` past the fields it read.
+/// Those methods took `Iter<String>` before fields were borrowed from the reader's buffer.
+pub fn with_borrowed_fields<R>(
+    fields_iter: &mut Iter<String>,
+    decode: impl FnOnce(&mut Iter<&str>) -> R,
+) -> R {
+    let fields = fields_iter
+        .as_slice()
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<&str>>();
+    let mut borrowed = fields.iter();
+    let result = decode(&mut borrowed);
+    let read = fields.len() - borrowed.len();
+    if read > 0 {
+        fields_iter.nth(read - 1);
+    }
+    result
+}
+
+//==================================================================================================
+pub fn decode_bool<S: AsRef<str>>(
+    iter: &mut impl Iterator<Item = S>,
+) -> Result<bool, IBKRApiLibError> {
     let next = iter.next();
     //info!("{:?}", next);
-    let retval: i32 = next.unwrap_or(&"0".to_string()).parse().unwrap_or(0);
+    let retval: i32 = next
+        .as_ref()
+        .map_or("0", |next| next.as_ref())
+        .parse()
+        .unwrap_or(0);
     Ok(retval != 0)
 }
 
+//==================================================================================================
+/// How the decoder reaches the wrapper
+enum Dispatch<T> {
+    /// Locked for every callback, so it can be shared with other threads
+    Shared(Arc<Mutex<T>>),
+    /// Owned by the decoder, so callbacks need no lock
+    Exclusive(T),
+}
+
+//==================================================================================================
+/// Access to the wrapper for one callback
+pub(crate) enum WrapperGuard<'a, T> {
+    Locked(MutexGuard<'a, T>),
+    Exclusive(&'a mut T),
+}
+
+impl<'a, T> Deref for WrapperGuard<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        match self {
+            WrapperGuard::Locked(guard) => guard,
+            WrapperGuard::Exclusive(wrapper) => wrapper,
+        }
+    }
+}
+
+impl<'a, T> DerefMut for WrapperGuard<'a, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        match self {
+            WrapperGuard::Locked(guard) => guard,
+            WrapperGuard::Exclusive(wrapper) => wrapper,
+        }
+    }
+}
+
 //==================================================================================================
 pub struct Decoder<T: Wrapper> {
     msg_queue: Receiver<String>,
-    pub wrapper: Arc<Mutex<T>>,
+    dispatch: Dispatch<T>,
     pub server_version: i32,
     conn_state: Arc<Mutex<ConnStatus>>,
+    metrics: Option<Arc<dyn MetricsSink>>,
+    /// Where messages go once decoded, to be reused by the reader
+    spare_buffers: Option<Sender<String>>,
+    #[cfg(feature = "tracing")]
+    request_spans: Option<Arc<RequestSpans>>,
 }
 
 impl<T> Decoder<T>
@@ -115,23 +212,130 @@ where
         msg_queue: Receiver<String>,
         server_version: i32,
         conn_state: Arc<Mutex<ConnStatus>>,
+    ) -> Self {
+        Decoder::with_dispatch(
+            Dispatch::Shared(the_wrapper),
+            msg_queue,
+            server_version,
+            conn_state,
+        )
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Creates a decoder that owns the handler, so that callbacks are made without locking.  The
+    /// handler can make requests through a [ClientHandle](crate::core::client_handle::ClientHandle).
+    pub fn with_handler(
+        handler: T,
+        msg_queue: Receiver<String>,
+        server_version: i32,
+        conn_state: Arc<Mutex<ConnStatus>>,
+    ) -> Self {
+        Decoder::with_dispatch(
+            Dispatch::Exclusive(handler),
+            msg_queue,
+            server_version,
+            conn_state,
+        )
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn with_dispatch(
+        dispatch: Dispatch<T>,
+        msg_queue: Receiver<String>,
+        server_version: i32,
+        conn_state: Arc<Mutex<ConnStatus>>,
     ) -> Self {
         Decoder {
-            wrapper: the_wrapper,
+            dispatch,
             msg_queue: msg_queue,
             server_version,
             conn_state,
+            metrics: None,
+            spare_buffers: None,
+            #[cfg(feature = "tracing")]
+            request_spans: None,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Gives back the handler of a decoder created with with_handler
+    pub fn into_handler(self) -> Option<T> {
+        match self.dispatch {
+            Dispatch::Shared(_) => None,
+            Dispatch::Exclusive(handler) => Some(handler),
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub(crate) fn wrapper(&mut self) -> WrapperGuard<'_, T> {
+        match &mut self.dispatch {
+            Dispatch::Shared(wrapper) => {
+                WrapperGuard::Locked(wrapper.lock().expect(WRAPPER_POISONED_MUTEX))
+            }
+            Dispatch::Exclusive(handler) => WrapperGuard::Exclusive(handler),
         }
     }
 
+    //----------------------------------------------------------------------------------------------
+    pub fn set_metrics(&mut self, metrics: Option<Arc<dyn MetricsSink>>) {
+        self.metrics = metrics;
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Sends decoded messages back to the reader, so that their Strings are reused rather than
+    /// allocated for every message
+    pub fn set_spare_buffers(&mut self, spare_buffers: Option<Sender<String>>) {
+        self.spare_buffers = spare_buffers;
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Sets the spans that messages are traced in, shared with the client that sends the requests
+    #[cfg(feature = "tracing")]
+    pub fn set_request_spans(&mut self, request_spans: Option<Arc<RequestSpans>>) {
+        self.request_spans = request_spans;
+    }
+
     //----------------------------------------------------------------------------------------------
     pub fn interpret(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+        let fields: Vec<&str> = fields.iter().map(String::as_str).collect();
+        self.interpret_fields(&fields)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Decodes a message as received from TWS, without its size prefix.  The fields are borrowed
+    /// from the message, and for all but the longest messages are kept on the stack.
+    pub fn interpret_message(&mut self, msg: &str) -> Result<(), IBKRApiLibError> {
+        with_fields(msg, |fields| self.interpret_fields(fields))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn interpret_fields(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         if fields.is_empty() {
             return Ok(());
         }
 
-        let msg_id = i32::from_str(fields.get(0).unwrap().as_str())?;
+        let msg_id = i32::from_str(fields[0])?;
+
+        #[cfg(feature = "tracing")]
+        {
+            let received = self
+                .request_spans
+                .as_ref()
+                .and_then(|spans| spans.received(msg_id, fields, self.server_version));
+            if let Some(received) = received {
+                let result = received.span().in_scope(|| self.dispatch(msg_id, fields));
+                if let Some(spans) = &self.request_spans {
+                    spans.handled(received);
+                }
+                return result;
+            }
+        }
+
+        self.dispatch(msg_id, fields)
+    }
 
+    //----------------------------------------------------------------------------------------------
+    fn dispatch(&mut self, msg_id: i32, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         match FromPrimitive::from_i32(msg_id) {
             Some(IncomingMessageIds::TickPrice) => self.process_tick_price(fields)?,
             Some(IncomingMessageIds::AccountSummary) => self.process_account_summary(fields)?,
@@ -273,7 +477,7 @@ where
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_tick_price(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_tick_price(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -297,15 +501,12 @@ where
         if self.server_version >= MIN_SERVER_VER_PRE_OPEN_BID_ASK {
             tick_arrtibute.pre_open = attr_mask & 4 != 0;
         }
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .tick_price(
-                req_id,
-                FromPrimitive::from_i32(tick_type).unwrap(),
-                price,
-                tick_arrtibute,
-            );
+        self.wrapper().tick_price(
+            req_id,
+            FromPrimitive::from_i32(tick_type).unwrap(),
+            price,
+            tick_arrtibute,
+        );
 
         // process ver 2 fields
 
@@ -320,16 +521,13 @@ where
         };
 
         if size_tick_type as i32 != TickType::NotSet as i32 {
-            self.wrapper
-                .lock()
-                .expect(WRAPPER_POISONED_MUTEX)
-                .tick_size(req_id, size_tick_type, size);
+            self.wrapper().tick_size(req_id, size_tick_type, size);
         }
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_tick_string(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_tick_string(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -341,19 +539,55 @@ where
         let tick_type: i32 = decode_i32(&mut fields_itr)?;
         let value = decode_string(&mut fields_itr)?;
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .tick_string(
-                req_id,
-                FromPrimitive::from_i32(tick_type).unwrap(),
-                value.as_ref(),
-            );
+        let tick_type: TickType = FromPrimitive::from_i32(tick_type).unwrap();
+        // composite payloads are delivered through their own callbacks when they parse cleanly
+        if self.process_composite_tick_string(req_id, tick_type, value.as_ref()) {
+            return Ok(());
+        }
+
+        self.wrapper()
+            .tick_string(req_id, tick_type, value.as_ref());
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_account_summary(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    /// Parses the tick_string payloads that carry structured data and hands them to the typed
+    /// wrapper callbacks.  Returns false if the tick type is not a composite one or the payload
+    /// could not be parsed, in which case the raw string should be delivered instead.
+    fn process_composite_tick_string(
+        &mut self,
+        req_id: i32,
+        tick_type: TickType,
+        value: &str,
+    ) -> bool {
+        let result = match tick_type {
+            TickType::RtVolume | TickType::RtTrdVolume => {
+                value.parse::<RtVolume>().map(|rt_volume| {
+                    self.wrapper()
+                        .tick_rt_volume(req_id, tick_type, rt_volume, value)
+                })
+            }
+            TickType::FundamentalRatios => value.parse::<FundamentalRatios>().map(|ratios| {
+                self.wrapper()
+                    .tick_fundamental_ratios(req_id, ratios, value)
+            }),
+            TickType::IbDividends => value
+                .parse::<DividendInfo>()
+                .map(|dividends| self.wrapper().tick_dividends(req_id, dividends, value)),
+            _ => return false,
+        };
+
+        match result {
+            Ok(()) => true,
+            Err(err) => {
+                warn!("Could not parse {} tick string: {}", tick_type, err);
+                false
+            }
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn process_account_summary(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -361,21 +595,18 @@ where
         //throw away version
         fields_itr.next();
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .account_summary(
-                decode_i32(&mut fields_itr)?,
-                decode_string(&mut fields_itr)?.as_ref(),
-                decode_string(&mut fields_itr)?.as_ref(),
-                decode_string(&mut fields_itr)?.as_ref(),
-                decode_string(&mut fields_itr)?.as_ref(),
-            );
+        self.wrapper().account_summary(
+            decode_i32(&mut fields_itr)?,
+            decode_string(&mut fields_itr)?.as_ref(),
+            decode_string(&mut fields_itr)?.as_ref(),
+            decode_string(&mut fields_itr)?.as_ref(),
+            decode_string(&mut fields_itr)?.as_ref(),
+        );
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_account_summary_end(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_account_summary_end(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -383,15 +614,13 @@ where
         //throw away version
         fields_itr.next();
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
+        self.wrapper()
             .account_summary_end(decode_i32(&mut fields_itr)?);
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_account_update_multi(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_account_update_multi(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -406,25 +635,19 @@ where
         let value = decode_string(&mut fields_itr)?;
         let currency = decode_string(&mut fields_itr)?;
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .account_update_multi(
-                req_id,
-                account.as_ref(),
-                model_code.as_ref(),
-                key.as_ref(),
-                value.as_ref(),
-                currency.as_ref(),
-            );
+        self.wrapper().account_update_multi(
+            req_id,
+            account.as_ref(),
+            model_code.as_ref(),
+            key.as_ref(),
+            value.as_ref(),
+            currency.as_ref(),
+        );
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_account_update_multi_end(
-        &mut self,
-        fields: &[String],
-    ) -> Result<(), IBKRApiLibError> {
+    fn process_account_update_multi_end(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -434,15 +657,12 @@ where
 
         let req_id: i32 = decode_i32(&mut fields_itr)?;
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .account_update_multi_end(req_id);
+        self.wrapper().account_update_multi_end(req_id);
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_account_download_end(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_account_download_end(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -450,15 +670,13 @@ where
         //throw away version
         fields_itr.next();
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
+        self.wrapper()
             .account_download_end(decode_string(&mut fields_itr)?.as_ref());
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_account_update_time(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_account_update_time(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -466,15 +684,13 @@ where
         //throw away version
         fields_itr.next();
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
+        self.wrapper()
             .update_account_time(decode_string(&mut fields_itr)?.as_ref());
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_account_value(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_account_value(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -482,20 +698,17 @@ where
         //throw away version
         fields_itr.next();
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .update_account_value(
-                decode_string(&mut fields_itr)?.as_ref(),
-                decode_string(&mut fields_itr)?.as_ref(),
-                decode_string(&mut fields_itr)?.as_ref(),
-                decode_string(&mut fields_itr)?.as_ref(),
-            );
+        self.wrapper().update_account_value(
+            decode_string(&mut fields_itr)?.as_ref(),
+            decode_string(&mut fields_itr)?.as_ref(),
+            decode_string(&mut fields_itr)?.as_ref(),
+            decode_string(&mut fields_itr)?.as_ref(),
+        );
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_bond_contract_data(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_bond_contract_data(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -566,15 +779,13 @@ where
             contract.market_rule_ids = decode_string(&mut fields_itr)?;
         }
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
+        self.wrapper()
             .bond_contract_details(req_id, contract.clone());
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_commission_report(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_commission_report(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
         //throw away message_id
         fields_itr.next();
@@ -592,15 +803,12 @@ where
 
         commission_report.yield_redemption_date = decode_string(&mut fields_itr)?;
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .commission_report(commission_report);
+        self.wrapper().commission_report(commission_report);
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_completed_order(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_completed_order(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -620,28 +828,22 @@ where
 
         order_decoder.decode_completed(&mut fields_itr)?;
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .completed_order(contract, order, order_state);
+        self.wrapper().completed_order(contract, order, order_state);
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_complete_orders_end(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_complete_orders_end(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
         fields_itr.next();
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .completed_orders_end();
+        self.wrapper().completed_orders_end();
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_contract_details(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_contract_details(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -725,15 +927,12 @@ where
             contract.real_expiration_date = decode_string(&mut fields_itr)?;
         }
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .contract_details(req_id, contract.clone());
+        self.wrapper().contract_details(req_id, contract.clone());
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_contract_details_end(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_contract_details_end(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -743,15 +942,12 @@ where
 
         let req_id = decode_i32(&mut fields_itr)?;
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .contract_details_end(req_id);
+        self.wrapper().contract_details_end(req_id);
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_current_time(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_current_time(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -759,18 +955,12 @@ where
         //throw away version
         fields_itr.next();
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .current_time(decode_i64(&mut fields_itr)?);
+        self.wrapper().current_time(decode_i64(&mut fields_itr)?);
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_delta_neutral_validation(
-        &mut self,
-        fields: &[String],
-    ) -> Result<(), IBKRApiLibError> {
+    fn process_delta_neutral_validation(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -786,15 +976,13 @@ where
         delta_neutral_contract.delta = decode_f64(&mut fields_itr)?;
         delta_neutral_contract.price = decode_f64(&mut fields_itr)?;
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
+        self.wrapper()
             .delta_neutral_validation(req_id, delta_neutral_contract);
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_display_group_list(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_display_group_list(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -806,15 +994,12 @@ where
 
         let groups = decode_string(&mut fields_itr)?;
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .display_group_list(req_id, groups.as_ref());
+        self.wrapper().display_group_list(req_id, groups.as_ref());
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_display_group_updated(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_display_group_updated(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -826,13 +1011,11 @@ where
 
         let contract_info = decode_string(&mut fields_itr)?;
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
+        self.wrapper()
             .display_group_updated(req_id, contract_info.as_ref());
         Ok(())
     }
-    fn process_error_message(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_error_message(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         print!("{:?}", fields);
         let mut fields_itr = fields.iter();
 
@@ -841,16 +1024,18 @@ where
         //throw away version
         fields_itr.next();
 
-        self.wrapper.lock().expect(WRAPPER_POISONED_MUTEX).error(
-            decode_i32(&mut fields_itr)?,
-            decode_i32(&mut fields_itr)?,
-            decode_string(&mut fields_itr)?.as_ref(),
-        );
+        let req_id = decode_i32(&mut fields_itr)?;
+        let error_code = decode_i32(&mut fields_itr)?;
+        if let Some(metrics) = &self.metrics {
+            metrics.error_received(req_id, error_code);
+        }
+        self.wrapper()
+            .error(req_id, error_code, decode_string(&mut fields_itr)?.as_ref());
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_execution_data(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_execution_data(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -920,7 +1105,7 @@ where
         if version >= 9 {
             execution.ev_rule = decode_string(&mut fields_itr)?;
 
-            let tmp_ev_mult = (&mut fields_itr).peekable().peek().unwrap().as_str();
+            let tmp_ev_mult = **(&mut fields_itr).peekable().peek().unwrap();
             if tmp_ev_mult != "" {
                 execution.ev_multiplier = decode_f64(&mut fields_itr)?;
             } else {
@@ -935,15 +1120,12 @@ where
             execution.last_liquidity = decode_i32(&mut fields_itr)?;
         }
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .exec_details(req_id, contract, execution);
+        self.wrapper().exec_details(req_id, contract, execution);
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_execution_data_end(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_execution_data_end(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -953,15 +1135,12 @@ where
 
         let req_id = decode_i32(&mut fields_itr)?;
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .exec_details_end(req_id);
+        self.wrapper().exec_details_end(req_id);
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_family_codes(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_family_codes(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -976,15 +1155,12 @@ where
             family_codes.push(fam_code);
         }
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .family_codes(family_codes);
+        self.wrapper().family_codes(family_codes);
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_fundamental_data(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_fundamental_data(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -994,15 +1170,12 @@ where
 
         let req_id = decode_i32(&mut fields_itr)?;
         let data = decode_string(&mut fields_itr)?;
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .fundamental_data(req_id, data.as_ref());
+        self.wrapper().fundamental_data(req_id, data.as_ref());
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_head_timestamp(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_head_timestamp(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -1010,15 +1183,12 @@ where
 
         let req_id = decode_i32(&mut fields_itr)?;
         let timestamp = decode_string(&mut fields_itr)?;
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .fundamental_data(req_id, timestamp.as_ref());
+        self.wrapper().fundamental_data(req_id, timestamp.as_ref());
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_histogram_data(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_histogram_data(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -1035,15 +1205,12 @@ where
             histogram.push(data_point);
         }
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .histogram_data(req_id, histogram);
+        self.wrapper().histogram_data(req_id, histogram);
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_historical_data(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_historical_data(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
         //throw away message_id
         fields_itr.next();
@@ -1080,22 +1247,17 @@ where
 
             bar.bar_count = decode_i32(&mut fields_itr)?; // ver 3 field
 
-            self.wrapper
-                .lock()
-                .expect(WRAPPER_POISONED_MUTEX)
-                .historical_data(req_id, bar);
+            self.wrapper().historical_data(req_id, bar);
         }
 
         // send end of dataset marker
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
+        self.wrapper()
             .historical_data_end(req_id, start_date.as_ref(), end_date.as_ref());
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_historical_data_update(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_historical_data_update(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -1112,15 +1274,12 @@ where
         bar.low = decode_f64(&mut fields_itr)?;
         bar.average = decode_f64(&mut fields_itr)?;
         bar.volume = decode_i64(&mut fields_itr)?;
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .historical_data_update(req_id, bar);
+        self.wrapper().historical_data_update(req_id, bar);
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_historical_news(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_historical_news(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -1131,21 +1290,18 @@ where
         let provider_code = decode_string(&mut fields_itr)?;
         let article_id = decode_string(&mut fields_itr)?;
         let headline = decode_string(&mut fields_itr)?;
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .historical_news(
-                req_id,
-                time.as_ref(),
-                provider_code.as_ref(),
-                article_id.as_ref(),
-                headline.as_ref(),
-            );
+        self.wrapper().historical_news(
+            req_id,
+            time.as_ref(),
+            provider_code.as_ref(),
+            article_id.as_ref(),
+            headline.as_ref(),
+        );
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_historical_news_end(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_historical_news_end(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -1154,15 +1310,12 @@ where
         let req_id = decode_i32(&mut fields_itr)?;
         let has_more = decode_bool(&mut fields_itr)?;
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .historical_news_end(req_id, has_more);
+        self.wrapper().historical_news_end(req_id, has_more);
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_historical_ticks(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_historical_ticks(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -1184,18 +1337,12 @@ where
 
         let done = decode_bool(&mut fields_itr)?;
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .historical_ticks(req_id, ticks, done);
+        self.wrapper().historical_ticks(req_id, ticks, done);
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_historical_ticks_bid_ask(
-        &mut self,
-        fields: &[String],
-    ) -> Result<(), IBKRApiLibError> {
+    fn process_historical_ticks_bid_ask(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -1223,15 +1370,12 @@ where
 
         let done = decode_bool(&mut fields_itr)?;
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .historical_ticks_bid_ask(req_id, ticks, done);
+        self.wrapper().historical_ticks_bid_ask(req_id, ticks, done);
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_historical_ticks_last(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_historical_ticks_last(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -1259,15 +1403,12 @@ where
 
         let done = decode_bool(&mut fields_itr)?;
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .historical_ticks_last(req_id, ticks, done);
+        self.wrapper().historical_ticks_last(req_id, ticks, done);
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_managed_accounts(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_managed_accounts(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -1277,16 +1418,13 @@ where
 
         let accounts_list = decode_string(&mut fields_itr)?;
         info!("calling managed_accounts");
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .managed_accounts(accounts_list.as_ref());
+        self.wrapper().managed_accounts(accounts_list.as_ref());
         info!("finished calling managed_accounts");
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_market_data_type(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_market_data_type(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
         //throw away message_id
         fields_itr.next();
@@ -1294,15 +1432,12 @@ where
         fields_itr.next();
         let req_id = decode_i32(&mut fields_itr)?;
         let market_data_type = decode_i32(&mut fields_itr)?;
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .market_data_type(req_id, market_data_type);
+        self.wrapper().market_data_type(req_id, market_data_type);
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_market_depth(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_market_depth(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
         //throw away message_id
         fields_itr.next();
@@ -1317,15 +1452,13 @@ where
         let price = decode_f64(&mut fields_itr)?;
         let size = decode_i32(&mut fields_itr)?;
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
+        self.wrapper()
             .update_mkt_depth(req_id, position, operation, side, price, size);
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_market_depth_l2(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_market_depth_l2(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -1336,7 +1469,7 @@ where
         let req_id = decode_i32(&mut fields_itr)?;
 
         let position = decode_i32(&mut fields_itr)?;
-        let market_maker = decode_string(&mut fields_itr)?;
+        let market_maker = decode_str(&mut fields_itr)?;
         let operation = decode_i32(&mut fields_itr)?;
         let side = decode_i32(&mut fields_itr)?;
         let price = decode_f64(&mut fields_itr)?;
@@ -1347,24 +1480,21 @@ where
             is_smart_depth = decode_bool(&mut fields_itr)?;
         }
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .update_mkt_depth_l2(
-                req_id,
-                position,
-                market_maker.as_ref(),
-                operation,
-                side,
-                price,
-                size,
-                is_smart_depth,
-            );
+        self.wrapper().update_mkt_depth_l2(
+            req_id,
+            position,
+            market_maker,
+            operation,
+            side,
+            price,
+            size,
+            is_smart_depth,
+        );
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_market_rule(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_market_rule(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -1382,15 +1512,12 @@ where
             price_increments.push(prc_inc);
         }
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .market_rule(market_rule_id, price_increments);
+        self.wrapper().market_rule(market_rule_id, price_increments);
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_market_depth_exchanges(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_market_depth_exchanges(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -1413,15 +1540,13 @@ where
             depth_mkt_data_descriptions.push(desc);
         }
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
+        self.wrapper()
             .mkt_depth_exchanges(depth_mkt_data_descriptions);
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_news_article(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_news_article(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -1430,15 +1555,13 @@ where
         let req_id = decode_i32(&mut fields_itr)?;
         let article_type = decode_i32(&mut fields_itr)?;
         let article_text = decode_string(&mut fields_itr)?;
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
+        self.wrapper()
             .news_article(req_id, article_type, article_text.as_ref());
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_news_bulletins(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_news_bulletins(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -1451,20 +1574,17 @@ where
         let news_message = decode_string(&mut fields_itr)?;
         let originating_exch = decode_string(&mut fields_itr)?;
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .update_news_bulletin(
-                news_msg_id,
-                news_msg_type,
-                news_message.as_ref(),
-                originating_exch.as_ref(),
-            );
+        self.wrapper().update_news_bulletin(
+            news_msg_id,
+            news_msg_type,
+            news_message.as_ref(),
+            originating_exch.as_ref(),
+        );
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_news_providers(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_news_providers(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -1479,15 +1599,12 @@ where
             news_providers.push(provider);
         }
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .news_providers(news_providers);
+        self.wrapper().news_providers(news_providers);
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_next_valid_id(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_next_valid_id(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -1496,15 +1613,12 @@ where
         fields_itr.next();
 
         let order_id = decode_i32(&mut fields_itr)?;
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .next_valid_id(order_id);
+        self.wrapper().next_valid_id(order_id);
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_open_order(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_open_order(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
         //info!("Processing open order");
         //throw away message_id
@@ -1529,24 +1643,19 @@ where
 
         order_decoder.decode_open(&mut fields_itr)?;
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
+        self.wrapper()
             .open_order(order.order_id, contract, order, order_state);
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_open_order_end(&mut self, _fields: &[String]) -> Result<(), IBKRApiLibError> {
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .open_order_end();
+    fn process_open_order_end(&mut self, _fields: &[&str]) -> Result<(), IBKRApiLibError> {
+        self.wrapper().open_order_end();
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_order_bound(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_order_bound(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -1556,15 +1665,13 @@ where
         let api_client_id = decode_i32(&mut fields_itr)?;
         let api_order_id = decode_i32(&mut fields_itr)?;
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
+        self.wrapper()
             .order_bound(req_id, api_client_id, api_order_id);
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_order_status(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_order_status(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -1606,27 +1713,24 @@ where
             mkt_cap_price = decode_f64(&mut fields_itr)?;
         }
 
-        self.wrapper
-            .try_lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .order_status(
-                order_id,
-                status.as_ref(),
-                filled,
-                remaining,
-                avg_fill_price,
-                perm_id,
-                parent_id,
-                last_fill_price,
-                client_id,
-                why_held.as_ref(),
-                mkt_cap_price,
-            );
+        self.wrapper().order_status(
+            order_id,
+            status.as_ref(),
+            filled,
+            remaining,
+            avg_fill_price,
+            perm_id,
+            parent_id,
+            last_fill_price,
+            client_id,
+            why_held.as_ref(),
+            mkt_cap_price,
+        );
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_pnl(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_pnl(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -1645,17 +1749,13 @@ where
             realized_pnl = decode_f64(&mut fields_itr)?;
         }
 
-        self.wrapper.lock().expect(WRAPPER_POISONED_MUTEX).pnl(
-            req_id,
-            daily_pnl,
-            unrealized_pnl,
-            realized_pnl,
-        );
+        self.wrapper()
+            .pnl(req_id, daily_pnl, unrealized_pnl, realized_pnl);
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_pnl_single(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_pnl_single(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -1677,15 +1777,13 @@ where
 
         let value = decode_f64(&mut fields_itr)?;
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
+        self.wrapper()
             .pnl_single(req_id, pos, daily_pnl, unrealized_pnl, realized_pnl, value);
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_portfolio_value(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_portfolio_value(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -1732,24 +1830,21 @@ where
             contract.primary_exchange = decode_string(&mut fields_itr)?;
         }
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .update_portfolio(
-                contract,
-                position,
-                market_price,
-                market_value,
-                average_cost,
-                unrealized_pnl,
-                realized_pnl,
-                account_name.as_ref(),
-            );
+        self.wrapper().update_portfolio(
+            contract,
+            position,
+            market_price,
+            market_value,
+            average_cost,
+            unrealized_pnl,
+            realized_pnl,
+            account_name.as_ref(),
+        );
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_position_data(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_position_data(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -1787,26 +1882,19 @@ where
             avg_cost = decode_f64(&mut fields_itr)?;
         }
 
-        self.wrapper.lock().expect(WRAPPER_POISONED_MUTEX).position(
-            account.as_ref(),
-            contract,
-            position,
-            avg_cost,
-        );
+        self.wrapper()
+            .position(account.as_ref(), contract, position, avg_cost);
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_position_end(&mut self, _fields: &[String]) -> Result<(), IBKRApiLibError> {
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .position_end();
+    fn process_position_end(&mut self, _fields: &[&str]) -> Result<(), IBKRApiLibError> {
+        self.wrapper().position_end();
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_position_multi(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_position_multi(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -1836,23 +1924,20 @@ where
         let avg_cost = decode_f64(&mut fields_itr)?;
         let model_code = decode_string(&mut fields_itr)?;
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .position_multi(
-                req_id,
-                account.as_ref(),
-                model_code.as_ref(),
-                contract,
-                position,
-                avg_cost,
-            );
+        self.wrapper().position_multi(
+            req_id,
+            account.as_ref(),
+            model_code.as_ref(),
+            contract,
+            position,
+            avg_cost,
+        );
 
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_position_multi_end(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_position_multi_end(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -1861,15 +1946,12 @@ where
         fields_itr.next();
 
         let req_id = decode_i32(&mut fields_itr)?;
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .position_multi_end(req_id);
+        self.wrapper().position_multi_end(req_id);
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_real_time_bars(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_real_time_bars(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -1889,15 +1971,12 @@ where
         bar.wap = decode_f64(&mut fields_itr)?;
         bar.count = decode_i32(&mut fields_itr)?;
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .realtime_bar(req_id, bar);
+        self.wrapper().realtime_bar(req_id, bar);
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_receive_fa(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_receive_fa(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -1908,15 +1987,13 @@ where
         let fa_data_type = decode_i32(&mut fields_itr)?;
         let xml = decode_string(&mut fields_itr)?;
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
+        self.wrapper()
             .receive_fa(FromPrimitive::from_i32(fa_data_type).unwrap(), xml.as_ref());
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_reroute_mkt_data_req(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_reroute_mkt_data_req(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -1926,15 +2003,13 @@ where
         let con_id = decode_i32(&mut fields_itr)?;
         let exchange = decode_string(&mut fields_itr)?;
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
+        self.wrapper()
             .reroute_mkt_data_req(req_id, con_id, exchange.as_ref());
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_reroute_mkt_depth_req(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_reroute_mkt_depth_req(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -1944,15 +2019,13 @@ where
         let con_id = decode_i32(&mut fields_itr)?;
         let exchange = decode_string(&mut fields_itr)?;
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
+        self.wrapper()
             .reroute_mkt_depth_req(req_id, con_id, exchange.as_ref());
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_scanner_data(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_scanner_data(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -1985,29 +2058,23 @@ where
             data.benchmark = decode_string(&mut fields_itr)?;
             data.projection = decode_string(&mut fields_itr)?;
             data.legs = decode_string(&mut fields_itr)?;
-            self.wrapper
-                .lock()
-                .expect(WRAPPER_POISONED_MUTEX)
-                .scanner_data(
-                    req_id,
-                    data.rank,
-                    data.contract,
-                    data.distance.as_ref(),
-                    data.benchmark.as_ref(),
-                    data.projection.as_ref(),
-                    data.legs.as_ref(),
-                );
+            self.wrapper().scanner_data(
+                req_id,
+                data.rank,
+                data.contract,
+                data.distance.as_ref(),
+                data.benchmark.as_ref(),
+                data.projection.as_ref(),
+                data.legs.as_ref(),
+            );
         }
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .scanner_data_end(req_id);
+        self.wrapper().scanner_data_end(req_id);
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_scanner_parameters(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_scanner_parameters(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -2016,17 +2083,14 @@ where
         fields_itr.next();
 
         let xml = decode_string(&mut fields_itr)?;
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .scanner_parameters(xml.as_ref());
+        self.wrapper().scanner_parameters(xml.as_ref());
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
     fn process_security_definition_option_parameter(
         &mut self,
-        fields: &[String],
+        fields: &[&str],
     ) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
@@ -2055,25 +2119,22 @@ where
             strikes.insert(big_strike);
         }
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .security_definition_option_parameter(
-                req_id,
-                exchange.as_ref(),
-                underlying_con_id,
-                trading_class.as_ref(),
-                multiplier.as_ref(),
-                expirations,
-                strikes,
-            );
+        self.wrapper().security_definition_option_parameter(
+            req_id,
+            exchange.as_ref(),
+            underlying_con_id,
+            trading_class.as_ref(),
+            multiplier.as_ref(),
+            expirations,
+            strikes,
+        );
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
     fn process_security_definition_option_parameter_end(
         &mut self,
-        fields: &[String],
+        fields: &[&str],
     ) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
@@ -2081,15 +2142,13 @@ where
         fields_itr.next();
 
         let req_id = decode_i32(&mut fields_itr)?;
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
+        self.wrapper()
             .security_definition_option_parameter_end(req_id);
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_smart_components(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_smart_components(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -2108,15 +2167,12 @@ where
             smart_components.push(smart_component)
         }
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .smart_components(req_id, smart_components);
+        self.wrapper().smart_components(req_id, smart_components);
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_soft_dollar_tiers(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_soft_dollar_tiers(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -2135,15 +2191,12 @@ where
             tiers.push(tier);
         }
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .soft_dollar_tiers(req_id, tiers);
+        self.wrapper().soft_dollar_tiers(req_id, tiers);
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_symbol_samples(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_symbol_samples(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -2169,15 +2222,12 @@ where
             }
             contract_descriptions.push(con_desc)
         }
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .symbol_samples(req_id, contract_descriptions);
+        self.wrapper().symbol_samples(req_id, contract_descriptions);
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_tick_by_tick(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_tick_by_tick(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -2199,21 +2249,18 @@ where
                 let mut tick_attrib_last = TickAttribLast::default();
                 tick_attrib_last.past_limit = mask & 1 != 0;
                 tick_attrib_last.unreported = mask & 2 != 0;
-                let exchange = decode_string(&mut fields_itr)?;
-                let special_conditions = decode_string(&mut fields_itr)?;
-                self.wrapper
-                    .lock()
-                    .expect(WRAPPER_POISONED_MUTEX)
-                    .tick_by_tick_all_last(
-                        req_id,
-                        FromPrimitive::from_i32(tick_type).unwrap(),
-                        time,
-                        price,
-                        size,
-                        tick_attrib_last,
-                        exchange.as_ref(),
-                        special_conditions.as_ref(),
-                    );
+                let exchange = decode_str(&mut fields_itr)?;
+                let special_conditions = decode_str(&mut fields_itr)?;
+                self.wrapper().tick_by_tick_all_last(
+                    req_id,
+                    FromPrimitive::from_i32(tick_type).unwrap(),
+                    time,
+                    price,
+                    size,
+                    tick_attrib_last,
+                    exchange,
+                    special_conditions,
+                );
             }
             3 =>
             // BidAsk
@@ -2226,26 +2273,21 @@ where
                 let mut tick_attrib_bid_ask = TickAttribBidAsk::default();
                 tick_attrib_bid_ask.bid_past_low = mask & 1 != 0;
                 tick_attrib_bid_ask.ask_past_high = mask & 2 != 0;
-                self.wrapper
-                    .lock()
-                    .expect(WRAPPER_POISONED_MUTEX)
-                    .tick_by_tick_bid_ask(
-                        req_id,
-                        time,
-                        bid_price,
-                        ask_price,
-                        bid_size,
-                        ask_size,
-                        tick_attrib_bid_ask,
-                    );
+                self.wrapper().tick_by_tick_bid_ask(
+                    req_id,
+                    time,
+                    bid_price,
+                    ask_price,
+                    bid_size,
+                    ask_size,
+                    tick_attrib_bid_ask,
+                );
             }
             4 =>
             // MidPoint
             {
                 let mid_point = decode_f64(&mut fields_itr)?;
-                self.wrapper
-                    .lock()
-                    .expect(WRAPPER_POISONED_MUTEX)
+                self.wrapper()
                     .tick_by_tick_mid_point(req_id, time, mid_point);
             }
             _ => return Ok(()),
@@ -2255,7 +2297,7 @@ where
 
     //----------------------------------------------------------------------------------------------
     #[allow(dead_code)]
-    fn process_tick_efp(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_tick_efp(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -2272,7 +2314,7 @@ where
         let future_last_trade_date = decode_string(&mut fields_itr)?;
         let dividend_impact = decode_f64(&mut fields_itr)?;
         let dividends_to_last_trade_date = decode_f64(&mut fields_itr)?;
-        self.wrapper.lock().expect(WRAPPER_POISONED_MUTEX).tick_efp(
+        self.wrapper().tick_efp(
             ticker_id,
             FromPrimitive::from_i32(tick_type).unwrap(),
             basis_points,
@@ -2287,7 +2329,7 @@ where
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_tick_generic(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_tick_generic(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -2299,19 +2341,16 @@ where
         let tick_type = decode_i32(&mut fields_itr)?;
         let value = decode_f64(&mut fields_itr)?;
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .tick_generic(
-                ticker_id,
-                FromPrimitive::from_i32(tick_type).unwrap(),
-                value,
-            );
+        self.wrapper().tick_generic(
+            ticker_id,
+            FromPrimitive::from_i32(tick_type).unwrap(),
+            value,
+        );
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_tick_news(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_tick_news(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -2322,25 +2361,19 @@ where
         let article_id = decode_string(&mut fields_itr)?;
         let headline = decode_string(&mut fields_itr)?;
         let extra_data = decode_string(&mut fields_itr)?;
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .tick_news(
-                ticker_id,
-                time_stamp,
-                provider_code.as_ref(),
-                article_id.as_ref(),
-                headline.as_ref(),
-                extra_data.as_ref(),
-            );
+        self.wrapper().tick_news(
+            ticker_id,
+            time_stamp,
+            provider_code.as_ref(),
+            article_id.as_ref(),
+            headline.as_ref(),
+            extra_data.as_ref(),
+        );
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_tick_option_computation(
-        &mut self,
-        fields: &[String],
-    ) -> Result<(), IBKRApiLibError> {
+    fn process_tick_option_computation(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -2405,26 +2438,23 @@ where
             }
         }
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .tick_option_computation(
-                ticker_id,
-                FromPrimitive::from_i32(tick_type).unwrap(),
-                implied_vol,
-                delta,
-                opt_price,
-                pv_dividend,
-                gamma,
-                vega,
-                theta,
-                und_price,
-            );
+        self.wrapper().tick_option_computation(
+            ticker_id,
+            FromPrimitive::from_i32(tick_type).unwrap(),
+            implied_vol,
+            delta,
+            opt_price,
+            pv_dividend,
+            gamma,
+            vega,
+            theta,
+            und_price,
+        );
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_tick_req_params(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_tick_req_params(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -2434,20 +2464,17 @@ where
         let min_tick = decode_f64(&mut fields_itr)?;
         let bbo_exchange = decode_string(&mut fields_itr)?;
         let snapshot_permissions = decode_i32(&mut fields_itr)?;
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .tick_req_params(
-                ticker_id,
-                min_tick,
-                bbo_exchange.as_ref(),
-                snapshot_permissions,
-            );
+        self.wrapper().tick_req_params(
+            ticker_id,
+            min_tick,
+            bbo_exchange.as_ref(),
+            snapshot_permissions,
+        );
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_tick_size(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_tick_size(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -2459,15 +2486,13 @@ where
         let tick_type = decode_i32(&mut fields_itr)?;
         let size = decode_i32(&mut fields_itr)?;
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
+        self.wrapper()
             .tick_size(ticker_id, FromPrimitive::from_i32(tick_type).unwrap(), size);
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_tick_snapshot_end(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_tick_snapshot_end(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
         //throw away message_id
@@ -2477,17 +2502,14 @@ where
 
         let req_id = decode_i32(&mut fields_itr)?;
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .tick_snapshot_end(req_id);
+        self.wrapper().tick_snapshot_end(req_id);
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
     fn process_verify_and_auth_completed(
         &mut self,
-        fields: &[String],
+        fields: &[&str],
     ) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
@@ -2499,9 +2521,7 @@ where
         let is_successful = "true" == decode_string(&mut fields_itr)?;
         let error_text = decode_string(&mut fields_itr)?;
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
+        self.wrapper()
             .verify_and_auth_completed(is_successful, error_text.as_ref());
         Ok(())
     }
@@ -2509,7 +2529,7 @@ where
     //----------------------------------------------------------------------------------------------
     fn process_verify_and_auth_message_api(
         &mut self,
-        fields: &[String],
+        fields: &[&str],
     ) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
 
@@ -2521,15 +2541,13 @@ where
         let api_data = decode_string(&mut fields_itr)?;
         let xyz_challenge = decode_string(&mut fields_itr)?;
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
+        self.wrapper()
             .verify_and_auth_message_api(api_data.as_ref(), xyz_challenge.as_ref());
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_verify_completed(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_verify_completed(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
         //throw away message_id
         fields_itr.next();
@@ -2540,16 +2558,14 @@ where
         let is_successful = "true" == decode_string(&mut fields_itr)?;
         let error_text = decode_string(&mut fields_itr)?;
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
+        self.wrapper()
             .verify_completed(is_successful, error_text.as_ref());
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
     #[allow(dead_code)]
-    fn process_verify_message_api(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_verify_message_api(&mut self, fields: &[&str]) -> Result<(), IBKRApiLibError> {
         let mut fields_itr = fields.iter();
         //throw away message_id
         fields_itr.next();
@@ -2558,10 +2574,7 @@ where
 
         let api_data = decode_string(&mut fields_itr)?;
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .verify_message_api(api_data.as_ref());
+        self.wrapper().verify_message_api(api_data.as_ref());
         Ok(())
     }
 
@@ -2604,26 +2617,34 @@ where
             let text = self.msg_queue.recv();
             match text {
                 Result::Ok(val) => {
+                    if let Some(metrics) = &self.metrics {
+                        metrics.message_dequeued();
+                    }
                     if val.len() > MAX_MSG_LEN as usize {
-                        self.wrapper.lock().expect(WRAPPER_POISONED_MUTEX).error(
+                        self.wrapper().error(
                             NO_VALID_ID,
                             TwsError::NotConnected.code(),
                             format!("{}:{}:{}", TwsError::NotConnected.message(), val.len(), val)
                                 .as_str(),
                         );
                         error!("Error receiving message.  Disconnected: Message too big");
-                        self.wrapper
-                            .lock()
-                            .expect(WRAPPER_POISONED_MUTEX)
-                            .connection_closed();
+                        self.wrapper().connection_closed();
                         *self.conn_state.lock().expect(CONN_STATE_POISONED) =
                             ConnStatus::DISCONNECTED;
                         error!("Error receiving message.  Invalid size.  Disconnected.");
                         return Ok(());
                     } else {
-                        let fields = read_fields((&val).as_ref());
-
-                        self.interpret(fields.as_slice())?;
+                        let start = Instant::now();
+                        self.interpret_message(&val)?;
+                        if let Some(metrics) = &self.metrics {
+                            if let Ok(msg_id) = val.split('\0').next().unwrap_or("").parse() {
+                                metrics.message_received(msg_id, start.elapsed());
+                            }
+                        }
+                        if let Some(spare_buffers) = &self.spare_buffers {
+                            // the reader may be gone already, in which case the String is dropped
+                            let _ = spare_buffers.send(val);
+                        }
                     }
                 }
                 Result::Err(err) => {
@@ -2631,10 +2652,7 @@ where
                         != ConnStatus::DISCONNECTED as i32
                     {
                         info!("Error receiving message.  Disconnected: {:?}", err);
-                        self.wrapper
-                            .lock()
-                            .expect(WRAPPER_POISONED_MUTEX)
-                            .connection_closed();
+                        self.wrapper().connection_closed();
                         *self.conn_state.lock().expect(CONN_STATE_POISONED) =
                             ConnStatus::DISCONNECTED;
 
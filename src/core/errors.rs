@@ -83,6 +83,7 @@ pub enum IBKRApiLibError {
     RecvError(RecvError),
     RecvTimeoutError(RecvTimeoutError),
     ApiError(TwsApiReportableError),
+    RiskRejected(RiskViolation),
 }
 
 impl fmt::Display for IBKRApiLibError {
@@ -96,6 +97,7 @@ impl fmt::Display for IBKRApiLibError {
             IBKRApiLibError::RecvError(ref err) => write!(f, "Recieve error: {}", err),
             IBKRApiLibError::RecvTimeoutError(ref err) => write!(f, "Reader Send error {}", err),
             IBKRApiLibError::ApiError(ref err) => write!(f, "TWS Error: {}", err),
+            IBKRApiLibError::RiskRejected(ref err) => write!(f, "Risk check failed: {}", err),
         }
     }
 }
@@ -111,6 +113,7 @@ impl fmt::Debug for IBKRApiLibError {
             IBKRApiLibError::RecvError(ref err) => write!(f, "Recieve error: {}", err),
             IBKRApiLibError::RecvTimeoutError(ref err) => write!(f, "Reader Send error {}", err),
             IBKRApiLibError::ApiError(ref err) => write!(f, "TWS Error: {}", err),
+            IBKRApiLibError::RiskRejected(ref err) => write!(f, "Risk check failed: {}", err),
         }
     }
 }
@@ -128,6 +131,7 @@ impl error::Error for IBKRApiLibError {
             IBKRApiLibError::RecvError(ref err) => Some(err),
             IBKRApiLibError::RecvTimeoutError(ref err) => Some(err),
             IBKRApiLibError::ApiError(ref err) => Some(err),
+            IBKRApiLibError::RiskRejected(ref err) => Some(err),
         }
     }
 }
@@ -168,6 +172,12 @@ impl From<TwsApiReportableError> for IBKRApiLibError {
     }
 }
 
+impl From<RiskViolation> for IBKRApiLibError {
+    fn from(err: RiskViolation) -> IBKRApiLibError {
+        IBKRApiLibError::RiskRejected(err)
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct TwsApiReportableError {
     pub req_id: i32,
@@ -196,3 +206,92 @@ impl fmt::Display for TwsApiReportableError {
 }
 
 impl error::Error for TwsApiReportableError {}
+
+/// Reason a [RiskGate](crate::core::risk_gate::RiskGate) rejected an order
+#[derive(Clone, Debug, PartialEq)]
+pub enum RiskViolation {
+    SymbolNotAllowed {
+        symbol: String,
+    },
+    OrderNotional {
+        notional: f64,
+        limit: f64,
+    },
+    Position {
+        account: String,
+        con_id: i32,
+        position: f64,
+        limit: f64,
+    },
+    PriceDeviation {
+        price: f64,
+        last_price: f64,
+        limit: f64,
+    },
+    DailyLoss {
+        daily_pnl: f64,
+        limit: f64,
+    },
+    DuplicateOrder {
+        previous_order_id: i32,
+    },
+    /// The last price is not known yet.  The notional limit only needs it for orders without a
+    /// price, the price deviation limit for orders with one.
+    NoPrice {
+        con_id: i32,
+    },
+    /// A limit on positions or prices is set, which are kept per con_id, but the contract has none
+    MissingConId {
+        symbol: String,
+    },
+}
+
+impl fmt::Display for RiskViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RiskViolation::SymbolNotAllowed { symbol } => {
+                write!(f, "symbol {} is not allowed", symbol)
+            }
+            RiskViolation::OrderNotional { notional, limit } => {
+                write!(f, "order notional {} exceeds {}", notional, limit)
+            }
+            RiskViolation::Position {
+                account,
+                con_id,
+                position,
+                limit,
+            } => write!(
+                f,
+                "position {} in con_id {} of account '{}' would exceed {}",
+                position, con_id, account, limit
+            ),
+            RiskViolation::PriceDeviation {
+                price,
+                last_price,
+                limit,
+            } => write!(
+                f,
+                "price {} deviates more than {} from last price {}",
+                price, limit, last_price
+            ),
+            RiskViolation::DailyLoss { daily_pnl, limit } => {
+                write!(
+                    f,
+                    "daily pnl {} is beyond the loss limit {}",
+                    daily_pnl, limit
+                )
+            }
+            RiskViolation::DuplicateOrder { previous_order_id } => {
+                write!(f, "duplicate of order {}", previous_order_id)
+            }
+            RiskViolation::NoPrice { con_id } => {
+                write!(f, "no last price for con_id {}", con_id)
+            }
+            RiskViolation::MissingConId { symbol } => {
+                write!(f, "contract {} has no con_id", symbol)
+            }
+        }
+    }
+}
+
+impl error::Error for RiskViolation {}
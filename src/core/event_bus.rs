@@ -0,0 +1,2195 @@
+//! Dispatcher that implements Wrapper and fans callbacks out to any number of subscribers
+//!
+//! [EClient](crate::core::client::EClient) drives a single wrapper.  An [EventBus] can be that
+//! wrapper: it turns each callback into an [Event] and delivers it to every subscriber whose
+//! [EventFilter] matches.  Subscribers are channels ([EventBus::subscribe]), closures
+//! ([EventBus::subscribe_fn]) or [EventHandler] trait objects ([EventBus::subscribe_handler]).
+//!
+//! Every subscriber has a bounded queue, and its [Backpressure] policy decides what happens when
+//! the queue is full.  Closures and handlers run on a thread of their own, so a slow subscriber
+//! only holds up the decoder thread if it uses [Backpressure::Block].
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use bigdecimal::BigDecimal;
+
+use crate::core::common::{
+    BarData, CommissionReport, DepthMktDataDescription, FaDataType, FamilyCode, HistogramData,
+    HistoricalTick, HistoricalTickBidAsk, HistoricalTickLast, NewsProvider, PriceIncrement,
+    RealTimeBar, SmartComponent, TickAttrib, TickAttribBidAsk, TickAttribLast, TickByTickType,
+    TickType,
+};
+use crate::core::contract::{Contract, ContractDescription, ContractDetails, DeltaNeutralContract};
+use crate::core::execution::Execution;
+use crate::core::order::{Order, OrderState, SoftDollarTier};
+use crate::core::tick_parsers::{DividendInfo, FundamentalRatios, RtVolume};
+use crate::core::wrapper::Wrapper;
+
+const QUEUE_POISONED_MUTEX: &str = "Event queue mutex was poisoned";
+const SUBSCRIBERS_POISONED_MUTEX: &str = "Event bus subscribers mutex was poisoned";
+
+/// Identifies a subscription on an [EventBus]
+pub type SubscriptionId = usize;
+
+/// Decides which events a filtered [EventBus] publishes
+type PublishFilter = Arc<dyn Fn(&Event) -> bool + Send + Sync>;
+
+//==================================================================================================
+/// A Wrapper callback and its arguments.  There is one variant per callback, named after it.
+#[derive(Clone, Debug)]
+pub enum Event {
+    Error {
+        req_id: i32,
+        error_code: i32,
+        error_string: String,
+    },
+    WinError {
+        text: String,
+        last_error: i32,
+    },
+    ConnectAck,
+    MarketDataType {
+        req_id: i32,
+        market_data_type: i32,
+    },
+    TickPrice {
+        req_id: i32,
+        tick_type: TickType,
+        price: f64,
+        attrib: TickAttrib,
+    },
+    TickSize {
+        req_id: i32,
+        tick_type: TickType,
+        size: i32,
+    },
+    TickSnapshotEnd {
+        req_id: i32,
+    },
+    TickGeneric {
+        req_id: i32,
+        tick_type: TickType,
+        value: f64,
+    },
+    TickString {
+        req_id: i32,
+        tick_type: TickType,
+        value: String,
+    },
+    TickRtVolume {
+        req_id: i32,
+        tick_type: TickType,
+        rt_volume: RtVolume,
+    },
+    TickFundamentalRatios {
+        req_id: i32,
+        ratios: FundamentalRatios,
+    },
+    TickDividends {
+        req_id: i32,
+        dividends: DividendInfo,
+    },
+    TickEfp {
+        req_id: i32,
+        tick_type: TickType,
+        basis_points: f64,
+        formatted_basis_points: String,
+        implied_future: f64,
+        hold_days: i32,
+        future_last_trade_date: String,
+        dividend_impact: f64,
+        dividends_to_last_trade_date: f64,
+    },
+    OrderStatus {
+        order_id: i32,
+        status: String,
+        filled: f64,
+        remaining: f64,
+        avg_fill_price: f64,
+        perm_id: i32,
+        parent_id: i32,
+        last_fill_price: f64,
+        client_id: i32,
+        why_held: String,
+        mkt_cap_price: f64,
+    },
+    OpenOrder {
+        order_id: i32,
+        contract: Contract,
+        order: Order,
+        order_state: OrderState,
+    },
+    OpenOrderEnd,
+    ConnectionClosed,
+    UpdateAccountValue {
+        key: String,
+        val: String,
+        currency: String,
+        account_name: String,
+    },
+    UpdatePortfolio {
+        contract: Contract,
+        position: f64,
+        market_price: f64,
+        market_value: f64,
+        average_cost: f64,
+        unrealized_pnl: f64,
+        realized_pnl: f64,
+        account_name: String,
+    },
+    UpdateAccountTime {
+        time_stamp: String,
+    },
+    AccountDownloadEnd {
+        account_name: String,
+    },
+    NextValidId {
+        order_id: i32,
+    },
+    ContractDetails {
+        req_id: i32,
+        contract_details: ContractDetails,
+    },
+    BondContractDetails {
+        req_id: i32,
+        contract_details: ContractDetails,
+    },
+    ContractDetailsEnd {
+        req_id: i32,
+    },
+    ExecDetails {
+        req_id: i32,
+        contract: Contract,
+        execution: Execution,
+    },
+    ExecDetailsEnd {
+        req_id: i32,
+    },
+    UpdateMktDepth {
+        req_id: i32,
+        position: i32,
+        operation: i32,
+        side: i32,
+        price: f64,
+        size: i32,
+    },
+    UpdateMktDepthL2 {
+        req_id: i32,
+        position: i32,
+        market_maker: String,
+        operation: i32,
+        side: i32,
+        price: f64,
+        size: i32,
+        is_smart_depth: bool,
+    },
+    UpdateNewsBulletin {
+        msg_id: i32,
+        msg_type: i32,
+        news_message: String,
+        origin_exch: String,
+    },
+    ManagedAccounts {
+        accounts_list: String,
+    },
+    ReceiveFa {
+        fa_data: FaDataType,
+        cxml: String,
+    },
+    HistoricalData {
+        req_id: i32,
+        bar: BarData,
+    },
+    HistoricalDataEnd {
+        req_id: i32,
+        start: String,
+        end: String,
+    },
+    ScannerParameters {
+        xml: String,
+    },
+    ScannerData {
+        req_id: i32,
+        rank: i32,
+        contract_details: ContractDetails,
+        distance: String,
+        benchmark: String,
+        projection: String,
+        legs_str: String,
+    },
+    ScannerDataEnd {
+        req_id: i32,
+    },
+    RealtimeBar {
+        req_id: i32,
+        bar: RealTimeBar,
+    },
+    CurrentTime {
+        time: i64,
+    },
+    FundamentalData {
+        req_id: i32,
+        data: String,
+    },
+    DeltaNeutralValidation {
+        req_id: i32,
+        delta_neutral_contract: DeltaNeutralContract,
+    },
+    CommissionReport {
+        commission_report: CommissionReport,
+    },
+    Position {
+        account: String,
+        contract: Contract,
+        position: f64,
+        avg_cost: f64,
+    },
+    PositionEnd,
+    AccountSummary {
+        req_id: i32,
+        account: String,
+        tag: String,
+        value: String,
+        currency: String,
+    },
+    AccountSummaryEnd {
+        req_id: i32,
+    },
+    VerifyMessageApi {
+        api_data: String,
+    },
+    VerifyCompleted {
+        is_successful: bool,
+        error_text: String,
+    },
+    VerifyAndAuthMessageApi {
+        api_data: String,
+        xyz_challange: String,
+    },
+    VerifyAndAuthCompleted {
+        is_successful: bool,
+        error_text: String,
+    },
+    DisplayGroupList {
+        req_id: i32,
+        groups: String,
+    },
+    DisplayGroupUpdated {
+        req_id: i32,
+        contract_info: String,
+    },
+    PositionMulti {
+        req_id: i32,
+        account: String,
+        model_code: String,
+        contract: Contract,
+        pos: f64,
+        avg_cost: f64,
+    },
+    PositionMultiEnd {
+        req_id: i32,
+    },
+    AccountUpdateMulti {
+        req_id: i32,
+        account: String,
+        model_code: String,
+        key: String,
+        value: String,
+        currency: String,
+    },
+    AccountUpdateMultiEnd {
+        req_id: i32,
+    },
+    TickOptionComputation {
+        req_id: i32,
+        tick_type: TickType,
+        implied_vol: f64,
+        delta: f64,
+        opt_price: f64,
+        pv_dividend: f64,
+        gamma: f64,
+        vega: f64,
+        theta: f64,
+        und_price: f64,
+    },
+    SecurityDefinitionOptionParameter {
+        req_id: i32,
+        exchange: String,
+        underlying_con_id: i32,
+        trading_class: String,
+        multiplier: String,
+        expirations: HashSet<String>,
+        strikes: HashSet<BigDecimal>,
+    },
+    SecurityDefinitionOptionParameterEnd {
+        req_id: i32,
+    },
+    SoftDollarTiers {
+        req_id: i32,
+        tiers: Vec<SoftDollarTier>,
+    },
+    FamilyCodes {
+        family_codes: Vec<FamilyCode>,
+    },
+    SymbolSamples {
+        req_id: i32,
+        contract_descriptions: Vec<ContractDescription>,
+    },
+    MktDepthExchanges {
+        depth_mkt_data_descriptions: Vec<DepthMktDataDescription>,
+    },
+    TickNews {
+        ticker_id: i32,
+        time_stamp: i32,
+        provider_code: String,
+        article_id: String,
+        headline: String,
+        extra_data: String,
+    },
+    SmartComponents {
+        req_id: i32,
+        smart_components: Vec<SmartComponent>,
+    },
+    TickReqParams {
+        ticker_id: i32,
+        min_tick: f64,
+        bbo_exchange: String,
+        snapshot_permissions: i32,
+    },
+    NewsProviders {
+        news_providers: Vec<NewsProvider>,
+    },
+    NewsArticle {
+        request_id: i32,
+        article_type: i32,
+        article_text: String,
+    },
+    HistoricalNews {
+        request_id: i32,
+        time: String,
+        provider_code: String,
+        article_id: String,
+        headline: String,
+    },
+    HistoricalNewsEnd {
+        request_id: i32,
+        has_more: bool,
+    },
+    HeadTimestamp {
+        req_id: i32,
+        head_timestamp: String,
+    },
+    HistogramData {
+        req_id: i32,
+        items: Vec<HistogramData>,
+    },
+    HistoricalDataUpdate {
+        req_id: i32,
+        bar: BarData,
+    },
+    RerouteMktDataReq {
+        req_id: i32,
+        con_id: i32,
+        exchange: String,
+    },
+    RerouteMktDepthReq {
+        req_id: i32,
+        con_id: i32,
+        exchange: String,
+    },
+    MarketRule {
+        market_rule_id: i32,
+        price_increments: Vec<PriceIncrement>,
+    },
+    Pnl {
+        req_id: i32,
+        daily_pn_l: f64,
+        unrealized_pn_l: f64,
+        realized_pn_l: f64,
+    },
+    PnlSingle {
+        req_id: i32,
+        pos: i32,
+        daily_pn_l: f64,
+        unrealized_pn_l: f64,
+        realized_pn_l: f64,
+        value: f64,
+    },
+    HistoricalTicks {
+        req_id: i32,
+        ticks: Vec<HistoricalTick>,
+        done: bool,
+    },
+    HistoricalTicksBidAsk {
+        req_id: i32,
+        ticks: Vec<HistoricalTickBidAsk>,
+        done: bool,
+    },
+    HistoricalTicksLast {
+        req_id: i32,
+        ticks: Vec<HistoricalTickLast>,
+        done: bool,
+    },
+    TickByTickAllLast {
+        req_id: i32,
+        tick_type: TickByTickType,
+        time: i64,
+        price: f64,
+        size: i32,
+        tick_attrib_last: TickAttribLast,
+        exchange: String,
+        special_conditions: String,
+    },
+    TickByTickBidAsk {
+        req_id: i32,
+        time: i64,
+        bid_price: f64,
+        ask_price: f64,
+        bid_size: i32,
+        ask_size: i32,
+        tick_attrib_bid_ask: TickAttribBidAsk,
+    },
+    TickByTickMidPoint {
+        req_id: i32,
+        time: i64,
+        mid_point: f64,
+    },
+    OrderBound {
+        req_id: i32,
+        api_client_id: i32,
+        api_order_id: i32,
+    },
+    CompletedOrder {
+        contract: Contract,
+        order: Order,
+        order_state: OrderState,
+    },
+    CompletedOrdersEnd,
+}
+
+impl Event {
+    pub fn kind(&self) -> EventKind {
+        match self {
+            Event::Error { .. } => EventKind::Error,
+            Event::WinError { .. } => EventKind::WinError,
+            Event::ConnectAck => EventKind::ConnectAck,
+            Event::MarketDataType { .. } => EventKind::MarketDataType,
+            Event::TickPrice { .. } => EventKind::TickPrice,
+            Event::TickSize { .. } => EventKind::TickSize,
+            Event::TickSnapshotEnd { .. } => EventKind::TickSnapshotEnd,
+            Event::TickGeneric { .. } => EventKind::TickGeneric,
+            Event::TickString { .. } => EventKind::TickString,
+            Event::TickRtVolume { .. } => EventKind::TickRtVolume,
+            Event::TickFundamentalRatios { .. } => EventKind::TickFundamentalRatios,
+            Event::TickDividends { .. } => EventKind::TickDividends,
+            Event::TickEfp { .. } => EventKind::TickEfp,
+            Event::OrderStatus { .. } => EventKind::OrderStatus,
+            Event::OpenOrder { .. } => EventKind::OpenOrder,
+            Event::OpenOrderEnd => EventKind::OpenOrderEnd,
+            Event::ConnectionClosed => EventKind::ConnectionClosed,
+            Event::UpdateAccountValue { .. } => EventKind::UpdateAccountValue,
+            Event::UpdatePortfolio { .. } => EventKind::UpdatePortfolio,
+            Event::UpdateAccountTime { .. } => EventKind::UpdateAccountTime,
+            Event::AccountDownloadEnd { .. } => EventKind::AccountDownloadEnd,
+            Event::NextValidId { .. } => EventKind::NextValidId,
+            Event::ContractDetails { .. } => EventKind::ContractDetails,
+            Event::BondContractDetails { .. } => EventKind::BondContractDetails,
+            Event::ContractDetailsEnd { .. } => EventKind::ContractDetailsEnd,
+            Event::ExecDetails { .. } => EventKind::ExecDetails,
+            Event::ExecDetailsEnd { .. } => EventKind::ExecDetailsEnd,
+            Event::UpdateMktDepth { .. } => EventKind::UpdateMktDepth,
+            Event::UpdateMktDepthL2 { .. } => EventKind::UpdateMktDepthL2,
+            Event::UpdateNewsBulletin { .. } => EventKind::UpdateNewsBulletin,
+            Event::ManagedAccounts { .. } => EventKind::ManagedAccounts,
+            Event::ReceiveFa { .. } => EventKind::ReceiveFa,
+            Event::HistoricalData { .. } => EventKind::HistoricalData,
+            Event::HistoricalDataEnd { .. } => EventKind::HistoricalDataEnd,
+            Event::ScannerParameters { .. } => EventKind::ScannerParameters,
+            Event::ScannerData { .. } => EventKind::ScannerData,
+            Event::ScannerDataEnd { .. } => EventKind::ScannerDataEnd,
+            Event::RealtimeBar { .. } => EventKind::RealtimeBar,
+            Event::CurrentTime { .. } => EventKind::CurrentTime,
+            Event::FundamentalData { .. } => EventKind::FundamentalData,
+            Event::DeltaNeutralValidation { .. } => EventKind::DeltaNeutralValidation,
+            Event::CommissionReport { .. } => EventKind::CommissionReport,
+            Event::Position { .. } => EventKind::Position,
+            Event::PositionEnd => EventKind::PositionEnd,
+            Event::AccountSummary { .. } => EventKind::AccountSummary,
+            Event::AccountSummaryEnd { .. } => EventKind::AccountSummaryEnd,
+            Event::VerifyMessageApi { .. } => EventKind::VerifyMessageApi,
+            Event::VerifyCompleted { .. } => EventKind::VerifyCompleted,
+            Event::VerifyAndAuthMessageApi { .. } => EventKind::VerifyAndAuthMessageApi,
+            Event::VerifyAndAuthCompleted { .. } => EventKind::VerifyAndAuthCompleted,
+            Event::DisplayGroupList { .. } => EventKind::DisplayGroupList,
+            Event::DisplayGroupUpdated { .. } => EventKind::DisplayGroupUpdated,
+            Event::PositionMulti { .. } => EventKind::PositionMulti,
+            Event::PositionMultiEnd { .. } => EventKind::PositionMultiEnd,
+            Event::AccountUpdateMulti { .. } => EventKind::AccountUpdateMulti,
+            Event::AccountUpdateMultiEnd { .. } => EventKind::AccountUpdateMultiEnd,
+            Event::TickOptionComputation { .. } => EventKind::TickOptionComputation,
+            Event::SecurityDefinitionOptionParameter { .. } => {
+                EventKind::SecurityDefinitionOptionParameter
+            }
+            Event::SecurityDefinitionOptionParameterEnd { .. } => {
+                EventKind::SecurityDefinitionOptionParameterEnd
+            }
+            Event::SoftDollarTiers { .. } => EventKind::SoftDollarTiers,
+            Event::FamilyCodes { .. } => EventKind::FamilyCodes,
+            Event::SymbolSamples { .. } => EventKind::SymbolSamples,
+            Event::MktDepthExchanges { .. } => EventKind::MktDepthExchanges,
+            Event::TickNews { .. } => EventKind::TickNews,
+            Event::SmartComponents { .. } => EventKind::SmartComponents,
+            Event::TickReqParams { .. } => EventKind::TickReqParams,
+            Event::NewsProviders { .. } => EventKind::NewsProviders,
+            Event::NewsArticle { .. } => EventKind::NewsArticle,
+            Event::HistoricalNews { .. } => EventKind::HistoricalNews,
+            Event::HistoricalNewsEnd { .. } => EventKind::HistoricalNewsEnd,
+            Event::HeadTimestamp { .. } => EventKind::HeadTimestamp,
+            Event::HistogramData { .. } => EventKind::HistogramData,
+            Event::HistoricalDataUpdate { .. } => EventKind::HistoricalDataUpdate,
+            Event::RerouteMktDataReq { .. } => EventKind::RerouteMktDataReq,
+            Event::RerouteMktDepthReq { .. } => EventKind::RerouteMktDepthReq,
+            Event::MarketRule { .. } => EventKind::MarketRule,
+            Event::Pnl { .. } => EventKind::Pnl,
+            Event::PnlSingle { .. } => EventKind::PnlSingle,
+            Event::HistoricalTicks { .. } => EventKind::HistoricalTicks,
+            Event::HistoricalTicksBidAsk { .. } => EventKind::HistoricalTicksBidAsk,
+            Event::HistoricalTicksLast { .. } => EventKind::HistoricalTicksLast,
+            Event::TickByTickAllLast { .. } => EventKind::TickByTickAllLast,
+            Event::TickByTickBidAsk { .. } => EventKind::TickByTickBidAsk,
+            Event::TickByTickMidPoint { .. } => EventKind::TickByTickMidPoint,
+            Event::OrderBound { .. } => EventKind::OrderBound,
+            Event::CompletedOrder { .. } => EventKind::CompletedOrder,
+            Event::CompletedOrdersEnd => EventKind::CompletedOrdersEnd,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The req_id, ticker_id or request_id of the callback.  For errors this is the id of the
+    /// request or order the error is about.
+    pub fn req_id(&self) -> Option<i32> {
+        match self {
+            Event::Error { req_id, .. }
+            | Event::MarketDataType { req_id, .. }
+            | Event::TickPrice { req_id, .. }
+            | Event::TickSize { req_id, .. }
+            | Event::TickSnapshotEnd { req_id, .. }
+            | Event::TickGeneric { req_id, .. }
+            | Event::TickString { req_id, .. }
+            | Event::TickRtVolume { req_id, .. }
+            | Event::TickFundamentalRatios { req_id, .. }
+            | Event::TickDividends { req_id, .. }
+            | Event::TickEfp { req_id, .. }
+            | Event::ContractDetails { req_id, .. }
+            | Event::BondContractDetails { req_id, .. }
+            | Event::ContractDetailsEnd { req_id, .. }
+            | Event::ExecDetails { req_id, .. }
+            | Event::ExecDetailsEnd { req_id, .. }
+            | Event::UpdateMktDepth { req_id, .. }
+            | Event::UpdateMktDepthL2 { req_id, .. }
+            | Event::HistoricalData { req_id, .. }
+            | Event::HistoricalDataEnd { req_id, .. }
+            | Event::ScannerData { req_id, .. }
+            | Event::ScannerDataEnd { req_id, .. }
+            | Event::RealtimeBar { req_id, .. }
+            | Event::FundamentalData { req_id, .. }
+            | Event::DeltaNeutralValidation { req_id, .. }
+            | Event::AccountSummary { req_id, .. }
+            | Event::AccountSummaryEnd { req_id, .. }
+            | Event::DisplayGroupList { req_id, .. }
+            | Event::DisplayGroupUpdated { req_id, .. }
+            | Event::PositionMulti { req_id, .. }
+            | Event::PositionMultiEnd { req_id, .. }
+            | Event::AccountUpdateMulti { req_id, .. }
+            | Event::AccountUpdateMultiEnd { req_id, .. }
+            | Event::TickOptionComputation { req_id, .. }
+            | Event::SecurityDefinitionOptionParameter { req_id, .. }
+            | Event::SecurityDefinitionOptionParameterEnd { req_id, .. }
+            | Event::SoftDollarTiers { req_id, .. }
+            | Event::SymbolSamples { req_id, .. }
+            | Event::TickNews {
+                ticker_id: req_id, ..
+            }
+            | Event::SmartComponents { req_id, .. }
+            | Event::TickReqParams {
+                ticker_id: req_id, ..
+            }
+            | Event::NewsArticle {
+                request_id: req_id, ..
+            }
+            | Event::HistoricalNews {
+                request_id: req_id, ..
+            }
+            | Event::HistoricalNewsEnd {
+                request_id: req_id, ..
+            }
+            | Event::HeadTimestamp { req_id, .. }
+            | Event::HistogramData { req_id, .. }
+            | Event::HistoricalDataUpdate { req_id, .. }
+            | Event::RerouteMktDataReq { req_id, .. }
+            | Event::RerouteMktDepthReq { req_id, .. }
+            | Event::Pnl { req_id, .. }
+            | Event::PnlSingle { req_id, .. }
+            | Event::HistoricalTicks { req_id, .. }
+            | Event::HistoricalTicksBidAsk { req_id, .. }
+            | Event::HistoricalTicksLast { req_id, .. }
+            | Event::TickByTickAllLast { req_id, .. }
+            | Event::TickByTickBidAsk { req_id, .. }
+            | Event::TickByTickMidPoint { req_id, .. } => Some(*req_id),
+            _ => None,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The order an order or execution callback is about.  Errors report order problems with the
+    /// order_id in place of the req_id, so it is returned for errors too.
+    pub fn order_id(&self) -> Option<i32> {
+        match self {
+            Event::OrderStatus { order_id, .. } | Event::OpenOrder { order_id, .. } => {
+                Some(*order_id)
+            }
+            Event::ExecDetails { execution, .. } => Some(execution.order_id),
+            Event::CompletedOrder { order, .. } => Some(order.order_id),
+            Event::OrderBound { api_order_id, .. } => Some(*api_order_id),
+            Event::Error { req_id, .. } => Some(*req_id),
+            _ => None,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The account an account, position or order callback is about
+    pub fn account(&self) -> Option<&str> {
+        match self {
+            Event::UpdateAccountValue { account_name, .. }
+            | Event::UpdatePortfolio { account_name, .. }
+            | Event::AccountDownloadEnd { account_name } => Some(account_name),
+            Event::Position { account, .. }
+            | Event::AccountSummary { account, .. }
+            | Event::PositionMulti { account, .. }
+            | Event::AccountUpdateMulti { account, .. } => Some(account),
+            Event::ExecDetails { execution, .. } => Some(&execution.acct_number),
+            Event::OpenOrder { order, .. } | Event::CompletedOrder { order, .. } => {
+                Some(&order.account)
+            }
+            _ => None,
+        }
+    }
+}
+
+//==================================================================================================
+/// The Wrapper callback an [Event] comes from
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum EventKind {
+    Error,
+    WinError,
+    ConnectAck,
+    MarketDataType,
+    TickPrice,
+    TickSize,
+    TickSnapshotEnd,
+    TickGeneric,
+    TickString,
+    TickRtVolume,
+    TickFundamentalRatios,
+    TickDividends,
+    TickEfp,
+    OrderStatus,
+    OpenOrder,
+    OpenOrderEnd,
+    ConnectionClosed,
+    UpdateAccountValue,
+    UpdatePortfolio,
+    UpdateAccountTime,
+    AccountDownloadEnd,
+    NextValidId,
+    ContractDetails,
+    BondContractDetails,
+    ContractDetailsEnd,
+    ExecDetails,
+    ExecDetailsEnd,
+    UpdateMktDepth,
+    UpdateMktDepthL2,
+    UpdateNewsBulletin,
+    ManagedAccounts,
+    ReceiveFa,
+    HistoricalData,
+    HistoricalDataEnd,
+    ScannerParameters,
+    ScannerData,
+    ScannerDataEnd,
+    RealtimeBar,
+    CurrentTime,
+    FundamentalData,
+    DeltaNeutralValidation,
+    CommissionReport,
+    Position,
+    PositionEnd,
+    AccountSummary,
+    AccountSummaryEnd,
+    VerifyMessageApi,
+    VerifyCompleted,
+    VerifyAndAuthMessageApi,
+    VerifyAndAuthCompleted,
+    DisplayGroupList,
+    DisplayGroupUpdated,
+    PositionMulti,
+    PositionMultiEnd,
+    AccountUpdateMulti,
+    AccountUpdateMultiEnd,
+    TickOptionComputation,
+    SecurityDefinitionOptionParameter,
+    SecurityDefinitionOptionParameterEnd,
+    SoftDollarTiers,
+    FamilyCodes,
+    SymbolSamples,
+    MktDepthExchanges,
+    TickNews,
+    SmartComponents,
+    TickReqParams,
+    NewsProviders,
+    NewsArticle,
+    HistoricalNews,
+    HistoricalNewsEnd,
+    HeadTimestamp,
+    HistogramData,
+    HistoricalDataUpdate,
+    RerouteMktDataReq,
+    RerouteMktDepthReq,
+    MarketRule,
+    Pnl,
+    PnlSingle,
+    HistoricalTicks,
+    HistoricalTicksBidAsk,
+    HistoricalTicksLast,
+    TickByTickAllLast,
+    TickByTickBidAsk,
+    TickByTickMidPoint,
+    OrderBound,
+    CompletedOrder,
+    CompletedOrdersEnd,
+}
+
+impl EventKind {
+    /// Name of the Wrapper callback
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventKind::Error => "error",
+            EventKind::WinError => "win_error",
+            EventKind::ConnectAck => "connect_ack",
+            EventKind::MarketDataType => "market_data_type",
+            EventKind::TickPrice => "tick_price",
+            EventKind::TickSize => "tick_size",
+            EventKind::TickSnapshotEnd => "tick_snapshot_end",
+            EventKind::TickGeneric => "tick_generic",
+            EventKind::TickString => "tick_string",
+            EventKind::TickRtVolume => "tick_rt_volume",
+            EventKind::TickFundamentalRatios => "tick_fundamental_ratios",
+            EventKind::TickDividends => "tick_dividends",
+            EventKind::TickEfp => "tick_efp",
+            EventKind::OrderStatus => "order_status",
+            EventKind::OpenOrder => "open_order",
+            EventKind::OpenOrderEnd => "open_order_end",
+            EventKind::ConnectionClosed => "connection_closed",
+            EventKind::UpdateAccountValue => "update_account_value",
+            EventKind::UpdatePortfolio => "update_portfolio",
+            EventKind::UpdateAccountTime => "update_account_time",
+            EventKind::AccountDownloadEnd => "account_download_end",
+            EventKind::NextValidId => "next_valid_id",
+            EventKind::ContractDetails => "contract_details",
+            EventKind::BondContractDetails => "bond_contract_details",
+            EventKind::ContractDetailsEnd => "contract_details_end",
+            EventKind::ExecDetails => "exec_details",
+            EventKind::ExecDetailsEnd => "exec_details_end",
+            EventKind::UpdateMktDepth => "update_mkt_depth",
+            EventKind::UpdateMktDepthL2 => "update_mkt_depth_l2",
+            EventKind::UpdateNewsBulletin => "update_news_bulletin",
+            EventKind::ManagedAccounts => "managed_accounts",
+            EventKind::ReceiveFa => "receive_fa",
+            EventKind::HistoricalData => "historical_data",
+            EventKind::HistoricalDataEnd => "historical_data_end",
+            EventKind::ScannerParameters => "scanner_parameters",
+            EventKind::ScannerData => "scanner_data",
+            EventKind::ScannerDataEnd => "scanner_data_end",
+            EventKind::RealtimeBar => "realtime_bar",
+            EventKind::CurrentTime => "current_time",
+            EventKind::FundamentalData => "fundamental_data",
+            EventKind::DeltaNeutralValidation => "delta_neutral_validation",
+            EventKind::CommissionReport => "commission_report",
+            EventKind::Position => "position",
+            EventKind::PositionEnd => "position_end",
+            EventKind::AccountSummary => "account_summary",
+            EventKind::AccountSummaryEnd => "account_summary_end",
+            EventKind::VerifyMessageApi => "verify_message_api",
+            EventKind::VerifyCompleted => "verify_completed",
+            EventKind::VerifyAndAuthMessageApi => "verify_and_auth_message_api",
+            EventKind::VerifyAndAuthCompleted => "verify_and_auth_completed",
+            EventKind::DisplayGroupList => "display_group_list",
+            EventKind::DisplayGroupUpdated => "display_group_updated",
+            EventKind::PositionMulti => "position_multi",
+            EventKind::PositionMultiEnd => "position_multi_end",
+            EventKind::AccountUpdateMulti => "account_update_multi",
+            EventKind::AccountUpdateMultiEnd => "account_update_multi_end",
+            EventKind::TickOptionComputation => "tick_option_computation",
+            EventKind::SecurityDefinitionOptionParameter => "security_definition_option_parameter",
+            EventKind::SecurityDefinitionOptionParameterEnd => {
+                "security_definition_option_parameter_end"
+            }
+            EventKind::SoftDollarTiers => "soft_dollar_tiers",
+            EventKind::FamilyCodes => "family_codes",
+            EventKind::SymbolSamples => "symbol_samples",
+            EventKind::MktDepthExchanges => "mkt_depth_exchanges",
+            EventKind::TickNews => "tick_news",
+            EventKind::SmartComponents => "smart_components",
+            EventKind::TickReqParams => "tick_req_params",
+            EventKind::NewsProviders => "news_providers",
+            EventKind::NewsArticle => "news_article",
+            EventKind::HistoricalNews => "historical_news",
+            EventKind::HistoricalNewsEnd => "historical_news_end",
+            EventKind::HeadTimestamp => "head_timestamp",
+            EventKind::HistogramData => "histogram_data",
+            EventKind::HistoricalDataUpdate => "historical_data_update",
+            EventKind::RerouteMktDataReq => "reroute_mkt_data_req",
+            EventKind::RerouteMktDepthReq => "reroute_mkt_depth_req",
+            EventKind::MarketRule => "market_rule",
+            EventKind::Pnl => "pnl",
+            EventKind::PnlSingle => "pnl_single",
+            EventKind::HistoricalTicks => "historical_ticks",
+            EventKind::HistoricalTicksBidAsk => "historical_ticks_bid_ask",
+            EventKind::HistoricalTicksLast => "historical_ticks_last",
+            EventKind::TickByTickAllLast => "tick_by_tick_all_last",
+            EventKind::TickByTickBidAsk => "tick_by_tick_bid_ask",
+            EventKind::TickByTickMidPoint => "tick_by_tick_mid_point",
+            EventKind::OrderBound => "order_bound",
+            EventKind::CompletedOrder => "completed_order",
+            EventKind::CompletedOrdersEnd => "completed_orders_end",
+        }
+    }
+}
+
+impl fmt::Display for EventKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+//==================================================================================================
+/// Selects the events a subscriber receives.  Each criterion that is set must match; within a
+/// criterion any of the values may match.  An empty filter matches every event.
+#[derive(Clone, Debug, Default)]
+pub struct EventFilter {
+    pub req_ids: HashSet<i32>,
+    pub order_ids: HashSet<i32>,
+    pub kinds: HashSet<EventKind>,
+    pub accounts: HashSet<String>,
+}
+
+impl EventFilter {
+    pub fn new() -> Self {
+        EventFilter::default()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn req_id(mut self, req_id: i32) -> Self {
+        self.req_ids.insert(req_id);
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn order_id(mut self, order_id: i32) -> Self {
+        self.order_ids.insert(order_id);
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn kind(mut self, kind: EventKind) -> Self {
+        self.kinds.insert(kind);
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn account(mut self, account: &str) -> Self {
+        self.accounts.insert(account.to_string());
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn matches(&self, event: &Event) -> bool {
+        (self.kinds.is_empty() || self.kinds.contains(&event.kind()))
+            && (self.req_ids.is_empty()
+                || event.req_id().is_some_and(|id| self.req_ids.contains(&id)))
+            && (self.order_ids.is_empty()
+                || event
+                    .order_id()
+                    .is_some_and(|id| self.order_ids.contains(&id)))
+            && (self.accounts.is_empty()
+                || event
+                    .account()
+                    .is_some_and(|account| self.accounts.contains(account)))
+    }
+}
+
+//==================================================================================================
+/// What happens to an event for a subscriber whose queue is full
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backpressure {
+    /// Wait for the subscriber to take an event off the queue.  This holds up the decoder, and
+    /// with it every other subscriber.  The bus cannot be subscribed to or unsubscribed from
+    /// while it waits, so a handler with this policy must not do either.
+    Block,
+    /// Drop the new event
+    DropNewest,
+    /// Drop the oldest queued event to make room for the new one
+    DropOldest,
+}
+
+//==================================================================================================
+/// Receives the events of a subscription run on a thread of the bus
+pub trait EventHandler: Send {
+    fn on_event(&mut self, event: &Event);
+}
+
+impl<F> EventHandler for F
+where
+    F: FnMut(&Event) + Send,
+{
+    fn on_event(&mut self, event: &Event) {
+        self(event)
+    }
+}
+
+//==================================================================================================
+struct QueueState {
+    events: VecDeque<Event>,
+    closed: bool,
+    dropped: u64,
+}
+
+//==================================================================================================
+/// Bounded queue between the bus and one subscriber
+struct EventQueue {
+    state: Mutex<QueueState>,
+    not_empty: Condvar,
+    not_full: Condvar,
+    capacity: usize,
+    backpressure: Backpressure,
+}
+
+impl EventQueue {
+    fn new(capacity: usize, backpressure: Backpressure) -> Self {
+        EventQueue {
+            state: Mutex::new(QueueState {
+                events: VecDeque::new(),
+                closed: false,
+                dropped: 0,
+            }),
+            not_empty: Condvar::new(),
+            not_full: Condvar::new(),
+            capacity: capacity.max(1),
+            backpressure,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn push(&self, event: Event) {
+        let mut state = self.state.lock().expect(QUEUE_POISONED_MUTEX);
+        while state.events.len() >= self.capacity && !state.closed {
+            match self.backpressure {
+                Backpressure::Block => {
+                    state = self.not_full.wait(state).expect(QUEUE_POISONED_MUTEX);
+                }
+                Backpressure::DropNewest => {
+                    state.dropped += 1;
+                    return;
+                }
+                Backpressure::DropOldest => {
+                    state.events.pop_front();
+                    state.dropped += 1;
+                }
+            }
+        }
+        if !state.closed {
+            state.events.push_back(event);
+            self.not_empty.notify_one();
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Waits for an event until the deadline, if any.  Returns None once the queue is closed and
+    /// empty, or at the deadline.
+    fn pop(&self, deadline: Option<Instant>) -> Option<Event> {
+        let mut state = self.state.lock().expect(QUEUE_POISONED_MUTEX);
+        loop {
+            if let Some(event) = state.events.pop_front() {
+                self.not_full.notify_one();
+                return Some(event);
+            }
+            if state.closed {
+                return None;
+            }
+            state = match deadline {
+                None => self.not_empty.wait(state).expect(QUEUE_POISONED_MUTEX),
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return None;
+                    }
+                    self.not_empty
+                        .wait_timeout(state, deadline - now)
+                        .expect(QUEUE_POISONED_MUTEX)
+                        .0
+                }
+            };
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn try_pop(&self) -> Option<Event> {
+        let event = self
+            .state
+            .lock()
+            .expect(QUEUE_POISONED_MUTEX)
+            .events
+            .pop_front();
+        if event.is_some() {
+            self.not_full.notify_one();
+        }
+        event
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Stops accepting events.  Events already queued can still be taken off.
+    fn close(&self) {
+        self.state.lock().expect(QUEUE_POISONED_MUTEX).closed = true;
+        self.not_empty.notify_all();
+        self.not_full.notify_all();
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn is_closed(&self) -> bool {
+        self.state.lock().expect(QUEUE_POISONED_MUTEX).closed
+    }
+}
+
+//==================================================================================================
+/// Receiving end of a channel subscription.  Dropping it ends the subscription.
+pub struct EventReceiver {
+    id: SubscriptionId,
+    queue: Arc<EventQueue>,
+}
+
+impl EventReceiver {
+    pub fn id(&self) -> SubscriptionId {
+        self.id
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Waits for the next event.  Returns None once the subscription has ended and every queued
+    /// event was received.
+    pub fn recv(&self) -> Option<Event> {
+        self.queue.pop(None)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<Event> {
+        self.queue.pop(Some(Instant::now() + timeout))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn try_recv(&self) -> Option<Event> {
+        self.queue.try_pop()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Number of events waiting in the queue
+    pub fn len(&self) -> usize {
+        self.queue
+            .state
+            .lock()
+            .expect(QUEUE_POISONED_MUTEX)
+            .events
+            .len()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Number of events dropped because the queue was full
+    pub fn dropped(&self) -> u64 {
+        self.queue.state.lock().expect(QUEUE_POISONED_MUTEX).dropped
+    }
+}
+
+impl Iterator for EventReceiver {
+    type Item = Event;
+
+    fn next(&mut self) -> Option<Event> {
+        self.recv()
+    }
+}
+
+impl Drop for EventReceiver {
+    fn drop(&mut self) {
+        self.queue.close();
+    }
+}
+
+//==================================================================================================
+struct Subscriber {
+    id: SubscriptionId,
+    filter: EventFilter,
+    queue: Arc<EventQueue>,
+}
+
+impl Drop for Subscriber {
+    fn drop(&mut self) {
+        self.queue.close();
+    }
+}
+
+//==================================================================================================
+/// Wrapper that delivers every callback as an [Event] to its subscribers.
+///
+/// Clones share the same subscribers, so one clone can be handed to
+/// [EClient::new](crate::core::client::EClient::new) and another kept to subscribe with.  When
+/// the last clone is dropped, every subscription ends: receivers return None once drained and
+/// handler threads exit.
+#[derive(Clone, Default)]
+pub struct EventBus {
+    subscribers: Arc<Mutex<Vec<Subscriber>>>,
+    next_id: Arc<AtomicUsize>,
+    /// Events this clone publishes, all of them if None
+    published: Option<PublishFilter>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        EventBus::default()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Bus with the same subscribers that only publishes the events `publish` accepts, such as
+    /// the wrapper of one of several clients sharing subscribers.  Filters of earlier calls apply
+    /// too.
+    pub fn filtered<F>(&self, publish: F) -> EventBus
+    where
+        F: Fn(&Event) -> bool + Send + Sync + 'static,
+    {
+        let previous = self.published.clone();
+        EventBus {
+            subscribers: self.subscribers.clone(),
+            next_id: self.next_id.clone(),
+            published: Some(Arc::new(move |event| {
+                previous.as_ref().iter().all(|previous| previous(event)) && publish(event)
+            })),
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn add(&self, filter: EventFilter, queue: Arc<EventQueue>) -> SubscriptionId {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        self.subscribers
+            .lock()
+            .expect(SUBSCRIBERS_POISONED_MUTEX)
+            .push(Subscriber { id, filter, queue });
+        id
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Subscribes a channel that queues up to capacity matching events
+    pub fn subscribe(
+        &self,
+        filter: EventFilter,
+        capacity: usize,
+        backpressure: Backpressure,
+    ) -> EventReceiver {
+        let queue = Arc::new(EventQueue::new(capacity, backpressure));
+        let id = self.add(filter, queue.clone());
+        EventReceiver { id, queue }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Subscribes a handler that is called with each matching event on a thread of its own
+    pub fn subscribe_handler(
+        &self,
+        filter: EventFilter,
+        capacity: usize,
+        backpressure: Backpressure,
+        mut handler: Box<dyn EventHandler>,
+    ) -> SubscriptionId {
+        let queue = Arc::new(EventQueue::new(capacity, backpressure));
+        let id = self.add(filter, queue.clone());
+        thread::spawn(move || {
+            while let Some(event) = queue.pop(None) {
+                handler.on_event(&event);
+            }
+        });
+        id
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Subscribes a closure that is called with each matching event on a thread of its own
+    pub fn subscribe_fn<F>(
+        &self,
+        filter: EventFilter,
+        capacity: usize,
+        backpressure: Backpressure,
+        f: F,
+    ) -> SubscriptionId
+    where
+        F: FnMut(&Event) + Send + 'static,
+    {
+        self.subscribe_handler(filter, capacity, backpressure, Box::new(f))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Ends a subscription.  Events already queued are still delivered.  Returns false if there
+    /// was no such subscription.
+    pub fn unsubscribe(&self, id: SubscriptionId) -> bool {
+        let mut subscribers = self.subscribers.lock().expect(SUBSCRIBERS_POISONED_MUTEX);
+        let count = subscribers.len();
+        subscribers.retain(|subscriber| subscriber.id != id);
+        subscribers.len() != count
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn subscriber_count(&self) -> usize {
+        let mut subscribers = self.subscribers.lock().expect(SUBSCRIBERS_POISONED_MUTEX);
+        subscribers.retain(|subscriber| !subscriber.queue.is_closed());
+        subscribers.len()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Delivers an event to every subscriber whose filter matches it, unless this bus was
+    /// [filtered](EventBus::filtered) not to publish it.  Subscribers whose receiver was dropped
+    /// are removed.
+    pub fn publish(&self, event: Event) {
+        if let Some(published) = &self.published {
+            if !published(&event) {
+                return;
+            }
+        }
+        let mut subscribers = self.subscribers.lock().expect(SUBSCRIBERS_POISONED_MUTEX);
+        subscribers.retain(|subscriber| !subscriber.queue.is_closed());
+        for subscriber in subscribers.iter() {
+            if subscriber.filter.matches(&event) {
+                subscriber.queue.push(event.clone());
+            }
+        }
+    }
+}
+
+impl Wrapper for EventBus {
+    fn error(&mut self, req_id: i32, error_code: i32, error_string: &str) {
+        self.publish(Event::Error {
+            req_id,
+            error_code,
+            error_string: error_string.to_string(),
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn win_error(&mut self, text: &str, last_error: i32) {
+        self.publish(Event::WinError {
+            text: text.to_string(),
+            last_error,
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn connect_ack(&mut self) {
+        self.publish(Event::ConnectAck);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn market_data_type(&mut self, req_id: i32, market_data_type: i32) {
+        self.publish(Event::MarketDataType {
+            req_id,
+            market_data_type,
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn tick_price(&mut self, req_id: i32, tick_type: TickType, price: f64, attrib: TickAttrib) {
+        self.publish(Event::TickPrice {
+            req_id,
+            tick_type,
+            price,
+            attrib,
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn tick_size(&mut self, req_id: i32, tick_type: TickType, size: i32) {
+        self.publish(Event::TickSize {
+            req_id,
+            tick_type,
+            size,
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn tick_snapshot_end(&mut self, req_id: i32) {
+        self.publish(Event::TickSnapshotEnd { req_id });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn tick_generic(&mut self, req_id: i32, tick_type: TickType, value: f64) {
+        self.publish(Event::TickGeneric {
+            req_id,
+            tick_type,
+            value,
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn tick_string(&mut self, req_id: i32, tick_type: TickType, value: &str) {
+        self.publish(Event::TickString {
+            req_id,
+            tick_type,
+            value: value.to_string(),
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn tick_rt_volume(
+        &mut self,
+        req_id: i32,
+        tick_type: TickType,
+        rt_volume: RtVolume,
+        _value: &str,
+    ) {
+        self.publish(Event::TickRtVolume {
+            req_id,
+            tick_type,
+            rt_volume,
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn tick_fundamental_ratios(&mut self, req_id: i32, ratios: FundamentalRatios, _value: &str) {
+        self.publish(Event::TickFundamentalRatios { req_id, ratios });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn tick_dividends(&mut self, req_id: i32, dividends: DividendInfo, _value: &str) {
+        self.publish(Event::TickDividends { req_id, dividends });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn tick_efp(
+        &mut self,
+        req_id: i32,
+        tick_type: TickType,
+        basis_points: f64,
+        formatted_basis_points: &str,
+        implied_future: f64,
+        hold_days: i32,
+        future_last_trade_date: &str,
+        dividend_impact: f64,
+        dividends_to_last_trade_date: f64,
+    ) {
+        self.publish(Event::TickEfp {
+            req_id,
+            tick_type,
+            basis_points,
+            formatted_basis_points: formatted_basis_points.to_string(),
+            implied_future,
+            hold_days,
+            future_last_trade_date: future_last_trade_date.to_string(),
+            dividend_impact,
+            dividends_to_last_trade_date,
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn order_status(
+        &mut self,
+        order_id: i32,
+        status: &str,
+        filled: f64,
+        remaining: f64,
+        avg_fill_price: f64,
+        perm_id: i32,
+        parent_id: i32,
+        last_fill_price: f64,
+        client_id: i32,
+        why_held: &str,
+        mkt_cap_price: f64,
+    ) {
+        self.publish(Event::OrderStatus {
+            order_id,
+            status: status.to_string(),
+            filled,
+            remaining,
+            avg_fill_price,
+            perm_id,
+            parent_id,
+            last_fill_price,
+            client_id,
+            why_held: why_held.to_string(),
+            mkt_cap_price,
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn open_order(
+        &mut self,
+        order_id: i32,
+        contract: Contract,
+        order: Order,
+        order_state: OrderState,
+    ) {
+        self.publish(Event::OpenOrder {
+            order_id,
+            contract,
+            order,
+            order_state,
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn open_order_end(&mut self) {
+        self.publish(Event::OpenOrderEnd);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn connection_closed(&mut self) {
+        self.publish(Event::ConnectionClosed);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn update_account_value(&mut self, key: &str, val: &str, currency: &str, account_name: &str) {
+        self.publish(Event::UpdateAccountValue {
+            key: key.to_string(),
+            val: val.to_string(),
+            currency: currency.to_string(),
+            account_name: account_name.to_string(),
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn update_portfolio(
+        &mut self,
+        contract: Contract,
+        position: f64,
+        market_price: f64,
+        market_value: f64,
+        average_cost: f64,
+        unrealized_pnl: f64,
+        realized_pnl: f64,
+        account_name: &str,
+    ) {
+        self.publish(Event::UpdatePortfolio {
+            contract,
+            position,
+            market_price,
+            market_value,
+            average_cost,
+            unrealized_pnl,
+            realized_pnl,
+            account_name: account_name.to_string(),
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn update_account_time(&mut self, time_stamp: &str) {
+        self.publish(Event::UpdateAccountTime {
+            time_stamp: time_stamp.to_string(),
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn account_download_end(&mut self, account_name: &str) {
+        self.publish(Event::AccountDownloadEnd {
+            account_name: account_name.to_string(),
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn next_valid_id(&mut self, order_id: i32) {
+        self.publish(Event::NextValidId { order_id });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn contract_details(&mut self, req_id: i32, contract_details: ContractDetails) {
+        self.publish(Event::ContractDetails {
+            req_id,
+            contract_details,
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn bond_contract_details(&mut self, req_id: i32, contract_details: ContractDetails) {
+        self.publish(Event::BondContractDetails {
+            req_id,
+            contract_details,
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn contract_details_end(&mut self, req_id: i32) {
+        self.publish(Event::ContractDetailsEnd { req_id });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn exec_details(&mut self, req_id: i32, contract: Contract, execution: Execution) {
+        self.publish(Event::ExecDetails {
+            req_id,
+            contract,
+            execution,
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn exec_details_end(&mut self, req_id: i32) {
+        self.publish(Event::ExecDetailsEnd { req_id });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn update_mkt_depth(
+        &mut self,
+        req_id: i32,
+        position: i32,
+        operation: i32,
+        side: i32,
+        price: f64,
+        size: i32,
+    ) {
+        self.publish(Event::UpdateMktDepth {
+            req_id,
+            position,
+            operation,
+            side,
+            price,
+            size,
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn update_mkt_depth_l2(
+        &mut self,
+        req_id: i32,
+        position: i32,
+        market_maker: &str,
+        operation: i32,
+        side: i32,
+        price: f64,
+        size: i32,
+        is_smart_depth: bool,
+    ) {
+        self.publish(Event::UpdateMktDepthL2 {
+            req_id,
+            position,
+            market_maker: market_maker.to_string(),
+            operation,
+            side,
+            price,
+            size,
+            is_smart_depth,
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn update_news_bulletin(
+        &mut self,
+        msg_id: i32,
+        msg_type: i32,
+        news_message: &str,
+        origin_exch: &str,
+    ) {
+        self.publish(Event::UpdateNewsBulletin {
+            msg_id,
+            msg_type,
+            news_message: news_message.to_string(),
+            origin_exch: origin_exch.to_string(),
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn managed_accounts(&mut self, accounts_list: &str) {
+        self.publish(Event::ManagedAccounts {
+            accounts_list: accounts_list.to_string(),
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn receive_fa(&mut self, fa_data: FaDataType, cxml: &str) {
+        self.publish(Event::ReceiveFa {
+            fa_data,
+            cxml: cxml.to_string(),
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn historical_data(&mut self, req_id: i32, bar: BarData) {
+        self.publish(Event::HistoricalData { req_id, bar });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn historical_data_end(&mut self, req_id: i32, start: &str, end: &str) {
+        self.publish(Event::HistoricalDataEnd {
+            req_id,
+            start: start.to_string(),
+            end: end.to_string(),
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn scanner_parameters(&mut self, xml: &str) {
+        self.publish(Event::ScannerParameters {
+            xml: xml.to_string(),
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn scanner_data(
+        &mut self,
+        req_id: i32,
+        rank: i32,
+        contract_details: ContractDetails,
+        distance: &str,
+        benchmark: &str,
+        projection: &str,
+        legs_str: &str,
+    ) {
+        self.publish(Event::ScannerData {
+            req_id,
+            rank,
+            contract_details,
+            distance: distance.to_string(),
+            benchmark: benchmark.to_string(),
+            projection: projection.to_string(),
+            legs_str: legs_str.to_string(),
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn scanner_data_end(&mut self, req_id: i32) {
+        self.publish(Event::ScannerDataEnd { req_id });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn realtime_bar(&mut self, req_id: i32, bar: RealTimeBar) {
+        self.publish(Event::RealtimeBar { req_id, bar });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn current_time(&mut self, time: i64) {
+        self.publish(Event::CurrentTime { time });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn fundamental_data(&mut self, req_id: i32, data: &str) {
+        self.publish(Event::FundamentalData {
+            req_id,
+            data: data.to_string(),
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn delta_neutral_validation(
+        &mut self,
+        req_id: i32,
+        delta_neutral_contract: DeltaNeutralContract,
+    ) {
+        self.publish(Event::DeltaNeutralValidation {
+            req_id,
+            delta_neutral_contract,
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn commission_report(&mut self, commission_report: CommissionReport) {
+        self.publish(Event::CommissionReport { commission_report });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn position(&mut self, account: &str, contract: Contract, position: f64, avg_cost: f64) {
+        self.publish(Event::Position {
+            account: account.to_string(),
+            contract,
+            position,
+            avg_cost,
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn position_end(&mut self) {
+        self.publish(Event::PositionEnd);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn account_summary(
+        &mut self,
+        req_id: i32,
+        account: &str,
+        tag: &str,
+        value: &str,
+        currency: &str,
+    ) {
+        self.publish(Event::AccountSummary {
+            req_id,
+            account: account.to_string(),
+            tag: tag.to_string(),
+            value: value.to_string(),
+            currency: currency.to_string(),
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn account_summary_end(&mut self, req_id: i32) {
+        self.publish(Event::AccountSummaryEnd { req_id });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn verify_message_api(&mut self, api_data: &str) {
+        self.publish(Event::VerifyMessageApi {
+            api_data: api_data.to_string(),
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn verify_completed(&mut self, is_successful: bool, error_text: &str) {
+        self.publish(Event::VerifyCompleted {
+            is_successful,
+            error_text: error_text.to_string(),
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn verify_and_auth_message_api(&mut self, api_data: &str, xyz_challange: &str) {
+        self.publish(Event::VerifyAndAuthMessageApi {
+            api_data: api_data.to_string(),
+            xyz_challange: xyz_challange.to_string(),
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn verify_and_auth_completed(&mut self, is_successful: bool, error_text: &str) {
+        self.publish(Event::VerifyAndAuthCompleted {
+            is_successful,
+            error_text: error_text.to_string(),
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn display_group_list(&mut self, req_id: i32, groups: &str) {
+        self.publish(Event::DisplayGroupList {
+            req_id,
+            groups: groups.to_string(),
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn display_group_updated(&mut self, req_id: i32, contract_info: &str) {
+        self.publish(Event::DisplayGroupUpdated {
+            req_id,
+            contract_info: contract_info.to_string(),
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn position_multi(
+        &mut self,
+        req_id: i32,
+        account: &str,
+        model_code: &str,
+        contract: Contract,
+        pos: f64,
+        avg_cost: f64,
+    ) {
+        self.publish(Event::PositionMulti {
+            req_id,
+            account: account.to_string(),
+            model_code: model_code.to_string(),
+            contract,
+            pos,
+            avg_cost,
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn position_multi_end(&mut self, req_id: i32) {
+        self.publish(Event::PositionMultiEnd { req_id });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn account_update_multi(
+        &mut self,
+        req_id: i32,
+        account: &str,
+        model_code: &str,
+        key: &str,
+        value: &str,
+        currency: &str,
+    ) {
+        self.publish(Event::AccountUpdateMulti {
+            req_id,
+            account: account.to_string(),
+            model_code: model_code.to_string(),
+            key: key.to_string(),
+            value: value.to_string(),
+            currency: currency.to_string(),
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn account_update_multi_end(&mut self, req_id: i32) {
+        self.publish(Event::AccountUpdateMultiEnd { req_id });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn tick_option_computation(
+        &mut self,
+        req_id: i32,
+        tick_type: TickType,
+        implied_vol: f64,
+        delta: f64,
+        opt_price: f64,
+        pv_dividend: f64,
+        gamma: f64,
+        vega: f64,
+        theta: f64,
+        und_price: f64,
+    ) {
+        self.publish(Event::TickOptionComputation {
+            req_id,
+            tick_type,
+            implied_vol,
+            delta,
+            opt_price,
+            pv_dividend,
+            gamma,
+            vega,
+            theta,
+            und_price,
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn security_definition_option_parameter(
+        &mut self,
+        req_id: i32,
+        exchange: &str,
+        underlying_con_id: i32,
+        trading_class: &str,
+        multiplier: &str,
+        expirations: HashSet<String>,
+        strikes: HashSet<BigDecimal>,
+    ) {
+        self.publish(Event::SecurityDefinitionOptionParameter {
+            req_id,
+            exchange: exchange.to_string(),
+            underlying_con_id,
+            trading_class: trading_class.to_string(),
+            multiplier: multiplier.to_string(),
+            expirations,
+            strikes,
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn security_definition_option_parameter_end(&mut self, req_id: i32) {
+        self.publish(Event::SecurityDefinitionOptionParameterEnd { req_id });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn soft_dollar_tiers(&mut self, req_id: i32, tiers: Vec<SoftDollarTier>) {
+        self.publish(Event::SoftDollarTiers { req_id, tiers });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn family_codes(&mut self, family_codes: Vec<FamilyCode>) {
+        self.publish(Event::FamilyCodes { family_codes });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn symbol_samples(&mut self, req_id: i32, contract_descriptions: Vec<ContractDescription>) {
+        self.publish(Event::SymbolSamples {
+            req_id,
+            contract_descriptions,
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn mkt_depth_exchanges(&mut self, depth_mkt_data_descriptions: Vec<DepthMktDataDescription>) {
+        self.publish(Event::MktDepthExchanges {
+            depth_mkt_data_descriptions,
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn tick_news(
+        &mut self,
+        ticker_id: i32,
+        time_stamp: i32,
+        provider_code: &str,
+        article_id: &str,
+        headline: &str,
+        extra_data: &str,
+    ) {
+        self.publish(Event::TickNews {
+            ticker_id,
+            time_stamp,
+            provider_code: provider_code.to_string(),
+            article_id: article_id.to_string(),
+            headline: headline.to_string(),
+            extra_data: extra_data.to_string(),
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn smart_components(&mut self, req_id: i32, smart_components: Vec<SmartComponent>) {
+        self.publish(Event::SmartComponents {
+            req_id,
+            smart_components,
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn tick_req_params(
+        &mut self,
+        ticker_id: i32,
+        min_tick: f64,
+        bbo_exchange: &str,
+        snapshot_permissions: i32,
+    ) {
+        self.publish(Event::TickReqParams {
+            ticker_id,
+            min_tick,
+            bbo_exchange: bbo_exchange.to_string(),
+            snapshot_permissions,
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn news_providers(&mut self, news_providers: Vec<NewsProvider>) {
+        self.publish(Event::NewsProviders { news_providers });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn news_article(&mut self, request_id: i32, article_type: i32, article_text: &str) {
+        self.publish(Event::NewsArticle {
+            request_id,
+            article_type,
+            article_text: article_text.to_string(),
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn historical_news(
+        &mut self,
+        request_id: i32,
+        time: &str,
+        provider_code: &str,
+        article_id: &str,
+        headline: &str,
+    ) {
+        self.publish(Event::HistoricalNews {
+            request_id,
+            time: time.to_string(),
+            provider_code: provider_code.to_string(),
+            article_id: article_id.to_string(),
+            headline: headline.to_string(),
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn historical_news_end(&mut self, request_id: i32, has_more: bool) {
+        self.publish(Event::HistoricalNewsEnd {
+            request_id,
+            has_more,
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn head_timestamp(&mut self, req_id: i32, head_timestamp: &str) {
+        self.publish(Event::HeadTimestamp {
+            req_id,
+            head_timestamp: head_timestamp.to_string(),
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn histogram_data(&mut self, req_id: i32, items: Vec<HistogramData>) {
+        self.publish(Event::HistogramData { req_id, items });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn historical_data_update(&mut self, req_id: i32, bar: BarData) {
+        self.publish(Event::HistoricalDataUpdate { req_id, bar });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn reroute_mkt_data_req(&mut self, req_id: i32, con_id: i32, exchange: &str) {
+        self.publish(Event::RerouteMktDataReq {
+            req_id,
+            con_id,
+            exchange: exchange.to_string(),
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn reroute_mkt_depth_req(&mut self, req_id: i32, con_id: i32, exchange: &str) {
+        self.publish(Event::RerouteMktDepthReq {
+            req_id,
+            con_id,
+            exchange: exchange.to_string(),
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn market_rule(&mut self, market_rule_id: i32, price_increments: Vec<PriceIncrement>) {
+        self.publish(Event::MarketRule {
+            market_rule_id,
+            price_increments,
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn pnl(&mut self, req_id: i32, daily_pn_l: f64, unrealized_pn_l: f64, realized_pn_l: f64) {
+        self.publish(Event::Pnl {
+            req_id,
+            daily_pn_l,
+            unrealized_pn_l,
+            realized_pn_l,
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn pnl_single(
+        &mut self,
+        req_id: i32,
+        pos: i32,
+        daily_pn_l: f64,
+        unrealized_pn_l: f64,
+        realized_pn_l: f64,
+        value: f64,
+    ) {
+        self.publish(Event::PnlSingle {
+            req_id,
+            pos,
+            daily_pn_l,
+            unrealized_pn_l,
+            realized_pn_l,
+            value,
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn historical_ticks(&mut self, req_id: i32, ticks: Vec<HistoricalTick>, done: bool) {
+        self.publish(Event::HistoricalTicks {
+            req_id,
+            ticks,
+            done,
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn historical_ticks_bid_ask(
+        &mut self,
+        req_id: i32,
+        ticks: Vec<HistoricalTickBidAsk>,
+        done: bool,
+    ) {
+        self.publish(Event::HistoricalTicksBidAsk {
+            req_id,
+            ticks,
+            done,
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn historical_ticks_last(&mut self, req_id: i32, ticks: Vec<HistoricalTickLast>, done: bool) {
+        self.publish(Event::HistoricalTicksLast {
+            req_id,
+            ticks,
+            done,
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn tick_by_tick_all_last(
+        &mut self,
+        req_id: i32,
+        tick_type: TickByTickType,
+        time: i64,
+        price: f64,
+        size: i32,
+        tick_attrib_last: TickAttribLast,
+        exchange: &str,
+        special_conditions: &str,
+    ) {
+        self.publish(Event::TickByTickAllLast {
+            req_id,
+            tick_type,
+            time,
+            price,
+            size,
+            tick_attrib_last,
+            exchange: exchange.to_string(),
+            special_conditions: special_conditions.to_string(),
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn tick_by_tick_bid_ask(
+        &mut self,
+        req_id: i32,
+        time: i64,
+        bid_price: f64,
+        ask_price: f64,
+        bid_size: i32,
+        ask_size: i32,
+        tick_attrib_bid_ask: TickAttribBidAsk,
+    ) {
+        self.publish(Event::TickByTickBidAsk {
+            req_id,
+            time,
+            bid_price,
+            ask_price,
+            bid_size,
+            ask_size,
+            tick_attrib_bid_ask,
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn tick_by_tick_mid_point(&mut self, req_id: i32, time: i64, mid_point: f64) {
+        self.publish(Event::TickByTickMidPoint {
+            req_id,
+            time,
+            mid_point,
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn order_bound(&mut self, req_id: i32, api_client_id: i32, api_order_id: i32) {
+        self.publish(Event::OrderBound {
+            req_id,
+            api_client_id,
+            api_order_id,
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn completed_order(&mut self, contract: Contract, order: Order, order_state: OrderState) {
+        self.publish(Event::CompletedOrder {
+            contract,
+            order,
+            order_state,
+        });
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn completed_orders_end(&mut self) {
+        self.publish(Event::CompletedOrdersEnd);
+    }
+}
@@ -0,0 +1,770 @@
+//! Typed financial advisor groups, allocation profiles and account aliases
+//!
+//! Enabled with the `financial-advisor` feature.
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use roxmltree::{Document, Node};
+use serde::{Deserialize, Serialize};
+
+use crate::core::client::EClient;
+use crate::core::common::{FaDataType, Method, NO_VALID_ID};
+use crate::core::errors::{IBKRApiLibError, TwsApiReportableError, TwsError};
+use crate::core::wrapper::Wrapper;
+use crate::core::xml::{child, child_text, children, node_path, text};
+
+const XML_HEADER: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>";
+
+//==================================================================================================
+fn fa_error(description: String) -> IBKRApiLibError {
+    IBKRApiLibError::ApiError(TwsApiReportableError::new(
+        NO_VALID_ID,
+        TwsError::BadMessage.code().to_string(),
+        description,
+    ))
+}
+
+//==================================================================================================
+fn escape_xml(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+//==================================================================================================
+fn parse_fa_document<'input>(
+    xml: &'input str,
+    root_name: &str,
+) -> Result<Document<'input>, IBKRApiLibError> {
+    let document = Document::parse(xml)
+        .map_err(|err| fa_error(format!("Invalid FA configuration XML: {}", err)))?;
+    let found = document.root_element().tag_name().name().to_string();
+    if found != root_name {
+        return Err(fa_error(format!(
+            "Invalid FA configuration XML: expected root element <{}> but found <{}>",
+            root_name, found
+        )));
+    }
+    Ok(document)
+}
+
+//==================================================================================================
+fn expect_element(node: Node, name: &str) -> Result<(), IBKRApiLibError> {
+    if node.tag_name().name() == name {
+        Ok(())
+    } else {
+        Err(fa_error(format!(
+            "Invalid FA configuration XML: expected <{}> but found <{}> at {}",
+            name,
+            node.tag_name().name(),
+            node_path(node)
+        )))
+    }
+}
+
+//==================================================================================================
+/// Splits the comma separated list received in Wrapper::managed_accounts
+pub fn parse_managed_accounts(accounts_list: &str) -> Vec<String> {
+    accounts_list
+        .split(',')
+        .map(|account| account.trim())
+        .filter(|account| !account.is_empty())
+        .map(|account| account.to_string())
+        .collect()
+}
+
+//==================================================================================================
+/// A group of accounts that orders are allocated across with a single method
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct FaGroup {
+    pub name: String,
+    pub accounts: Vec<String>,
+    pub default_method: Method,
+}
+
+impl FaGroup {
+    pub fn new(name: &str, accounts: Vec<String>, default_method: Method) -> Self {
+        FaGroup {
+            name: name.to_string(),
+            accounts,
+            default_method,
+        }
+    }
+}
+
+impl fmt::Display for FaGroup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "name: {}, accounts: {}, default_method: {}",
+            self.name,
+            self.accounts.join(","),
+            self.default_method
+        )
+    }
+}
+
+//==================================================================================================
+/// How the amounts of an allocation profile are interpreted
+#[repr(i32)]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FaProfileType {
+    Percentages = 1,
+    Ratios = 2,
+    Shares = 3,
+}
+
+impl FaProfileType {
+    pub fn from_code(code: i32) -> Option<Self> {
+        match code {
+            1 => Some(FaProfileType::Percentages),
+            2 => Some(FaProfileType::Ratios),
+            3 => Some(FaProfileType::Shares),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for FaProfileType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FaProfileType::Percentages => write!(f, "Percentages"),
+            FaProfileType::Ratios => write!(f, "Ratios"),
+            FaProfileType::Shares => write!(f, "Shares"),
+        }
+    }
+}
+
+//==================================================================================================
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct FaAllocation {
+    pub account: String,
+    pub amount: f64,
+}
+
+impl FaAllocation {
+    pub fn new(account: &str, amount: f64) -> Self {
+        FaAllocation {
+            account: account.to_string(),
+            amount,
+        }
+    }
+}
+
+//==================================================================================================
+/// An allocation profile which allocates shares account by account
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct FaProfile {
+    pub name: String,
+    pub profile_type: FaProfileType,
+    pub allocations: Vec<FaAllocation>,
+}
+
+impl FaProfile {
+    pub fn new(name: &str, profile_type: FaProfileType, allocations: Vec<FaAllocation>) -> Self {
+        FaProfile {
+            name: name.to_string(),
+            profile_type,
+            allocations,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn amount(&self, account: &str) -> Option<f64> {
+        self.allocations
+            .iter()
+            .find(|allocation| allocation.account == account)
+            .map(|allocation| allocation.amount)
+    }
+}
+
+impl fmt::Display for FaProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "name: {}, type: {}, allocations: ",
+            self.name, self.profile_type
+        )?;
+        for allocation in self.allocations.iter() {
+            write!(f, "{}={};", allocation.account, allocation.amount)?;
+        }
+        Ok(())
+    }
+}
+
+//==================================================================================================
+/// A meaningful name for an account
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct FaAlias {
+    pub account: String,
+    pub alias: String,
+}
+
+impl FaAlias {
+    pub fn new(account: &str, alias: &str) -> Self {
+        FaAlias {
+            account: account.to_string(),
+            alias: alias.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for FaAlias {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "account: {}, alias: {}", self.account, self.alias)
+    }
+}
+
+//==================================================================================================
+/// A single difference between two FA configurations, as reported by [FaConfiguration::diff]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum FaChange {
+    GroupAdded(FaGroup),
+    GroupRemoved(String),
+    GroupAccountAdded {
+        group: String,
+        account: String,
+    },
+    GroupAccountRemoved {
+        group: String,
+        account: String,
+    },
+    GroupMethodChanged {
+        group: String,
+        from: Method,
+        to: Method,
+    },
+    ProfileAdded(FaProfile),
+    ProfileRemoved(String),
+    ProfileTypeChanged {
+        profile: String,
+        from: FaProfileType,
+        to: FaProfileType,
+    },
+    AllocationChanged {
+        profile: String,
+        account: String,
+        from: Option<f64>,
+        to: Option<f64>,
+    },
+    AliasAdded(FaAlias),
+    AliasRemoved(FaAlias),
+    AliasChanged {
+        account: String,
+        from: String,
+        to: String,
+    },
+}
+
+impl fmt::Display for FaChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let amount = |amount: &Option<f64>| match amount {
+            Some(amount) => format!("{}", amount),
+            None => "none".to_string(),
+        };
+        match self {
+            FaChange::GroupAdded(group) => write!(f, "add group {}", group),
+            FaChange::GroupRemoved(name) => write!(f, "remove group {}", name),
+            FaChange::GroupAccountAdded { group, account } => {
+                write!(f, "group {}: add account {}", group, account)
+            }
+            FaChange::GroupAccountRemoved { group, account } => {
+                write!(f, "group {}: remove account {}", group, account)
+            }
+            FaChange::GroupMethodChanged { group, from, to } => {
+                write!(f, "group {}: default method {} -> {}", group, from, to)
+            }
+            FaChange::ProfileAdded(profile) => write!(f, "add profile {}", profile),
+            FaChange::ProfileRemoved(name) => write!(f, "remove profile {}", name),
+            FaChange::ProfileTypeChanged { profile, from, to } => {
+                write!(f, "profile {}: type {} -> {}", profile, from, to)
+            }
+            FaChange::AllocationChanged {
+                profile,
+                account,
+                from,
+                to,
+            } => write!(
+                f,
+                "profile {}: account {} {} -> {}",
+                profile,
+                account,
+                amount(from),
+                amount(to)
+            ),
+            FaChange::AliasAdded(alias) => write!(f, "add alias {}", alias),
+            FaChange::AliasRemoved(alias) => write!(f, "remove alias {}", alias),
+            FaChange::AliasChanged { account, from, to } => {
+                write!(f, "account {}: alias {} -> {}", account, from, to)
+            }
+        }
+    }
+}
+
+//==================================================================================================
+/// The FA configuration of an advisor account: groups, allocation profiles and account aliases.
+///
+/// Forward receive_fa to [FaConfiguration::receive_fa] to load the current configuration,
+/// edit the typed values, check them with [FaConfiguration::validate] and [FaConfiguration::diff]
+/// and send them with [FaConfiguration::replace_fa].
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct FaConfiguration {
+    pub groups: Vec<FaGroup>,
+    pub profiles: Vec<FaProfile>,
+    pub aliases: Vec<FaAlias>,
+}
+
+impl FaConfiguration {
+    pub fn new() -> Self {
+        FaConfiguration::default()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn group(&self, name: &str) -> Option<&FaGroup> {
+        self.groups.iter().find(|group| group.name == name)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn profile(&self, name: &str) -> Option<&FaProfile> {
+        self.profiles.iter().find(|profile| profile.name == name)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn alias(&self, account: &str) -> Option<&str> {
+        self.aliases
+            .iter()
+            .find(|alias| alias.account == account)
+            .map(|alias| alias.alias.as_str())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Forwarded from Wrapper::receive_fa.  Replaces the part of the configuration described
+    /// by fa_data with the parsed XML.
+    pub fn receive_fa(&mut self, fa_data: FaDataType, cxml: &str) -> Result<(), IBKRApiLibError> {
+        match fa_data {
+            FaDataType::GROUPS => self.groups = groups_from_xml(cxml)?,
+            FaDataType::PROFILES => self.profiles = profiles_from_xml(cxml)?,
+            FaDataType::ALIASES => self.aliases = aliases_from_xml(cxml)?,
+            FaDataType::NA => {
+                return Err(fa_error(
+                    "Invalid FA data type: NA carries no configuration".to_string(),
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The XML sent to replace_fa for the part of the configuration described by fa_data
+    pub fn to_xml(&self, fa_data: FaDataType) -> Result<String, IBKRApiLibError> {
+        match fa_data {
+            FaDataType::GROUPS => Ok(groups_to_xml(self.groups.as_slice())),
+            FaDataType::PROFILES => Ok(profiles_to_xml(self.profiles.as_slice())),
+            FaDataType::ALIASES => Ok(aliases_to_xml(self.aliases.as_slice())),
+            FaDataType::NA => Err(fa_error(
+                "Invalid FA data type: NA carries no configuration".to_string(),
+            )),
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Checks the configuration against the accounts received in Wrapper::managed_accounts.
+    ///
+    /// Names must be unique and not empty, every account must be managed, groups must have
+    /// accounts, allocation amounts must be positive and percentage profiles must add up to 100.
+    /// All problems found are reported in a single error.
+    pub fn validate(&self, managed_accounts: &str) -> Result<(), IBKRApiLibError> {
+        let managed = parse_managed_accounts(managed_accounts)
+            .into_iter()
+            .collect::<HashSet<String>>();
+        let mut problems = vec![];
+        let check_account = |owner: String, account: &str, problems: &mut Vec<String>| {
+            if !managed.contains(account) {
+                problems.push(format!("{}: account {} is not managed", owner, account));
+            }
+        };
+
+        let mut names = HashSet::new();
+        for group in self.groups.iter() {
+            let owner = format!("group '{}'", group.name);
+            if group.name.trim().is_empty() {
+                problems.push("group with an empty name".to_string());
+            } else if !names.insert(group.name.as_str()) {
+                problems.push(format!("{}: duplicate name", owner));
+            }
+            if group.accounts.is_empty() {
+                problems.push(format!("{}: no accounts", owner));
+            }
+            let mut accounts = HashSet::new();
+            for account in group.accounts.iter() {
+                if !accounts.insert(account.as_str()) {
+                    problems.push(format!("{}: account {} listed twice", owner, account));
+                }
+                check_account(owner.clone(), account, &mut problems);
+            }
+        }
+
+        let mut names = HashSet::new();
+        for profile in self.profiles.iter() {
+            let owner = format!("profile '{}'", profile.name);
+            if profile.name.trim().is_empty() {
+                problems.push("profile with an empty name".to_string());
+            } else if !names.insert(profile.name.as_str()) {
+                problems.push(format!("{}: duplicate name", owner));
+            }
+            if profile.allocations.is_empty() {
+                problems.push(format!("{}: no allocations", owner));
+            }
+            let mut accounts = HashSet::new();
+            for allocation in profile.allocations.iter() {
+                if !accounts.insert(allocation.account.as_str()) {
+                    problems.push(format!(
+                        "{}: account {} allocated twice",
+                        owner, allocation.account
+                    ));
+                }
+                if allocation.amount.is_nan() || allocation.amount <= 0.0 {
+                    problems.push(format!(
+                        "{}: amount {} for account {} must be positive",
+                        owner, allocation.amount, allocation.account
+                    ));
+                }
+                check_account(owner.clone(), allocation.account.as_str(), &mut problems);
+            }
+            if profile.profile_type == FaProfileType::Percentages && !profile.allocations.is_empty()
+            {
+                let total: f64 = profile.allocations.iter().map(|a| a.amount).sum();
+                if (total - 100.0).abs() > 1e-6 {
+                    problems.push(format!(
+                        "{}: percentages add up to {} instead of 100",
+                        owner, total
+                    ));
+                }
+            }
+        }
+
+        let mut accounts = HashSet::new();
+        for alias in self.aliases.iter() {
+            let owner = format!("alias '{}'", alias.alias);
+            if !accounts.insert(alias.account.as_str()) {
+                problems.push(format!("account {} has more than one alias", alias.account));
+            }
+            check_account(owner, alias.account.as_str(), &mut problems);
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(fa_error(format!(
+                "Invalid FA configuration: {}",
+                problems.join("; ")
+            )))
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// What replacing this configuration with `new` would change, in the order groups,
+    /// profiles, aliases
+    pub fn diff(&self, new: &FaConfiguration) -> Vec<FaChange> {
+        let mut changes = vec![];
+
+        let old_groups = self
+            .groups
+            .iter()
+            .map(|group| (group.name.as_str(), group))
+            .collect::<HashMap<&str, &FaGroup>>();
+        for group in new.groups.iter() {
+            match old_groups.get(group.name.as_str()) {
+                None => changes.push(FaChange::GroupAdded(group.clone())),
+                Some(old) => {
+                    for account in group.accounts.iter() {
+                        if !old.accounts.contains(account) {
+                            changes.push(FaChange::GroupAccountAdded {
+                                group: group.name.clone(),
+                                account: account.clone(),
+                            });
+                        }
+                    }
+                    for account in old.accounts.iter() {
+                        if !group.accounts.contains(account) {
+                            changes.push(FaChange::GroupAccountRemoved {
+                                group: group.name.clone(),
+                                account: account.clone(),
+                            });
+                        }
+                    }
+                    if old.default_method != group.default_method {
+                        changes.push(FaChange::GroupMethodChanged {
+                            group: group.name.clone(),
+                            from: old.default_method,
+                            to: group.default_method,
+                        });
+                    }
+                }
+            }
+        }
+        for group in self.groups.iter() {
+            if new.group(group.name.as_str()).is_none() {
+                changes.push(FaChange::GroupRemoved(group.name.clone()));
+            }
+        }
+
+        for profile in new.profiles.iter() {
+            match self.profile(profile.name.as_str()) {
+                None => changes.push(FaChange::ProfileAdded(profile.clone())),
+                Some(old) => {
+                    if old.profile_type != profile.profile_type {
+                        changes.push(FaChange::ProfileTypeChanged {
+                            profile: profile.name.clone(),
+                            from: old.profile_type,
+                            to: profile.profile_type,
+                        });
+                    }
+                    for allocation in profile.allocations.iter() {
+                        let from = old.amount(allocation.account.as_str());
+                        if from != Some(allocation.amount) {
+                            changes.push(FaChange::AllocationChanged {
+                                profile: profile.name.clone(),
+                                account: allocation.account.clone(),
+                                from,
+                                to: Some(allocation.amount),
+                            });
+                        }
+                    }
+                    for allocation in old.allocations.iter() {
+                        if profile.amount(allocation.account.as_str()).is_none() {
+                            changes.push(FaChange::AllocationChanged {
+                                profile: profile.name.clone(),
+                                account: allocation.account.clone(),
+                                from: Some(allocation.amount),
+                                to: None,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        for profile in self.profiles.iter() {
+            if new.profile(profile.name.as_str()).is_none() {
+                changes.push(FaChange::ProfileRemoved(profile.name.clone()));
+            }
+        }
+
+        for alias in new.aliases.iter() {
+            match self.alias(alias.account.as_str()) {
+                None => changes.push(FaChange::AliasAdded(alias.clone())),
+                Some(old) if old != alias.alias => changes.push(FaChange::AliasChanged {
+                    account: alias.account.clone(),
+                    from: old.to_string(),
+                    to: alias.alias.clone(),
+                }),
+                Some(_) => (),
+            }
+        }
+        for alias in self.aliases.iter() {
+            if new.alias(alias.account.as_str()).is_none() {
+                changes.push(FaChange::AliasRemoved(alias.clone()));
+            }
+        }
+
+        changes
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Validates the configuration and sends the part described by fa_data to replace_fa
+    pub fn replace_fa<T: Wrapper + Send + Sync + 'static>(
+        &self,
+        client: &mut EClient<T>,
+        fa_data: FaDataType,
+        managed_accounts: &str,
+    ) -> Result<(), IBKRApiLibError> {
+        self.validate(managed_accounts)?;
+        let cxml = self.to_xml(fa_data.clone())?;
+        client.replace_fa(fa_data, cxml.as_str())
+    }
+}
+
+impl fmt::Display for FaConfiguration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "groups: {}, profiles: {}, aliases: {}",
+            self.groups.len(),
+            self.profiles.len(),
+            self.aliases.len()
+        )
+    }
+}
+
+//==================================================================================================
+/// Parses the ListOfGroups XML used by receive_fa and replace_fa
+pub fn groups_from_xml(xml: &str) -> Result<Vec<FaGroup>, IBKRApiLibError> {
+    let document = parse_fa_document(xml, "ListOfGroups")?;
+    let mut groups = vec![];
+    for group in children(document.root_element()) {
+        expect_element(group, "Group")?;
+        let method = child_text(group, "defaultMethod");
+        let mut accounts = vec![];
+        if let Some(list) = child(group, "ListOfAccts") {
+            for account in children(list) {
+                expect_element(account, "String")?;
+                accounts.push(text(account));
+            }
+        }
+        groups.push(FaGroup {
+            name: child_text(group, "name"),
+            accounts,
+            default_method: Method::from_name(method.as_str()).ok_or_else(|| {
+                fa_error(format!(
+                    "Invalid FA configuration XML: unknown allocation method '{}' at {}",
+                    method,
+                    node_path(group)
+                ))
+            })?,
+        });
+    }
+    Ok(groups)
+}
+
+//==================================================================================================
+/// Writes the ListOfGroups XML used by replace_fa
+pub fn groups_to_xml(groups: &[FaGroup]) -> String {
+    let mut xml = format!("{}<ListOfGroups>", XML_HEADER);
+    for group in groups {
+        xml.push_str(
+            format!(
+                "<Group><name>{}</name><ListOfAccts varName=\"list\">",
+                escape_xml(group.name.as_str())
+            )
+            .as_str(),
+        );
+        for account in group.accounts.iter() {
+            xml.push_str(format!("<String>{}</String>", escape_xml(account)).as_str());
+        }
+        xml.push_str(
+            format!(
+                "</ListOfAccts><defaultMethod>{}</defaultMethod></Group>",
+                group.default_method
+            )
+            .as_str(),
+        );
+    }
+    xml.push_str("</ListOfGroups>");
+    xml
+}
+
+//==================================================================================================
+/// Parses the ListOfAllocationProfiles XML used by receive_fa and replace_fa
+pub fn profiles_from_xml(xml: &str) -> Result<Vec<FaProfile>, IBKRApiLibError> {
+    let document = parse_fa_document(xml, "ListOfAllocationProfiles")?;
+    let mut profiles = vec![];
+    for profile in children(document.root_element()) {
+        expect_element(profile, "AllocationProfile")?;
+        let profile_type = child_text(profile, "type");
+        let profile_type = profile_type
+            .parse::<i32>()
+            .ok()
+            .and_then(FaProfileType::from_code)
+            .ok_or_else(|| {
+                fa_error(format!(
+                    "Invalid FA configuration XML: unknown profile type '{}' at {}",
+                    profile_type,
+                    node_path(profile)
+                ))
+            })?;
+        let mut allocations = vec![];
+        if let Some(list) = child(profile, "ListOfAllocations") {
+            for allocation in children(list) {
+                expect_element(allocation, "Allocation")?;
+                let amount = child_text(allocation, "amount");
+                allocations.push(FaAllocation {
+                    account: child_text(allocation, "acct"),
+                    amount: amount.parse::<f64>().map_err(|_| {
+                        fa_error(format!(
+                            "Invalid FA configuration XML: amount '{}' is not a number at {}",
+                            amount,
+                            node_path(allocation)
+                        ))
+                    })?,
+                });
+            }
+        }
+        profiles.push(FaProfile {
+            name: child_text(profile, "name"),
+            profile_type,
+            allocations,
+        });
+    }
+    Ok(profiles)
+}
+
+//==================================================================================================
+/// Writes the ListOfAllocationProfiles XML used by replace_fa
+pub fn profiles_to_xml(profiles: &[FaProfile]) -> String {
+    let mut xml = format!("{}<ListOfAllocationProfiles>", XML_HEADER);
+    for profile in profiles {
+        xml.push_str(
+            format!(
+                "<AllocationProfile><name>{}</name><type>{}</type><ListOfAllocations varName=\"listOfAllocations\">",
+                escape_xml(profile.name.as_str()),
+                profile.profile_type as i32
+            )
+            .as_str(),
+        );
+        for allocation in profile.allocations.iter() {
+            xml.push_str(
+                format!(
+                    "<Allocation><acct>{}</acct><amount>{:?}</amount></Allocation>",
+                    escape_xml(allocation.account.as_str()),
+                    allocation.amount
+                )
+                .as_str(),
+            );
+        }
+        xml.push_str("</ListOfAllocations></AllocationProfile>");
+    }
+    xml.push_str("</ListOfAllocationProfiles>");
+    xml
+}
+
+//==================================================================================================
+/// Parses the ListOfAccountAliases XML used by receive_fa and replace_fa
+pub fn aliases_from_xml(xml: &str) -> Result<Vec<FaAlias>, IBKRApiLibError> {
+    let document = parse_fa_document(xml, "ListOfAccountAliases")?;
+    let mut aliases = vec![];
+    for alias in children(document.root_element()) {
+        expect_element(alias, "AccountAlias")?;
+        aliases.push(FaAlias {
+            account: child_text(alias, "account"),
+            alias: child_text(alias, "alias"),
+        });
+    }
+    Ok(aliases)
+}
+
+//==================================================================================================
+/// Writes the ListOfAccountAliases XML used by replace_fa
+pub fn aliases_to_xml(aliases: &[FaAlias]) -> String {
+    let mut xml = format!("{}<ListOfAccountAliases>", XML_HEADER);
+    for alias in aliases {
+        xml.push_str(
+            format!(
+                "<AccountAlias><account>{}</account><alias>{}</alias></AccountAlias>",
+                escape_xml(alias.account.as_str()),
+                escape_xml(alias.alias.as_str())
+            )
+            .as_str(),
+        );
+    }
+    xml.push_str("</ListOfAccountAliases>");
+    xml
+}
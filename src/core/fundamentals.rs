@@ -0,0 +1,901 @@
+//! Typed parsing of the XML reports returned to fundamental_data.
+//!
+//! Enabled with the `fundamentals` feature.  Call [parse_fundamental_data] with the
+//! [FundamentalType] that was passed to req_fundamental_data and the XML received in the
+//! fundamental_data callback.  Documents whose root element does not match the report type,
+//! elements that are not allowed where they appear, and values that can't be parsed are
+//! reported as errors naming the offending element.
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use roxmltree::{Document, Node};
+use serde::{Deserialize, Serialize};
+
+use crate::core::common::{FundamentalType, NO_VALID_ID};
+use crate::core::errors::{IBKRApiLibError, TwsApiReportableError, TwsError};
+use crate::core::xml::{attribute, child, child_text, children, node_path, text};
+
+//==================================================================================================
+fn fundamentals_error(node: Option<Node>, description: &str) -> IBKRApiLibError {
+    let location = match node {
+        Some(node) => format!(" at {}", node_path(node)),
+        None => "".to_string(),
+    };
+    IBKRApiLibError::ApiError(TwsApiReportableError::new(
+        NO_VALID_ID,
+        TwsError::BadMessage.code().to_string(),
+        format!("Fundamental data error{}: {}", location, description),
+    ))
+}
+
+//----------------------------------------------------------------------------------------------
+fn parse_document(xml: &str) -> Result<Document<'_>, IBKRApiLibError> {
+    Document::parse(xml).map_err(|err| fundamentals_error(None, err.to_string().as_str()))
+}
+
+//----------------------------------------------------------------------------------------------
+fn expect_root<'a, 'input>(
+    document: &'a Document<'input>,
+    expected: &[&str],
+) -> Result<Node<'a, 'input>, IBKRApiLibError> {
+    let root = document.root_element();
+    if expected.contains(&root.tag_name().name()) {
+        Ok(root)
+    } else {
+        Err(fundamentals_error(
+            None,
+            format!(
+                "expected root element <{}> but found <{}>",
+                expected.join("> or <"),
+                root.tag_name().name()
+            )
+            .as_str(),
+        ))
+    }
+}
+
+//----------------------------------------------------------------------------------------------
+fn required_attribute(node: Node, name: &str) -> Result<String, IBKRApiLibError> {
+    node.attribute(name)
+        .map(|val| val.to_string())
+        .ok_or_else(|| {
+            fundamentals_error(
+                Some(node),
+                format!("missing required attribute '{}'", name).as_str(),
+            )
+        })
+}
+
+//----------------------------------------------------------------------------------------------
+fn parse_f64(node: Node, value: &str) -> Result<f64, IBKRApiLibError> {
+    value.trim().parse::<f64>().map_err(|_| {
+        fundamentals_error(
+            Some(node),
+            format!("'{}' is not a number", value.trim()).as_str(),
+        )
+    })
+}
+
+//----------------------------------------------------------------------------------------------
+fn optional_f64(node: Option<Node>) -> Result<Option<f64>, IBKRApiLibError> {
+    match node {
+        Some(node) if !text(node).is_empty() => Ok(Some(parse_f64(node, text(node).as_str())?)),
+        _ => Ok(None),
+    }
+}
+
+//----------------------------------------------------------------------------------------------
+fn optional_i32_attribute(node: Node, name: &str) -> Result<Option<i32>, IBKRApiLibError> {
+    match node.attribute(name) {
+        Some(value) if !value.trim().is_empty() => Ok(Some(parse_f64(node, value)? as i32)),
+        _ => Ok(None),
+    }
+}
+
+//----------------------------------------------------------------------------------------------
+fn unexpected_element(node: Node) -> IBKRApiLibError {
+    fundamentals_error(
+        Some(node),
+        format!("unexpected element <{}>", node.tag_name().name()).as_str(),
+    )
+}
+
+//----------------------------------------------------------------------------------------------
+fn company_ids(root: Node) -> HashMap<String, String> {
+    child(root, "CoIDs")
+        .map(|ids| {
+            children(ids)
+                .filter(|id| id.tag_name().name() == "CoID")
+                .map(|id| (attribute(id, "Type"), text(id)))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+//==================================================================================================
+/// A report parsed from fundamental_data
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum FundamentalReport {
+    Snapshot(CompanySnapshot),
+    FinancialSummary(FinancialSummary),
+    Ratios(RatioReport),
+    FinancialStatements(FinancialStatements),
+    Estimates(AnalystEstimates),
+    Calendar(CompanyCalendar),
+}
+
+//==================================================================================================
+/// Parses the XML received in fundamental_data for a request of the given report type
+pub fn parse_fundamental_data(
+    report_type: &FundamentalType,
+    xml: &str,
+) -> Result<FundamentalReport, IBKRApiLibError> {
+    match report_type {
+        FundamentalType::ReportSnapshot => {
+            Ok(FundamentalReport::Snapshot(CompanySnapshot::from_xml(xml)?))
+        }
+        FundamentalType::ReportsFinSummary => Ok(FundamentalReport::FinancialSummary(
+            FinancialSummary::from_xml(xml)?,
+        )),
+        FundamentalType::ReportRatios => Ok(FundamentalReport::Ratios(RatioReport::from_xml(xml)?)),
+        FundamentalType::ReportsFinStatements => Ok(FundamentalReport::FinancialStatements(
+            FinancialStatements::from_xml(xml)?,
+        )),
+        FundamentalType::RESC => Ok(FundamentalReport::Estimates(AnalystEstimates::from_xml(
+            xml,
+        )?)),
+        FundamentalType::CalendarReport => {
+            Ok(FundamentalReport::Calendar(CompanyCalendar::from_xml(xml)?))
+        }
+        FundamentalType::ReportsOwnership => Err(fundamentals_error(
+            None,
+            "parsing of ReportsOwnership is not supported",
+        )),
+    }
+}
+
+//==================================================================================================
+/// A single named ratio.  Type is N for numeric, D for date and S for string values.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Ratio {
+    pub field_name: String,
+    pub ratio_type: String,
+    pub value: String,
+}
+
+impl Ratio {
+    /// Numeric value, if this is a numeric ratio
+    pub fn numeric_value(&self) -> Option<f64> {
+        if self.ratio_type == "N" || self.ratio_type.is_empty() {
+            self.value.parse::<f64>().ok()
+        } else {
+            None
+        }
+    }
+}
+
+//==================================================================================================
+/// Ratios grouped the way TWS groups them, e.g. "Price and Volume" or "Income Statement"
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct RatioGroup {
+    pub name: String,
+    pub ratios: Vec<Ratio>,
+}
+
+//----------------------------------------------------------------------------------------------
+fn parse_ratio(node: Node) -> Result<Ratio, IBKRApiLibError> {
+    Ok(Ratio {
+        field_name: required_attribute(node, "FieldName")?,
+        ratio_type: attribute(node, "Type"),
+        value: text(node),
+    })
+}
+
+//----------------------------------------------------------------------------------------------
+fn parse_ratio_groups(ratios: Node) -> Result<Vec<RatioGroup>, IBKRApiLibError> {
+    let mut groups = vec![];
+    let mut ungrouped = RatioGroup::default();
+    for element in children(ratios) {
+        match element.tag_name().name() {
+            "Group" => {
+                let mut group = RatioGroup {
+                    name: attribute(element, "ID"),
+                    ratios: vec![],
+                };
+                for ratio in children(element) {
+                    if ratio.tag_name().name() != "Ratio" {
+                        return Err(unexpected_element(ratio));
+                    }
+                    group.ratios.push(parse_ratio(ratio)?);
+                }
+                groups.push(group);
+            }
+            "Ratio" => ungrouped.ratios.push(parse_ratio(element)?),
+            // header information that accompanies the ratios
+            _ => (),
+        }
+    }
+    if !ungrouped.ratios.is_empty() {
+        groups.push(ungrouped);
+    }
+    Ok(groups)
+}
+
+//----------------------------------------------------------------------------------------------
+fn find_ratio<'a>(groups: &'a [RatioGroup], field_name: &str) -> Option<&'a Ratio> {
+    groups
+        .iter()
+        .flat_map(|group| group.ratios.iter())
+        .find(|ratio| ratio.field_name == field_name)
+}
+
+//==================================================================================================
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Industry {
+    pub classification: String,
+    pub code: String,
+    pub description: String,
+}
+
+//==================================================================================================
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Officer {
+    pub rank: i32,
+    pub first_name: String,
+    pub last_name: String,
+    pub age: String,
+    pub title: String,
+}
+
+//==================================================================================================
+/// Company overview from the ReportSnapshot report
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct CompanySnapshot {
+    pub company_ids: HashMap<String, String>,
+    pub company_name: String,
+    pub ticker: String,
+    pub exchange: String,
+    pub employees: Option<f64>,
+    pub shares_outstanding: Option<f64>,
+    pub reporting_currency: String,
+    pub business_summary: String,
+    pub financial_summary: String,
+    pub web_site: String,
+    pub industries: Vec<Industry>,
+    pub officers: Vec<Officer>,
+    pub ratios: Vec<RatioGroup>,
+    pub forecasts: Vec<Ratio>,
+}
+
+impl CompanySnapshot {
+    pub fn from_xml(xml: &str) -> Result<Self, IBKRApiLibError> {
+        let document = parse_document(xml)?;
+        let root = expect_root(&document, &["ReportSnapshot"])?;
+
+        let company_ids = company_ids(root);
+        let mut snapshot = CompanySnapshot {
+            company_name: company_ids.get("CompanyName").cloned().unwrap_or_default(),
+            company_ids,
+            ..Default::default()
+        };
+
+        if let Some(issue) = child(root, "Issues").and_then(|issues| child(issues, "Issue")) {
+            snapshot.ticker = children(issue)
+                .find(|id| id.tag_name().name() == "IssueID" && attribute(*id, "Type") == "Ticker")
+                .map(text)
+                .unwrap_or_default();
+            snapshot.exchange = child(issue, "Exchange")
+                .map(|exchange| attribute(exchange, "Code"))
+                .unwrap_or_default();
+        }
+
+        if let Some(info) = child(root, "CoGeneralInfo") {
+            snapshot.employees = optional_f64(child(info, "Employees"))?;
+            snapshot.shares_outstanding = optional_f64(child(info, "SharesOut"))?;
+            snapshot.reporting_currency = child(info, "ReportingCurrency")
+                .map(|currency| attribute(currency, "Code"))
+                .unwrap_or_default();
+        }
+
+        if let Some(text_info) = child(root, "TextInfo") {
+            for info in children(text_info) {
+                match attribute(info, "Type").as_str() {
+                    "Business Summary" => snapshot.business_summary = text(info),
+                    "Financial Summary" => snapshot.financial_summary = text(info),
+                    _ => (),
+                }
+            }
+        }
+
+        if let Some(links) = child(root, "webLinks") {
+            snapshot.web_site = child_text(links, "webSite");
+        }
+
+        if let Some(industry_info) =
+            child(root, "peerInfo").and_then(|peers| child(peers, "IndustryInfo"))
+        {
+            for industry in children(industry_info) {
+                if industry.tag_name().name() != "Industry" {
+                    return Err(unexpected_element(industry));
+                }
+                snapshot.industries.push(Industry {
+                    classification: attribute(industry, "type"),
+                    code: attribute(industry, "code"),
+                    description: text(industry),
+                });
+            }
+        }
+
+        if let Some(officers) = child(root, "officers") {
+            for officer in children(officers) {
+                if officer.tag_name().name() != "officer" {
+                    return Err(unexpected_element(officer));
+                }
+                snapshot.officers.push(Officer {
+                    rank: optional_i32_attribute(officer, "rank")?.unwrap_or(0),
+                    first_name: child_text(officer, "firstName"),
+                    last_name: child_text(officer, "lastName"),
+                    age: child_text(officer, "age"),
+                    title: child_text(officer, "title"),
+                });
+            }
+        }
+
+        if let Some(ratios) = child(root, "Ratios") {
+            snapshot.ratios = parse_ratio_groups(ratios)?;
+        }
+
+        if let Some(forecasts) = child(root, "ForecastData") {
+            for forecast in children(forecasts) {
+                if forecast.tag_name().name() != "Ratio" {
+                    return Err(unexpected_element(forecast));
+                }
+                snapshot.forecasts.push(Ratio {
+                    field_name: required_attribute(forecast, "FieldName")?,
+                    ratio_type: attribute(forecast, "Type"),
+                    value: child_text(forecast, "Value"),
+                });
+            }
+        }
+
+        Ok(snapshot)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Looks up a ratio by field name in any group
+    pub fn ratio(&self, field_name: &str) -> Option<&Ratio> {
+        find_ratio(self.ratios.as_slice(), field_name)
+    }
+}
+
+impl fmt::Display for CompanySnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "company_name: {}, ticker: {}, exchange: {}, reporting_currency: {}, industries: {}, officers: {}, ratio groups: {}",
+            self.company_name,
+            self.ticker,
+            self.exchange,
+            self.reporting_currency,
+            self.industries.len(),
+            self.officers.len(),
+            self.ratios.len()
+        )
+    }
+}
+
+//==================================================================================================
+/// Standalone ratios from the ReportRatios report
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct RatioReport {
+    pub groups: Vec<RatioGroup>,
+}
+
+impl RatioReport {
+    pub fn from_xml(xml: &str) -> Result<Self, IBKRApiLibError> {
+        let document = parse_document(xml)?;
+        let root = expect_root(&document, &["Ratios", "ReportRatios"])?;
+        let ratios = if root.tag_name().name() == "Ratios" {
+            root
+        } else {
+            child(root, "Ratios").ok_or_else(|| {
+                fundamentals_error(Some(root), "missing required element <Ratios>")
+            })?
+        };
+        Ok(RatioReport {
+            groups: parse_ratio_groups(ratios)?,
+        })
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Looks up a ratio by field name in any group
+    pub fn ratio(&self, field_name: &str) -> Option<&Ratio> {
+        find_ratio(self.groups.as_slice(), field_name)
+    }
+}
+
+//==================================================================================================
+/// One value in the financial summary.  Report type is A (audited), R (restated) or
+/// P (preliminary); period is e.g. 3M or 12M.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct SummaryValue {
+    pub as_of_date: String,
+    pub report_type: String,
+    pub period: String,
+    pub value: f64,
+}
+
+//==================================================================================================
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Dividend {
+    pub dividend_type: String,
+    pub ex_date: String,
+    pub record_date: String,
+    pub pay_date: String,
+    pub declaration_date: String,
+    pub value: f64,
+}
+
+//==================================================================================================
+/// Revenue, EPS and dividend history from the ReportsFinSummary report
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct FinancialSummary {
+    pub currency: String,
+    pub total_revenues: Vec<SummaryValue>,
+    pub dividends_per_share: Vec<SummaryValue>,
+    pub earnings_per_share: Vec<SummaryValue>,
+    pub dividends: Vec<Dividend>,
+}
+
+impl FinancialSummary {
+    pub fn from_xml(xml: &str) -> Result<Self, IBKRApiLibError> {
+        let document = parse_document(xml)?;
+        let root = expect_root(&document, &["FinancialSummary"])?;
+
+        let mut summary = FinancialSummary::default();
+        for list in children(root) {
+            let (item_name, values) = match list.tag_name().name() {
+                "TotalRevenues" => ("TotalRevenue", &mut summary.total_revenues),
+                "DividendPerShares" => ("DividendPerShare", &mut summary.dividends_per_share),
+                "EPSs" => ("EPS", &mut summary.earnings_per_share),
+                "Dividends" => {
+                    for dividend in children(list) {
+                        if dividend.tag_name().name() != "Dividend" {
+                            return Err(unexpected_element(dividend));
+                        }
+                        summary.dividends.push(Dividend {
+                            dividend_type: attribute(dividend, "type"),
+                            ex_date: attribute(dividend, "exDate"),
+                            record_date: attribute(dividend, "recordDate"),
+                            pay_date: attribute(dividend, "payDate"),
+                            declaration_date: attribute(dividend, "declarationDate"),
+                            value: parse_f64(dividend, text(dividend).as_str())?,
+                        });
+                    }
+                    continue;
+                }
+                _ => return Err(unexpected_element(list)),
+            };
+            if summary.currency.is_empty() {
+                summary.currency = attribute(list, "currency");
+            }
+            for item in children(list) {
+                if item.tag_name().name() != item_name {
+                    return Err(unexpected_element(item));
+                }
+                values.push(SummaryValue {
+                    as_of_date: required_attribute(item, "asofDate")?,
+                    report_type: attribute(item, "reportType"),
+                    period: attribute(item, "period"),
+                    value: parse_f64(item, text(item).as_str())?,
+                });
+            }
+        }
+        Ok(summary)
+    }
+}
+
+//==================================================================================================
+/// Statement types used by ReportsFinStatements
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum StatementType {
+    Income,
+    BalanceSheet,
+    CashFlow,
+}
+
+impl StatementType {
+    fn from_code(node: Node, code: &str) -> Result<Self, IBKRApiLibError> {
+        match code {
+            "INC" => Ok(StatementType::Income),
+            "BAL" => Ok(StatementType::BalanceSheet),
+            "CAS" => Ok(StatementType::CashFlow),
+            _ => Err(fundamentals_error(
+                Some(node),
+                format!("unknown statement type '{}'", code).as_str(),
+            )),
+        }
+    }
+}
+
+impl fmt::Display for StatementType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            StatementType::Income => write!(f, "INC"),
+            StatementType::BalanceSheet => write!(f, "BAL"),
+            StatementType::CashFlow => write!(f, "CAS"),
+        }
+    }
+}
+
+//==================================================================================================
+/// Description of a chart of accounts code used in the statements' line items
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CoaItem {
+    pub statement_type: StatementType,
+    pub line_id: i32,
+    pub description: String,
+}
+
+//==================================================================================================
+/// One statement for a fiscal period.  Line items are keyed by chart of accounts code.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Statement {
+    pub statement_type: StatementType,
+    pub statement_date: String,
+    pub period_length: Option<i32>,
+    pub period_unit: String,
+    pub source: String,
+    pub line_items: BTreeMap<String, f64>,
+}
+
+//==================================================================================================
+/// A fiscal year or interim period and its statements
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FiscalPeriod {
+    pub end_date: String,
+    pub fiscal_year: i32,
+    pub fiscal_period_number: Option<i32>,
+    pub statements: Vec<Statement>,
+}
+
+impl FiscalPeriod {
+    /// The statement of the given type, if reported for this period
+    pub fn statement(&self, statement_type: StatementType) -> Option<&Statement> {
+        self.statements
+            .iter()
+            .find(|statement| statement.statement_type == statement_type)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Value of a line item in the statement of the given type
+    pub fn line_item(&self, statement_type: StatementType, coa_code: &str) -> Option<f64> {
+        self.statement(statement_type)
+            .and_then(|statement| statement.line_items.get(coa_code).copied())
+    }
+}
+
+//==================================================================================================
+/// Income statements, balance sheets and cash flows by period from the ReportsFinStatements
+/// report.  Periods are ordered as TWS sends them, most recent first.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct FinancialStatements {
+    pub company_ids: HashMap<String, String>,
+    pub coa_map: HashMap<String, CoaItem>,
+    pub annual_periods: Vec<FiscalPeriod>,
+    pub interim_periods: Vec<FiscalPeriod>,
+}
+
+impl FinancialStatements {
+    pub fn from_xml(xml: &str) -> Result<Self, IBKRApiLibError> {
+        let document = parse_document(xml)?;
+        let root = expect_root(&document, &["ReportFinancialStatements"])?;
+        let statements = child(root, "FinancialStatements").ok_or_else(|| {
+            fundamentals_error(Some(root), "missing required element <FinancialStatements>")
+        })?;
+
+        let mut report = FinancialStatements {
+            company_ids: company_ids(root),
+            ..Default::default()
+        };
+        for element in children(statements) {
+            match element.tag_name().name() {
+                "COAMap" => {
+                    for item in children(element) {
+                        if item.tag_name().name() != "mapItem" {
+                            return Err(unexpected_element(item));
+                        }
+                        report.coa_map.insert(
+                            required_attribute(item, "coaItem")?,
+                            CoaItem {
+                                statement_type: StatementType::from_code(
+                                    item,
+                                    required_attribute(item, "statementType")?.as_str(),
+                                )?,
+                                line_id: optional_i32_attribute(item, "lineID")?.unwrap_or(0),
+                                description: text(item),
+                            },
+                        );
+                    }
+                }
+                "AnnualPeriods" => report.annual_periods = parse_fiscal_periods(element)?,
+                "InterimPeriods" => report.interim_periods = parse_fiscal_periods(element)?,
+                _ => return Err(unexpected_element(element)),
+            }
+        }
+        Ok(report)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Description of a chart of accounts code, e.g. SREV is "Revenue"
+    pub fn describe(&self, coa_code: &str) -> Option<&str> {
+        self.coa_map
+            .get(coa_code)
+            .map(|item| item.description.as_str())
+    }
+}
+
+//----------------------------------------------------------------------------------------------
+fn parse_fiscal_periods(periods: Node) -> Result<Vec<FiscalPeriod>, IBKRApiLibError> {
+    let mut result = vec![];
+    for period in children(periods) {
+        if period.tag_name().name() != "FiscalPeriod" {
+            return Err(unexpected_element(period));
+        }
+        let mut fiscal_period = FiscalPeriod {
+            end_date: required_attribute(period, "EndDate")?,
+            fiscal_year: optional_i32_attribute(period, "FiscalYear")?.ok_or_else(|| {
+                fundamentals_error(Some(period), "missing required attribute 'FiscalYear'")
+            })?,
+            fiscal_period_number: optional_i32_attribute(period, "FiscalPeriodNumber")?,
+            statements: vec![],
+        };
+        for statement in children(period) {
+            if statement.tag_name().name() != "Statement" {
+                return Err(unexpected_element(statement));
+            }
+            fiscal_period.statements.push(parse_statement(statement)?);
+        }
+        result.push(fiscal_period);
+    }
+    Ok(result)
+}
+
+//----------------------------------------------------------------------------------------------
+fn parse_statement(node: Node) -> Result<Statement, IBKRApiLibError> {
+    let mut statement = Statement {
+        statement_type: StatementType::from_code(node, required_attribute(node, "Type")?.as_str())?,
+        statement_date: "".to_string(),
+        period_length: None,
+        period_unit: "".to_string(),
+        source: "".to_string(),
+        line_items: BTreeMap::new(),
+    };
+    for element in children(node) {
+        match element.tag_name().name() {
+            "FPHeader" => {
+                statement.statement_date = child_text(element, "StatementDate");
+                statement.period_length =
+                    optional_f64(child(element, "PeriodLength"))?.map(|length| length as i32);
+                statement.period_unit = child(element, "periodType")
+                    .map(|unit| attribute(unit, "Code"))
+                    .unwrap_or_default();
+                statement.source = child_text(element, "Source");
+            }
+            "lineItem" => {
+                statement.line_items.insert(
+                    required_attribute(element, "coaCode")?,
+                    parse_f64(element, text(element).as_str())?,
+                );
+            }
+            _ => return Err(unexpected_element(element)),
+        }
+    }
+    Ok(statement)
+}
+
+//==================================================================================================
+/// A reported actual value for a measure such as EPS or revenue
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ActualValue {
+    pub measure: String,
+    pub unit: String,
+    pub period_type: String,
+    pub fiscal_year: i32,
+    pub end_month: Option<i32>,
+    pub period_number: Option<i32>,
+    pub value: f64,
+}
+
+//==================================================================================================
+/// Analyst consensus for a measure and fiscal period.  Values are the current (CURR) consensus.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ConsensusEstimate {
+    pub measure: String,
+    pub unit: String,
+    pub period_type: String,
+    pub fiscal_year: i32,
+    pub end_month: Option<i32>,
+    pub period_number: Option<i32>,
+    pub high: Option<f64>,
+    pub low: Option<f64>,
+    pub mean: Option<f64>,
+    pub median: Option<f64>,
+    pub std_dev: Option<f64>,
+    pub number_of_estimates: Option<f64>,
+}
+
+//==================================================================================================
+/// Analyst estimates and actuals from the RESC report
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct AnalystEstimates {
+    pub company_name: String,
+    pub actuals: Vec<ActualValue>,
+    pub estimates: Vec<ConsensusEstimate>,
+}
+
+impl AnalystEstimates {
+    pub fn from_xml(xml: &str) -> Result<Self, IBKRApiLibError> {
+        let document = parse_document(xml)?;
+        let root = expect_root(&document, &["REarnEstCons"])?;
+
+        let mut report = AnalystEstimates::default();
+        if let Some(company) = child(root, "Company") {
+            report.company_name = child(company, "CoName")
+                .map(|name| child_text(name, "Name"))
+                .unwrap_or_default();
+        }
+
+        if let Some(actuals) = child(root, "Actuals") {
+            for list in children(actuals) {
+                for actual in children(list) {
+                    let measure = required_attribute(actual, "type")?;
+                    let unit = attribute(actual, "unit");
+                    for period in children(actual) {
+                        let (fiscal_year, end_month, period_number) =
+                            parse_estimate_period(period)?;
+                        let value = child(period, "ActValue").ok_or_else(|| {
+                            fundamentals_error(Some(period), "missing required element <ActValue>")
+                        })?;
+                        report.actuals.push(ActualValue {
+                            measure: measure.clone(),
+                            unit: unit.clone(),
+                            period_type: attribute(period, "periodType"),
+                            fiscal_year,
+                            end_month,
+                            period_number,
+                            value: parse_f64(value, text(value).as_str())?,
+                        });
+                    }
+                }
+            }
+        }
+
+        if let Some(estimates) = child(root, "ConsEstimates") {
+            for list in children(estimates) {
+                if list.tag_name().name() == "NPEstimates" {
+                    // non-period estimates such as recommendations and target prices
+                    continue;
+                }
+                for estimate in children(list) {
+                    let measure = required_attribute(estimate, "type")?;
+                    let unit = attribute(estimate, "unit");
+                    for period in children(estimate) {
+                        let (fiscal_year, end_month, period_number) =
+                            parse_estimate_period(period)?;
+                        let mut consensus = ConsensusEstimate {
+                            measure: measure.clone(),
+                            unit: unit.clone(),
+                            period_type: attribute(period, "periodType"),
+                            fiscal_year,
+                            end_month,
+                            period_number,
+                            ..Default::default()
+                        };
+                        for value in children(period) {
+                            if value.tag_name().name() != "ConsEstimate" {
+                                return Err(unexpected_element(value));
+                            }
+                            let current = children(value).find(|cons| {
+                                cons.tag_name().name() == "ConsValue"
+                                    && attribute(*cons, "dateType") == "CURR"
+                            });
+                            let current = optional_f64(current)?;
+                            match attribute(value, "type").as_str() {
+                                "High" => consensus.high = current,
+                                "Low" => consensus.low = current,
+                                "Mean" => consensus.mean = current,
+                                "Median" => consensus.median = current,
+                                "StdDev" => consensus.std_dev = current,
+                                "NumOfEst" => consensus.number_of_estimates = current,
+                                other => {
+                                    return Err(fundamentals_error(
+                                        Some(value),
+                                        format!("unknown consensus estimate type '{}'", other)
+                                            .as_str(),
+                                    ))
+                                }
+                            }
+                        }
+                        report.estimates.push(consensus);
+                    }
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Consensus for a measure (e.g. EPS) and fiscal year, annual periods only
+    pub fn annual_estimate(&self, measure: &str, fiscal_year: i32) -> Option<&ConsensusEstimate> {
+        self.estimates.iter().find(|estimate| {
+            estimate.measure == measure
+                && estimate.fiscal_year == fiscal_year
+                && estimate.period_type == "A"
+        })
+    }
+}
+
+//----------------------------------------------------------------------------------------------
+fn parse_estimate_period(period: Node) -> Result<(i32, Option<i32>, Option<i32>), IBKRApiLibError> {
+    match period.tag_name().name() {
+        "FYPeriod" | "QPeriod" => (),
+        _ => return Err(unexpected_element(period)),
+    }
+    let fiscal_year = optional_i32_attribute(period, "fYear")?
+        .ok_or_else(|| fundamentals_error(Some(period), "missing required attribute 'fYear'"))?;
+    Ok((
+        fiscal_year,
+        optional_i32_attribute(period, "endMonth")?,
+        optional_i32_attribute(period, "periodNum")?,
+    ))
+}
+
+//==================================================================================================
+/// An entry in the company calendar, such as an earnings release.  The event kind is the XML
+/// element name and the fields are its child elements.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct CalendarEvent {
+    pub kind: String,
+    pub fields: HashMap<String, String>,
+}
+
+//==================================================================================================
+/// Upcoming corporate events from the CalendarReport report
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct CompanyCalendar {
+    pub company_ids: HashMap<String, String>,
+    pub events: Vec<CalendarEvent>,
+}
+
+impl CompanyCalendar {
+    pub fn from_xml(xml: &str) -> Result<Self, IBKRApiLibError> {
+        let document = parse_document(xml)?;
+        let root = expect_root(&document, &["CalendarReport"])?;
+
+        let mut calendar = CompanyCalendar {
+            company_ids: company_ids(root),
+            events: vec![],
+        };
+        for list in children(root) {
+            if list.tag_name().name() == "CoIDs" || list.tag_name().name() == "Company" {
+                continue;
+            }
+            for event in children(list) {
+                calendar.events.push(CalendarEvent {
+                    kind: event.tag_name().name().to_string(),
+                    fields: children(event)
+                        .map(|field| (field.tag_name().name().to_string(), text(field)))
+                        .collect(),
+                });
+            }
+        }
+        Ok(calendar)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Events of one kind, e.g. "Earnings"
+    pub fn events_of_kind<'a>(&'a self, kind: &'a str) -> impl Iterator<Item = &'a CalendarEvent> {
+        self.events.iter().filter(move |event| event.kind == kind)
+    }
+}
@@ -0,0 +1,363 @@
+//! Market rules and rounding of order prices to valid price increments
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::client::EClient;
+use crate::core::common::{PriceIncrement, TRIGGER_ORDER_TYPES, UNSET_DOUBLE};
+use crate::core::contract::{Contract, ContractDetails};
+use crate::core::errors::{IBKRApiLibError, TwsApiReportableError, TwsError};
+use crate::core::order::Order;
+use crate::core::wrapper::Wrapper;
+
+/// Relative tolerance used to decide whether a price is already a multiple of its increment
+const GRID_TOLERANCE: f64 = 1e-9;
+
+//==================================================================================================
+fn market_rule_error(req_id: i32, description: String) -> IBKRApiLibError {
+    IBKRApiLibError::ApiError(TwsApiReportableError::new(
+        req_id,
+        TwsError::BadMessage.code().to_string(),
+        description,
+    ))
+}
+
+//==================================================================================================
+/// Which way to move a price that is not on the increment grid
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RoundingDirection {
+    Nearest,
+    Up,
+    Down,
+}
+
+impl RoundingDirection {
+    /// The direction that makes an order less aggressive: down for buys, up for sells
+    pub fn passive(action: &str) -> Self {
+        if action.eq_ignore_ascii_case("SELL") || action.eq_ignore_ascii_case("SSHORT") {
+            RoundingDirection::Up
+        } else {
+            RoundingDirection::Down
+        }
+    }
+}
+
+impl fmt::Display for RoundingDirection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            RoundingDirection::Nearest => write!(f, "Nearest"),
+            RoundingDirection::Up => write!(f, "Up"),
+            RoundingDirection::Down => write!(f, "Down"),
+        }
+    }
+}
+
+//==================================================================================================
+/// What [MarketRules::check_order] does with prices that are not on the increment grid
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PriceCheck {
+    /// Move the price to the grid in the passive direction for the order's action
+    Snap,
+    /// Fail without changing the order
+    Reject,
+}
+
+impl fmt::Display for PriceCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            PriceCheck::Snap => write!(f, "Snap"),
+            PriceCheck::Reject => write!(f, "Reject"),
+        }
+    }
+}
+
+//==================================================================================================
+/// Increment that applies at `price`: the one with the highest low edge not above it
+pub fn increment_at(increments: &[PriceIncrement], price: f64) -> Option<f64> {
+    increments
+        .iter()
+        .filter(|increment| increment.low_edge <= price.abs() + GRID_TOLERANCE)
+        .max_by(|a, b| a.low_edge.total_cmp(&b.low_edge))
+        .or_else(|| increments.first())
+        .map(|increment| increment.increment)
+        .filter(|increment| *increment > 0.0)
+}
+
+//==================================================================================================
+/// Rounds `price` to a multiple of the increment that applies to it
+pub fn round_to_increments(
+    increments: &[PriceIncrement],
+    price: f64,
+    direction: RoundingDirection,
+) -> Option<f64> {
+    increment_at(increments, price).map(|increment| round_to(increment, price, direction))
+}
+
+//==================================================================================================
+fn round_to(increment: f64, price: f64, direction: RoundingDirection) -> f64 {
+    let steps = price / increment;
+    let steps = match direction {
+        RoundingDirection::Nearest => steps.round(),
+        RoundingDirection::Up => (steps - GRID_TOLERANCE).ceil(),
+        RoundingDirection::Down => (steps + GRID_TOLERANCE).floor(),
+    };
+    // strip the representation error of the multiplication, increments have few decimals
+    (steps * increment * 1e8).round() / 1e8
+}
+
+//==================================================================================================
+/// True if `price` is a multiple of the increment that applies to it
+pub fn is_on_grid(increments: &[PriceIncrement], price: f64) -> bool {
+    match round_to_increments(increments, price, RoundingDirection::Nearest) {
+        Some(rounded) => (rounded - price).abs() <= GRID_TOLERANCE * price.abs().max(1.0),
+        None => false,
+    }
+}
+
+//==================================================================================================
+/// Registry of market rules and of the rule each contract uses on each exchange.
+///
+/// ContractDetails lists market_rule_ids in the same order as valid_exchanges.  Register details
+/// with [MarketRules::req_market_rules], which requests the rules that aren't known yet, and
+/// forward Wrapper::market_rule.  Prices can then be rounded with [MarketRules::round_price]
+/// and orders checked with [MarketRules::check_order] or placed with [MarketRules::place_order].
+#[derive(Debug, Default)]
+pub struct MarketRules {
+    rules: HashMap<i32, Vec<PriceIncrement>>,
+    contract_rules: HashMap<i32, HashMap<String, i32>>,
+    pending: HashSet<i32>,
+}
+
+impl MarketRules {
+    pub fn new() -> Self {
+        MarketRules::default()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Records the rule ids of a contract on each of its valid exchanges.  Returns the rule ids
+    /// that were neither received nor pending; they are pending from now on and have to be
+    /// requested with EClient::req_market_rule unless [MarketRules::req_market_rules] is used.
+    pub fn add_contract_details(
+        &mut self,
+        details: &ContractDetails,
+    ) -> Result<Vec<i32>, IBKRApiLibError> {
+        let exchanges = details
+            .valid_exchanges
+            .split(',')
+            .map(str::trim)
+            .filter(|exchange| !exchange.is_empty())
+            .collect::<Vec<&str>>();
+        let rule_ids = details
+            .market_rule_ids
+            .split(',')
+            .map(str::trim)
+            .filter(|rule_id| !rule_id.is_empty())
+            .map(|rule_id| {
+                rule_id.parse::<i32>().map_err(|_| {
+                    market_rule_error(
+                        -1,
+                        format!(
+                            "Invalid market rule id '{}' for con_id {}",
+                            rule_id, details.contract.con_id
+                        ),
+                    )
+                })
+            })
+            .collect::<Result<Vec<i32>, IBKRApiLibError>>()?;
+        if exchanges.len() != rule_ids.len() {
+            return Err(market_rule_error(
+                -1,
+                format!(
+                    "con_id {} has {} valid exchanges but {} market rule ids",
+                    details.contract.con_id,
+                    exchanges.len(),
+                    rule_ids.len()
+                ),
+            ));
+        }
+
+        let mut missing = vec![];
+        for (exchange, rule_id) in exchanges.iter().zip(rule_ids) {
+            self.contract_rules
+                .entry(details.contract.con_id)
+                .or_default()
+                .insert(exchange.to_uppercase(), rule_id);
+            if !self.rules.contains_key(&rule_id)
+                && !self.pending.contains(&rule_id)
+                && !missing.contains(&rule_id)
+            {
+                missing.push(rule_id);
+            }
+        }
+        for rule_id in missing.iter() {
+            self.pending.insert(*rule_id);
+        }
+        Ok(missing)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Registers contract details and requests the market rules that are not known yet
+    pub fn req_market_rules<T: Wrapper + Send + Sync + 'static>(
+        &mut self,
+        client: &mut EClient<T>,
+        details: &ContractDetails,
+    ) -> Result<Vec<i32>, IBKRApiLibError> {
+        let missing = self.add_contract_details(details)?;
+        for rule_id in missing.iter() {
+            client.req_market_rule(*rule_id)?;
+        }
+        Ok(missing)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Forwarded from Wrapper::market_rule.  Returns true if the rule was pending.
+    pub fn market_rule(
+        &mut self,
+        market_rule_id: i32,
+        price_increments: Vec<PriceIncrement>,
+    ) -> bool {
+        let mut price_increments = price_increments;
+        price_increments.sort_by(|a, b| a.low_edge.total_cmp(&b.low_edge));
+        self.rules.insert(market_rule_id, price_increments);
+        self.pending.remove(&market_rule_id)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn rule(&self, market_rule_id: i32) -> Option<&Vec<PriceIncrement>> {
+        self.rules.get(&market_rule_id)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// True when every rule referenced by registered contracts has been received
+    pub fn is_complete(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Rule id of a contract on an exchange.  An empty exchange means the contract's exchange,
+    /// and the primary exchange is tried when the exchange is not one of the valid ones.
+    pub fn rule_id(&self, contract: &Contract, exchange: &str) -> Option<i32> {
+        let exchange = if exchange.is_empty() {
+            contract.exchange.as_str()
+        } else {
+            exchange
+        };
+        let rules = self.contract_rules.get(&contract.con_id)?;
+        rules
+            .get(&exchange.to_uppercase())
+            .or_else(|| rules.get(&contract.primary_exchange.to_uppercase()))
+            .copied()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn increments(
+        &self,
+        req_id: i32,
+        contract: &Contract,
+        exchange: &str,
+    ) -> Result<&Vec<PriceIncrement>, IBKRApiLibError> {
+        let rule_id = self.rule_id(contract, exchange).ok_or_else(|| {
+            market_rule_error(
+                req_id,
+                format!(
+                    "No market rule for con_id {} on exchange '{}'",
+                    contract.con_id, exchange
+                ),
+            )
+        })?;
+        self.rules.get(&rule_id).ok_or_else(|| {
+            market_rule_error(
+                req_id,
+                format!("Market rule {} has not been received", rule_id),
+            )
+        })
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Rounds a price to the increments the contract's market rule on `exchange` allows
+    pub fn round_price(
+        &self,
+        contract: &Contract,
+        exchange: &str,
+        price: f64,
+        direction: RoundingDirection,
+    ) -> Result<f64, IBKRApiLibError> {
+        let increments = self.increments(-1, contract, exchange)?;
+        round_to_increments(increments, price, direction).ok_or_else(|| {
+            market_rule_error(
+                -1,
+                format!(
+                    "Market rule for con_id {} has no increments",
+                    contract.con_id
+                ),
+            )
+        })
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Checks lmt_price, and aux_price when it is a trigger price, of an order against the market
+    /// rule of the contract's exchange.  Off grid prices are snapped in the passive direction or
+    /// rejected, depending on `check`; a rejected order is left unchanged.  The aux_price of
+    /// other order types, such as the offset of TRAIL or REL orders, is not checked.
+    pub fn check_order(
+        &self,
+        contract: &Contract,
+        order: &mut Order,
+        check: PriceCheck,
+    ) -> Result<(), IBKRApiLibError> {
+        let increments = self.increments(order.order_id, contract, "")?;
+        let direction = RoundingDirection::passive(order.action.as_str());
+        let is_trigger = TRIGGER_ORDER_TYPES.contains(&order.order_type.to_uppercase().as_str());
+        let mut prices = [
+            ("lmt_price", order.lmt_price, true),
+            ("aux_price", order.aux_price, is_trigger),
+        ];
+        for (name, price, checked) in prices.iter_mut() {
+            if !*checked || *price == UNSET_DOUBLE || is_on_grid(increments, *price) {
+                continue;
+            }
+            let increment = increment_at(increments, *price).ok_or_else(|| {
+                market_rule_error(
+                    order.order_id,
+                    format!(
+                        "Market rule for con_id {} has no valid increment at {}",
+                        contract.con_id, price
+                    ),
+                )
+            })?;
+            match check {
+                PriceCheck::Snap => *price = round_to(increment, *price, direction),
+                PriceCheck::Reject => {
+                    return Err(market_rule_error(
+                        order.order_id,
+                        format!(
+                            "{} {} of order {} is not a multiple of {}",
+                            name, price, order.order_id, increment
+                        ),
+                    ));
+                }
+            }
+        }
+        order.lmt_price = prices[0].1;
+        order.aux_price = prices[1].1;
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Checks the prices of an order with [MarketRules::check_order] before placing it
+    pub fn place_order<T: Wrapper + Send + Sync + 'static>(
+        &self,
+        client: &mut EClient<T>,
+        order_id: i32,
+        contract: &Contract,
+        order: &Order,
+        check: PriceCheck,
+    ) -> Result<Order, IBKRApiLibError> {
+        let mut order = order.clone();
+        order.order_id = order_id;
+        self.check_order(contract, &mut order, check)?;
+        client.place_order(order_id, contract, &order)?;
+        Ok(order)
+    }
+}
@@ -12,8 +12,8 @@ use ascii::AsAsciiStr;
 use log::*;
 use num_derive::FromPrimitive;
 
-use crate::core::common::{UNSET_DOUBLE, UNSET_INTEGER};
-use crate::core::errors::IBKRApiLibError;
+use crate::core::common::{NO_VALID_ID, UNSET_DOUBLE, UNSET_INTEGER};
+use crate::core::errors::{IBKRApiLibError, TwsApiReportableError, TwsError};
 
 //==================================================================================================
 trait EClientMsgSink {
@@ -31,7 +31,7 @@ pub enum FAMessageDataTypes {
 
 //==================================================================================================
 /// incoming msg id's
-#[derive(FromPrimitive)]
+#[derive(FromPrimitive, Debug)]
 #[repr(i32)]
 pub enum IncomingMessageIds {
     TickPrice = 1,
@@ -115,7 +115,7 @@ pub enum IncomingMessageIds {
 
 //==================================================================================================
 /// Outgoing msg id's
-#[derive(FromPrimitive)]
+#[derive(FromPrimitive, Debug)]
 #[repr(i32)]
 pub enum OutgoingMessageIds {
     ReqMktData = 1,
@@ -221,15 +221,37 @@ pub fn read_msg<'a>(buf: &[u8]) -> Result<(usize, String, Vec<u8>), IBKRApiLibEr
         return Ok((0, String::new(), buf.to_vec()));
     }
 
+    match read_msg_slice(buf)? {
+        Some((text, remaining)) => Ok((text.len(), text.to_string(), remaining.to_vec())),
+        None => Ok((
+            i32::from_be_bytes(buf[0..4].try_into().unwrap()) as usize,
+            String::new(),
+            buf.to_vec(),
+        )),
+    }
+}
+
+//==================================================================================================
+/// Borrowing version of [read_msg]: returns the first message of the buffer and the bytes after
+/// it, or None if the buffer does not hold a whole message yet
+pub fn read_msg_slice(buf: &[u8]) -> Result<Option<(&str, &[u8])>, IBKRApiLibError> {
+    if buf.len() < 4 {
+        return Ok(None);
+    }
+
     let size = i32::from_be_bytes(buf[0..4].try_into().unwrap()) as usize;
-    //debug!("read_msg: Message size: {:?}", size);
 
     if buf.len() - 4 >= size {
-        let text = String::from_utf8(buf[4..4 + size].to_vec()).unwrap();
-        //debug!("read_msg: text in read message: {:?}", text);
-        Ok((size, text, buf[4 + size..].to_vec()))
+        let text = std::str::from_utf8(&buf[4..4 + size]).map_err(|err| {
+            IBKRApiLibError::ApiError(TwsApiReportableError::new(
+                NO_VALID_ID,
+                TwsError::BadMessage.code().to_string(),
+                format!("{}: {}", TwsError::BadMessage.message(), err),
+            ))
+        })?;
+        Ok(Some((text, &buf[4 + size..])))
     } else {
-        Ok((size, String::new(), buf.to_vec()))
+        Ok(None)
     }
 }
 
@@ -248,6 +270,34 @@ pub fn read_fields(buf: &str) -> Vec<String> {
         .collect::<Vec<String>>()
 }
 
+/// Messages with up to this many fields are split without allocating
+const INLINE_FIELDS: usize = 64;
+
+//==================================================================================================
+/// Splits a message into the same fields as [read_fields], borrowed from the message, and calls
+/// `f` with them.  The fields are kept on the stack unless there are more than 64 of them.
+pub fn with_fields<R>(buf: &str, f: impl FnOnce(&[&str]) -> R) -> R {
+    let mut inline = [""; INLINE_FIELDS];
+    let mut split = buf.split('\u{0}');
+    let mut count = 0;
+    for field in split.by_ref() {
+        inline[count] = field;
+        count += 1;
+        if count == INLINE_FIELDS {
+            break;
+        }
+    }
+    if count < INLINE_FIELDS {
+        //last one is empty
+        return f(&inline[..count.saturating_sub(1)]);
+    }
+
+    let mut fields = inline.to_vec();
+    fields.extend(split);
+    fields.pop();
+    f(&fields)
+}
+
 //==================================================================================================
 pub fn make_field(val: &dyn Any) -> Result<String, IBKRApiLibError> {
     // debug!("CALLING make_field!!");
@@ -279,6 +329,34 @@ pub fn make_field(val: &dyn Any) -> Result<String, IBKRApiLibError> {
     Ok(field)
 }
 
+//==================================================================================================
+/// An ErrMsg message carrying `err`, for handing an error to the wrapper through the decoder as
+/// if TWS had reported it.  `req_id` is used when the error has none of its own.
+pub(crate) fn error_message(req_id: i32, err: &IBKRApiLibError) -> Result<String, IBKRApiLibError> {
+    let (req_id, code, description) = match err {
+        IBKRApiLibError::ApiError(err) => (
+            if err.req_id == NO_VALID_ID {
+                req_id
+            } else {
+                err.req_id
+            },
+            err.code
+                .parse()
+                .unwrap_or_else(|_| TwsError::BadMessage.code()),
+            err.description.clone(),
+        ),
+        _ => (req_id, TwsError::BadMessage.code(), err.to_string()),
+    };
+    Ok(format!(
+        "{}{}{}{}{}",
+        make_field(&(IncomingMessageIds::ErrMsg as i32))?,
+        make_field(&2)?,
+        make_field(&req_id)?,
+        make_field(&code)?,
+        make_field(&description)?
+    ))
+}
+
 //==================================================================================================
 pub fn make_field_handle_empty(val: &dyn Any) -> Result<String, IBKRApiLibError> {
     if let Some(stringval) = val.downcast_ref::<f64>() {
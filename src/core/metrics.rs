@@ -0,0 +1,103 @@
+//! Hooks through which a connection reports its message flow to a metrics backend
+//!
+//! [EClient](crate::core::client::EClient), its reader and its decoder call a [MetricsSink] set
+//! with `EClient::set_metrics`.  A Prometheus backend is provided by
+//! [crate::core::prometheus_metrics] when the `metrics` feature is enabled.
+use std::fmt;
+use std::time::Duration;
+
+use crate::core::common::NO_VALID_ID;
+
+//==================================================================================================
+/// Type of a streaming subscription
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SubscriptionKind {
+    MktData,
+    MktDepth,
+    TickByTick,
+    RealTimeBars,
+    /// Historical data requested with keep_up_to_date
+    HistoricalData,
+    AccountUpdates,
+    AccountSummary,
+    AccountUpdatesMulti,
+    Positions,
+    PositionsMulti,
+    Pnl,
+    PnlSingle,
+    ScannerSubscription,
+    NewsBulletins,
+}
+
+impl SubscriptionKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SubscriptionKind::MktData => "mkt_data",
+            SubscriptionKind::MktDepth => "mkt_depth",
+            SubscriptionKind::TickByTick => "tick_by_tick",
+            SubscriptionKind::RealTimeBars => "real_time_bars",
+            SubscriptionKind::HistoricalData => "historical_data",
+            SubscriptionKind::AccountUpdates => "account_updates",
+            SubscriptionKind::AccountSummary => "account_summary",
+            SubscriptionKind::AccountUpdatesMulti => "account_updates_multi",
+            SubscriptionKind::Positions => "positions",
+            SubscriptionKind::PositionsMulti => "positions_multi",
+            SubscriptionKind::Pnl => "pnl",
+            SubscriptionKind::PnlSingle => "pnl_single",
+            SubscriptionKind::ScannerSubscription => "scanner_subscription",
+            SubscriptionKind::NewsBulletins => "news_bulletins",
+        }
+    }
+}
+
+impl fmt::Display for SubscriptionKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+//==================================================================================================
+/// Returns true if an error with this code ends the request it was reported for.  Warnings, in
+/// 2100..=2199, and the notice that delayed market data is sent instead, 10167, do not.
+pub fn ends_request(req_id: i32, code: i32) -> bool {
+    req_id != NO_VALID_ID && !(2100..=2199).contains(&code) && code != 10167
+}
+
+//==================================================================================================
+/// Receives the events of one connection.  Every method does nothing by default.  Methods are
+/// called from the client, reader and decoder threads, so implementations must be cheap and
+/// must not block.
+pub trait MetricsSink: Send + Sync {
+    /// A message with an IncomingMessageIds id was read from the socket and queued for decoding
+    fn message_queued(&self) {}
+
+    /// The decoder took a message off the queue
+    fn message_dequeued(&self) {}
+
+    /// A message with an IncomingMessageIds id was decoded and handed to the wrapper
+    fn message_received(&self, _msg_id: i32, _decode_time: Duration) {}
+
+    /// A message with an OutgoingMessageIds id was sent
+    fn message_sent(&self, _msg_id: i32) {}
+
+    fn bytes_received(&self, _count: usize) {}
+
+    fn bytes_sent(&self, _count: usize) {}
+
+    /// The client completed the handshake with TWS
+    fn connected(&self) {}
+
+    /// The connection was closed, by either side.  Reported once per connection, by the reader
+    /// when it stops.
+    fn disconnected(&self) {}
+
+    /// TWS reported an error or warning with the given code.  Errors for the req_id of a
+    /// streaming request, see [ends_request], also end the subscription.
+    fn error_received(&self, _req_id: i32, _code: i32) {}
+
+    /// A streaming request was sent.  Requests without an id use NO_VALID_ID.
+    fn subscribed(&self, _kind: SubscriptionKind, _req_id: i32) {}
+
+    /// A streaming request was cancelled
+    fn unsubscribed(&self, _kind: SubscriptionKind, _req_id: i32) {}
+}
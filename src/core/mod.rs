@@ -1,18 +1,58 @@
 //! Core structs, enums, and functions
 pub mod account_summary_tags;
 pub mod algo_params;
+pub mod algo_strategy;
+pub mod backtester;
 pub mod client;
+pub mod client_handle;
+pub mod client_pool;
+pub mod combo;
 pub mod common;
+pub mod condition_expression;
 pub mod contract;
+pub mod contract_cache;
 pub mod decoder;
 pub mod errors;
+pub mod event_bus;
 pub mod execution;
+#[cfg(feature = "financial-advisor")]
+pub mod financial_advisor;
+#[cfg(feature = "fundamentals")]
+pub mod fundamentals;
+pub mod market_rules;
 pub mod messages;
+pub mod metrics;
+pub mod news;
+pub mod option_chain;
+pub mod option_pricing;
 pub mod order;
 pub mod order_condition;
 pub mod order_decoder;
+pub mod order_group;
+#[cfg(feature = "metrics")]
+pub mod prometheus_metrics;
 pub mod reader;
+#[cfg(feature = "tracing")]
+pub mod request_spans;
+pub mod risk_gate;
 pub mod scanner;
+#[cfg(feature = "scanner-catalog")]
+pub mod scanner_catalog;
+pub mod series_csv;
+#[cfg(feature = "parquet-storage")]
+pub mod series_parquet;
+pub mod series_store;
 pub mod server_versions;
+pub mod sim_broker;
 pub mod streamer;
+pub mod tick_parsers;
+#[cfg(feature = "sqlite-store")]
+pub mod trade_store;
+pub mod trading_schedule;
 pub mod wrapper;
+#[cfg(any(
+    feature = "financial-advisor",
+    feature = "fundamentals",
+    feature = "scanner-catalog"
+))]
+pub(crate) mod xml;
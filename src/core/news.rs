@@ -0,0 +1,583 @@
+//! News providers, live and historical headlines and article retrieval
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+use log::*;
+use serde::{Deserialize, Serialize};
+
+use crate::core::client::EClient;
+use crate::core::common::NewsProvider;
+use crate::core::contract::Contract;
+use crate::core::errors::{IBKRApiLibError, TwsApiReportableError, TwsError};
+use crate::core::wrapper::Wrapper;
+
+/// Article type used by news_article for plain text or HTML articles
+pub const ARTICLE_TYPE_TEXT: i32 = 0;
+/// Article type used by news_article for base64 encoded PDF articles
+pub const ARTICLE_TYPE_BINARY: i32 = 1;
+/// Number of live headlines remembered for recognising repeats from other providers
+pub const LIVE_SEEN_CAPACITY: usize = 10_000;
+
+//==================================================================================================
+/// Key used to recognise the same headline published by several providers: lower case with
+/// whitespace collapsed
+fn normalize_headline(headline: &str) -> String {
+    headline
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+//==================================================================================================
+/// A news headline from tick_news or historical_news.
+///
+/// The time is as sent by TWS: `yyyy-MM-dd HH:mm:ss.0` for historical headlines and milliseconds
+/// since the epoch for live ones.  When other providers published the same headline their codes
+/// and article ids are listed in `other_sources`.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct NewsHeadline {
+    pub time: String,
+    pub provider_code: String,
+    pub provider_name: String,
+    pub article_id: String,
+    pub headline: String,
+    pub extra_data: String,
+    pub other_sources: Vec<(String, String)>,
+}
+
+impl fmt::Display for NewsHeadline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "time: {}, provider: {} ({}), article_id: {}, headline: {}",
+            self.time, self.provider_code, self.provider_name, self.article_id, self.headline
+        )
+    }
+}
+
+//==================================================================================================
+/// Body of a news article.  PDF articles arrive base64 encoded and are decoded to bytes.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum NewsArticle {
+    Text(String),
+    Pdf(Vec<u8>),
+}
+
+impl NewsArticle {
+    /// Builds an article from the arguments of Wrapper::news_article
+    pub fn from_message(article_type: i32, article_text: &str) -> Result<Self, IBKRApiLibError> {
+        match article_type {
+            ARTICLE_TYPE_TEXT => Ok(NewsArticle::Text(article_text.to_string())),
+            ARTICLE_TYPE_BINARY => base64::decode(article_text.trim())
+                .map(NewsArticle::Pdf)
+                .map_err(|err| {
+                    IBKRApiLibError::ApiError(TwsApiReportableError::new(
+                        -1,
+                        TwsError::BadMessage.code().to_string(),
+                        format!("Invalid base64 in binary news article: {}", err),
+                    ))
+                }),
+            _ => Err(IBKRApiLibError::ApiError(TwsApiReportableError::new(
+                -1,
+                TwsError::BadMessage.code().to_string(),
+                format!("Unknown news article type: {}", article_type),
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for NewsArticle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NewsArticle::Text(text) => write!(f, "{}", text),
+            NewsArticle::Pdf(bytes) => write!(f, "PDF article, {} bytes", bytes.len()),
+        }
+    }
+}
+
+//==================================================================================================
+/// Parameters of a historical news query.  Each page asks for up to `page_size` headlines
+/// ending at `end_date_time`, which moves back to the oldest headline received so far.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct HistoricalNewsQuery {
+    pub con_id: i32,
+    pub provider_codes: Vec<String>,
+    pub start_date_time: String,
+    pub end_date_time: String,
+    pub page_size: i32,
+}
+
+impl HistoricalNewsQuery {
+    pub fn new(
+        con_id: i32,
+        provider_codes: Vec<String>,
+        start_date_time: &str,
+        end_date_time: &str,
+        page_size: i32,
+    ) -> Self {
+        HistoricalNewsQuery {
+            con_id,
+            provider_codes,
+            start_date_time: start_date_time.to_string(),
+            end_date_time: end_date_time.to_string(),
+            page_size,
+        }
+    }
+}
+
+//==================================================================================================
+#[derive(Debug, Default)]
+struct HistoricalNews {
+    query: HistoricalNewsQuery,
+    headlines: Vec<NewsHeadline>,
+    page_headlines: i32,
+    pages: i32,
+    next_page_pending: bool,
+    complete: bool,
+}
+
+//==================================================================================================
+/// Keeps news providers, headlines and articles.
+///
+/// Owned by the application's Wrapper implementation: forward news_providers, tick_news,
+/// historical_news, historical_news_end and news_article to the methods of the same name.
+/// Historical queries keep paging while TWS reports that more headlines are available; after
+/// historical_news_end call [NewsClient::request_next_pages] to send the next page.
+/// Headlines with the same text from different providers are reported once, as long as the first
+/// copy is among the last [LIVE_SEEN_CAPACITY] live headlines.
+#[derive(Debug)]
+pub struct NewsClient {
+    next_req_id: i32,
+    providers: HashMap<String, String>,
+    providers_received: bool,
+    historical: HashMap<i32, HistoricalNews>,
+    article_requests: HashSet<i32>,
+    articles: HashMap<i32, NewsArticle>,
+    live_req_ids: HashSet<i32>,
+    live_seen: HashMap<String, NewsHeadline>,
+    live_seen_order: VecDeque<String>,
+    live_seen_capacity: usize,
+    live_senders: Vec<Sender<NewsHeadline>>,
+}
+
+impl Default for NewsClient {
+    fn default() -> Self {
+        NewsClient::new(0)
+    }
+}
+
+impl NewsClient {
+    pub fn new(first_req_id: i32) -> Self {
+        NewsClient {
+            next_req_id: first_req_id,
+            providers: HashMap::new(),
+            providers_received: false,
+            historical: HashMap::new(),
+            article_requests: HashSet::new(),
+            articles: HashMap::new(),
+            live_req_ids: HashSet::new(),
+            live_seen: HashMap::new(),
+            live_seen_order: VecDeque::new(),
+            live_seen_capacity: LIVE_SEEN_CAPACITY,
+            live_senders: vec![],
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Changes how many live headlines are remembered for recognising repeats.  The oldest are
+    /// forgotten first.
+    pub fn with_live_seen_capacity(mut self, capacity: usize) -> Self {
+        self.live_seen_capacity = capacity.max(1);
+        while self.live_seen_order.len() > self.live_seen_capacity {
+            self.forget_oldest_live_headline();
+        }
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn forget_oldest_live_headline(&mut self) {
+        if let Some(key) = self.live_seen_order.pop_front() {
+            self.live_seen.remove(&key);
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn allocate_req_id(&mut self) -> i32 {
+        let req_id = self.next_req_id;
+        self.next_req_id += 1;
+        req_id
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Returns true if the request id was allocated by this news client
+    pub fn owns_request(&self, req_id: i32) -> bool {
+        self.historical.contains_key(&req_id)
+            || self.article_requests.contains(&req_id)
+            || self.live_req_ids.contains(&req_id)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn request_providers<T: Wrapper + Send + Sync + 'static>(
+        &mut self,
+        client: &mut EClient<T>,
+    ) -> Result<(), IBKRApiLibError> {
+        client.req_news_providers()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Forwarded from Wrapper::news_providers
+    pub fn news_providers(&mut self, news_providers: Vec<NewsProvider>) {
+        for provider in news_providers {
+            self.providers.insert(provider.code, provider.name);
+        }
+        self.providers_received = true;
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn providers_received(&self) -> bool {
+        self.providers_received
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Name of a provider, or the code itself if the provider is not known
+    pub fn provider_name(&self, provider_code: &str) -> String {
+        self.providers
+            .get(provider_code)
+            .cloned()
+            .unwrap_or_else(|| provider_code.to_string())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Provider codes received in news_providers, sorted
+    pub fn provider_codes(&self) -> Vec<String> {
+        let mut codes = self.providers.keys().cloned().collect::<Vec<String>>();
+        codes.sort();
+        codes
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn make_headline(
+        &self,
+        time: String,
+        provider_code: &str,
+        article_id: &str,
+        headline: &str,
+        extra_data: &str,
+    ) -> NewsHeadline {
+        NewsHeadline {
+            time,
+            provider_code: provider_code.to_string(),
+            provider_name: self.provider_name(provider_code),
+            article_id: article_id.to_string(),
+            headline: headline.to_string(),
+            extra_data: extra_data.to_string(),
+            other_sources: vec![],
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Starts a historical news query and returns its request id
+    pub fn request_historical<T: Wrapper + Send + Sync + 'static>(
+        &mut self,
+        client: &mut EClient<T>,
+        query: HistoricalNewsQuery,
+    ) -> Result<i32, IBKRApiLibError> {
+        let req_id = self.start_historical(query);
+        self.request_next_pages(client)?;
+        Ok(req_id)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Registers a historical news query without sending it, and returns its request id.  The
+    /// first page is returned by the next call to [NewsClient::next_page_requests].
+    pub fn start_historical(&mut self, query: HistoricalNewsQuery) -> i32 {
+        let req_id = self.allocate_req_id();
+        self.historical.insert(
+            req_id,
+            HistoricalNews {
+                query,
+                next_page_pending: true,
+                ..Default::default()
+            },
+        );
+        req_id
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Takes the pages waiting to be requested, with the end time for each page.  Every page is
+    /// returned once.
+    pub fn next_page_requests(&mut self) -> Vec<(i32, HistoricalNewsQuery)> {
+        let mut requests = vec![];
+        for (req_id, news) in self.historical.iter_mut() {
+            if news.next_page_pending {
+                news.next_page_pending = false;
+                news.page_headlines = 0;
+                requests.push((*req_id, news.query.clone()));
+            }
+        }
+        requests.sort_by_key(|(req_id, _)| *req_id);
+        requests
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Sends the next page of every query for which TWS reported more headlines
+    pub fn request_next_pages<T: Wrapper + Send + Sync + 'static>(
+        &mut self,
+        client: &mut EClient<T>,
+    ) -> Result<(), IBKRApiLibError> {
+        for (req_id, query) in self.next_page_requests() {
+            client.req_historical_news(
+                req_id,
+                query.con_id,
+                query.provider_codes.join("+").as_str(),
+                query.start_date_time.as_str(),
+                query.end_date_time.as_str(),
+                query.page_size,
+                vec![],
+            )?;
+        }
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Forwarded from Wrapper::historical_news
+    pub fn historical_news(
+        &mut self,
+        request_id: i32,
+        time: &str,
+        provider_code: &str,
+        article_id: &str,
+        headline: &str,
+    ) -> bool {
+        if !self.historical.contains_key(&request_id) {
+            return false;
+        }
+        let new_headline =
+            self.make_headline(time.to_string(), provider_code, article_id, headline, "");
+        let news = self.historical.get_mut(&request_id).unwrap();
+        news.page_headlines += 1;
+
+        // pages overlap at the boundary time, so the same article can be sent twice
+        if news
+            .headlines
+            .iter()
+            .any(|old| old.provider_code == provider_code && old.article_id == article_id)
+        {
+            return true;
+        }
+        let key = normalize_headline(headline);
+        match news
+            .headlines
+            .iter_mut()
+            .find(|old| normalize_headline(old.headline.as_str()) == key)
+        {
+            Some(old) => old
+                .other_sources
+                .push((provider_code.to_string(), article_id.to_string())),
+            None => news.headlines.push(new_headline),
+        }
+        true
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Forwarded from Wrapper::historical_news_end.  When has_more is set the query's end time
+    /// moves back to its oldest headline and the next page becomes pending.
+    pub fn historical_news_end(&mut self, request_id: i32, has_more: bool) -> bool {
+        let news = match self.historical.get_mut(&request_id) {
+            Some(news) => news,
+            None => return false,
+        };
+        news.pages += 1;
+        let oldest = news
+            .headlines
+            .iter()
+            .map(|headline| headline.time.as_str())
+            .min()
+            .map(|time| time.to_string());
+        match oldest {
+            // stop if a page brought nothing new, otherwise the same page would be requested forever
+            Some(oldest)
+                if has_more && news.page_headlines > 0 && oldest != news.query.end_date_time =>
+            {
+                news.query.end_date_time = oldest;
+                news.next_page_pending = true;
+            }
+            _ => {
+                if has_more {
+                    warn!(
+                        "Historical news request {} reported more headlines but paging made no progress",
+                        request_id
+                    );
+                }
+                news.complete = true;
+            }
+        }
+        true
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Headlines received so far for a historical query, newest first
+    pub fn historical_headlines(&self, req_id: i32) -> Option<Vec<&NewsHeadline>> {
+        self.historical.get(&req_id).map(|news| {
+            let mut headlines = news.headlines.iter().collect::<Vec<&NewsHeadline>>();
+            headlines.sort_by(|a, b| b.time.cmp(&a.time));
+            headlines
+        })
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// True once all pages of a historical query have been received
+    pub fn historical_complete(&self, req_id: i32) -> bool {
+        self.historical
+            .get(&req_id)
+            .map(|news| news.complete)
+            .unwrap_or(false)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Number of pages received for a historical query
+    pub fn historical_pages(&self, req_id: i32) -> i32 {
+        self.historical
+            .get(&req_id)
+            .map(|news| news.pages)
+            .unwrap_or(0)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Requests the body of an article and returns the request id
+    pub fn request_article<T: Wrapper + Send + Sync + 'static>(
+        &mut self,
+        client: &mut EClient<T>,
+        provider_code: &str,
+        article_id: &str,
+    ) -> Result<i32, IBKRApiLibError> {
+        let req_id = self.allocate_req_id();
+        client.req_news_article(req_id, provider_code, article_id, vec![])?;
+        self.article_requests.insert(req_id);
+        Ok(req_id)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Forwarded from Wrapper::news_article.  Returns Ok(false) if the request is not ours and
+    /// an error if a binary article can't be decoded.
+    pub fn news_article(
+        &mut self,
+        request_id: i32,
+        article_type: i32,
+        article_text: &str,
+    ) -> Result<bool, IBKRApiLibError> {
+        if !self.article_requests.remove(&request_id) {
+            return Ok(false);
+        }
+        let article = NewsArticle::from_message(article_type, article_text)?;
+        self.articles.insert(request_id, article);
+        Ok(true)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn article(&self, req_id: i32) -> Option<&NewsArticle> {
+        self.articles.get(&req_id)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Removes and returns a received article
+    pub fn take_article(&mut self, req_id: i32) -> Option<NewsArticle> {
+        self.articles.remove(&req_id)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Subscribes to live headlines.  For a broad tape contract (sec_type NEWS) pass no provider
+    /// codes; for any other contract pass the providers whose headlines should be delivered.
+    pub fn subscribe_live<T: Wrapper + Send + Sync + 'static>(
+        &mut self,
+        client: &mut EClient<T>,
+        contract: &Contract,
+        provider_codes: &[&str],
+    ) -> Result<i32, IBKRApiLibError> {
+        let req_id = self.allocate_req_id();
+        let generic_ticks = if provider_codes.is_empty() {
+            "mdoff,292".to_string()
+        } else {
+            format!("mdoff,292:{}", provider_codes.join("+"))
+        };
+        client.req_mkt_data(
+            req_id,
+            contract,
+            generic_ticks.as_str(),
+            false,
+            false,
+            vec![],
+        )?;
+        self.live_req_ids.insert(req_id);
+        Ok(req_id)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn unsubscribe_live<T: Wrapper + Send + Sync + 'static>(
+        &mut self,
+        client: &mut EClient<T>,
+        req_id: i32,
+    ) -> Result<(), IBKRApiLibError> {
+        if self.live_req_ids.remove(&req_id) {
+            client.cancel_mkt_data(req_id)?;
+        }
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Delivers tick_news for a market data request that was made directly through the client
+    pub fn track_live(&mut self, ticker_id: i32) {
+        self.live_req_ids.insert(ticker_id);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// A receiver for live headlines.  Every receiver gets each headline once; headlines already
+    /// sent by another provider are not repeated.
+    pub fn headline_stream(&mut self) -> Receiver<NewsHeadline> {
+        let (sender, receiver) = channel();
+        self.live_senders.push(sender);
+        receiver
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Forwarded from Wrapper::tick_news
+    pub fn tick_news(
+        &mut self,
+        ticker_id: i32,
+        time_stamp: i32,
+        provider_code: &str,
+        article_id: &str,
+        headline: &str,
+        extra_data: &str,
+    ) -> bool {
+        if !self.live_req_ids.contains(&ticker_id) {
+            return false;
+        }
+        let key = normalize_headline(headline);
+        if let Some(seen) = self.live_seen.get_mut(&key) {
+            if seen.provider_code != provider_code || seen.article_id != article_id {
+                seen.other_sources
+                    .push((provider_code.to_string(), article_id.to_string()));
+            }
+            return true;
+        }
+        let new_headline = self.make_headline(
+            time_stamp.to_string(),
+            provider_code,
+            article_id,
+            headline,
+            extra_data,
+        );
+        if self.live_seen_order.len() >= self.live_seen_capacity {
+            self.forget_oldest_live_headline();
+        }
+        self.live_seen_order.push_back(key.clone());
+        self.live_seen.insert(key, new_headline.clone());
+        // receivers that have been dropped are forgotten
+        self.live_senders
+            .retain(|sender| sender.send(new_headline.clone()).is_ok());
+        true
+    }
+}
@@ -0,0 +1,753 @@
+//! Option chain model built from security definition parameters, contract details and option computation ticks
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use bigdecimal::{BigDecimal, ToPrimitive};
+use chrono::NaiveDate;
+use log::*;
+use serde::{Deserialize, Serialize};
+
+use crate::core::client::EClient;
+use crate::core::common::{Right, TickType, UNSET_DOUBLE};
+use crate::core::contract::{Contract, ContractDetails};
+use crate::core::errors::IBKRApiLibError;
+use crate::core::wrapper::Wrapper;
+
+//==================================================================================================
+/// Expirations and strikes for one exchange and trading class, as returned by
+/// security_definition_option_parameter.  Expirations and strikes are sorted ascending.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct OptionChainParams {
+    pub exchange: String,
+    pub underlying_con_id: i32,
+    pub trading_class: String,
+    pub multiplier: String,
+    pub expirations: Vec<String>,
+    pub strikes: Vec<f64>,
+}
+
+impl OptionChainParams {
+    pub fn new(
+        exchange: String,
+        underlying_con_id: i32,
+        trading_class: String,
+        multiplier: String,
+        expirations: HashSet<String>,
+        strikes: HashSet<BigDecimal>,
+    ) -> Self {
+        let mut expirations = expirations.into_iter().collect::<Vec<String>>();
+        expirations.sort();
+        let mut strikes = strikes
+            .iter()
+            .filter_map(|strike| strike.to_f64())
+            .collect::<Vec<f64>>();
+        strikes.sort_by(f64::total_cmp);
+        OptionChainParams {
+            exchange,
+            underlying_con_id,
+            trading_class,
+            multiplier,
+            expirations,
+            strikes,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Returns the `count` strikes below and above `price`, which is the usual way to pick a
+    /// strike window around the money
+    pub fn strikes_around(&self, price: f64, count: usize) -> Vec<f64> {
+        let split = self.strikes.iter().position(|strike| *strike >= price);
+        let split = split.unwrap_or(self.strikes.len());
+        let start = split.saturating_sub(count);
+        let end = usize::min(split + count, self.strikes.len());
+        self.strikes[start..end].to_vec()
+    }
+}
+
+impl fmt::Display for OptionChainParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "exchange: {}, underlying_con_id: {}, trading_class: {}, multiplier: {}, expirations: {}, strikes: {}",
+            self.exchange,
+            self.underlying_con_id,
+            self.trading_class,
+            self.multiplier,
+            self.expirations.len(),
+            self.strikes.len()
+        )
+    }
+}
+
+//==================================================================================================
+/// One option computation as delivered by tick_option_computation.  Values TWS has not computed
+/// yet are set to UNSET_DOUBLE.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct OptionGreeks {
+    pub implied_vol: f64,
+    pub delta: f64,
+    pub opt_price: f64,
+    pub pv_dividend: f64,
+    pub gamma: f64,
+    pub vega: f64,
+    pub theta: f64,
+    pub und_price: f64,
+}
+
+impl OptionGreeks {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        implied_vol: f64,
+        delta: f64,
+        opt_price: f64,
+        pv_dividend: f64,
+        gamma: f64,
+        vega: f64,
+        theta: f64,
+        und_price: f64,
+    ) -> Self {
+        OptionGreeks {
+            implied_vol,
+            delta,
+            opt_price,
+            pv_dividend,
+            gamma,
+            vega,
+            theta,
+            und_price,
+        }
+    }
+}
+
+impl Default for OptionGreeks {
+    fn default() -> Self {
+        OptionGreeks::new(
+            UNSET_DOUBLE,
+            UNSET_DOUBLE,
+            UNSET_DOUBLE,
+            UNSET_DOUBLE,
+            UNSET_DOUBLE,
+            UNSET_DOUBLE,
+            UNSET_DOUBLE,
+            UNSET_DOUBLE,
+        )
+    }
+}
+
+impl fmt::Display for OptionGreeks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "implied_vol: {}, delta: {}, opt_price: {}, pv_dividend: {}, gamma: {}, vega: {}, theta: {}, und_price: {}",
+            self.implied_vol,
+            self.delta,
+            self.opt_price,
+            self.pv_dividend,
+            self.gamma,
+            self.vega,
+            self.theta,
+            self.und_price
+        )
+    }
+}
+
+//==================================================================================================
+/// A single strike/right/expiry in the chain along with the latest computations received for it.
+///
+/// * contract - the fully resolved option contract
+/// * expiry - expiration date (YYYYMMDD)
+/// * strike - strike price
+/// * right - put or call
+/// * bid, ask, last - option computations based on the bid, ask and last prices
+/// * model - TWS's model computation, which is what the chain queries use
+/// * market_data_req_id - the req_id of the market data subscription, if subscribed
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct OptionQuote {
+    pub contract: Contract,
+    pub expiry: String,
+    pub strike: f64,
+    pub right: Right,
+    pub bid: Option<OptionGreeks>,
+    pub ask: Option<OptionGreeks>,
+    pub last: Option<OptionGreeks>,
+    pub model: Option<OptionGreeks>,
+    pub market_data_req_id: Option<i32>,
+}
+
+impl OptionQuote {
+    pub fn new(contract: Contract) -> Self {
+        OptionQuote {
+            expiry: contract.last_trade_date_or_contract_month.clone(),
+            strike: contract.strike,
+            right: Right::from_contract_right(contract.right.as_ref()),
+            contract,
+            bid: None,
+            ask: None,
+            last: None,
+            model: None,
+            market_data_req_id: None,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Calendar days from `today` until expiry, or None if the expiry can't be parsed
+    pub fn days_to_expiry(&self, today: NaiveDate) -> Option<i64> {
+        let expiry = self.expiry.get(0..8)?;
+        NaiveDate::parse_from_str(expiry, "%Y%m%d")
+            .ok()
+            .map(|expiry| (expiry - today).num_days())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Strike divided by the underlying price
+    pub fn moneyness(&self, underlying_price: f64) -> f64 {
+        self.strike / underlying_price
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Model implied volatility, if computed
+    pub fn implied_vol(&self) -> Option<f64> {
+        self.model_value(|greeks| greeks.implied_vol)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Model delta, if computed
+    pub fn delta(&self) -> Option<f64> {
+        self.model_value(|greeks| greeks.delta)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Model gamma, if computed
+    pub fn gamma(&self) -> Option<f64> {
+        self.model_value(|greeks| greeks.gamma)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Model vega, if computed
+    pub fn vega(&self) -> Option<f64> {
+        self.model_value(|greeks| greeks.vega)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Model theta, if computed
+    pub fn theta(&self) -> Option<f64> {
+        self.model_value(|greeks| greeks.theta)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Underlying price used by the model computation, if computed
+    pub fn underlying_price(&self) -> Option<f64> {
+        self.model_value(|greeks| greeks.und_price)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn model_value(&self, value: impl Fn(&OptionGreeks) -> f64) -> Option<f64> {
+        self.model
+            .as_ref()
+            .map(value)
+            .filter(|val| *val != UNSET_DOUBLE)
+    }
+}
+
+impl fmt::Display for OptionQuote {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "expiry: {}, strike: {}, right: {}, model: [{}]",
+            self.expiry,
+            self.strike,
+            self.right,
+            self.model
+                .map(|greeks| greeks.to_string())
+                .unwrap_or_default()
+        )
+    }
+}
+
+//==================================================================================================
+/// Filter used to query an [OptionChain].  Unset bounds are not applied.
+#[derive(Clone, Debug, Default)]
+pub struct OptionChainFilter {
+    pub right: Option<Right>,
+    pub min_moneyness: Option<f64>,
+    pub max_moneyness: Option<f64>,
+    pub min_days_to_expiry: Option<i64>,
+    pub max_days_to_expiry: Option<i64>,
+}
+
+impl OptionChainFilter {
+    pub fn new() -> Self {
+        OptionChainFilter::default()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn right(mut self, right: Right) -> Self {
+        self.right = Some(right);
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Keep strikes whose strike/underlying ratio is within [min, max]
+    pub fn moneyness(mut self, min: f64, max: f64) -> Self {
+        self.min_moneyness = Some(min);
+        self.max_moneyness = Some(max);
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Keep expiries that are between min and max calendar days away
+    pub fn days_to_expiry(mut self, min: i64, max: i64) -> Self {
+        self.min_days_to_expiry = Some(min);
+        self.max_days_to_expiry = Some(max);
+        self
+    }
+}
+
+//==================================================================================================
+/// Builds and maintains an option chain for one underlying.
+///
+/// The chain issues requests through an [EClient] and is fed by forwarding the matching
+/// [Wrapper] callbacks to it.  Each callback method returns true if the callback belonged to one
+/// of the chain's requests, so a wrapper can forward callbacks without tracking req_ids itself:
+///
+/// 1. [OptionChain::request_parameters] calls req_sec_def_opt_params.  Forward
+///    security_definition_option_parameter and security_definition_option_parameter_end.
+/// 2. [OptionChain::load_contracts] calls req_contract_details for each chosen expiry.  Forward
+///    contract_details, contract_details_end and error.  Only strikes inside the strike window
+///    are kept.
+/// 3. [OptionChain::subscribe] calls req_mkt_data for every resolved contract.  Forward
+///    tick_option_computation.
+///
+/// The chain allocates req_ids sequentially starting at the id passed to [OptionChain::new], so
+/// callers should reserve a block of ids for it.
+pub struct OptionChain {
+    underlying: Contract,
+    next_req_id: i32,
+    params_req_id: i32,
+    params: Vec<OptionChainParams>,
+    params_complete: bool,
+    strike_window: (f64, f64),
+    pending_contract_requests: HashSet<i32>,
+    failed_contract_requests: Vec<(i32, i32, String)>,
+    quotes: Vec<OptionQuote>,
+    quote_index_by_req_id: HashMap<i32, usize>,
+    underlying_price: f64,
+}
+
+impl OptionChain {
+    pub fn new(underlying: Contract, first_req_id: i32) -> Self {
+        OptionChain {
+            underlying,
+            next_req_id: first_req_id + 1,
+            params_req_id: first_req_id,
+            params: vec![],
+            params_complete: false,
+            strike_window: (0.0, UNSET_DOUBLE),
+            pending_contract_requests: HashSet::new(),
+            failed_contract_requests: vec![],
+            quotes: vec![],
+            quote_index_by_req_id: HashMap::new(),
+            underlying_price: UNSET_DOUBLE,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn underlying(&self) -> &Contract {
+        &self.underlying
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Expirations and strikes per exchange and trading class
+    pub fn params(&self) -> &[OptionChainParams] {
+        self.params.as_slice()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Parameters for one exchange and trading class
+    pub fn params_for(&self, exchange: &str, trading_class: &str) -> Option<&OptionChainParams> {
+        self.params
+            .iter()
+            .find(|params| params.exchange == exchange && params.trading_class == trading_class)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// True once security_definition_option_parameter_end has been received
+    pub fn params_complete(&self) -> bool {
+        self.params_complete
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// True once all contract details requests have completed or failed
+    pub fn contracts_complete(&self) -> bool {
+        self.pending_contract_requests.is_empty()
+            && !(self.quotes.is_empty() && self.failed_contract_requests.is_empty())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Contract details requests that TWS answered with an error, as (req_id, error_code,
+    /// error_string).  The expiries of these requests are missing from the chain.
+    pub fn failed_contract_requests(&self) -> &[(i32, i32, String)] {
+        self.failed_contract_requests.as_slice()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// All resolved strikes in the chain
+    pub fn quotes(&self) -> &[OptionQuote] {
+        self.quotes.as_slice()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Latest underlying price, either set explicitly or taken from the option computations
+    pub fn underlying_price(&self) -> Option<f64> {
+        if self.underlying_price == UNSET_DOUBLE {
+            None
+        } else {
+            Some(self.underlying_price)
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Overrides the underlying price used for moneyness, e.g. from the underlying's own ticks
+    pub fn set_underlying_price(&mut self, price: f64) {
+        self.underlying_price = price;
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Returns true if req_id belongs to one of the chain's requests.  Useful for routing
+    /// error callbacks.
+    pub fn owns_request(&self, req_id: i32) -> bool {
+        req_id == self.params_req_id
+            || self.pending_contract_requests.contains(&req_id)
+            || self.quote_index_by_req_id.contains_key(&req_id)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Requests expirations and strikes for the underlying
+    pub fn request_parameters<T: Wrapper + Send + Sync + 'static>(
+        &mut self,
+        client: &mut EClient<T>,
+    ) -> Result<(), IBKRApiLibError> {
+        self.params.clear();
+        self.params_complete = false;
+        client.req_sec_def_opt_params(
+            self.params_req_id,
+            self.underlying.symbol.as_ref(),
+            if self.underlying.sec_type == "FUT" {
+                self.underlying.exchange.as_ref()
+            } else {
+                ""
+            },
+            self.underlying.sec_type.as_ref(),
+            self.underlying.con_id,
+        )
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Forwarded from Wrapper::security_definition_option_parameter
+    #[allow(clippy::too_many_arguments)]
+    pub fn security_definition_option_parameter(
+        &mut self,
+        req_id: i32,
+        exchange: &str,
+        underlying_con_id: i32,
+        trading_class: &str,
+        multiplier: &str,
+        expirations: HashSet<String>,
+        strikes: HashSet<BigDecimal>,
+    ) -> bool {
+        if req_id != self.params_req_id {
+            return false;
+        }
+        self.params.push(OptionChainParams::new(
+            exchange.to_string(),
+            underlying_con_id,
+            trading_class.to_string(),
+            multiplier.to_string(),
+            expirations,
+            strikes,
+        ));
+        true
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Forwarded from Wrapper::security_definition_option_parameter_end
+    pub fn security_definition_option_parameter_end(&mut self, req_id: i32) -> bool {
+        if req_id != self.params_req_id {
+            return false;
+        }
+        self.params_complete = true;
+        true
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Registers one contract details request per expiry and returns the requests to send.
+    /// Strikes outside [min_strike, max_strike] are dropped when the details arrive.
+    /// [OptionChain::load_contracts] sends these requests; this is exposed separately so the
+    /// requests can be inspected or sent through another channel.
+    pub fn contract_detail_requests(
+        &mut self,
+        exchange: &str,
+        trading_class: &str,
+        expirations: &[String],
+        min_strike: f64,
+        max_strike: f64,
+    ) -> Vec<(i32, Contract)> {
+        self.strike_window = (min_strike, max_strike);
+        let multiplier = self
+            .params_for(exchange, trading_class)
+            .map(|params| params.multiplier.clone())
+            .unwrap_or_default();
+
+        let mut requests = vec![];
+        for expiry in expirations {
+            let contract = Contract {
+                symbol: self.underlying.symbol.clone(),
+                sec_type: if self.underlying.sec_type == "FUT" {
+                    "FOP".to_string()
+                } else {
+                    "OPT".to_string()
+                },
+                exchange: exchange.to_string(),
+                currency: self.underlying.currency.clone(),
+                last_trade_date_or_contract_month: expiry.clone(),
+                trading_class: trading_class.to_string(),
+                multiplier: multiplier.clone(),
+                ..Default::default()
+            };
+
+            let req_id = self.next_req_id;
+            self.next_req_id += 1;
+            self.pending_contract_requests.insert(req_id);
+            requests.push((req_id, contract));
+        }
+        requests
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Resolves all contracts for the given expiries on one exchange and trading class, keeping
+    /// strikes inside [min_strike, max_strike]
+    pub fn load_contracts<T: Wrapper + Send + Sync + 'static>(
+        &mut self,
+        client: &mut EClient<T>,
+        exchange: &str,
+        trading_class: &str,
+        expirations: &[String],
+        min_strike: f64,
+        max_strike: f64,
+    ) -> Result<(), IBKRApiLibError> {
+        for (req_id, contract) in self.contract_detail_requests(
+            exchange,
+            trading_class,
+            expirations,
+            min_strike,
+            max_strike,
+        ) {
+            client.req_contract_details(req_id, &contract)?;
+        }
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Forwarded from Wrapper::contract_details
+    pub fn contract_details(&mut self, req_id: i32, contract_details: &ContractDetails) -> bool {
+        if !self.pending_contract_requests.contains(&req_id) {
+            return false;
+        }
+        let contract = &contract_details.contract;
+        if contract.strike < self.strike_window.0 || contract.strike > self.strike_window.1 {
+            return true;
+        }
+        if self
+            .quotes
+            .iter()
+            .any(|quote| quote.contract.con_id == contract.con_id)
+        {
+            return true;
+        }
+        self.quotes.push(OptionQuote::new(contract.clone()));
+        true
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Forwarded from Wrapper::contract_details_end
+    pub fn contract_details_end(&mut self, req_id: i32) -> bool {
+        if !self.pending_contract_requests.remove(&req_id) {
+            return false;
+        }
+        self.contract_request_done();
+        true
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Forwarded from Wrapper::error.  A failed contract details request is recorded in
+    /// [OptionChain::failed_contract_requests] and no longer waited for.
+    pub fn error(&mut self, req_id: i32, error_code: i32, error_string: &str) -> bool {
+        if !self.pending_contract_requests.remove(&req_id) {
+            return false;
+        }
+        warn!(
+            "Option chain for {}: contract details request {} failed: {} {}",
+            self.underlying.symbol, req_id, error_code, error_string
+        );
+        self.failed_contract_requests
+            .push((req_id, error_code, error_string.to_string()));
+        self.contract_request_done();
+        true
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn contract_request_done(&mut self) {
+        if !self.pending_contract_requests.is_empty() {
+            return;
+        }
+        self.quotes.sort_by(|a, b| {
+            a.expiry
+                .cmp(&b.expiry)
+                .then(a.strike.total_cmp(&b.strike))
+                .then((a.right as i32).cmp(&(b.right as i32)))
+        });
+        self.quote_index_by_req_id = self
+            .quotes
+            .iter()
+            .enumerate()
+            .filter_map(|(index, quote)| quote.market_data_req_id.map(|id| (id, index)))
+            .collect();
+        info!(
+            "Option chain for {} resolved {} contracts",
+            self.underlying.symbol,
+            self.quotes.len()
+        );
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Allocates a market data req_id for every resolved contract that is not yet subscribed
+    /// and returns the requests to send
+    pub fn market_data_requests(&mut self) -> Vec<(i32, Contract)> {
+        let mut requests = vec![];
+        for (index, quote) in self.quotes.iter_mut().enumerate() {
+            if quote.market_data_req_id.is_some() {
+                continue;
+            }
+            let req_id = self.next_req_id;
+            self.next_req_id += 1;
+            quote.market_data_req_id = Some(req_id);
+            self.quote_index_by_req_id.insert(req_id, index);
+            requests.push((req_id, quote.contract.clone()));
+        }
+        requests
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Subscribes to market data, and therefore option computations, for every resolved contract
+    pub fn subscribe<T: Wrapper + Send + Sync + 'static>(
+        &mut self,
+        client: &mut EClient<T>,
+    ) -> Result<(), IBKRApiLibError> {
+        for (req_id, contract) in self.market_data_requests() {
+            client.req_mkt_data(req_id, &contract, "", false, false, vec![])?;
+        }
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Cancels all market data subscriptions made by [OptionChain::subscribe]
+    pub fn unsubscribe<T: Wrapper + Send + Sync + 'static>(
+        &mut self,
+        client: &mut EClient<T>,
+    ) -> Result<(), IBKRApiLibError> {
+        for quote in self.quotes.iter_mut() {
+            if let Some(req_id) = quote.market_data_req_id.take() {
+                client.cancel_mkt_data(req_id)?;
+            }
+        }
+        self.quote_index_by_req_id.clear();
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Forwarded from Wrapper::tick_option_computation
+    #[allow(clippy::too_many_arguments)]
+    pub fn tick_option_computation(
+        &mut self,
+        req_id: i32,
+        tick_type: TickType,
+        implied_vol: f64,
+        delta: f64,
+        opt_price: f64,
+        pv_dividend: f64,
+        gamma: f64,
+        vega: f64,
+        theta: f64,
+        und_price: f64,
+    ) -> bool {
+        let index = match self.quote_index_by_req_id.get(&req_id) {
+            Some(index) => *index,
+            None => return false,
+        };
+        let greeks = OptionGreeks::new(
+            implied_vol,
+            delta,
+            opt_price,
+            pv_dividend,
+            gamma,
+            vega,
+            theta,
+            und_price,
+        );
+        let quote = &mut self.quotes[index];
+        match tick_type {
+            TickType::BidOptionComputation | TickType::DelayedBidOption => quote.bid = Some(greeks),
+            TickType::AskOptionComputation | TickType::DelayedAskOption => quote.ask = Some(greeks),
+            TickType::LastOptionComputation | TickType::DelayedLastOption => {
+                quote.last = Some(greeks)
+            }
+            TickType::ModelOption | TickType::DelayedModelOption => {
+                quote.model = Some(greeks);
+                if und_price != UNSET_DOUBLE {
+                    self.underlying_price = und_price;
+                }
+            }
+            _ => (),
+        }
+        true
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Looks up a single strike
+    pub fn quote(&self, expiry: &str, strike: f64, right: Right) -> Option<&OptionQuote> {
+        self.quotes.iter().find(|quote| {
+            quote.expiry == expiry && quote.right == right && (quote.strike - strike).abs() < 1e-9
+        })
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Returns the strikes matching the filter.  Moneyness bounds are skipped while the
+    /// underlying price is unknown.
+    pub fn filter(&self, filter: &OptionChainFilter, today: NaiveDate) -> Vec<&OptionQuote> {
+        let underlying_price = self.underlying_price();
+        self.quotes
+            .iter()
+            .filter(|quote| filter.right.iter().all(|&right| quote.right == right))
+            .filter(|quote| match underlying_price {
+                Some(price) => {
+                    let moneyness = quote.moneyness(price);
+                    filter.min_moneyness.iter().all(|&min| moneyness >= min)
+                        && filter.max_moneyness.iter().all(|&max| moneyness <= max)
+                }
+                None => true,
+            })
+            .filter(|quote| {
+                if filter.min_days_to_expiry.is_none() && filter.max_days_to_expiry.is_none() {
+                    return true;
+                }
+                match quote.days_to_expiry(today) {
+                    Some(days) => {
+                        filter.min_days_to_expiry.iter().all(|&min| days >= min)
+                            && filter.max_days_to_expiry.iter().all(|&max| days <= max)
+                    }
+                    None => false,
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,508 @@
+//! Local option pricing, greeks and implied volatility.
+//!
+//! European options are priced with Black-Scholes-Merton using a continuous dividend yield and
+//! American options with a Cox-Ross-Rubinstein binomial tree.  Results can be converted to
+//! [OptionGreeks] so they can be compared field by field with the values TWS sends to
+//! tick_option_computation.  Following the TWS conventions, vega and rho are per one
+//! percentage point change in volatility or rates and theta is per calendar day.
+use std::f64::consts::PI;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::common::{Right, NO_VALID_ID};
+use crate::core::errors::{IBKRApiLibError, TwsApiReportableError};
+use crate::core::option_chain::OptionGreeks;
+
+/// Number of binomial steps used when none is given
+pub const DEFAULT_BINOMIAL_STEPS: usize = 200;
+
+const DAYS_PER_YEAR: f64 = 365.0;
+const MIN_VOLATILITY: f64 = 1e-4;
+const MAX_VOLATILITY: f64 = 5.0;
+const IMPLIED_VOL_TOLERANCE: f64 = 1e-8;
+const IMPLIED_VOL_MAX_ITERATIONS: usize = 100;
+
+//==================================================================================================
+/// Exercise style of an option.  Note that this differs from
+/// [ExerciseType](crate::core::common::ExerciseType), which is the action sent with
+/// exercise_options.
+#[repr(i32)]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExerciseStyle {
+    European,
+    American,
+}
+
+impl fmt::Display for ExerciseStyle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ExerciseStyle::European => write!(f, "European"),
+            ExerciseStyle::American => write!(f, "American"),
+        }
+    }
+}
+
+//==================================================================================================
+/// Inputs to the pricing models.
+///
+/// * right - put or call
+/// * style - European or American exercise
+/// * underlying_price - spot price of the underlying
+/// * strike - strike price
+/// * time_to_expiry - time to expiry in years
+/// * risk_free_rate - continuously compounded risk free rate, e.g. 0.05 for 5%
+/// * dividend_yield - continuous dividend yield, e.g. 0.02 for 2%
+/// * volatility - annualized volatility, e.g. 0.25 for 25%.  Ignored when solving for implied
+///   volatility.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct OptionPricingInput {
+    pub right: Right,
+    pub style: ExerciseStyle,
+    pub underlying_price: f64,
+    pub strike: f64,
+    pub time_to_expiry: f64,
+    pub risk_free_rate: f64,
+    pub dividend_yield: f64,
+    pub volatility: f64,
+}
+
+impl OptionPricingInput {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        right: Right,
+        style: ExerciseStyle,
+        underlying_price: f64,
+        strike: f64,
+        time_to_expiry: f64,
+        risk_free_rate: f64,
+        dividend_yield: f64,
+        volatility: f64,
+    ) -> Self {
+        OptionPricingInput {
+            right,
+            style,
+            underlying_price,
+            strike,
+            time_to_expiry,
+            risk_free_rate,
+            dividend_yield,
+            volatility,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Converts calendar days to expiry into the year fraction used by the models
+    pub fn years_from_days(days: f64) -> f64 {
+        days / DAYS_PER_YEAR
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Present value of the dividends paid over the option's life, as reported by TWS in
+    /// tick_option_computation's pv_dividend
+    pub fn pv_dividend(&self) -> f64 {
+        self.underlying_price * (1.0 - (-self.dividend_yield * self.time_to_expiry).exp())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn with_volatility(&self, volatility: f64) -> Self {
+        let mut input = *self;
+        input.volatility = volatility;
+        input
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn validate(&self, check_volatility: bool) -> Result<(), IBKRApiLibError> {
+        if self.right == Right::None {
+            return Err(pricing_error("the option right must be Put or Call"));
+        }
+        if self.underlying_price.is_nan() || self.underlying_price <= 0.0 {
+            return Err(pricing_error("the underlying price must be positive"));
+        }
+        if self.strike.is_nan() || self.strike <= 0.0 {
+            return Err(pricing_error("the strike must be positive"));
+        }
+        if self.time_to_expiry.is_nan() || self.time_to_expiry < 0.0 {
+            return Err(pricing_error("the time to expiry can't be negative"));
+        }
+        if check_volatility && (self.volatility.is_nan() || self.volatility <= 0.0) {
+            return Err(pricing_error("the volatility must be positive"));
+        }
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn intrinsic_value(&self, underlying_price: f64) -> f64 {
+        match self.right {
+            Right::Call => f64::max(underlying_price - self.strike, 0.0),
+            _ => f64::max(self.strike - underlying_price, 0.0),
+        }
+    }
+}
+
+impl fmt::Display for OptionPricingInput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "right: {}, style: {}, underlying_price: {}, strike: {}, time_to_expiry: {}, risk_free_rate: {}, dividend_yield: {}, volatility: {}",
+            self.right,
+            self.style,
+            self.underlying_price,
+            self.strike,
+            self.time_to_expiry,
+            self.risk_free_rate,
+            self.dividend_yield,
+            self.volatility
+        )
+    }
+}
+
+//==================================================================================================
+/// Model price and greeks.
+///
+/// * price - option value
+/// * delta - change in price per 1.0 change in the underlying
+/// * gamma - change in delta per 1.0 change in the underlying
+/// * vega - change in price per 1 percentage point change in volatility
+/// * theta - change in price per calendar day
+/// * rho - change in price per 1 percentage point change in the risk free rate
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct OptionValuation {
+    pub price: f64,
+    pub delta: f64,
+    pub gamma: f64,
+    pub vega: f64,
+    pub theta: f64,
+    pub rho: f64,
+}
+
+impl OptionValuation {
+    pub fn new(price: f64, delta: f64, gamma: f64, vega: f64, theta: f64, rho: f64) -> Self {
+        OptionValuation {
+            price,
+            delta,
+            gamma,
+            vega,
+            theta,
+            rho,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Converts the valuation to the same shape as a tick_option_computation callback
+    pub fn to_option_greeks(&self, input: &OptionPricingInput) -> OptionGreeks {
+        OptionGreeks::new(
+            input.volatility,
+            self.delta,
+            self.price,
+            input.pv_dividend(),
+            self.gamma,
+            self.vega,
+            self.theta,
+            input.underlying_price,
+        )
+    }
+}
+
+impl fmt::Display for OptionValuation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "price: {}, delta: {}, gamma: {}, vega: {}, theta: {}, rho: {}",
+            self.price, self.delta, self.gamma, self.vega, self.theta, self.rho
+        )
+    }
+}
+
+//==================================================================================================
+fn pricing_error(description: &str) -> IBKRApiLibError {
+    IBKRApiLibError::ApiError(TwsApiReportableError::new(
+        NO_VALID_ID,
+        "-1".to_string(),
+        format!("Option pricing error: {}", description),
+    ))
+}
+
+//==================================================================================================
+/// Standard normal probability density
+pub fn norm_pdf(x: f64) -> f64 {
+    (-0.5 * x * x).exp() / (2.0 * PI).sqrt()
+}
+
+//==================================================================================================
+/// Standard normal cumulative distribution, using Hart's double precision approximation
+pub fn norm_cdf(x: f64) -> f64 {
+    let z = x.abs();
+    let tail = if z > 37.0 {
+        0.0
+    } else {
+        let e = (-z * z / 2.0).exp();
+        if z < 7.071_067_811_865_47 {
+            let mut n = 3.526_249_659_989_11e-2 * z + 0.700_383_064_443_688;
+            n = n * z + 6.373_962_203_531_65;
+            n = n * z + 33.912_866_078_383;
+            n = n * z + 112.079_291_497_871;
+            n = n * z + 221.213_596_169_931;
+            n = n * z + 220.206_867_912_376;
+            let mut d = 8.838_834_764_831_84e-2 * z + 1.755_667_163_182_64;
+            d = d * z + 16.064_177_579_207;
+            d = d * z + 86.780_732_202_946_1;
+            d = d * z + 296.564_248_779_674;
+            d = d * z + 637.333_633_378_831;
+            d = d * z + 793.826_512_519_948;
+            d = d * z + 440.413_735_824_752;
+            e * n / d
+        } else {
+            let mut d = z + 0.65;
+            d = z + 4.0 / d;
+            d = z + 3.0 / d;
+            d = z + 2.0 / d;
+            d = z + 1.0 / d;
+            e / d / 2.506_628_274_631
+        }
+    };
+    if x > 0.0 {
+        1.0 - tail
+    } else {
+        tail
+    }
+}
+
+//==================================================================================================
+/// Prices a European option with Black-Scholes-Merton
+pub fn black_scholes(input: &OptionPricingInput) -> Result<OptionValuation, IBKRApiLibError> {
+    input.validate(true)?;
+
+    let s = input.underlying_price;
+    let k = input.strike;
+    let t = input.time_to_expiry;
+    let r = input.risk_free_rate;
+    let q = input.dividend_yield;
+    let sigma = input.volatility;
+
+    if t == 0.0 {
+        return Ok(expired_valuation(input));
+    }
+
+    let sqrt_t = t.sqrt();
+    let d1 = ((s / k).ln() + (r - q + 0.5 * sigma * sigma) * t) / (sigma * sqrt_t);
+    let d2 = d1 - sigma * sqrt_t;
+    let div_discount = (-q * t).exp();
+    let discount = (-r * t).exp();
+
+    let gamma = div_discount * norm_pdf(d1) / (s * sigma * sqrt_t);
+    let vega = s * div_discount * norm_pdf(d1) * sqrt_t;
+    let decay = -s * div_discount * norm_pdf(d1) * sigma / (2.0 * sqrt_t);
+
+    let (price, delta, theta, rho) = match input.right {
+        Right::Call => (
+            s * div_discount * norm_cdf(d1) - k * discount * norm_cdf(d2),
+            div_discount * norm_cdf(d1),
+            decay - r * k * discount * norm_cdf(d2) + q * s * div_discount * norm_cdf(d1),
+            k * t * discount * norm_cdf(d2),
+        ),
+        _ => (
+            k * discount * norm_cdf(-d2) - s * div_discount * norm_cdf(-d1),
+            div_discount * (norm_cdf(d1) - 1.0),
+            decay + r * k * discount * norm_cdf(-d2) - q * s * div_discount * norm_cdf(-d1),
+            -k * t * discount * norm_cdf(-d2),
+        ),
+    };
+
+    Ok(OptionValuation::new(
+        price,
+        delta,
+        gamma,
+        vega / 100.0,
+        theta / DAYS_PER_YEAR,
+        rho / 100.0,
+    ))
+}
+
+//==================================================================================================
+/// Prices an option with a Cox-Ross-Rubinstein binomial tree.  American options may be
+/// exercised at every node.  Delta, gamma and theta come from the tree itself; vega and rho
+/// are computed by repricing with bumped inputs.
+pub fn binomial(
+    input: &OptionPricingInput,
+    steps: usize,
+) -> Result<OptionValuation, IBKRApiLibError> {
+    input.validate(true)?;
+    if steps < 3 {
+        return Err(pricing_error("the binomial tree needs at least 3 steps"));
+    }
+    if input.time_to_expiry == 0.0 {
+        return Ok(expired_valuation(input));
+    }
+
+    let (price, delta, gamma, theta) = binomial_tree(input, steps);
+
+    let vol_bump = 0.01;
+    let vega = (binomial_tree(&input.with_volatility(input.volatility + vol_bump), steps).0
+        - binomial_tree(
+            &input.with_volatility(f64::max(input.volatility - vol_bump, MIN_VOLATILITY)),
+            steps,
+        )
+        .0)
+        / (input.volatility + vol_bump - f64::max(input.volatility - vol_bump, MIN_VOLATILITY));
+
+    let rate_bump = 0.0001;
+    let mut rate_up = *input;
+    rate_up.risk_free_rate += rate_bump;
+    let mut rate_down = *input;
+    rate_down.risk_free_rate -= rate_bump;
+    let rho =
+        (binomial_tree(&rate_up, steps).0 - binomial_tree(&rate_down, steps).0) / (2.0 * rate_bump);
+
+    Ok(OptionValuation::new(
+        price,
+        delta,
+        gamma,
+        vega / 100.0,
+        theta / DAYS_PER_YEAR,
+        rho / 100.0,
+    ))
+}
+
+//----------------------------------------------------------------------------------------------
+/// Returns price, delta, gamma and annual theta
+fn binomial_tree(input: &OptionPricingInput, steps: usize) -> (f64, f64, f64, f64) {
+    let dt = input.time_to_expiry / steps as f64;
+    let up = (input.volatility * dt.sqrt()).exp();
+    let down = 1.0 / up;
+    let growth = ((input.risk_free_rate - input.dividend_yield) * dt).exp();
+    let prob_up = ((growth - down) / (up - down)).clamp(0.0, 1.0);
+    let discount = (-input.risk_free_rate * dt).exp();
+    let american = input.style == ExerciseStyle::American;
+    let node_price = |step: usize, ups: usize| {
+        input.underlying_price * up.powi(ups as i32) * down.powi((step - ups) as i32)
+    };
+
+    let mut values = (0..=steps)
+        .map(|ups| input.intrinsic_value(node_price(steps, ups)))
+        .collect::<Vec<f64>>();
+    // values at steps 1 and 2, kept for the greeks
+    let mut step_one = [0.0; 2];
+    let mut step_two = [0.0; 3];
+
+    for step in (0..steps).rev() {
+        for ups in 0..=step {
+            let continuation =
+                discount * (prob_up * values[ups + 1] + (1.0 - prob_up) * values[ups]);
+            values[ups] = if american {
+                f64::max(continuation, input.intrinsic_value(node_price(step, ups)))
+            } else {
+                continuation
+            };
+        }
+        if step == 2 {
+            step_two.copy_from_slice(&values[0..3]);
+        } else if step == 1 {
+            step_one.copy_from_slice(&values[0..2]);
+        }
+    }
+
+    let price = values[0];
+    let delta = (step_one[1] - step_one[0]) / (node_price(1, 1) - node_price(1, 0));
+    let delta_up = (step_two[2] - step_two[1]) / (node_price(2, 2) - node_price(2, 1));
+    let delta_down = (step_two[1] - step_two[0]) / (node_price(2, 1) - node_price(2, 0));
+    let gamma = (delta_up - delta_down) / (0.5 * (node_price(2, 2) - node_price(2, 0)));
+    let theta = (step_two[1] - price) / (2.0 * dt);
+    (price, delta, gamma, theta)
+}
+
+//----------------------------------------------------------------------------------------------
+fn expired_valuation(input: &OptionPricingInput) -> OptionValuation {
+    let price = input.intrinsic_value(input.underlying_price);
+    let delta = if price > 0.0 {
+        match input.right {
+            Right::Call => 1.0,
+            _ => -1.0,
+        }
+    } else {
+        0.0
+    };
+    OptionValuation::new(price, delta, 0.0, 0.0, 0.0, 0.0)
+}
+
+//==================================================================================================
+/// Prices an option with the model matching its exercise style: Black-Scholes-Merton for
+/// European options and a binomial tree with [DEFAULT_BINOMIAL_STEPS] steps for American options
+pub fn price_option(input: &OptionPricingInput) -> Result<OptionValuation, IBKRApiLibError> {
+    match input.style {
+        ExerciseStyle::European => black_scholes(input),
+        ExerciseStyle::American => binomial(input, DEFAULT_BINOMIAL_STEPS),
+    }
+}
+
+//==================================================================================================
+/// Solves for the volatility that reproduces `option_price`, using the model matching the
+/// exercise style.  The volatility in `input` is used as the starting guess if it is positive.
+/// Newton's method is tried first and bisection is used if it fails to converge.
+pub fn implied_volatility(
+    input: &OptionPricingInput,
+    option_price: f64,
+) -> Result<f64, IBKRApiLibError> {
+    input.validate(false)?;
+    if input.time_to_expiry == 0.0 {
+        return Err(pricing_error(
+            "implied volatility is undefined for expired options",
+        ));
+    }
+
+    let model_price = |volatility: f64| -> Result<f64, IBKRApiLibError> {
+        Ok(price_option(&input.with_volatility(volatility))?.price)
+    };
+
+    let low_price = model_price(MIN_VOLATILITY)?;
+    let high_price = model_price(MAX_VOLATILITY)?;
+    if option_price < low_price - IMPLIED_VOL_TOLERANCE || option_price > high_price {
+        return Err(pricing_error(
+            format!(
+                "option price {} is outside the range the model can reach [{}, {}]",
+                option_price, low_price, high_price
+            )
+            .as_str(),
+        ));
+    }
+
+    // Newton's method
+    let mut volatility = if input.volatility > 0.0 {
+        input.volatility
+    } else {
+        0.3
+    };
+    for _ in 0..IMPLIED_VOL_MAX_ITERATIONS {
+        let valuation = price_option(&input.with_volatility(volatility))?;
+        let diff = valuation.price - option_price;
+        if diff.abs() < IMPLIED_VOL_TOLERANCE {
+            return Ok(volatility);
+        }
+        // vega is per percentage point
+        let vega = valuation.vega * 100.0;
+        if vega.abs() < 1e-10 {
+            break;
+        }
+        volatility -= diff / vega;
+        if !(MIN_VOLATILITY..=MAX_VOLATILITY).contains(&volatility) {
+            break;
+        }
+    }
+
+    // bisection, always converges since the price is bracketed
+    let mut low = MIN_VOLATILITY;
+    let mut high = MAX_VOLATILITY;
+    for _ in 0..IMPLIED_VOL_MAX_ITERATIONS {
+        let mid = 0.5 * (low + high);
+        let diff = model_price(mid)? - option_price;
+        if diff.abs() < IMPLIED_VOL_TOLERANCE || high - low < IMPLIED_VOL_TOLERANCE {
+            return Ok(mid);
+        }
+        if diff > 0.0 {
+            high = mid;
+        } else {
+            low = mid;
+        }
+    }
+    Ok(0.5 * (low + high))
+}
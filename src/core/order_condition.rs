@@ -8,8 +8,10 @@ use serde::export::fmt::Error;
 use serde::export::Formatter;
 use serde::{Deserialize, Serialize};
 
-use crate::core::decoder::{decode_bool, decode_f64, decode_i32, decode_string};
-use crate::core::errors::IBKRApiLibError;
+use crate::core::decoder::{
+    decode_bool, decode_f64, decode_i32, decode_string, with_borrowed_fields,
+};
+use crate::core::errors::{IBKRApiLibError, TwsApiReportableError, TwsError};
 use crate::core::messages::make_field;
 
 //==================================================================================================
@@ -104,7 +106,7 @@ pub enum OrderConditionEnum {
 }
 
 impl Condition for OrderConditionEnum {
-    fn decode(&mut self, fields_iter: &mut Iter<String>) -> Result<(), IBKRApiLibError> {
+    fn decode(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IBKRApiLibError> {
         match self {
             OrderConditionEnum::Execution(s) => s.decode(fields_iter),
             OrderConditionEnum::Price(p) => p.decode(fields_iter),
@@ -189,7 +191,12 @@ impl Debug for OrderConditionEnum {
 
 //==================================================================================================
 pub trait Condition: Display + Debug + Serialize {
-    fn decode(&mut self, fields_iter: &mut Iter<String>) -> Result<(), IBKRApiLibError>;
+    /// Reads the condition from fields borrowed from a message
+    fn decode(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IBKRApiLibError>;
+    /// Reads the condition from owned fields, as decode did before it borrowed them
+    fn decode_owned(&mut self, fields_iter: &mut Iter<String>) -> Result<(), IBKRApiLibError> {
+        with_borrowed_fields(fields_iter, |fields_iter| self.decode(fields_iter))
+    }
     fn make_fields(&self) -> Result<Vec<String>, IBKRApiLibError>;
     fn value_to_string(&self) -> String;
     fn set_value_from_string(&mut self, text: String);
@@ -229,7 +236,7 @@ impl OrderCondition {
     }
 
     //----------------------------------------------------------------------------------------------
-    pub fn decode(&mut self, fields_iter: &mut Iter<String>) -> Result<(), IBKRApiLibError> {
+    pub fn decode(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IBKRApiLibError> {
         let connector = decode_string(fields_iter)?;
         self.is_conjunction_connection = connector == "a";
         Ok(())
@@ -269,7 +276,7 @@ impl ExecutionCondition {
 
 impl Condition for ExecutionCondition {
     //----------------------------------------------------------------------------------------------
-    fn decode(&mut self, fields_iter: &mut Iter<String>) -> Result<(), IBKRApiLibError> {
+    fn decode(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IBKRApiLibError> {
         self.order_condition.decode(fields_iter)?;
         self.sec_type = decode_string(fields_iter)?;
         self.exchange = decode_string(fields_iter)?;
@@ -342,17 +349,34 @@ impl OperatorCondition {
     }
 
     //----------------------------------------------------------------------------------------------
+    /// The operator as written in condition expressions: ">=" when is_more, "<=" otherwise
     pub fn value_to_string(&self) -> String {
-        unimplemented!();
+        if self.is_more {
+            ">=".to_string()
+        } else {
+            "<=".to_string()
+        }
     }
 
     //----------------------------------------------------------------------------------------------
-    pub fn set_value_from_string(&self, _text: &str) {
-        unimplemented!();
+    /// Sets is_more from an operator.  ">=" and ">" mean more, "<=" and "<" mean less.
+    pub fn set_value_from_string(&mut self, text: &str) -> Result<(), IBKRApiLibError> {
+        self.is_more = match text.trim() {
+            ">=" | ">" => true,
+            "<=" | "<" => false,
+            _ => {
+                return Err(IBKRApiLibError::ApiError(TwsApiReportableError::new(
+                    -1,
+                    TwsError::BadMessage.code().to_string(),
+                    format!("Invalid condition operator '{}'", text),
+                )))
+            }
+        };
+        Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    pub fn decode(&mut self, fields_iter: &mut Iter<String>) -> Result<(), IBKRApiLibError> {
+    pub fn decode(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IBKRApiLibError> {
         self.order_condition.decode(fields_iter)?;
         self.is_more = decode_bool(fields_iter)?;
         Ok(())
@@ -385,7 +409,7 @@ impl MarginCondition {
 
 impl Condition for MarginCondition {
     //----------------------------------------------------------------------------------------------
-    fn decode(&mut self, fields_iter: &mut Iter<String>) -> Result<(), IBKRApiLibError> {
+    fn decode(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IBKRApiLibError> {
         self.operator_condition.decode(fields_iter)?;
         self.percent = decode_f64(fields_iter).unwrap();
         Ok(())
@@ -452,7 +476,7 @@ impl ContractCondition {
 
 impl Condition for ContractCondition {
     //----------------------------------------------------------------------------------------------
-    fn decode(&mut self, fields_iter: &mut Iter<String>) -> Result<(), IBKRApiLibError> {
+    fn decode(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IBKRApiLibError> {
         self.operator_condition.decode(fields_iter)?;
         self.con_id = decode_i32(fields_iter)?;
         self.exchange = decode_string(fields_iter)?;
@@ -522,7 +546,7 @@ impl TimeCondition {
 
 impl Condition for TimeCondition {
     //----------------------------------------------------------------------------------------------
-    fn decode(&mut self, fields_iter: &mut Iter<String>) -> Result<(), IBKRApiLibError> {
+    fn decode(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IBKRApiLibError> {
         self.operator_condition.decode(fields_iter)?;
         self.time = decode_string(fields_iter).unwrap();
         Ok(())
@@ -606,7 +630,7 @@ impl PriceCondition {
 
 impl Condition for PriceCondition {
     //----------------------------------------------------------------------------------------------
-    fn decode(&mut self, fields_iter: &mut Iter<String>) -> Result<(), IBKRApiLibError> {
+    fn decode(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IBKRApiLibError> {
         self.price = decode_f64(fields_iter)?;
         self.contract_condition.decode(fields_iter)?;
         self.trigger_method = FromPrimitive::from_i32(decode_i32(fields_iter)?).unwrap();
@@ -693,7 +717,7 @@ impl PercentChangeCondition {
 
 impl Condition for PercentChangeCondition {
     //----------------------------------------------------------------------------------------------
-    fn decode(&mut self, fields_iter: &mut Iter<String>) -> Result<(), IBKRApiLibError> {
+    fn decode(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IBKRApiLibError> {
         self.change_percent = decode_f64(fields_iter)?;
         self.contract_condition.decode(fields_iter)?;
         Ok(())
@@ -779,7 +803,7 @@ impl VolumeCondition {
 
 impl Condition for VolumeCondition {
     //----------------------------------------------------------------------------------------------
-    fn decode(&mut self, fields_iter: &mut Iter<String>) -> Result<(), IBKRApiLibError> {
+    fn decode(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IBKRApiLibError> {
         self.contract_condition.decode(fields_iter)?;
         self.volume = decode_i32(fields_iter)?;
 
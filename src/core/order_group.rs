@@ -0,0 +1,508 @@
+//! Placement of bracket, OCA and attached orders as one group
+use std::fmt;
+
+use log::*;
+use serde::{Deserialize, Serialize};
+
+use crate::core::client::EClient;
+use crate::core::common::TERMINAL_ORDER_STATUSES;
+use crate::core::contract::Contract;
+use crate::core::errors::{IBKRApiLibError, TwsApiReportableError, TwsError};
+use crate::core::order::{Order, OrderState};
+use crate::core::wrapper::Wrapper;
+
+//==================================================================================================
+fn order_group_error(description: String) -> IBKRApiLibError {
+    IBKRApiLibError::ApiError(TwsApiReportableError::new(
+        -1,
+        TwsError::BadMessage.code().to_string(),
+        description,
+    ))
+}
+
+//==================================================================================================
+fn opposite_action(action: &str) -> String {
+    if action.eq_ignore_ascii_case("BUY") {
+        "SELL".to_string()
+    } else {
+        "BUY".to_string()
+    }
+}
+
+//==================================================================================================
+/// How the remaining orders of an OCA group are handled when one of them fills
+#[repr(i32)]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OcaType {
+    /// Cancel the other orders, with block to prevent overfills
+    CancelWithBlock = 1,
+    /// Reduce the other orders proportionally, with block
+    ReduceWithBlock = 2,
+    /// Reduce the other orders proportionally, without block
+    ReduceNonBlock = 3,
+}
+
+impl fmt::Display for OcaType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            OcaType::CancelWithBlock => write!(f, "CancelWithBlock"),
+            OcaType::ReduceWithBlock => write!(f, "ReduceWithBlock"),
+            OcaType::ReduceNonBlock => write!(f, "ReduceNonBlock"),
+        }
+    }
+}
+
+//==================================================================================================
+/// Kind of an attached hedge order, sent as Order::hedge_type
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HedgeType {
+    Delta,
+    Beta,
+    Fx,
+    Pair,
+}
+
+impl HedgeType {
+    pub fn code(&self) -> &'static str {
+        match *self {
+            HedgeType::Delta => "D",
+            HedgeType::Beta => "B",
+            HedgeType::Fx => "F",
+            HedgeType::Pair => "P",
+        }
+    }
+}
+
+impl fmt::Display for HedgeType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.code())
+    }
+}
+
+//==================================================================================================
+/// Combined state of the orders in a group
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OrderGroupState {
+    /// Not placed yet
+    Staged,
+    /// At least one order can still fill
+    Working,
+    /// Every order is done and at least one filled
+    Completed,
+    /// Every order is done and none filled
+    Cancelled,
+}
+
+impl fmt::Display for OrderGroupState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            OrderGroupState::Staged => write!(f, "Staged"),
+            OrderGroupState::Working => write!(f, "Working"),
+            OrderGroupState::Completed => write!(f, "Completed"),
+            OrderGroupState::Cancelled => write!(f, "Cancelled"),
+        }
+    }
+}
+
+//==================================================================================================
+/// An order in a group with the latest status TWS reported for it
+#[derive(Clone, Debug)]
+pub struct GroupOrder {
+    pub contract: Contract,
+    pub order: Order,
+    /// Index of the parent order in the group
+    pub parent: Option<usize>,
+    pub status: String,
+    pub filled: f64,
+    pub remaining: f64,
+    pub avg_fill_price: f64,
+}
+
+impl GroupOrder {
+    pub fn new(contract: Contract, order: Order, parent: Option<usize>) -> Self {
+        GroupOrder {
+            contract,
+            order,
+            parent,
+            status: "".to_string(),
+            filled: 0.0,
+            remaining: 0.0,
+            avg_fill_price: 0.0,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn is_done(&self) -> bool {
+        TERMINAL_ORDER_STATUSES.contains(&self.status.as_str())
+    }
+}
+
+impl fmt::Display for GroupOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "order_id: {}, {} {} {} {}, status: {}, filled: {}, remaining: {}",
+            self.order.order_id,
+            self.order.action,
+            self.order.total_quantity,
+            self.contract.symbol,
+            self.order.order_type,
+            self.status,
+            self.filled,
+            self.remaining
+        )
+    }
+}
+
+//==================================================================================================
+/// Orders that are placed together: brackets, OCA groups, trailing stops and hedges attached to
+/// a parent.
+///
+/// [OrderGroup::place] allocates consecutive order ids, links children to their parents and sets
+/// transmit so that nothing in a parent's family is transmitted until its last order is placed.
+/// If placing any order fails, the orders already placed are cancelled.  Forward order_status
+/// and open_order to follow the combined [OrderGroupState].
+#[derive(Clone, Debug, Default)]
+pub struct OrderGroup {
+    orders: Vec<GroupOrder>,
+    placed: bool,
+}
+
+impl OrderGroup {
+    pub fn new() -> Self {
+        OrderGroup::default()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn push(&mut self, contract: &Contract, order: Order, parent: Option<usize>) -> usize {
+        self.orders
+            .push(GroupOrder::new(contract.clone(), order, parent));
+        self.orders.len() - 1
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn check_parent(&self, parent: usize) -> Result<(), IBKRApiLibError> {
+        if parent < self.orders.len() {
+            Ok(())
+        } else {
+            Err(order_group_error(format!(
+                "No order at index {} to attach to, the group has {} orders",
+                parent,
+                self.orders.len()
+            )))
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Adds an order without a parent and returns its index in the group
+    pub fn add(&mut self, contract: &Contract, order: Order) -> usize {
+        self.push(contract, order, None)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Adds a child of the order at index `parent` and returns its index in the group.  It is an
+    /// error if there is no order at `parent`.
+    pub fn attach(
+        &mut self,
+        parent: usize,
+        contract: &Contract,
+        order: Order,
+    ) -> Result<usize, IBKRApiLibError> {
+        self.check_parent(parent)?;
+        Ok(self.push(contract, order, Some(parent)))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// A limit entry order with a take profit limit order and a stop loss attached
+    pub fn bracket(
+        contract: &Contract,
+        action: &str,
+        quantity: f64,
+        limit_price: f64,
+        take_profit_limit_price: f64,
+        stop_loss_price: f64,
+    ) -> Self {
+        let mut group = OrderGroup::new();
+
+        let parent = Order {
+            action: action.to_string(),
+            order_type: "LMT".to_string(),
+            total_quantity: quantity,
+            lmt_price: limit_price,
+            ..Default::default()
+        };
+        let parent = group.add(contract, parent);
+
+        let take_profit = Order {
+            action: opposite_action(action),
+            order_type: "LMT".to_string(),
+            total_quantity: quantity,
+            lmt_price: take_profit_limit_price,
+            ..Default::default()
+        };
+        group.push(contract, take_profit, Some(parent));
+
+        let stop_loss = Order {
+            action: opposite_action(action),
+            order_type: "STP".to_string(),
+            total_quantity: quantity,
+            aux_price: stop_loss_price,
+            ..Default::default()
+        };
+        group.push(contract, stop_loss, Some(parent));
+        group
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Orders in one OCA group: when one fills the others are cancelled or reduced
+    pub fn oca(oca_group: &str, oca_type: OcaType, orders: Vec<(Contract, Order)>) -> Self {
+        let mut group = OrderGroup::new();
+        for (contract, mut order) in orders {
+            order.oca_group = oca_group.to_string();
+            order.oca_type = oca_type as i32;
+            group.add(&contract, order);
+        }
+        group
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Attaches a trailing stop that closes the position opened by the order at `parent`.  Set
+    /// `trailing_percent` to trail by a percentage or `trailing_amount` to trail by an amount;
+    /// the unused one should be UNSET_DOUBLE.
+    pub fn attach_trailing_stop(
+        &mut self,
+        parent: usize,
+        trailing_percent: f64,
+        trailing_amount: f64,
+        trail_stop_price: f64,
+    ) -> Result<usize, IBKRApiLibError> {
+        self.check_parent(parent)?;
+        let (contract, parent_order) = {
+            let parent = &self.orders[parent];
+            (parent.contract.clone(), &parent.order)
+        };
+        let order = Order {
+            action: opposite_action(parent_order.action.as_str()),
+            order_type: "TRAIL".to_string(),
+            total_quantity: parent_order.total_quantity,
+            trailing_percent,
+            aux_price: trailing_amount,
+            trail_stop_price,
+            ..Default::default()
+        };
+        self.attach(parent, &contract, order)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Attaches a market hedge order on `contract`.  TWS computes the quantity; `hedge_param` is
+    /// "beta=X" for beta hedges, "ratio=Y" for pair hedges and empty otherwise.
+    pub fn attach_hedge(
+        &mut self,
+        parent: usize,
+        contract: &Contract,
+        action: &str,
+        hedge_type: HedgeType,
+        hedge_param: &str,
+    ) -> Result<usize, IBKRApiLibError> {
+        let order = Order {
+            action: action.to_string(),
+            order_type: "MKT".to_string(),
+            total_quantity: 0.0,
+            hedge_type: hedge_type.code().to_string(),
+            hedge_param: hedge_param.to_string(),
+            ..Default::default()
+        };
+        self.attach(parent, contract, order)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn orders(&self) -> &[GroupOrder] {
+        self.orders.as_slice()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn order(&self, index: usize) -> Option<&GroupOrder> {
+        self.orders.get(index)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn owns_order(&self, order_id: i32) -> bool {
+        self.placed && self.index_of(order_id).is_some()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn index_of(&self, order_id: i32) -> Option<usize> {
+        self.orders
+            .iter()
+            .position(|group_order| group_order.order.order_id == order_id)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn root(&self, mut index: usize) -> usize {
+        while let Some(parent) = self.orders[index].parent {
+            index = parent;
+        }
+        index
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Allocates order ids starting at `first_order_id`, links children to their parents and sets
+    /// the transmit flags.  Only the last order of each family is transmitted, which releases
+    /// the orders before it.  Returns the next unused order id.
+    pub fn assign_ids(&mut self, first_order_id: i32) -> i32 {
+        let mut next_order_id = first_order_id;
+        for group_order in self.orders.iter_mut() {
+            group_order.order.order_id = next_order_id;
+            next_order_id += 1;
+        }
+        for index in 0..self.orders.len() {
+            let parent_id = self.orders[index]
+                .parent
+                .map_or(0, |parent| self.orders[parent].order.order_id);
+            let root = self.root(index);
+            let last_of_family =
+                !(index + 1..self.orders.len()).any(|later| self.root(later) == root);
+            let group_order = &mut self.orders[index];
+            group_order.order.parent_id = parent_id;
+            group_order.order.transmit = last_of_family;
+        }
+        next_order_id
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Places every order of the group with ids starting at `first_order_id`.  Returns the next
+    /// unused order id.  A group can only be placed once; placing it again is an error.
+    pub fn place<T: Wrapper + Send + Sync + 'static>(
+        &mut self,
+        client: &mut EClient<T>,
+        first_order_id: i32,
+    ) -> Result<i32, IBKRApiLibError> {
+        if self.placed {
+            return Err(order_group_error(format!(
+                "Order group starting at order {} was already placed",
+                self.orders
+                    .first()
+                    .map_or(first_order_id, |first| first.order.order_id)
+            )));
+        }
+        let next_order_id = self.assign_ids(first_order_id);
+        for (index, group_order) in self.orders.iter().enumerate() {
+            if let Err(err) = client.place_order(
+                group_order.order.order_id,
+                &group_order.contract,
+                &group_order.order,
+            ) {
+                for placed in self.orders[..index].iter() {
+                    if let Err(cancel_err) = client.cancel_order(placed.order.order_id) {
+                        warn!(
+                            "Could not cancel order {} of a failed group: {:?}",
+                            placed.order.order_id, cancel_err
+                        );
+                    }
+                }
+                return Err(err);
+            }
+        }
+        self.mark_placed();
+        Ok(next_order_id)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Marks the group as placed when its orders were sent some other way after
+    /// [OrderGroup::assign_ids]
+    pub fn mark_placed(&mut self) {
+        self.placed = true;
+        for group_order in self.orders.iter_mut() {
+            group_order.status = "PendingSubmit".to_string();
+            group_order.remaining = group_order.order.total_quantity;
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Cancels the orders that are still working
+    pub fn cancel<T: Wrapper + Send + Sync + 'static>(
+        &self,
+        client: &mut EClient<T>,
+    ) -> Result<(), IBKRApiLibError> {
+        for group_order in self.orders.iter().filter(|order| !order.is_done()) {
+            client.cancel_order(group_order.order.order_id)?;
+        }
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Forwarded from Wrapper::order_status
+    #[allow(clippy::too_many_arguments)]
+    pub fn order_status(
+        &mut self,
+        order_id: i32,
+        status: &str,
+        filled: f64,
+        remaining: f64,
+        avg_fill_price: f64,
+        _perm_id: i32,
+        _parent_id: i32,
+        _last_fill_price: f64,
+        _client_id: i32,
+        _why_held: &str,
+        _mkt_cap_price: f64,
+    ) -> bool {
+        if !self.placed {
+            return false;
+        }
+        match self.index_of(order_id) {
+            Some(index) => {
+                let group_order = &mut self.orders[index];
+                group_order.status = status.to_string();
+                group_order.filled = filled;
+                group_order.remaining = remaining;
+                group_order.avg_fill_price = avg_fill_price;
+                true
+            }
+            None => false,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Forwarded from Wrapper::open_order
+    pub fn open_order(&mut self, order_id: i32, order_state: &OrderState) -> bool {
+        if !self.placed {
+            return false;
+        }
+        match self.index_of(order_id) {
+            Some(index) => {
+                self.orders[index].status = order_state.status.clone();
+                true
+            }
+            None => false,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn state(&self) -> OrderGroupState {
+        if !self.placed {
+            OrderGroupState::Staged
+        } else if !self.orders.iter().all(GroupOrder::is_done) {
+            OrderGroupState::Working
+        } else if self.orders.iter().any(|order| order.filled > 0.0) {
+            OrderGroupState::Completed
+        } else {
+            OrderGroupState::Cancelled
+        }
+    }
+}
+
+impl fmt::Display for OrderGroup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "state: {}, orders: [{}]",
+            self.state(),
+            self.orders
+                .iter()
+                .map(|order| order.to_string())
+                .collect::<Vec<String>>()
+                .join("; ")
+        )
+    }
+}
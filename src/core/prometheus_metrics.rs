@@ -0,0 +1,443 @@
+//! Prometheus metrics of connections, with a minimal HTTP endpoint to scrape them.  Requires the
+//! `metrics` feature.
+//!
+//! One [PrometheusMetrics] holds the metrics of all connections, told apart by a `connection`
+//! label.  Give each client its own [ConnectionMetrics]:
+//!
+//! ```no_run
+//! # use std::sync::{Arc, Mutex};
+//! # use twsapi::core::client::EClient;
+//! # use twsapi::core::prometheus_metrics::PrometheusMetrics;
+//! # use twsapi::examples::defaults::DefaultWrapper;
+//! # fn main() -> Result<(), twsapi::core::errors::IBKRApiLibError> {
+//! let metrics = PrometheusMetrics::new()?;
+//! let _server = metrics.serve("127.0.0.1:9184")?;
+//! let mut client = EClient::new(Arc::new(Mutex::new(DefaultWrapper::new())));
+//! client.set_metrics(Some(metrics.connection("gateway-1")));
+//! # Ok(())
+//! # }
+//! ```
+use std::collections::{HashMap, HashSet};
+use std::io::{Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use log::*;
+use num_traits::FromPrimitive;
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec, Opts,
+    Registry, TextEncoder,
+};
+
+use crate::core::errors::{IBKRApiLibError, TwsApiReportableError, TwsError};
+use crate::core::messages::{IncomingMessageIds, OutgoingMessageIds};
+use crate::core::metrics::{ends_request, MetricsSink, SubscriptionKind};
+
+/// Buckets of the decode time histogram, in seconds
+const DECODE_BUCKETS: &[f64] = &[
+    0.000_005, 0.000_01, 0.000_025, 0.000_05, 0.000_1, 0.000_25, 0.000_5, 0.001, 0.005, 0.025,
+];
+
+//==================================================================================================
+fn metrics_error(description: String) -> IBKRApiLibError {
+    IBKRApiLibError::ApiError(TwsApiReportableError::new(
+        -1,
+        TwsError::BadMessage.code().to_string(),
+        description,
+    ))
+}
+
+//==================================================================================================
+fn prometheus_error(err: prometheus::Error) -> IBKRApiLibError {
+    metrics_error(format!("Prometheus error: {}", err))
+}
+
+//==================================================================================================
+fn incoming_name(msg_id: i32) -> String {
+    match <IncomingMessageIds as FromPrimitive>::from_i32(msg_id) {
+        Some(id) => format!("{:?}", id),
+        None => msg_id.to_string(),
+    }
+}
+
+//==================================================================================================
+fn outgoing_name(msg_id: i32) -> String {
+    match <OutgoingMessageIds as FromPrimitive>::from_i32(msg_id) {
+        Some(id) => format!("{:?}", id),
+        None => msg_id.to_string(),
+    }
+}
+
+//==================================================================================================
+/// Metrics of all connections, registered in one registry
+#[derive(Clone)]
+pub struct PrometheusMetrics {
+    registry: Registry,
+    messages_received: IntCounterVec,
+    messages_sent: IntCounterVec,
+    decode_seconds: HistogramVec,
+    queue_depth: IntGaugeVec,
+    bytes_received: IntCounterVec,
+    bytes_sent: IntCounterVec,
+    connects: IntCounterVec,
+    reconnects: IntCounterVec,
+    disconnects: IntCounterVec,
+    errors: IntCounterVec,
+    subscriptions: IntGaugeVec,
+}
+
+impl PrometheusMetrics {
+    /// Metrics in a registry of their own
+    pub fn new() -> Result<Self, IBKRApiLibError> {
+        Self::with_registry(Registry::new())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Registers the metrics in an existing registry, e.g. `prometheus::default_registry()`
+    pub fn with_registry(registry: Registry) -> Result<Self, IBKRApiLibError> {
+        let counter = |name: &str, help: &str, labels: &[&str]| {
+            IntCounterVec::new(Opts::new(name, help), labels).map_err(prometheus_error)
+        };
+        let gauge = |name: &str, help: &str, labels: &[&str]| {
+            IntGaugeVec::new(Opts::new(name, help), labels).map_err(prometheus_error)
+        };
+        let metrics = PrometheusMetrics {
+            messages_received: counter(
+                "twsapi_messages_received_total",
+                "Messages decoded, by IncomingMessageIds",
+                &["connection", "message"],
+            )?,
+            messages_sent: counter(
+                "twsapi_messages_sent_total",
+                "Requests sent, by OutgoingMessageIds",
+                &["connection", "message"],
+            )?,
+            decode_seconds: HistogramVec::new(
+                HistogramOpts::new(
+                    "twsapi_decode_seconds",
+                    "Time to decode a message and run its wrapper callback",
+                )
+                .buckets(DECODE_BUCKETS.to_vec()),
+                &["connection"],
+            )
+            .map_err(prometheus_error)?,
+            queue_depth: gauge(
+                "twsapi_queue_depth",
+                "Messages read from the socket and waiting for the decoder",
+                &["connection"],
+            )?,
+            bytes_received: counter(
+                "twsapi_bytes_received_total",
+                "Bytes read from the socket",
+                &["connection"],
+            )?,
+            bytes_sent: counter(
+                "twsapi_bytes_sent_total",
+                "Bytes written to the socket",
+                &["connection"],
+            )?,
+            connects: counter(
+                "twsapi_connects_total",
+                "Completed connections",
+                &["connection"],
+            )?,
+            reconnects: counter(
+                "twsapi_reconnects_total",
+                "Connections after the first one",
+                &["connection"],
+            )?,
+            disconnects: counter(
+                "twsapi_disconnects_total",
+                "Closed connections",
+                &["connection"],
+            )?,
+            errors: counter(
+                "twsapi_errors_total",
+                "Errors and warnings reported by TWS, by code",
+                &["connection", "code"],
+            )?,
+            subscriptions: gauge(
+                "twsapi_subscriptions",
+                "Active streaming subscriptions, by type",
+                &["connection", "kind"],
+            )?,
+            registry,
+        };
+        metrics.register()?;
+        Ok(metrics)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn register(&self) -> Result<(), IBKRApiLibError> {
+        let collectors: Vec<Box<dyn prometheus::core::Collector>> = vec![
+            Box::new(self.messages_received.clone()),
+            Box::new(self.messages_sent.clone()),
+            Box::new(self.decode_seconds.clone()),
+            Box::new(self.queue_depth.clone()),
+            Box::new(self.bytes_received.clone()),
+            Box::new(self.bytes_sent.clone()),
+            Box::new(self.connects.clone()),
+            Box::new(self.reconnects.clone()),
+            Box::new(self.disconnects.clone()),
+            Box::new(self.errors.clone()),
+            Box::new(self.subscriptions.clone()),
+        ];
+        for collector in collectors {
+            self.registry
+                .register(collector)
+                .map_err(prometheus_error)?;
+        }
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn registry(&self) -> &Registry {
+        &self.registry
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Sink for one connection, to be passed to EClient::set_metrics
+    pub fn connection(&self, name: &str) -> Arc<ConnectionMetrics> {
+        let labels = &[name];
+        Arc::new(ConnectionMetrics {
+            name: name.to_string(),
+            messages_received: self.messages_received.clone(),
+            messages_sent: self.messages_sent.clone(),
+            decode_seconds: self.decode_seconds.with_label_values(labels),
+            queue_depth: self.queue_depth.with_label_values(labels),
+            bytes_received: self.bytes_received.with_label_values(labels),
+            bytes_sent: self.bytes_sent.with_label_values(labels),
+            connects: self.connects.with_label_values(labels),
+            reconnects: self.reconnects.with_label_values(labels),
+            disconnects: self.disconnects.with_label_values(labels),
+            errors: self.errors.clone(),
+            subscriptions: self.subscriptions.clone(),
+            active: Mutex::new(HashMap::new()),
+        })
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// All metrics of the registry in the Prometheus text format
+    pub fn encode(&self) -> Result<String, IBKRApiLibError> {
+        encode(&self.registry)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Serves the registry over HTTP on a background thread until the returned server is stopped
+    /// or dropped.  Use port 0 to pick a free port.
+    pub fn serve<A: ToSocketAddrs>(&self, addr: A) -> Result<MetricsServer, IBKRApiLibError> {
+        MetricsServer::start(addr, self.registry.clone())
+    }
+}
+
+//==================================================================================================
+fn encode(registry: &Registry) -> Result<String, IBKRApiLibError> {
+    let mut buffer = vec![];
+    TextEncoder::new()
+        .encode(&registry.gather(), &mut buffer)
+        .map_err(prometheus_error)?;
+    String::from_utf8(buffer).map_err(|err| metrics_error(err.to_string()))
+}
+
+//==================================================================================================
+/// [MetricsSink] of one connection
+pub struct ConnectionMetrics {
+    name: String,
+    messages_received: IntCounterVec,
+    messages_sent: IntCounterVec,
+    decode_seconds: prometheus::Histogram,
+    queue_depth: IntGauge,
+    bytes_received: IntCounter,
+    bytes_sent: IntCounter,
+    connects: IntCounter,
+    reconnects: IntCounter,
+    disconnects: IntCounter,
+    errors: IntCounterVec,
+    subscriptions: IntGaugeVec,
+    /// Request ids of the active subscriptions of each kind
+    active: Mutex<HashMap<SubscriptionKind, HashSet<i32>>>,
+}
+
+impl ConnectionMetrics {
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn update_subscriptions(&self, kind: SubscriptionKind, req_id: i32, subscribed: bool) {
+        let mut active = self.active.lock().unwrap();
+        let req_ids = active.entry(kind).or_default();
+        if subscribed {
+            req_ids.insert(req_id);
+        } else {
+            req_ids.remove(&req_id);
+        }
+        self.subscriptions
+            .with_label_values(&[self.name.as_str(), kind.as_str()])
+            .set(req_ids.len() as i64);
+    }
+}
+
+impl MetricsSink for ConnectionMetrics {
+    fn message_queued(&self) {
+        self.queue_depth.inc();
+    }
+
+    fn message_dequeued(&self) {
+        self.queue_depth.dec();
+    }
+
+    fn message_received(&self, msg_id: i32, decode_time: Duration) {
+        self.messages_received
+            .with_label_values(&[self.name.as_str(), incoming_name(msg_id).as_str()])
+            .inc();
+        self.decode_seconds.observe(decode_time.as_secs_f64());
+    }
+
+    fn message_sent(&self, msg_id: i32) {
+        self.messages_sent
+            .with_label_values(&[self.name.as_str(), outgoing_name(msg_id).as_str()])
+            .inc();
+    }
+
+    fn bytes_received(&self, count: usize) {
+        self.bytes_received.inc_by(count as u64);
+    }
+
+    fn bytes_sent(&self, count: usize) {
+        self.bytes_sent.inc_by(count as u64);
+    }
+
+    fn connected(&self) {
+        if self.connects.get() > 0 {
+            self.reconnects.inc();
+        }
+        self.connects.inc();
+    }
+
+    fn disconnected(&self) {
+        self.disconnects.inc();
+        // TWS forgets the subscriptions of a closed connection
+        let mut active = self.active.lock().unwrap();
+        for (kind, req_ids) in active.iter_mut() {
+            req_ids.clear();
+            self.subscriptions
+                .with_label_values(&[self.name.as_str(), kind.as_str()])
+                .set(0);
+        }
+        self.queue_depth.set(0);
+    }
+
+    fn error_received(&self, req_id: i32, code: i32) {
+        self.errors
+            .with_label_values(&[self.name.as_str(), code.to_string().as_str()])
+            .inc();
+        if !ends_request(req_id, code) {
+            return;
+        }
+        let kinds = self
+            .active
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, req_ids)| req_ids.contains(&req_id))
+            .map(|(kind, _)| *kind)
+            .collect::<Vec<SubscriptionKind>>();
+        for kind in kinds {
+            self.update_subscriptions(kind, req_id, false);
+        }
+    }
+
+    fn subscribed(&self, kind: SubscriptionKind, req_id: i32) {
+        self.update_subscriptions(kind, req_id, true);
+    }
+
+    fn unsubscribed(&self, kind: SubscriptionKind, req_id: i32) {
+        self.update_subscriptions(kind, req_id, false);
+    }
+}
+
+//==================================================================================================
+/// HTTP endpoint answering every GET with the metrics of a registry
+pub struct MetricsServer {
+    local_addr: SocketAddr,
+    stop_requested: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl MetricsServer {
+    fn start<A: ToSocketAddrs>(addr: A, registry: Registry) -> Result<Self, IBKRApiLibError> {
+        let listener = TcpListener::bind(addr)?;
+        let local_addr = listener.local_addr()?;
+        let stop_requested = Arc::new(AtomicBool::new(false));
+        let stop = stop_requested.clone();
+        let handle = thread::spawn(move || {
+            for stream in listener.incoming() {
+                if stop.load(Ordering::Acquire) {
+                    break;
+                }
+                match stream {
+                    Ok(stream) => {
+                        if let Err(err) = respond(stream, &registry) {
+                            warn!("metrics request failed: {:?}", err);
+                        }
+                    }
+                    Err(err) => warn!("metrics connection failed: {}", err),
+                }
+            }
+        });
+        info!("serving metrics on http://{}/metrics", local_addr);
+        Ok(MetricsServer {
+            local_addr,
+            stop_requested,
+            handle: Some(handle),
+        })
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn stop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            self.stop_requested.store(true, Ordering::Release);
+            // wake up the accept loop
+            let _ = TcpStream::connect(self.local_addr);
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for MetricsServer {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+//==================================================================================================
+fn respond(mut stream: TcpStream, registry: &Registry) -> Result<(), IBKRApiLibError> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut request = [0; 1024];
+    let count = stream.read(&mut request)?;
+    let request = String::from_utf8_lossy(&request[..count]);
+    let response = if request.starts_with("GET ") {
+        let body = encode(registry)?;
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            prometheus::TEXT_FORMAT,
+            body.len(),
+            body
+        )
+    } else {
+        "HTTP/1.1 405 Method Not Allowed\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+            .to_string()
+    };
+    stream.write_all(response.as_bytes())?;
+    stream.shutdown(Shutdown::Both)?;
+    Ok(())
+}
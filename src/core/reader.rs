@@ -1,15 +1,18 @@
 //! Reads and processes messages from the TCP socket
+use std::convert::TryInto;
 use std::io::Read;
 use std::net::Shutdown;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::mpsc::Sender;
+use std::sync::mpsc::{Receiver, Sender};
 use std::sync::Arc;
 
 use log::*;
 
 use super::streamer::Streamer;
+use crate::core::common::NO_VALID_ID;
 use crate::core::errors::IBKRApiLibError;
-use crate::core::messages::read_msg;
+use crate::core::messages::{error_message, read_msg_slice};
+use crate::core::metrics::MetricsSink;
 
 //==================================================================================================
 pub struct Reader {
@@ -17,6 +20,11 @@ pub struct Reader {
     messages: Sender<String>,
     disconnect_requested: Arc<AtomicBool>,
     is_connected: bool,
+    metrics: Option<Arc<dyn MetricsSink>>,
+    /// Bytes received but not yet sent on as messages, kept to avoid reallocating per packet
+    packet: Vec<u8>,
+    /// Strings the decoder is done with, reused for the next messages
+    spare_buffers: Option<Receiver<String>>,
 }
 
 impl Reader {
@@ -30,13 +38,31 @@ impl Reader {
             messages,
             disconnect_requested,
             is_connected: true,
+            metrics: None,
+            packet: Vec::new(),
+            spare_buffers: None,
         }
     }
 
+    //----------------------------------------------------------------------------------------------
+    pub fn set_metrics(&mut self, metrics: Option<Arc<dyn MetricsSink>>) {
+        self.metrics = metrics;
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Reuses the Strings the decoder sends back for new messages, see
+    /// [Decoder::set_spare_buffers](crate::core::decoder::Decoder::set_spare_buffers)
+    pub fn set_spare_buffers(&mut self, spare_buffers: Option<Receiver<String>>) {
+        self.spare_buffers = spare_buffers;
+    }
+
     //----------------------------------------------------------------------------------------------
     pub fn recv_packet(&mut self) -> Result<Vec<u8>, IBKRApiLibError> {
         //debug!("_recv_all_msg");
         let buf = self._recv_all_msg()?;
+        if let Some(metrics) = &self.metrics {
+            metrics.bytes_received(buf.len());
+        }
         // receiving 0 bytes outside a timeout means the connection is either
         // closed or broken
         if buf.len() == 0 {
@@ -72,44 +98,111 @@ impl Reader {
         Ok(allbuf)
     }
 
+    //----------------------------------------------------------------------------------------------
+    /// Appends what the socket has to the packet buffer, and returns the number of bytes read
+    fn fill_packet(&mut self) -> Result<usize, IBKRApiLibError> {
+        const NUM_BYTES: usize = 4096;
+        let mut total = 0;
+
+        loop {
+            let start = self.packet.len();
+            self.packet.resize(start + NUM_BYTES, 0);
+            let bytes_read = self
+                .stream
+                .read(&mut self.packet[start..])
+                .expect("Couldnt read from reader...");
+            self.packet.truncate(start + bytes_read);
+            total += bytes_read;
+
+            if bytes_read < NUM_BYTES {
+                break;
+            }
+        }
+        if let Some(metrics) = &self.metrics {
+            metrics.bytes_received(total);
+        }
+        // receiving 0 bytes outside a timeout means the connection is either
+        // closed or broken
+        if total == 0 && !self.disconnect_requested.load(Ordering::Acquire) {
+            info!("socket either closed or broken, disconnecting");
+            self.stream.shutdown(Shutdown::Both)?;
+            self.is_connected = false;
+        }
+        Ok(total)
+    }
+
     //----------------------------------------------------------------------------------------------
     fn process_reader_msgs(&mut self) -> Result<(), IBKRApiLibError> {
         // grab a packet of messages from the socket
-        let mut message_packet = self.recv_packet()?;
-        //debug!(" recvd size {}", message_packet.len());
-
-        // Read messages from the packet until there are no more.
-        // When this loop ends, break into the outer loop and grab another packet.
-        // Repeat until the connection is closed
-        //
-        let _msg = String::new();
-        while message_packet.len() > 0 {
-            // Read a message from the packet then add it to the message queue below.
-            let (_size, msg, remaining_messages) = read_msg(message_packet.as_slice())?;
-
-            // clear the Vec that holds the bytes from the packet
-            // and reload with the bytes that haven't been read.
-            // The variable remaining_messages only holds the unread messagesleft in the packet
-            message_packet.clear();
-            message_packet.extend_from_slice(remaining_messages.as_slice());
-
-            if msg.as_str() != "" {
-                self.messages.send(msg).expect("READER CANNOT SEND MESSAGE");
-            } else {
-                //Break to the outer loop in run and get another packet of messages.
-
-                debug!("more incoming packet(s) are needed ");
-                break;
+        if self.fill_packet()? == 0 {
+            return Ok(());
+        }
+
+        // Read messages from the packet until there are no more, or only part of one is left.
+        // The unread bytes stay in the buffer, in front of the next packet.
+        let mut consumed = 0;
+        loop {
+            let text = match read_msg_slice(&self.packet[consumed..]) {
+                Ok(Some((msg, remaining_messages))) => {
+                    consumed = self.packet.len() - remaining_messages.len();
+                    if msg.is_empty() {
+                        continue;
+                    }
+                    let mut text = self
+                        .spare_buffers
+                        .as_ref()
+                        .and_then(|spare_buffers| spare_buffers.try_recv().ok())
+                        .unwrap_or_default();
+                    text.clear();
+                    text.push_str(msg);
+                    text
+                }
+                Ok(None) => break,
+                Err(err) => {
+                    // the whole frame is there but is not text: skip it and tell the wrapper
+                    let size =
+                        i32::from_be_bytes(self.packet[consumed..consumed + 4].try_into().unwrap())
+                            as usize;
+                    consumed += 4 + size;
+                    error!("Skipping malformed message of {} bytes: {:?}", size, err);
+                    error_message(NO_VALID_ID, &err)?
+                }
+            };
+            if !self.queue_message(text)? {
+                return Ok(());
             }
         }
+        if consumed < self.packet.len() {
+            debug!("more incoming packet(s) are needed ");
+        }
+        self.packet.drain(..consumed);
         Ok(())
     }
+
+    //----------------------------------------------------------------------------------------------
+    /// Hands a message to the decoder.  Returns false, after shutting the connection down, if the
+    /// decoder has stopped.
+    fn queue_message(&mut self, text: String) -> Result<bool, IBKRApiLibError> {
+        // counted before sending, so the decoder can't dequeue it first and take the depth below
+        // zero
+        if let Some(metrics) = &self.metrics {
+            metrics.message_queued();
+        }
+        if self.messages.send(text).is_err() {
+            info!("decoder stopped, disconnecting");
+            self.stream.shutdown(Shutdown::Both)?;
+            self.is_connected = false;
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
     //----------------------------------------------------------------------------------------------
     pub fn run(&mut self) {
         debug!("starting reader loop");
         loop {
             if self.disconnect_requested.load(Ordering::Acquire) || !self.is_connected {
-                return;
+                break;
             }
             let result = self.process_reader_msgs();
             if !result.is_err() {
@@ -117,5 +210,9 @@ impl Reader {
             }
             error!("{:?}", result);
         }
+        // every end of the connection, requested or not, stops the reader, so it is reported here
+        if let Some(metrics) = &self.metrics {
+            metrics.disconnected();
+        }
     }
 }
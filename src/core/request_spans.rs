@@ -0,0 +1,481 @@
+//! Tracing spans that follow a request from the call on EClient to its last callback
+//!
+//! When the `tracing` feature is enabled, [EClient](crate::core::client::EClient) opens a span
+//! for each request it sends, keyed by its req_id or order_id.  The decoder enters that span
+//! while it hands the matching messages to the wrapper and records an event for each callback,
+//! so callbacks logged by the wrapper, and errors reported by TWS, end up in the same trace.
+//! A span is closed by the message that ends its request: an `*End` message, the last batch of
+//! historical ticks, a terminal order status, an error that cancels the request, or a cancel call
+//! on the client.  A request that can't be sent closes its span right away.
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use num_traits::FromPrimitive;
+use tracing::{debug, info_span, warn, Span};
+
+use crate::core::contract::Contract;
+use crate::core::messages::IncomingMessageIds;
+use crate::core::server_versions::{
+    MIN_SERVER_VER_LAST_LIQUIDITY, MIN_SERVER_VER_MARKET_CAP_PRICE, MIN_SERVER_VER_ORDER_CONTAINER,
+    MIN_SERVER_VER_SYNT_REALTIME_BARS,
+};
+
+const SPANS_POISONED_MUTEX: &str = "Request spans mutex was poisoned";
+
+/// Order statuses after which TWS sends nothing more for an order except late fills
+const TERMINAL_ORDER_STATUSES: [&str; 4] = ["Filled", "Cancelled", "ApiCancelled", "Inactive"];
+
+/// Error codes after which TWS sends nothing more for the request
+const TERMINAL_ERROR_CODES: [i32; 10] = [162, 200, 201, 202, 203, 321, 322, 354, 366, 10197];
+
+/// How long an execution waits for its commission report, which TWS sends right after it, before
+/// it is forgotten
+pub const EXECUTION_SPAN_TTL: Duration = Duration::from_secs(300);
+
+//==================================================================================================
+#[derive(Default)]
+struct Spans {
+    requests: HashMap<i32, Span>,
+    /// Executions of an order and when they arrived, so that their commission reports, which
+    /// only carry the exec_id, are recorded in the span of the order even after its final status.
+    executions: HashMap<String, (Instant, Span)>,
+    /// Historical data requests made with keep_up_to_date, which go on after the initial bars
+    kept_open: HashSet<i32>,
+}
+
+//==================================================================================================
+/// Spans of the requests that are still open, shared by a client and its decoder
+pub struct RequestSpans {
+    spans: Mutex<Spans>,
+    execution_ttl: Duration,
+}
+
+impl Default for RequestSpans {
+    fn default() -> Self {
+        RequestSpans::with_execution_ttl(EXECUTION_SPAN_TTL)
+    }
+}
+
+impl RequestSpans {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Executions whose commission report has not arrived after `execution_ttl` are dropped
+    pub fn with_execution_ttl(execution_ttl: Duration) -> Self {
+        RequestSpans {
+            spans: Mutex::new(Spans::default()),
+            execution_ttl,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Opens the span of a request sent with the given req_id or order_id, replacing any span
+    /// still open for that id.  Placing an order again with the same id modifies it, so the span
+    /// of the original order is kept and a modification event is recorded instead.  Returns true
+    /// if a new span was opened.
+    pub fn open(
+        &self,
+        id: i32,
+        message: &str,
+        contract: Option<&Contract>,
+        client_id: i32,
+    ) -> bool {
+        let mut spans = self.spans.lock().expect(SPANS_POISONED_MUTEX);
+        if message == "place_order" {
+            if let Some(span) = spans.requests.get(&id) {
+                span.in_scope(|| debug!(order_id = id, "order modified"));
+                return false;
+            }
+        }
+        let (symbol, sec_type, con_id) = match contract {
+            Some(contract) => (
+                contract.symbol.as_str(),
+                contract.sec_type.as_str(),
+                contract.con_id,
+            ),
+            None => ("", "", 0),
+        };
+        let span = info_span!(
+            "request",
+            req_id = id,
+            message,
+            symbol,
+            sec_type,
+            con_id,
+            client_id
+        );
+        span.in_scope(|| debug!("request sent"));
+        spans.requests.insert(id, span);
+        spans.kept_open.remove(&id);
+        true
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Keeps the span of a historical data request open after its initial bars, until it is
+    /// cancelled.  Used for requests made with keep_up_to_date.
+    pub fn keep_open(&self, id: i32) {
+        self.spans
+            .lock()
+            .expect(SPANS_POISONED_MUTEX)
+            .kept_open
+            .insert(id);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Records an event in the span of a request, if it is open
+    pub fn record(&self, id: i32, event: &str) {
+        if let Some(span) = self.span(id) {
+            span.in_scope(|| debug!(req_id = id, "{}", event));
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Closes the span of a request.  The span ends once the last copy of it is dropped.
+    pub fn close(&self, id: i32) {
+        let mut spans = self.spans.lock().expect(SPANS_POISONED_MUTEX);
+        spans.requests.remove(&id);
+        spans.kept_open.remove(&id);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn span(&self, id: i32) -> Option<Span> {
+        self.spans
+            .lock()
+            .expect(SPANS_POISONED_MUTEX)
+            .requests
+            .get(&id)
+            .cloned()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Ids of the requests whose span is open
+    pub fn open_ids(&self) -> Vec<i32> {
+        let mut ids = self
+            .spans
+            .lock()
+            .expect(SPANS_POISONED_MUTEX)
+            .requests
+            .keys()
+            .cloned()
+            .collect::<Vec<i32>>();
+        ids.sort_unstable();
+        ids
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Looks up the span of the request a decoded message belongs to and records the callback
+    /// in it.  Returns None for messages that belong to no open request.
+    pub(crate) fn received(
+        &self,
+        msg_id: i32,
+        fields: &[&str],
+        server_version: i32,
+    ) -> Option<ReceivedMessage> {
+        let message: IncomingMessageIds = FromPrimitive::from_i32(msg_id)?;
+        let mut spans = self.spans.lock().expect(SPANS_POISONED_MUTEX);
+
+        if let IncomingMessageIds::CommissionReport = message {
+            let exec_id = fields.get(2)?;
+            let (_, span) = spans.executions.remove(*exec_id)?;
+            span.in_scope(|| debug!(exec_id = *exec_id, "commission_report"));
+            return Some(ReceivedMessage {
+                id: None,
+                span,
+                closes: false,
+            });
+        }
+
+        // executions answer req_executions with its req_id, or report fills of an order
+        let id = match message {
+            IncomingMessageIds::ExecutionData => execution_req_id(fields, server_version)
+                .filter(|req_id| spans.requests.contains_key(req_id))
+                .or_else(|| request_id(&message, fields, server_version))?,
+            _ => request_id(&message, fields, server_version)?,
+        };
+        let span = spans.requests.get(&id)?.clone();
+        let mut closes = ends_request(&message);
+        match message {
+            IncomingMessageIds::ErrMsg => {
+                let code = field(fields, 3).unwrap_or(0);
+                let text = fields.get(4).copied().unwrap_or("");
+                span.in_scope(|| warn!(req_id = id, code, "error: {}", text));
+                closes = TERMINAL_ERROR_CODES.contains(&code);
+            }
+            IncomingMessageIds::OrderStatus => {
+                let status = fields
+                    .get(status_index(server_version))
+                    .copied()
+                    .unwrap_or("");
+                span.in_scope(|| debug!(order_id = id, status, "order_status"));
+                closes = TERMINAL_ORDER_STATUSES.contains(&status);
+            }
+            IncomingMessageIds::ExecutionData => {
+                if let Some(exec_id) = fields.get(exec_id_index(fields, server_version)) {
+                    span.in_scope(|| debug!(order_id = id, exec_id = *exec_id, "exec_details"));
+                    let now = Instant::now();
+                    let ttl = self.execution_ttl;
+                    spans
+                        .executions
+                        .retain(|_, (received, _)| now.duration_since(*received) < ttl);
+                    spans
+                        .executions
+                        .insert(exec_id.to_string(), (now, span.clone()));
+                }
+            }
+            IncomingMessageIds::HistoricalData => {
+                span.in_scope(|| debug!(req_id = id, "historical_data"));
+                closes = !spans.kept_open.contains(&id);
+            }
+            IncomingMessageIds::HistoricalTicks
+            | IncomingMessageIds::HistoricalTicksBidAsk
+            | IncomingMessageIds::HistoricalTicksLast => {
+                span.in_scope(|| debug!(req_id = id, "{}", callback_name(&message)));
+                closes = historical_ticks_done(&message, fields);
+            }
+            _ => span.in_scope(|| debug!(req_id = id, "{}", callback_name(&message))),
+        }
+        Some(ReceivedMessage {
+            id: Some(id),
+            span,
+            closes,
+        })
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Called once the wrapper has handled a message returned by received
+    pub(crate) fn handled(&self, message: ReceivedMessage) {
+        if let (Some(id), true) = (message.id, message.closes) {
+            self.close(id);
+        }
+    }
+}
+
+//==================================================================================================
+/// A decoded message that belongs to an open request
+pub(crate) struct ReceivedMessage {
+    id: Option<i32>,
+    span: Span,
+    closes: bool,
+}
+
+impl ReceivedMessage {
+    pub(crate) fn span(&self) -> &Span {
+        &self.span
+    }
+}
+
+//==================================================================================================
+fn field(fields: &[&str], index: usize) -> Option<i32> {
+    fields.get(index)?.parse().ok()
+}
+
+//==================================================================================================
+/// Position of the req_id or order_id in a message, following the layout read by the decoder
+fn request_id(message: &IncomingMessageIds, fields: &[&str], server_version: i32) -> Option<i32> {
+    let index = match message {
+        // message id, version, id
+        IncomingMessageIds::TickPrice
+        | IncomingMessageIds::TickSize
+        | IncomingMessageIds::TickString
+        | IncomingMessageIds::TickGeneric
+        | IncomingMessageIds::TickEfp
+        | IncomingMessageIds::TickOptionComputation
+        | IncomingMessageIds::TickSnapshotEnd
+        | IncomingMessageIds::AccountSummary
+        | IncomingMessageIds::AccountSummaryEnd
+        | IncomingMessageIds::AccountUpdateMulti
+        | IncomingMessageIds::AccountUpdateMultiEnd
+        | IncomingMessageIds::PositionMulti
+        | IncomingMessageIds::PositionMultiEnd
+        | IncomingMessageIds::ContractDataEnd
+        | IncomingMessageIds::DeltaNeutralValidation
+        | IncomingMessageIds::ErrMsg
+        | IncomingMessageIds::ExecutionDataEnd
+        | IncomingMessageIds::FundamentalData
+        | IncomingMessageIds::MarketDataType
+        | IncomingMessageIds::MarketDepth
+        | IncomingMessageIds::MarketDepthL2
+        | IncomingMessageIds::RealTimeBars
+        | IncomingMessageIds::ScannerData => 2,
+        // message id, id
+        IncomingMessageIds::HeadTimestamp
+        | IncomingMessageIds::HistogramData
+        | IncomingMessageIds::HistoricalDataUpdate
+        | IncomingMessageIds::HistoricalNews
+        | IncomingMessageIds::HistoricalNewsEnd
+        | IncomingMessageIds::HistoricalTicks
+        | IncomingMessageIds::HistoricalTicksBidAsk
+        | IncomingMessageIds::HistoricalTicksLast
+        | IncomingMessageIds::NewsArticle
+        | IncomingMessageIds::Pnl
+        | IncomingMessageIds::PnlSingle
+        | IncomingMessageIds::RerouteMktDataReq
+        | IncomingMessageIds::RerouteMktDepthReq
+        | IncomingMessageIds::SecurityDefinitionOptionParameter
+        | IncomingMessageIds::SecurityDefinitionOptionParameterEnd
+        | IncomingMessageIds::SmartComponents
+        | IncomingMessageIds::SoftDollarTiers
+        | IncomingMessageIds::SymbolSamples
+        | IncomingMessageIds::TickByTick
+        | IncomingMessageIds::TickNews
+        | IncomingMessageIds::TickReqParams => 1,
+        IncomingMessageIds::HistoricalData
+            if server_version < MIN_SERVER_VER_SYNT_REALTIME_BARS =>
+        {
+            2
+        }
+        IncomingMessageIds::HistoricalData => 1,
+        IncomingMessageIds::OrderStatus => status_index(server_version) - 1,
+        IncomingMessageIds::OpenOrder if server_version < MIN_SERVER_VER_ORDER_CONTAINER => 2,
+        IncomingMessageIds::OpenOrder => 1,
+        // the req_id is only sent from version 3
+        IncomingMessageIds::ContractData | IncomingMessageIds::BondContractData => {
+            if field(fields, 1)? < 3 {
+                return None;
+            }
+            2
+        }
+        IncomingMessageIds::ExecutionData => execution_order_id_index(fields, server_version).0,
+        _ => return None,
+    };
+    field(fields, index)
+}
+
+//==================================================================================================
+fn status_index(server_version: i32) -> usize {
+    if server_version < MIN_SERVER_VER_MARKET_CAP_PRICE {
+        3
+    } else {
+        2
+    }
+}
+
+//==================================================================================================
+/// Position of the order_id in an execution message, and the version its fields follow
+fn execution_order_id_index(fields: &[&str], server_version: i32) -> (usize, i32) {
+    if server_version < MIN_SERVER_VER_LAST_LIQUIDITY {
+        let version = field(fields, 1).unwrap_or(0);
+        (if version >= 7 { 3 } else { 2 }, version)
+    } else {
+        (2, server_version)
+    }
+}
+
+//==================================================================================================
+/// The req_id of an execution message, sent from version 7 just before the order_id
+fn execution_req_id(fields: &[&str], server_version: i32) -> Option<i32> {
+    match execution_order_id_index(fields, server_version) {
+        (2, version) if version < 7 => None,
+        (order_id_index, _) => field(fields, order_id_index - 1),
+    }
+}
+
+//==================================================================================================
+/// Position of the exec_id in an execution message, which follows the order_id and the contract
+fn exec_id_index(fields: &[&str], server_version: i32) -> usize {
+    let (order_id_index, version) = execution_order_id_index(fields, server_version);
+    let mut index = order_id_index + 10;
+    if version >= 9 {
+        index += 1;
+    }
+    if version >= 10 {
+        index += 1;
+    }
+    index
+}
+
+//==================================================================================================
+/// The done flag of a historical ticks message, which follows its ticks
+fn historical_ticks_done(message: &IncomingMessageIds, fields: &[&str]) -> bool {
+    // time, unused, price, size for trades; time, mask and four more for bid/ask and last
+    let tick_fields = match message {
+        IncomingMessageIds::HistoricalTicks => 4,
+        _ => 6,
+    };
+    let done_index = field(fields, 2).map(|count| 3 + count.max(0) as usize * tick_fields);
+    done_index.and_then(|index| field(fields, index)) == Some(1)
+}
+
+//==================================================================================================
+fn ends_request(message: &IncomingMessageIds) -> bool {
+    matches!(
+        message,
+        IncomingMessageIds::TickSnapshotEnd
+            | IncomingMessageIds::AccountSummaryEnd
+            | IncomingMessageIds::AccountUpdateMultiEnd
+            | IncomingMessageIds::PositionMultiEnd
+            | IncomingMessageIds::ContractDataEnd
+            | IncomingMessageIds::ExecutionDataEnd
+            | IncomingMessageIds::FundamentalData
+            | IncomingMessageIds::HeadTimestamp
+            | IncomingMessageIds::HistogramData
+            | IncomingMessageIds::HistoricalNewsEnd
+            | IncomingMessageIds::NewsArticle
+            | IncomingMessageIds::SecurityDefinitionOptionParameterEnd
+            | IncomingMessageIds::SmartComponents
+            | IncomingMessageIds::SoftDollarTiers
+            | IncomingMessageIds::SymbolSamples
+    )
+}
+
+//==================================================================================================
+/// Name of the Wrapper callback a message is handed to
+fn callback_name(message: &IncomingMessageIds) -> &'static str {
+    match message {
+        IncomingMessageIds::TickPrice => "tick_price",
+        IncomingMessageIds::TickSize => "tick_size",
+        IncomingMessageIds::TickString => "tick_string",
+        IncomingMessageIds::TickGeneric => "tick_generic",
+        IncomingMessageIds::TickEfp => "tick_efp",
+        IncomingMessageIds::TickOptionComputation => "tick_option_computation",
+        IncomingMessageIds::TickSnapshotEnd => "tick_snapshot_end",
+        IncomingMessageIds::TickByTick => "tick_by_tick",
+        IncomingMessageIds::TickNews => "tick_news",
+        IncomingMessageIds::TickReqParams => "tick_req_params",
+        IncomingMessageIds::AccountSummary => "account_summary",
+        IncomingMessageIds::AccountSummaryEnd => "account_summary_end",
+        IncomingMessageIds::AccountUpdateMulti => "account_update_multi",
+        IncomingMessageIds::AccountUpdateMultiEnd => "account_update_multi_end",
+        IncomingMessageIds::PositionMulti => "position_multi",
+        IncomingMessageIds::PositionMultiEnd => "position_multi_end",
+        IncomingMessageIds::ContractData => "contract_details",
+        IncomingMessageIds::BondContractData => "bond_contract_details",
+        IncomingMessageIds::ContractDataEnd => "contract_details_end",
+        IncomingMessageIds::DeltaNeutralValidation => "delta_neutral_validation",
+        IncomingMessageIds::ExecutionDataEnd => "exec_details_end",
+        IncomingMessageIds::FundamentalData => "fundamental_data",
+        IncomingMessageIds::MarketDataType => "market_data_type",
+        IncomingMessageIds::MarketDepth => "update_mkt_depth",
+        IncomingMessageIds::MarketDepthL2 => "update_mkt_depth_l2",
+        IncomingMessageIds::RealTimeBars => "realtime_bar",
+        IncomingMessageIds::ScannerData => "scanner_data",
+        IncomingMessageIds::HeadTimestamp => "head_timestamp",
+        IncomingMessageIds::HistogramData => "histogram_data",
+        IncomingMessageIds::HistoricalData => "historical_data",
+        IncomingMessageIds::HistoricalDataUpdate => "historical_data_update",
+        IncomingMessageIds::HistoricalNews => "historical_news",
+        IncomingMessageIds::HistoricalNewsEnd => "historical_news_end",
+        IncomingMessageIds::HistoricalTicks => "historical_ticks",
+        IncomingMessageIds::HistoricalTicksBidAsk => "historical_ticks_bid_ask",
+        IncomingMessageIds::HistoricalTicksLast => "historical_ticks_last",
+        IncomingMessageIds::NewsArticle => "news_article",
+        IncomingMessageIds::Pnl => "pnl",
+        IncomingMessageIds::PnlSingle => "pnl_single",
+        IncomingMessageIds::RerouteMktDataReq => "reroute_mkt_data_req",
+        IncomingMessageIds::RerouteMktDepthReq => "reroute_mkt_depth_req",
+        IncomingMessageIds::SecurityDefinitionOptionParameter => {
+            "security_definition_option_parameter"
+        }
+        IncomingMessageIds::SecurityDefinitionOptionParameterEnd => {
+            "security_definition_option_parameter_end"
+        }
+        IncomingMessageIds::SmartComponents => "smart_components",
+        IncomingMessageIds::SoftDollarTiers => "soft_dollar_tiers",
+        IncomingMessageIds::SymbolSamples => "symbol_samples",
+        IncomingMessageIds::OpenOrder => "open_order",
+        _ => "callback",
+    }
+}
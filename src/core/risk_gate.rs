@@ -0,0 +1,504 @@
+//! Pre-trade risk checks applied to orders before they are sent to TWS
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use log::*;
+use serde::{Deserialize, Serialize};
+
+use crate::core::common::{
+    TickAttrib, TickType, TERMINAL_ORDER_STATUSES, TRIGGER_ORDER_TYPES, UNSET_DOUBLE,
+};
+use crate::core::contract::Contract;
+pub use crate::core::errors::RiskViolation;
+use crate::core::errors::{IBKRApiLibError, TwsApiReportableError, TwsError};
+use crate::core::order::Order;
+
+/// Log target of the accept and reject decisions, so they can be routed to a separate appender
+pub const RISK_LOG_TARGET: &str = "twsapi::risk";
+
+const LIMITS_POISONED_LOCK: &str = "Risk limits lock was poisoned";
+const STATE_POISONED_MUTEX: &str = "Risk state mutex was poisoned";
+
+//==================================================================================================
+/// Limits enforced by [RiskGate].  A limit that is None is not checked.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct RiskLimits {
+    /// Largest quantity * price * multiplier of a single order
+    pub max_order_notional: Option<f64>,
+    /// Largest absolute position per account and contract once the order and the other working
+    /// orders fill.  Orders without an account are checked against the contract's position
+    /// summed over all accounts.
+    pub max_position: Option<f64>,
+    /// Largest relative deviation of the limit or trigger price from the last price, e.g. 0.05.
+    /// Like the notional limit, this fails closed: an order with a price is rejected while the
+    /// last price is unknown.
+    pub max_price_deviation: Option<f64>,
+    /// Largest daily loss, as a positive number, before new orders are rejected
+    pub max_daily_loss: Option<f64>,
+    /// Symbols that may be traded; all symbols if None
+    pub allowed_symbols: Option<HashSet<String>>,
+    /// Orders identical to one accepted within this many seconds are rejected
+    pub duplicate_window_secs: Option<u64>,
+}
+
+impl RiskLimits {
+    /// Reads limits from a JSON file
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, IBKRApiLibError> {
+        let reader = BufReader::new(File::open(path)?);
+        serde_json::from_reader(reader).map_err(|err| {
+            IBKRApiLibError::ApiError(TwsApiReportableError::new(
+                -1,
+                TwsError::BadMessage.code().to_string(),
+                format!("Invalid risk limits: {}", err),
+            ))
+        })
+    }
+}
+
+//==================================================================================================
+/// An order that was sent and can still fill
+#[derive(Debug)]
+struct WorkingOrder {
+    account: String,
+    con_id: i32,
+    /// Remaining quantity, negative for sells
+    quantity: f64,
+}
+
+//==================================================================================================
+#[derive(Debug, Default)]
+struct RiskState {
+    /// market data req_id -> con_id
+    market_data: HashMap<i32, i32>,
+    last_prices: HashMap<i32, f64>,
+    /// (account, con_id) -> position
+    positions: HashMap<(String, i32), f64>,
+    /// pnl req_id -> daily pnl
+    daily_pnl: HashMap<i32, f64>,
+    /// accepted orders: time, fingerprint, order id
+    recent_orders: VecDeque<(Instant, String, i32)>,
+    /// order id -> order that was sent and is not done yet
+    working_orders: HashMap<i32, WorkingOrder>,
+}
+
+impl RiskState {
+    /// Position in a contract once the working orders other than `order_id` fill.  An empty
+    /// account matches every account.
+    fn expected_position(&self, account: &str, con_id: i32, order_id: i32) -> f64 {
+        let matches = |other: &str| account.is_empty() || other.is_empty() || other == account;
+        let held: f64 = self
+            .positions
+            .iter()
+            .filter(|((held_account, held_con_id), _)| {
+                *held_con_id == con_id && (account.is_empty() || held_account == account)
+            })
+            .map(|(_, position)| position)
+            .sum();
+        let working: f64 = self
+            .working_orders
+            .iter()
+            .filter(|(working_id, working)| {
+                **working_id != order_id && working.con_id == con_id && matches(&working.account)
+            })
+            .map(|(_, working)| working.quantity)
+            .sum();
+        held + working
+    }
+}
+
+//==================================================================================================
+/// Pre-trade risk checks.
+///
+/// Install the gate with EClient::set_risk_gate so that every EClient::place_order is checked
+/// before anything is sent; rejected orders fail with IBKRApiLibError::RiskRejected.  The gate
+/// learns last prices, positions, working orders and daily pnl from the Wrapper callbacks
+/// forwarded to it ([RiskGate::tick_price], [RiskGate::position], [RiskGate::order_status],
+/// [RiskGate::pnl]) or set directly.  Limits can
+/// be replaced at any time with [RiskGate::set_limits] or [RiskGate::load_limits], and every
+/// decision is logged under [RISK_LOG_TARGET].
+#[derive(Debug, Default)]
+pub struct RiskGate {
+    limits: RwLock<RiskLimits>,
+    state: Mutex<RiskState>,
+}
+
+impl RiskGate {
+    pub fn new(limits: RiskLimits) -> Self {
+        RiskGate {
+            limits: RwLock::new(limits),
+            state: Mutex::new(RiskState::default()),
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn limits(&self) -> RiskLimits {
+        self.limits.read().expect(LIMITS_POISONED_LOCK).clone()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Replaces the limits; the next order is checked against the new ones
+    pub fn set_limits(&self, limits: RiskLimits) {
+        info!(target: RISK_LOG_TARGET, "risk limits changed to {:?}", limits);
+        *self.limits.write().expect(LIMITS_POISONED_LOCK) = limits;
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Replaces the limits with the ones in a JSON file.  The current limits are kept if the
+    /// file can't be read.
+    pub fn load_limits<P: AsRef<Path>>(&self, path: P) -> Result<(), IBKRApiLibError> {
+        self.set_limits(RiskLimits::from_file(path)?);
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Associates a market data request with a contract so that [RiskGate::tick_price] can
+    /// record its last price
+    pub fn track_market_data(&self, req_id: i32, con_id: i32) {
+        self.state
+            .lock()
+            .expect(STATE_POISONED_MUTEX)
+            .market_data
+            .insert(req_id, con_id);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn set_last_price(&self, con_id: i32, price: f64) {
+        self.state
+            .lock()
+            .expect(STATE_POISONED_MUTEX)
+            .last_prices
+            .insert(con_id, price);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn last_price(&self, con_id: i32) -> Option<f64> {
+        self.state
+            .lock()
+            .expect(STATE_POISONED_MUTEX)
+            .last_prices
+            .get(&con_id)
+            .copied()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Forwarded from Wrapper::tick_price.  Returns true if the request is tracked.
+    pub fn tick_price(
+        &self,
+        req_id: i32,
+        tick_type: TickType,
+        price: f64,
+        _attrib: &TickAttrib,
+    ) -> bool {
+        let mut state = self.state.lock().expect(STATE_POISONED_MUTEX);
+        let con_id = match state.market_data.get(&req_id) {
+            Some(con_id) => *con_id,
+            None => return false,
+        };
+        if let TickType::Last | TickType::DelayedLast = tick_type {
+            if price > 0.0 {
+                state.last_prices.insert(con_id, price);
+            }
+        }
+        true
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Forwarded from Wrapper::position
+    pub fn position(&self, account: &str, contract: &Contract, position: f64, _avg_cost: f64) {
+        self.state
+            .lock()
+            .expect(STATE_POISONED_MUTEX)
+            .positions
+            .insert((account.to_string(), contract.con_id), position);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Forwarded from Wrapper::order_status.  Orders count against the position limit until they
+    /// are done.  Returns true if the order was sent through the gate.
+    #[allow(clippy::too_many_arguments)]
+    pub fn order_status(
+        &self,
+        order_id: i32,
+        status: &str,
+        _filled: f64,
+        remaining: f64,
+        _avg_fill_price: f64,
+        _perm_id: i32,
+        _parent_id: i32,
+        _last_fill_price: f64,
+        _client_id: i32,
+        _why_held: &str,
+        _mkt_cap_price: f64,
+    ) -> bool {
+        let mut state = self.state.lock().expect(STATE_POISONED_MUTEX);
+        if TERMINAL_ORDER_STATUSES.contains(&status) {
+            return state.working_orders.remove(&order_id).is_some();
+        }
+        match state.working_orders.get_mut(&order_id) {
+            Some(working) => {
+                working.quantity = remaining.abs().copysign(working.quantity);
+                true
+            }
+            None => false,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Forwarded from Wrapper::pnl.  The daily pnl of all pnl subscriptions is summed.
+    pub fn pnl(&self, req_id: i32, daily_pnl: f64, _unrealized_pnl: f64, _realized_pnl: f64) {
+        if daily_pnl != UNSET_DOUBLE {
+            self.state
+                .lock()
+                .expect(STATE_POISONED_MUTEX)
+                .daily_pnl
+                .insert(req_id, daily_pnl);
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn daily_pnl(&self) -> f64 {
+        self.state
+            .lock()
+            .expect(STATE_POISONED_MUTEX)
+            .daily_pnl
+            .values()
+            .sum()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Checks an order against the limits and logs the decision.  Orders are only remembered
+    /// for the duplicate check once [RiskGate::record_order] is called after sending them, so an
+    /// order that failed to send can be retried.
+    pub fn check(
+        &self,
+        order_id: i32,
+        contract: &Contract,
+        order: &Order,
+    ) -> Result<(), IBKRApiLibError> {
+        let limits = self.limits.read().expect(LIMITS_POISONED_LOCK).clone();
+        let mut state = self.state.lock().expect(STATE_POISONED_MUTEX);
+        let fingerprint = order_fingerprint(contract, order);
+        let now = Instant::now();
+
+        match check_limits(
+            &limits,
+            &mut state,
+            order_id,
+            contract,
+            order,
+            &fingerprint,
+            now,
+        ) {
+            Ok(()) => {
+                info!(
+                    target: RISK_LOG_TARGET,
+                    "accepted order {}: {}", order_id, fingerprint
+                );
+                Ok(())
+            }
+            Err(violation) => {
+                warn!(
+                    target: RISK_LOG_TARGET,
+                    "rejected order {}: {}: {}", order_id, fingerprint, violation
+                );
+                Err(IBKRApiLibError::RiskRejected(violation))
+            }
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Remembers an accepted order for the duplicate check and as a working order for the
+    /// position limit.  EClient::place_order calls this after the order was sent.
+    pub fn record_order(&self, order_id: i32, contract: &Contract, order: &Order) {
+        let check_duplicates = self
+            .limits
+            .read()
+            .expect(LIMITS_POISONED_LOCK)
+            .duplicate_window_secs
+            .is_some();
+        let mut state = self.state.lock().expect(STATE_POISONED_MUTEX);
+        if check_duplicates {
+            state.recent_orders.push_back((
+                Instant::now(),
+                order_fingerprint(contract, order),
+                order_id,
+            ));
+        }
+        if contract.con_id != 0 {
+            state.working_orders.insert(
+                order_id,
+                WorkingOrder {
+                    account: order.account.clone(),
+                    con_id: contract.con_id,
+                    quantity: signed_quantity(order),
+                },
+            );
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Orders sent through the gate that have not been reported done
+    pub fn working_orders(&self) -> usize {
+        self.state
+            .lock()
+            .expect(STATE_POISONED_MUTEX)
+            .working_orders
+            .len()
+    }
+}
+
+//==================================================================================================
+fn order_fingerprint(contract: &Contract, order: &Order) -> String {
+    format!(
+        "{} {} {} {}@{} {} lmt={} aux={} account='{}'",
+        order.action,
+        order.total_quantity,
+        contract.symbol,
+        contract.con_id,
+        contract.exchange,
+        order.order_type,
+        order.lmt_price,
+        order.aux_price,
+        order.account
+    )
+}
+
+//==================================================================================================
+/// Prices of the order that have to be near the last price
+fn order_prices(order: &Order) -> Vec<f64> {
+    let mut prices = vec![];
+    if order.lmt_price != UNSET_DOUBLE && order.lmt_price != 0.0 {
+        prices.push(order.lmt_price);
+    }
+    if order.aux_price != UNSET_DOUBLE
+        && order.aux_price != 0.0
+        && TRIGGER_ORDER_TYPES.contains(&order.order_type.to_uppercase().as_str())
+    {
+        prices.push(order.aux_price);
+    }
+    prices
+}
+
+//==================================================================================================
+fn signed_quantity(order: &Order) -> f64 {
+    if order.action.eq_ignore_ascii_case("BUY") {
+        order.total_quantity
+    } else {
+        -order.total_quantity
+    }
+}
+
+//==================================================================================================
+fn check_limits(
+    limits: &RiskLimits,
+    state: &mut RiskState,
+    order_id: i32,
+    contract: &Contract,
+    order: &Order,
+    fingerprint: &str,
+    now: Instant,
+) -> Result<(), RiskViolation> {
+    if let Some(allowed) = &limits.allowed_symbols {
+        if !allowed.contains(&contract.symbol) {
+            return Err(RiskViolation::SymbolNotAllowed {
+                symbol: contract.symbol.clone(),
+            });
+        }
+    }
+
+    if let Some(limit) = limits.max_daily_loss {
+        let daily_pnl: f64 = state.daily_pnl.values().sum();
+        if daily_pnl <= -limit.abs() {
+            return Err(RiskViolation::DailyLoss {
+                daily_pnl,
+                limit: limit.abs(),
+            });
+        }
+    }
+
+    // positions and last prices are kept per con_id, a contract without one matches none of them
+    if contract.con_id == 0
+        && (limits.max_position.is_some() || limits.max_price_deviation.is_some())
+    {
+        return Err(RiskViolation::MissingConId {
+            symbol: contract.symbol.clone(),
+        });
+    }
+    let last_price = if contract.con_id == 0 {
+        None
+    } else {
+        state.last_prices.get(&contract.con_id).copied()
+    };
+    let prices = order_prices(order);
+
+    if let Some(limit) = limits.max_price_deviation {
+        if !prices.is_empty() {
+            let last_price = last_price.ok_or(RiskViolation::NoPrice {
+                con_id: contract.con_id,
+            })?;
+            for price in prices.iter() {
+                if ((price - last_price) / last_price).abs() > limit {
+                    return Err(RiskViolation::PriceDeviation {
+                        price: *price,
+                        last_price,
+                        limit,
+                    });
+                }
+            }
+        }
+    }
+
+    if let Some(limit) = limits.max_order_notional {
+        let price = prices
+            .first()
+            .copied()
+            .or(last_price)
+            .ok_or(RiskViolation::NoPrice {
+                con_id: contract.con_id,
+            })?;
+        let multiplier = contract.multiplier.parse::<f64>().unwrap_or(1.0);
+        let notional = (order.total_quantity * price * multiplier).abs();
+        if notional > limit {
+            return Err(RiskViolation::OrderNotional { notional, limit });
+        }
+    }
+
+    if let Some(limit) = limits.max_position {
+        let position = state.expected_position(order.account.as_str(), contract.con_id, order_id)
+            + signed_quantity(order);
+        if position.abs() > limit {
+            return Err(RiskViolation::Position {
+                account: order.account.clone(),
+                con_id: contract.con_id,
+                position,
+                limit,
+            });
+        }
+    }
+
+    if let Some(window) = limits.duplicate_window_secs {
+        let window = Duration::from_secs(window);
+        while let Some((time, _, _)) = state.recent_orders.front() {
+            if now.duration_since(*time) <= window {
+                break;
+            }
+            state.recent_orders.pop_front();
+        }
+        // re-sending the same order id is a modification, not a duplicate
+        if let Some((_, _, previous_order_id)) = state
+            .recent_orders
+            .iter()
+            .find(|(_, previous, previous_id)| previous == fingerprint && *previous_id != order_id)
+        {
+            return Err(RiskViolation::DuplicateOrder {
+                previous_order_id: *previous_order_id,
+            });
+        }
+    }
+    Ok(())
+}
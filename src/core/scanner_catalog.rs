@@ -0,0 +1,724 @@
+//! Scanner parameters catalog, subscription validation and ranked scanner results
+//!
+//! Enabled with the `scanner-catalog` feature.
+use std::collections::HashMap;
+use std::fmt;
+
+use log::*;
+use roxmltree::{Document, Node};
+use serde::{Deserialize, Serialize};
+
+use crate::core::client::EClient;
+use crate::core::common::{TagValue, NO_VALID_ID};
+use crate::core::contract::ContractDetails;
+use crate::core::errors::{IBKRApiLibError, TwsApiReportableError, TwsError};
+use crate::core::scanner::{ScanData, ScannerSubscription};
+use crate::core::wrapper::Wrapper;
+use crate::core::xml::{child, child_text, children, node_path};
+
+/// Maximum number of rows TWS returns for a scanner subscription
+pub const MAX_SCANNER_ROWS: i32 = 50;
+
+//==================================================================================================
+fn scanner_error(req_id: i32, description: String) -> IBKRApiLibError {
+    IBKRApiLibError::ApiError(TwsApiReportableError::new(
+        req_id,
+        TwsError::BadMessage.code().to_string(),
+        description,
+    ))
+}
+
+//==================================================================================================
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|item| item.trim())
+        .filter(|item| !item.is_empty())
+        .map(|item| item.to_string())
+        .collect()
+}
+
+//==================================================================================================
+/// An instrument type that can be scanned, e.g. STK or FUT.EU
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ScannerInstrument {
+    pub name: String,
+    pub instrument_type: String,
+    pub sec_type: String,
+    pub group: String,
+    pub short_name: String,
+    /// Ids of the filters that may be applied when scanning this instrument
+    pub filters: Vec<String>,
+}
+
+impl fmt::Display for ScannerInstrument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "name: {}, type: {}, sec_type: {}, filters: {}",
+            self.name,
+            self.instrument_type,
+            self.sec_type,
+            self.filters.len()
+        )
+    }
+}
+
+//==================================================================================================
+/// A node in the location tree, e.g. STK.US containing STK.US.MAJOR
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ScannerLocation {
+    pub display_name: String,
+    pub location_code: String,
+    /// Instrument types that can be scanned at this location.  Empty if the location
+    /// inherits them from its parent.
+    pub instruments: Vec<String>,
+    pub route_exchange: String,
+    pub children: Vec<ScannerLocation>,
+}
+
+impl fmt::Display for ScannerLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "display_name: {}, location_code: {}, instruments: {}, children: {}",
+            self.display_name,
+            self.location_code,
+            self.instruments.join(","),
+            self.children.len()
+        )
+    }
+}
+
+//==================================================================================================
+/// A scan code, e.g. TOP_PERC_GAIN, and the instruments it supports
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ScanType {
+    pub display_name: String,
+    pub scan_code: String,
+    pub instruments: Vec<String>,
+}
+
+impl fmt::Display for ScanType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "display_name: {}, scan_code: {}, instruments: {}",
+            self.display_name,
+            self.scan_code,
+            self.instruments.join(",")
+        )
+    }
+}
+
+//==================================================================================================
+/// Value type of a filter field, taken from the type attribute of AbstractField
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScannerFieldType {
+    Double,
+    Int,
+    Combo,
+    Date,
+    String,
+}
+
+impl ScannerFieldType {
+    fn from_xml_type(field_type: &str) -> Self {
+        match field_type {
+            "DoubleField" => ScannerFieldType::Double,
+            "IntField" => ScannerFieldType::Int,
+            "ComboField" => ScannerFieldType::Combo,
+            "DateField" => ScannerFieldType::Date,
+            _ => ScannerFieldType::String,
+        }
+    }
+}
+
+//==================================================================================================
+/// A field of a scanner filter.  The code is the tag used in the filter options passed to
+/// req_scanner_subscription, e.g. priceAbove.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ScannerFilterField {
+    pub code: String,
+    pub display_name: String,
+    pub field_type: ScannerFieldType,
+    pub accept_negative: bool,
+    /// Allowed values for combo fields
+    pub values: Vec<String>,
+}
+
+impl ScannerFilterField {
+    /// Checks that a value is acceptable for this field
+    pub fn check_value(&self, value: &str) -> Result<(), String> {
+        let numeric = match self.field_type {
+            ScannerFieldType::Double => value.trim().parse::<f64>().ok(),
+            ScannerFieldType::Int => value.trim().parse::<i64>().ok().map(|val| val as f64),
+            ScannerFieldType::Combo => {
+                if self.values.is_empty() || self.values.iter().any(|val| val == value) {
+                    return Ok(());
+                }
+                return Err(format!(
+                    "'{}' is not one of {} for filter {}",
+                    value,
+                    self.values.join(","),
+                    self.code
+                ));
+            }
+            ScannerFieldType::Date | ScannerFieldType::String => return Ok(()),
+        };
+        match numeric {
+            None => Err(format!(
+                "'{}' is not a valid {:?} value for filter {}",
+                value, self.field_type, self.code
+            )),
+            Some(val) if val < 0.0 && !self.accept_negative => Err(format!(
+                "filter {} does not accept negative values",
+                self.code
+            )),
+            Some(_) => Ok(()),
+        }
+    }
+}
+
+//==================================================================================================
+/// A filter from the filter list.  Range filters have an above and a below field, simple
+/// filters a single field.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ScannerFilter {
+    pub id: String,
+    pub category: String,
+    pub range: bool,
+    pub fields: Vec<ScannerFilterField>,
+}
+
+impl fmt::Display for ScannerFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "id: {}, category: {}, fields: {}",
+            self.id,
+            self.category,
+            self.fields
+                .iter()
+                .map(|field| field.code.as_str())
+                .collect::<Vec<&str>>()
+                .join(",")
+        )
+    }
+}
+
+//==================================================================================================
+/// The scanner parameters document returned to scanner_parameters, parsed into instruments,
+/// locations, scan codes and filters
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ScannerCatalog {
+    pub instruments: Vec<ScannerInstrument>,
+    pub locations: Vec<ScannerLocation>,
+    pub scan_types: Vec<ScanType>,
+    pub filters: Vec<ScannerFilter>,
+}
+
+impl ScannerCatalog {
+    pub fn from_xml(xml: &str) -> Result<Self, IBKRApiLibError> {
+        let document = Document::parse(xml).map_err(|err| {
+            scanner_error(
+                NO_VALID_ID,
+                format!("Invalid scanner parameters XML: {}", err),
+            )
+        })?;
+        let root = document.root_element();
+        if root.tag_name().name() != "ScanParameterResponse" {
+            return Err(scanner_error(
+                NO_VALID_ID,
+                format!(
+                    "Invalid scanner parameters XML: expected root element <ScanParameterResponse> but found <{}>",
+                    root.tag_name().name()
+                ),
+            ));
+        }
+
+        let mut catalog = ScannerCatalog::default();
+        if let Some(instruments) = child(root, "InstrumentList") {
+            for instrument in children(instruments) {
+                catalog.instruments.push(ScannerInstrument {
+                    name: child_text(instrument, "name"),
+                    instrument_type: child_text(instrument, "type"),
+                    sec_type: child_text(instrument, "secType"),
+                    group: child_text(instrument, "group"),
+                    short_name: child_text(instrument, "shortName"),
+                    filters: split_list(child_text(instrument, "filters").as_str()),
+                });
+            }
+        }
+        if let Some(locations) = child(root, "LocationTree") {
+            catalog.locations = parse_locations(locations);
+        }
+        if let Some(scan_types) = child(root, "ScanTypeList") {
+            for scan_type in children(scan_types) {
+                catalog.scan_types.push(ScanType {
+                    display_name: child_text(scan_type, "displayName"),
+                    scan_code: child_text(scan_type, "scanCode"),
+                    instruments: split_list(child_text(scan_type, "instruments").as_str()),
+                });
+            }
+        }
+        if let Some(filters) = child(root, "FilterList") {
+            for filter in children(filters) {
+                catalog.filters.push(parse_filter(filter)?);
+            }
+        }
+        Ok(catalog)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn instrument(&self, instrument_type: &str) -> Option<&ScannerInstrument> {
+        self.instruments
+            .iter()
+            .find(|instrument| instrument.instrument_type == instrument_type)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Finds a location anywhere in the tree
+    pub fn location(&self, location_code: &str) -> Option<&ScannerLocation> {
+        self.location_with_instruments(location_code)
+            .map(|(location, _)| location)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn scan_type(&self, scan_code: &str) -> Option<&ScanType> {
+        self.scan_types
+            .iter()
+            .find(|scan_type| scan_type.scan_code == scan_code)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Finds the filter and field for a filter option tag such as priceAbove
+    pub fn filter_field(&self, code: &str) -> Option<(&ScannerFilter, &ScannerFilterField)> {
+        self.filters.iter().find_map(|filter| {
+            filter
+                .fields
+                .iter()
+                .find(|field| field.code == code)
+                .map(|field| (filter, field))
+        })
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Scan codes that support the given instrument type
+    pub fn scan_types_for(&self, instrument_type: &str) -> Vec<&ScanType> {
+        self.scan_types
+            .iter()
+            .filter(|scan_type| scan_type.instruments.iter().any(|i| i == instrument_type))
+            .collect()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Checks a subscription and its filter options against the catalog: the instrument,
+    /// location and scan code must exist and fit together, and each filter option must name a
+    /// filter that applies to the instrument and carry a valid value.
+    pub fn validate(
+        &self,
+        req_id: i32,
+        subscription: &ScannerSubscription,
+        filter_options: &[TagValue],
+    ) -> Result<(), IBKRApiLibError> {
+        let invalid = |description: String| {
+            scanner_error(
+                req_id,
+                format!(
+                    "Invalid scanner subscription ({}): {}",
+                    subscription, description
+                ),
+            )
+        };
+
+        if subscription.number_of_rows > MAX_SCANNER_ROWS {
+            return Err(invalid(format!(
+                "number_of_rows {} exceeds the maximum of {}",
+                subscription.number_of_rows, MAX_SCANNER_ROWS
+            )));
+        }
+
+        let instrument = self
+            .instrument(subscription.instrument.as_str())
+            .ok_or_else(|| invalid(format!("unknown instrument '{}'", subscription.instrument)))?;
+
+        for location_code in split_list(subscription.location_code.as_str()) {
+            let (_, instruments) = self
+                .location_with_instruments(location_code.as_str())
+                .ok_or_else(|| invalid(format!("unknown location code '{}'", location_code)))?;
+            if !instruments.is_empty() && !instruments.contains(&instrument.instrument_type) {
+                return Err(invalid(format!(
+                    "location code '{}' does not support instrument '{}'",
+                    location_code, instrument.instrument_type
+                )));
+            }
+        }
+
+        let scan_type = self
+            .scan_type(subscription.scan_code.as_str())
+            .ok_or_else(|| invalid(format!("unknown scan code '{}'", subscription.scan_code)))?;
+        if !scan_type.instruments.contains(&instrument.instrument_type) {
+            return Err(invalid(format!(
+                "scan code '{}' does not support instrument '{}'",
+                scan_type.scan_code, instrument.instrument_type
+            )));
+        }
+
+        for option in filter_options {
+            let (filter, field) = self
+                .filter_field(option.tag.as_str())
+                .ok_or_else(|| invalid(format!("unknown filter '{}'", option.tag)))?;
+            if !instrument.filters.contains(&filter.id) {
+                return Err(invalid(format!(
+                    "filter '{}' does not apply to instrument '{}'",
+                    option.tag, instrument.instrument_type
+                )));
+            }
+            field.check_value(option.value.as_str()).map_err(invalid)?;
+        }
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Finds a location and the instruments it supports, inherited from its ancestors when the
+    /// location does not list its own
+    fn location_with_instruments(
+        &self,
+        location_code: &str,
+    ) -> Option<(&ScannerLocation, &[String])> {
+        fn find<'a>(
+            locations: &'a [ScannerLocation],
+            location_code: &str,
+            inherited: &'a [String],
+        ) -> Option<(&'a ScannerLocation, &'a [String])> {
+            for location in locations {
+                let instruments = if location.instruments.is_empty() {
+                    inherited
+                } else {
+                    location.instruments.as_slice()
+                };
+                if location.location_code == location_code {
+                    return Some((location, instruments));
+                }
+                if let Some(found) = find(location.children.as_slice(), location_code, instruments)
+                {
+                    return Some(found);
+                }
+            }
+            None
+        }
+        find(self.locations.as_slice(), location_code, &[])
+    }
+}
+
+impl fmt::Display for ScannerCatalog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "instruments: {}, locations: {}, scan_types: {}, filters: {}",
+            self.instruments.len(),
+            self.locations.len(),
+            self.scan_types.len(),
+            self.filters.len()
+        )
+    }
+}
+
+//==================================================================================================
+fn parse_locations(tree: Node) -> Vec<ScannerLocation> {
+    children(tree)
+        .filter(|location| location.tag_name().name() == "Location")
+        .map(|location| ScannerLocation {
+            display_name: child_text(location, "displayName"),
+            location_code: child_text(location, "locationCode"),
+            instruments: split_list(child_text(location, "instruments").as_str()),
+            route_exchange: child_text(location, "routeExchange"),
+            children: child(location, "LocationTree")
+                .map(parse_locations)
+                .unwrap_or_default(),
+        })
+        .collect()
+}
+
+//==================================================================================================
+fn parse_filter(filter: Node) -> Result<ScannerFilter, IBKRApiLibError> {
+    let range = match filter.tag_name().name() {
+        "RangeFilter" => true,
+        "SimpleFilter" => false,
+        other => {
+            return Err(scanner_error(
+                NO_VALID_ID,
+                format!(
+                    "Invalid scanner parameters XML: unexpected element <{}> at {}",
+                    other,
+                    node_path(filter)
+                ),
+            ))
+        }
+    };
+    let mut fields = vec![];
+    for field in children(filter).filter(|field| field.tag_name().name() == "AbstractField") {
+        fields.push(ScannerFilterField {
+            code: child_text(field, "code"),
+            display_name: child_text(field, "displayName"),
+            field_type: ScannerFieldType::from_xml_type(field.attribute("type").unwrap_or("")),
+            accept_negative: child_text(field, "acceptNegative") == "true",
+            values: child(field, "ComboValues")
+                .map(|values| {
+                    children(values)
+                        .map(|value| child_text(value, "code"))
+                        .collect()
+                })
+                .unwrap_or_default(),
+        });
+    }
+    Ok(ScannerFilter {
+        id: child_text(filter, "id"),
+        category: child_text(filter, "category"),
+        range,
+        fields,
+    })
+}
+
+//==================================================================================================
+/// The latest complete set of rows for a scanner subscription, ordered by rank.  Rows that
+/// entered or left the set since the previous scanner_data_end are tracked by con_id.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ScannerResultSet {
+    pub req_id: i32,
+    pub rows: Vec<ScanData>,
+    pub update_count: i32,
+    pub added: Vec<i32>,
+    pub removed: Vec<i32>,
+    previous_ranks: HashMap<i32, i32>,
+}
+
+impl ScannerResultSet {
+    pub fn new(req_id: i32) -> Self {
+        ScannerResultSet {
+            req_id,
+            ..Default::default()
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Rank of a contract in the current result set
+    pub fn rank(&self, con_id: i32) -> Option<i32> {
+        self.rows
+            .iter()
+            .find(|row| row.contract.contract.con_id == con_id)
+            .map(|row| row.rank)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Rank of a contract in the previous result set
+    pub fn previous_rank(&self, con_id: i32) -> Option<i32> {
+        self.previous_ranks.get(&con_id).copied()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Positive when the contract moved up the ranking since the previous update
+    pub fn rank_change(&self, con_id: i32) -> Option<i32> {
+        match (self.previous_rank(con_id), self.rank(con_id)) {
+            (Some(previous), Some(current)) => Some(previous - current),
+            _ => None,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn replace_rows(&mut self, mut rows: Vec<ScanData>) {
+        rows.sort_by_key(|row| row.rank);
+        self.previous_ranks = self
+            .rows
+            .iter()
+            .map(|row| (row.contract.contract.con_id, row.rank))
+            .collect();
+        self.added = rows
+            .iter()
+            .map(|row| row.contract.contract.con_id)
+            .filter(|con_id| !self.previous_ranks.contains_key(con_id))
+            .collect();
+        self.removed = self
+            .rows
+            .iter()
+            .map(|row| row.contract.contract.con_id)
+            .filter(|con_id| {
+                !rows
+                    .iter()
+                    .any(|row| row.contract.contract.con_id == *con_id)
+            })
+            .collect();
+        self.rows = rows;
+        self.update_count += 1;
+    }
+}
+
+impl fmt::Display for ScannerResultSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "req_id: {}, rows: {}, update_count: {}, added: {:?}, removed: {:?}",
+            self.req_id,
+            self.rows.len(),
+            self.update_count,
+            self.added,
+            self.removed
+        )
+    }
+}
+
+//==================================================================================================
+/// Keeps the scanner catalog and the ranked results of scanner subscriptions.
+///
+/// Owned by the application's Wrapper implementation: forward scanner_parameters, scanner_data
+/// and scanner_data_end to the methods of the same name.  Subscriptions are validated against
+/// the catalog, once it has been received, before they are sent to TWS.
+#[derive(Debug, Default)]
+pub struct MarketScanner {
+    catalog: Option<ScannerCatalog>,
+    pending: HashMap<i32, Vec<ScanData>>,
+    results: HashMap<i32, ScannerResultSet>,
+}
+
+impl MarketScanner {
+    pub fn new() -> Self {
+        MarketScanner::default()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn catalog(&self) -> Option<&ScannerCatalog> {
+        self.catalog.as_ref()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn set_catalog(&mut self, catalog: ScannerCatalog) {
+        self.catalog = Some(catalog);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Requests the scanner parameters document which is used to build the catalog
+    pub fn request_parameters<T: Wrapper + Send + Sync + 'static>(
+        &mut self,
+        client: &mut EClient<T>,
+    ) -> Result<(), IBKRApiLibError> {
+        client.req_scanner_parameters()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Forwarded from Wrapper::scanner_parameters
+    pub fn scanner_parameters(&mut self, xml: &str) -> Result<(), IBKRApiLibError> {
+        let catalog = ScannerCatalog::from_xml(xml)?;
+        info!("Scanner catalog loaded: {}", catalog);
+        self.catalog = Some(catalog);
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Validates the subscription against the catalog and starts it.  Fails without sending
+    /// anything if the catalog has not been received yet or the subscription is invalid.
+    pub fn subscribe<T: Wrapper + Send + Sync + 'static>(
+        &mut self,
+        client: &mut EClient<T>,
+        req_id: i32,
+        subscription: ScannerSubscription,
+        filter_options: Vec<TagValue>,
+    ) -> Result<(), IBKRApiLibError> {
+        self.validate(req_id, &subscription, filter_options.as_slice())?;
+        client.req_scanner_subscription(req_id, subscription, vec![], filter_options)?;
+        self.pending.insert(req_id, vec![]);
+        self.results.insert(req_id, ScannerResultSet::new(req_id));
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Validates a subscription against the catalog
+    pub fn validate(
+        &self,
+        req_id: i32,
+        subscription: &ScannerSubscription,
+        filter_options: &[TagValue],
+    ) -> Result<(), IBKRApiLibError> {
+        match self.catalog.as_ref() {
+            Some(catalog) => catalog.validate(req_id, subscription, filter_options),
+            None => Err(scanner_error(
+                req_id,
+                "Scanner catalog not loaded, request scanner parameters first".to_string(),
+            )),
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Cancels a subscription made with [MarketScanner::subscribe] and drops its results
+    pub fn unsubscribe<T: Wrapper + Send + Sync + 'static>(
+        &mut self,
+        client: &mut EClient<T>,
+        req_id: i32,
+    ) -> Result<(), IBKRApiLibError> {
+        client.cancel_scanner_subscription(req_id)?;
+        self.pending.remove(&req_id);
+        self.results.remove(&req_id);
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Forwarded from Wrapper::scanner_data
+    #[allow(clippy::too_many_arguments)]
+    pub fn scanner_data(
+        &mut self,
+        req_id: i32,
+        rank: i32,
+        contract_details: ContractDetails,
+        distance: &str,
+        benchmark: &str,
+        projection: &str,
+        legs_str: &str,
+    ) -> bool {
+        match self.pending.get_mut(&req_id) {
+            Some(rows) => {
+                rows.push(ScanData::new(
+                    contract_details,
+                    rank,
+                    distance.to_string(),
+                    benchmark.to_string(),
+                    projection.to_string(),
+                    legs_str.to_string(),
+                ));
+                true
+            }
+            None => false,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Forwarded from Wrapper::scanner_data_end.  Replaces the result set with the rows received
+    /// since the previous scanner_data_end.
+    pub fn scanner_data_end(&mut self, req_id: i32) -> bool {
+        let rows = match self.pending.get_mut(&req_id) {
+            Some(rows) => std::mem::take(rows),
+            None => return false,
+        };
+        let results = self
+            .results
+            .entry(req_id)
+            .or_insert_with(|| ScannerResultSet::new(req_id));
+        results.replace_rows(rows);
+        true
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Latest complete results for a subscription
+    pub fn results(&self, req_id: i32) -> Option<&ScannerResultSet> {
+        self.results.get(&req_id)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Starts collecting results for a subscription that was requested directly through the
+    /// client rather than with [MarketScanner::subscribe]
+    pub fn track(&mut self, req_id: i32) {
+        self.pending.entry(req_id).or_default();
+        self.results
+            .entry(req_id)
+            .or_insert_with(|| ScannerResultSet::new(req_id));
+    }
+}
@@ -0,0 +1,87 @@
+//! CSV reader and writer for bars and ticks, with a header row naming the schema's columns
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::Path;
+
+use crate::core::errors::IBKRApiLibError;
+use crate::core::series_store::{storage_error, SeriesRecord, Value, Values};
+
+//==================================================================================================
+fn csv_error(err: csv::Error) -> IBKRApiLibError {
+    storage_error(format!("CSV error: {}", err))
+}
+
+//==================================================================================================
+fn header<T: SeriesRecord>() -> Vec<&'static str> {
+    T::schema().iter().map(|(name, _)| *name).collect()
+}
+
+//==================================================================================================
+/// Writes records, preceded by the header row if `with_header` is set
+pub fn write<T: SeriesRecord, W: Write>(
+    writer: W,
+    records: &[T],
+    with_header: bool,
+) -> Result<(), IBKRApiLibError> {
+    let mut writer = csv::Writer::from_writer(writer);
+    if with_header {
+        writer.write_record(header::<T>()).map_err(csv_error)?;
+    }
+    for record in records {
+        writer
+            .write_record(record.to_values().iter().map(Value::to_string))
+            .map_err(csv_error)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+//==================================================================================================
+/// Reads records written by [write].  The header row must match the schema of `T`.
+pub fn read<T: SeriesRecord, R: Read>(reader: R) -> Result<Vec<T>, IBKRApiLibError> {
+    let mut reader = csv::Reader::from_reader(reader);
+    let found = reader
+        .headers()
+        .map_err(csv_error)?
+        .iter()
+        .map(str::to_string)
+        .collect::<Vec<String>>();
+    if found != header::<T>() {
+        return Err(storage_error(format!(
+            "CSV columns {:?} don't match the {} schema {:?}",
+            found,
+            T::KIND,
+            header::<T>()
+        )));
+    }
+
+    let mut records = vec![];
+    for row in reader.records() {
+        let row = row.map_err(csv_error)?;
+        let values = T::schema()
+            .iter()
+            .zip(row.iter())
+            .map(|((_, column_type), text)| Value::parse(*column_type, text))
+            .collect::<Result<Vec<Value>, IBKRApiLibError>>()?;
+        records.push(T::from_values(&mut Values::new(T::KIND, values))?);
+    }
+    Ok(records)
+}
+
+//==================================================================================================
+pub fn write_file<T: SeriesRecord>(path: &Path, records: &[T]) -> Result<(), IBKRApiLibError> {
+    write(File::create(path)?, records, true)
+}
+
+//==================================================================================================
+/// Appends records to a file, writing the header first if the file is new or empty
+pub fn append_file<T: SeriesRecord>(path: &Path, records: &[T]) -> Result<(), IBKRApiLibError> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    let is_empty = file.metadata()?.len() == 0;
+    write(file, records, is_empty)
+}
+
+//==================================================================================================
+pub fn read_file<T: SeriesRecord>(path: &Path) -> Result<Vec<T>, IBKRApiLibError> {
+    read(File::open(path)?)
+}
@@ -0,0 +1,182 @@
+//! Parquet reader and writer for bars and ticks.  Requires the `parquet-storage` feature.
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+
+use arrow_array::{
+    Array, ArrayRef, BooleanArray, Float64Array, Int64Array, RecordBatch, StringArray,
+};
+use arrow_schema::{DataType, Field, Schema};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::arrow::ArrowWriter;
+
+use crate::core::errors::IBKRApiLibError;
+use crate::core::series_store::{storage_error, ColumnType, SeriesRecord, Value, Values};
+
+//==================================================================================================
+fn parquet_error<E: std::fmt::Display>(err: E) -> IBKRApiLibError {
+    storage_error(format!("Parquet error: {}", err))
+}
+
+//==================================================================================================
+fn data_type(column_type: ColumnType) -> DataType {
+    match column_type {
+        ColumnType::Text => DataType::Utf8,
+        ColumnType::Integer => DataType::Int64,
+        ColumnType::Float => DataType::Float64,
+        ColumnType::Boolean => DataType::Boolean,
+    }
+}
+
+//==================================================================================================
+/// Arrow schema of a series type.  No column is nullable.
+pub fn arrow_schema<T: SeriesRecord>() -> Schema {
+    Schema::new(
+        T::schema()
+            .iter()
+            .map(|(name, column_type)| Field::new(*name, data_type(*column_type), false))
+            .collect::<Vec<Field>>(),
+    )
+}
+
+//==================================================================================================
+/// Converts records to one record batch
+pub fn to_batch<T: SeriesRecord>(records: &[T]) -> Result<RecordBatch, IBKRApiLibError> {
+    let rows = records
+        .iter()
+        .map(SeriesRecord::to_values)
+        .collect::<Vec<Vec<Value>>>();
+    let mut columns: Vec<ArrayRef> = vec![];
+    for (index, (_, column_type)) in T::schema().iter().enumerate() {
+        let values = rows.iter().map(|row| &row[index]);
+        let column: ArrayRef =
+            match column_type {
+                ColumnType::Text => {
+                    Arc::new(StringArray::from_iter_values(values.map(
+                        |value| match value {
+                            Value::Text(value) => value.clone(),
+                            value => value.to_string(),
+                        },
+                    )))
+                }
+                ColumnType::Integer => Arc::new(Int64Array::from_iter_values(values.map(
+                    |value| match value {
+                        Value::Integer(value) => *value,
+                        _ => 0,
+                    },
+                ))),
+                ColumnType::Float => Arc::new(Float64Array::from_iter_values(values.map(
+                    |value| match value {
+                        Value::Float(value) => *value,
+                        _ => 0.0,
+                    },
+                ))),
+                ColumnType::Boolean => Arc::new(BooleanArray::from(
+                    values
+                        .map(|value| matches!(value, Value::Boolean(true)))
+                        .collect::<Vec<bool>>(),
+                )),
+            };
+        columns.push(column);
+    }
+    RecordBatch::try_new(Arc::new(arrow_schema::<T>()), columns).map_err(parquet_error)
+}
+
+//==================================================================================================
+fn column_value(
+    column: &ArrayRef,
+    column_type: ColumnType,
+    row: usize,
+) -> Result<Value, IBKRApiLibError> {
+    let any = column.as_any();
+    let value = match column_type {
+        ColumnType::Text => any
+            .downcast_ref::<StringArray>()
+            .map(|array| Value::Text(array.value(row).to_string())),
+        ColumnType::Integer => any
+            .downcast_ref::<Int64Array>()
+            .map(|array| Value::Integer(array.value(row))),
+        ColumnType::Float => any
+            .downcast_ref::<Float64Array>()
+            .map(|array| Value::Float(array.value(row))),
+        ColumnType::Boolean => any
+            .downcast_ref::<BooleanArray>()
+            .map(|array| Value::Boolean(array.value(row))),
+    };
+    value.ok_or_else(|| {
+        storage_error(format!(
+            "Expected {:?} column, got {}",
+            column_type,
+            column.data_type()
+        ))
+    })
+}
+
+//==================================================================================================
+/// Converts a record batch back to records.  The batch's columns must match the schema of `T`.
+pub fn from_batch<T: SeriesRecord>(batch: &RecordBatch) -> Result<Vec<T>, IBKRApiLibError> {
+    let found = batch
+        .schema()
+        .fields()
+        .iter()
+        .map(|field| field.name().clone())
+        .collect::<Vec<String>>();
+    let expected = T::schema()
+        .iter()
+        .map(|(name, _)| *name)
+        .collect::<Vec<&str>>();
+    if found != expected {
+        return Err(storage_error(format!(
+            "Parquet columns {:?} don't match the {} schema {:?}",
+            found,
+            T::KIND,
+            expected
+        )));
+    }
+
+    let mut records = Vec::with_capacity(batch.num_rows());
+    for row in 0..batch.num_rows() {
+        let values = T::schema()
+            .iter()
+            .zip(batch.columns())
+            .map(|((_, column_type), column)| column_value(column, *column_type, row))
+            .collect::<Result<Vec<Value>, IBKRApiLibError>>()?;
+        records.push(T::from_values(&mut Values::new(T::KIND, values))?);
+    }
+    Ok(records)
+}
+
+//==================================================================================================
+pub fn write_file<T: SeriesRecord>(path: &Path, records: &[T]) -> Result<(), IBKRApiLibError> {
+    let batch = to_batch(records)?;
+    let mut writer =
+        ArrowWriter::try_new(File::create(path)?, batch.schema(), None).map_err(parquet_error)?;
+    writer.write(&batch).map_err(parquet_error)?;
+    writer.close().map_err(parquet_error)?;
+    Ok(())
+}
+
+//==================================================================================================
+pub fn read_file<T: SeriesRecord>(path: &Path) -> Result<Vec<T>, IBKRApiLibError> {
+    let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(path)?)
+        .map_err(parquet_error)?
+        .build()
+        .map_err(parquet_error)?;
+    let mut records = vec![];
+    for batch in reader {
+        records.extend(from_batch::<T>(&batch.map_err(parquet_error)?)?);
+    }
+    Ok(records)
+}
+
+//==================================================================================================
+/// Parquet files can't be extended in place, so the existing records are read and the file is
+/// written again
+pub fn append_file<T: SeriesRecord>(path: &Path, records: &[T]) -> Result<(), IBKRApiLibError> {
+    if !path.exists() {
+        return write_file(path, records);
+    }
+    let mut all = read_file::<T>(path)?;
+    all.extend_from_slice(records);
+    write_file(path, all.as_slice())
+}
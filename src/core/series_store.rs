@@ -0,0 +1,554 @@
+//! Column schemas of bars and ticks and a store partitioned by symbol and date.
+//!
+//! Every series type implements [SeriesRecord], which fixes its column names and types.  The
+//! CSV reader and writer in [crate::core::series_csv] are always available; Parquet files are
+//! written by [crate::core::series_parquet] when the `parquet-storage` feature is enabled.
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::{fmt, vec};
+
+use chrono::{DateTime, NaiveDate};
+
+use crate::core::backtester::bar_time;
+use crate::core::common::{
+    BarData, HistoricalTick, HistoricalTickBidAsk, HistoricalTickLast, RealTimeBar,
+    TickAttribBidAsk, TickAttribLast,
+};
+use crate::core::errors::{IBKRApiLibError, TwsApiReportableError, TwsError};
+use crate::core::series_csv;
+#[cfg(feature = "parquet-storage")]
+use crate::core::series_parquet;
+
+//==================================================================================================
+pub(crate) fn storage_error(description: String) -> IBKRApiLibError {
+    IBKRApiLibError::ApiError(TwsApiReportableError::new(
+        -1,
+        TwsError::BadMessage.code().to_string(),
+        description,
+    ))
+}
+
+//==================================================================================================
+/// Type of a column.  Integers are stored as 64 bit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColumnType {
+    Text,
+    Integer,
+    Float,
+    Boolean,
+}
+
+//==================================================================================================
+/// A value of one column of a record
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Text(String),
+    Integer(i64),
+    Float(f64),
+    Boolean(bool),
+}
+
+impl Value {
+    /// Parses the text of a column of the given type
+    pub fn parse(column_type: ColumnType, text: &str) -> Result<Self, IBKRApiLibError> {
+        let invalid = || storage_error(format!("Invalid {:?} value '{}'", column_type, text));
+        Ok(match column_type {
+            ColumnType::Text => Value::Text(text.to_string()),
+            ColumnType::Integer => Value::Integer(text.parse().map_err(|_| invalid())?),
+            ColumnType::Float => Value::Float(text.parse().map_err(|_| invalid())?),
+            ColumnType::Boolean => Value::Boolean(text.parse().map_err(|_| invalid())?),
+        })
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Text(value) => write!(f, "{}", value),
+            Value::Integer(value) => write!(f, "{}", value),
+            Value::Float(value) => write!(f, "{}", value),
+            Value::Boolean(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+//==================================================================================================
+/// Takes the values of a record in schema order
+pub struct Values {
+    kind: &'static str,
+    values: vec::IntoIter<Value>,
+}
+
+impl Values {
+    pub fn new(kind: &'static str, values: Vec<Value>) -> Self {
+        Values {
+            kind,
+            values: values.into_iter(),
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn next(&mut self, expected: ColumnType) -> Result<Value, IBKRApiLibError> {
+        self.values.next().ok_or_else(|| {
+            storage_error(format!(
+                "Missing {:?} column in {} record",
+                expected, self.kind
+            ))
+        })
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn mismatch(&self, expected: ColumnType, value: Value) -> IBKRApiLibError {
+        storage_error(format!(
+            "Expected {:?} in {} record, got {:?}",
+            expected, self.kind, value
+        ))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn text(&mut self) -> Result<String, IBKRApiLibError> {
+        match self.next(ColumnType::Text)? {
+            Value::Text(value) => Ok(value),
+            value => Err(self.mismatch(ColumnType::Text, value)),
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn integer(&mut self) -> Result<i64, IBKRApiLibError> {
+        match self.next(ColumnType::Integer)? {
+            Value::Integer(value) => Ok(value),
+            value => Err(self.mismatch(ColumnType::Integer, value)),
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn float(&mut self) -> Result<f64, IBKRApiLibError> {
+        match self.next(ColumnType::Float)? {
+            Value::Float(value) => Ok(value),
+            value => Err(self.mismatch(ColumnType::Float, value)),
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn boolean(&mut self) -> Result<bool, IBKRApiLibError> {
+        match self.next(ColumnType::Boolean)? {
+            Value::Boolean(value) => Ok(value),
+            value => Err(self.mismatch(ColumnType::Boolean, value)),
+        }
+    }
+}
+
+//==================================================================================================
+/// A bar or tick type with a stable column schema
+pub trait SeriesRecord: Clone {
+    /// Name of the series, used as directory name by [SeriesStore]
+    const KIND: &'static str;
+
+    /// Column names and types, in the order of [SeriesRecord::to_values]
+    fn schema() -> &'static [(&'static str, ColumnType)];
+
+    fn to_values(&self) -> Vec<Value>;
+
+    fn from_values(values: &mut Values) -> Result<Self, IBKRApiLibError>;
+
+    /// Seconds since the epoch, which decides the date partition
+    fn time(&self) -> Result<i64, IBKRApiLibError>;
+}
+
+//==================================================================================================
+impl SeriesRecord for BarData {
+    const KIND: &'static str = "bars";
+
+    fn schema() -> &'static [(&'static str, ColumnType)] {
+        &[
+            ("date", ColumnType::Text),
+            ("open", ColumnType::Float),
+            ("high", ColumnType::Float),
+            ("low", ColumnType::Float),
+            ("close", ColumnType::Float),
+            ("volume", ColumnType::Integer),
+            ("bar_count", ColumnType::Integer),
+            ("average", ColumnType::Float),
+        ]
+    }
+
+    fn to_values(&self) -> Vec<Value> {
+        vec![
+            Value::Text(self.date.clone()),
+            Value::Float(self.open),
+            Value::Float(self.high),
+            Value::Float(self.low),
+            Value::Float(self.close),
+            Value::Integer(self.volume),
+            Value::Integer(self.bar_count as i64),
+            Value::Float(self.average),
+        ]
+    }
+
+    fn from_values(values: &mut Values) -> Result<Self, IBKRApiLibError> {
+        Ok(BarData {
+            date: values.text()?,
+            open: values.float()?,
+            high: values.float()?,
+            low: values.float()?,
+            close: values.float()?,
+            volume: values.integer()?,
+            bar_count: values.integer()? as i32,
+            average: values.float()?,
+        })
+    }
+
+    fn time(&self) -> Result<i64, IBKRApiLibError> {
+        bar_time(self.date.as_str())
+    }
+}
+
+//==================================================================================================
+impl SeriesRecord for RealTimeBar {
+    const KIND: &'static str = "realtime_bars";
+
+    fn schema() -> &'static [(&'static str, ColumnType)] {
+        &[
+            ("date_time", ColumnType::Text),
+            ("open", ColumnType::Float),
+            ("high", ColumnType::Float),
+            ("low", ColumnType::Float),
+            ("close", ColumnType::Float),
+            ("volume", ColumnType::Integer),
+            ("wap", ColumnType::Float),
+            ("count", ColumnType::Integer),
+        ]
+    }
+
+    fn to_values(&self) -> Vec<Value> {
+        vec![
+            Value::Text(self.date_time.clone()),
+            Value::Float(self.open),
+            Value::Float(self.high),
+            Value::Float(self.low),
+            Value::Float(self.close),
+            Value::Integer(self.volume),
+            Value::Float(self.wap),
+            Value::Integer(self.count as i64),
+        ]
+    }
+
+    fn from_values(values: &mut Values) -> Result<Self, IBKRApiLibError> {
+        Ok(RealTimeBar {
+            date_time: values.text()?,
+            open: values.float()?,
+            high: values.float()?,
+            low: values.float()?,
+            close: values.float()?,
+            volume: values.integer()?,
+            wap: values.float()?,
+            count: values.integer()? as i32,
+        })
+    }
+
+    fn time(&self) -> Result<i64, IBKRApiLibError> {
+        bar_time(self.date_time.as_str())
+    }
+}
+
+//==================================================================================================
+impl SeriesRecord for HistoricalTick {
+    const KIND: &'static str = "ticks_midpoint";
+
+    fn schema() -> &'static [(&'static str, ColumnType)] {
+        &[
+            ("time", ColumnType::Integer),
+            ("price", ColumnType::Float),
+            ("size", ColumnType::Integer),
+        ]
+    }
+
+    fn to_values(&self) -> Vec<Value> {
+        vec![
+            Value::Integer(self.time as i64),
+            Value::Float(self.price),
+            Value::Integer(self.size as i64),
+        ]
+    }
+
+    fn from_values(values: &mut Values) -> Result<Self, IBKRApiLibError> {
+        Ok(HistoricalTick {
+            time: values.integer()? as i32,
+            price: values.float()?,
+            size: values.integer()? as i32,
+        })
+    }
+
+    fn time(&self) -> Result<i64, IBKRApiLibError> {
+        Ok(self.time as i64)
+    }
+}
+
+//==================================================================================================
+impl SeriesRecord for HistoricalTickBidAsk {
+    const KIND: &'static str = "ticks_bid_ask";
+
+    fn schema() -> &'static [(&'static str, ColumnType)] {
+        &[
+            ("time", ColumnType::Integer),
+            ("bid_past_low", ColumnType::Boolean),
+            ("ask_past_high", ColumnType::Boolean),
+            ("price_bid", ColumnType::Float),
+            ("price_ask", ColumnType::Float),
+            ("size_bid", ColumnType::Integer),
+            ("size_ask", ColumnType::Integer),
+        ]
+    }
+
+    fn to_values(&self) -> Vec<Value> {
+        vec![
+            Value::Integer(self.time as i64),
+            Value::Boolean(self.tick_attrib_bid_ask.bid_past_low),
+            Value::Boolean(self.tick_attrib_bid_ask.ask_past_high),
+            Value::Float(self.price_bid),
+            Value::Float(self.price_ask),
+            Value::Integer(self.size_bid as i64),
+            Value::Integer(self.size_ask as i64),
+        ]
+    }
+
+    fn from_values(values: &mut Values) -> Result<Self, IBKRApiLibError> {
+        Ok(HistoricalTickBidAsk {
+            time: values.integer()? as i32,
+            tick_attrib_bid_ask: TickAttribBidAsk {
+                bid_past_low: values.boolean()?,
+                ask_past_high: values.boolean()?,
+            },
+            price_bid: values.float()?,
+            price_ask: values.float()?,
+            size_bid: values.integer()? as i32,
+            size_ask: values.integer()? as i32,
+        })
+    }
+
+    fn time(&self) -> Result<i64, IBKRApiLibError> {
+        Ok(self.time as i64)
+    }
+}
+
+//==================================================================================================
+impl SeriesRecord for HistoricalTickLast {
+    const KIND: &'static str = "ticks_last";
+
+    fn schema() -> &'static [(&'static str, ColumnType)] {
+        &[
+            ("time", ColumnType::Integer),
+            ("past_limit", ColumnType::Boolean),
+            ("unreported", ColumnType::Boolean),
+            ("price", ColumnType::Float),
+            ("size", ColumnType::Integer),
+            ("exchange", ColumnType::Text),
+            ("special_conditions", ColumnType::Text),
+        ]
+    }
+
+    fn to_values(&self) -> Vec<Value> {
+        vec![
+            Value::Integer(self.time as i64),
+            Value::Boolean(self.tick_attrib_last.past_limit),
+            Value::Boolean(self.tick_attrib_last.unreported),
+            Value::Float(self.price),
+            Value::Integer(self.size as i64),
+            Value::Text(self.exchange.clone()),
+            Value::Text(self.special_conditions.clone()),
+        ]
+    }
+
+    fn from_values(values: &mut Values) -> Result<Self, IBKRApiLibError> {
+        Ok(HistoricalTickLast {
+            time: values.integer()? as i32,
+            tick_attrib_last: TickAttribLast {
+                past_limit: values.boolean()?,
+                unreported: values.boolean()?,
+            },
+            price: values.float()?,
+            size: values.integer()? as i32,
+            exchange: values.text()?,
+            special_conditions: values.text()?,
+        })
+    }
+
+    fn time(&self) -> Result<i64, IBKRApiLibError> {
+        Ok(self.time as i64)
+    }
+}
+
+//==================================================================================================
+/// File format of a [SeriesStore]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StorageFormat {
+    Csv,
+    #[cfg(feature = "parquet-storage")]
+    Parquet,
+}
+
+impl StorageFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            StorageFormat::Csv => "csv",
+            #[cfg(feature = "parquet-storage")]
+            StorageFormat::Parquet => "parquet",
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Writes records to a new file, replacing an existing one
+    pub fn write<T: SeriesRecord>(
+        &self,
+        path: &Path,
+        records: &[T],
+    ) -> Result<(), IBKRApiLibError> {
+        match self {
+            StorageFormat::Csv => series_csv::write_file(path, records),
+            #[cfg(feature = "parquet-storage")]
+            StorageFormat::Parquet => series_parquet::write_file(path, records),
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Adds records to the end of a file, creating it if necessary.  Parquet files can't be
+    /// extended, so they are read and written again.
+    pub fn append<T: SeriesRecord>(
+        &self,
+        path: &Path,
+        records: &[T],
+    ) -> Result<(), IBKRApiLibError> {
+        match self {
+            StorageFormat::Csv => series_csv::append_file(path, records),
+            #[cfg(feature = "parquet-storage")]
+            StorageFormat::Parquet => series_parquet::append_file(path, records),
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn read<T: SeriesRecord>(&self, path: &Path) -> Result<Vec<T>, IBKRApiLibError> {
+        match self {
+            StorageFormat::Csv => series_csv::read_file(path),
+            #[cfg(feature = "parquet-storage")]
+            StorageFormat::Parquet => series_parquet::read_file(path),
+        }
+    }
+}
+
+//==================================================================================================
+/// Directory of series files partitioned by symbol, series kind and UTC date:
+/// `root/SYMBOL/kind/yyyymmdd.ext`
+#[derive(Clone, Debug)]
+pub struct SeriesStore {
+    root: PathBuf,
+    format: StorageFormat,
+}
+
+impl SeriesStore {
+    pub fn new<P: Into<PathBuf>>(root: P, format: StorageFormat) -> Self {
+        SeriesStore {
+            root: root.into(),
+            format,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn root(&self) -> &Path {
+        self.root.as_path()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn series_dir<T: SeriesRecord>(&self, symbol: &str) -> PathBuf {
+        // symbols such as "BRK B" or "EUR.USD" are kept, path separators are not
+        let symbol = symbol.replace(['/', '\\'], "_");
+        self.root.join(symbol).join(T::KIND)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn partition_path<T: SeriesRecord>(&self, symbol: &str, date: NaiveDate) -> PathBuf {
+        self.series_dir::<T>(symbol).join(format!(
+            "{}.{}",
+            date.format("%Y%m%d"),
+            self.format.extension()
+        ))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Appends records to the partitions of their dates and returns the files written
+    pub fn append<T: SeriesRecord>(
+        &self,
+        symbol: &str,
+        records: &[T],
+    ) -> Result<Vec<PathBuf>, IBKRApiLibError> {
+        let mut partitions: BTreeMap<NaiveDate, Vec<T>> = BTreeMap::new();
+        for record in records {
+            let time = record.time()?;
+            let date = DateTime::from_timestamp(time, 0)
+                .ok_or_else(|| storage_error(format!("Invalid time {}", time)))?
+                .date_naive();
+            partitions.entry(date).or_default().push(record.clone());
+        }
+        fs::create_dir_all(self.series_dir::<T>(symbol))?;
+        let mut paths = vec![];
+        for (date, records) in partitions {
+            let path = self.partition_path::<T>(symbol, date);
+            self.format.append(path.as_path(), records.as_slice())?;
+            paths.push(path);
+        }
+        Ok(paths)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Records of one date, empty if nothing was stored
+    pub fn read<T: SeriesRecord>(
+        &self,
+        symbol: &str,
+        date: NaiveDate,
+    ) -> Result<Vec<T>, IBKRApiLibError> {
+        let path = self.partition_path::<T>(symbol, date);
+        if !path.exists() {
+            return Ok(vec![]);
+        }
+        self.format.read(path.as_path())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Records of the dates from `first` to `last`, both included
+    pub fn read_range<T: SeriesRecord>(
+        &self,
+        symbol: &str,
+        first: NaiveDate,
+        last: NaiveDate,
+    ) -> Result<Vec<T>, IBKRApiLibError> {
+        let mut records = vec![];
+        for date in self.dates::<T>(symbol)? {
+            if date >= first && date <= last {
+                records.extend(self.read::<T>(symbol, date)?);
+            }
+        }
+        Ok(records)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Dates that have a partition, in order
+    pub fn dates<T: SeriesRecord>(&self, symbol: &str) -> Result<Vec<NaiveDate>, IBKRApiLibError> {
+        let dir = self.series_dir::<T>(symbol);
+        if !dir.exists() {
+            return Ok(vec![]);
+        }
+        let extension = format!(".{}", self.format.extension());
+        let mut dates = vec![];
+        for entry in fs::read_dir(dir)? {
+            let name = entry?.file_name();
+            let name = name.to_string_lossy();
+            if let Some(stem) = name.strip_suffix(extension.as_str()) {
+                if let Ok(date) = NaiveDate::parse_from_str(stem, "%Y%m%d") {
+                    dates.push(date);
+                }
+            }
+        }
+        dates.sort();
+        Ok(dates)
+    }
+}
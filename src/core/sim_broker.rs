@@ -0,0 +1,862 @@
+//! Paper trading simulator that fills orders against a price feed and reports through a Wrapper
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+use crate::core::common::{BarData, CommissionReport, UNSET_DOUBLE};
+use crate::core::contract::Contract;
+use crate::core::errors::{IBKRApiLibError, TwsApiReportableError, TwsError};
+use crate::core::execution::Execution;
+use crate::core::order::{Order, OrderState};
+use crate::core::wrapper::Wrapper;
+
+/// Error code TWS uses when an order to cancel doesn't exist
+const ORDER_NOT_FOUND: i32 = 10147;
+
+const SUPPORTED_ORDER_TYPES: [&str; 5] = ["MKT", "LMT", "STP", "STP LMT", "TRAIL"];
+
+//==================================================================================================
+fn sim_error(order_id: i32, description: String) -> IBKRApiLibError {
+    IBKRApiLibError::ApiError(TwsApiReportableError::new(
+        order_id,
+        TwsError::BadMessage.code().to_string(),
+        description,
+    ))
+}
+
+//==================================================================================================
+fn is_set(price: f64) -> bool {
+    price != UNSET_DOUBLE
+}
+
+//==================================================================================================
+/// Decides whether a limit order is reached when the market trades at a price
+pub trait FillModel: Send {
+    fn limit_reached(&self, is_buy: bool, limit: f64, price: f64) -> bool;
+}
+
+//==================================================================================================
+/// Limit orders fill as soon as the market touches the limit
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TouchFill;
+
+impl FillModel for TouchFill {
+    fn limit_reached(&self, is_buy: bool, limit: f64, price: f64) -> bool {
+        if is_buy {
+            price <= limit
+        } else {
+            price >= limit
+        }
+    }
+}
+
+//==================================================================================================
+/// Limit orders fill only when the market trades through the limit by at least `margin`, which
+/// accounts for the queue ahead of the order
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TradeThroughFill {
+    pub margin: f64,
+}
+
+impl FillModel for TradeThroughFill {
+    fn limit_reached(&self, is_buy: bool, limit: f64, price: f64) -> bool {
+        if is_buy {
+            price <= limit - self.margin
+        } else {
+            price >= limit + self.margin
+        }
+    }
+}
+
+//==================================================================================================
+/// Moves the fill price of market, stop and trailing stop orders against the order
+pub trait SlippageModel: Send {
+    fn slipped_price(&self, is_buy: bool, quantity: f64, price: f64) -> f64;
+}
+
+//==================================================================================================
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoSlippage;
+
+impl SlippageModel for NoSlippage {
+    fn slipped_price(&self, _is_buy: bool, _quantity: f64, price: f64) -> f64 {
+        price
+    }
+}
+
+//==================================================================================================
+/// Slippage of a fixed amount per share
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FixedSlippage {
+    pub amount: f64,
+}
+
+impl SlippageModel for FixedSlippage {
+    fn slipped_price(&self, is_buy: bool, _quantity: f64, price: f64) -> f64 {
+        if is_buy {
+            price + self.amount
+        } else {
+            price - self.amount
+        }
+    }
+}
+
+//==================================================================================================
+/// Slippage of a percentage of the price
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PercentSlippage {
+    pub percent: f64,
+}
+
+impl SlippageModel for PercentSlippage {
+    fn slipped_price(&self, is_buy: bool, _quantity: f64, price: f64) -> f64 {
+        let slippage = price * self.percent / 100.0;
+        if is_buy {
+            price + slippage
+        } else {
+            price - slippage
+        }
+    }
+}
+
+//==================================================================================================
+/// An execution of the simulated account
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SimFill {
+    pub order_id: i32,
+    pub con_id: i32,
+    pub time: String,
+    /// Negative for sells
+    pub quantity: f64,
+    pub price: f64,
+    pub commission: f64,
+    /// Pnl net of commission of fills that reduce a position
+    pub realized_pnl: Option<f64>,
+}
+
+//==================================================================================================
+/// Position of the simulated account in one contract
+#[derive(Clone, Debug, Default)]
+pub struct SimPosition {
+    pub contract: Contract,
+    pub position: f64,
+    /// Average cost including the multiplier, as in Wrapper::position
+    pub avg_cost: f64,
+    /// Realized pnl net of commissions
+    pub realized_pnl: f64,
+    pub market_price: f64,
+}
+
+impl SimPosition {
+    fn multiplier(&self) -> f64 {
+        self.contract.multiplier.parse::<f64>().unwrap_or(1.0)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn market_value(&self) -> f64 {
+        self.position * self.market_price * self.multiplier()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn unrealized_pnl(&self) -> f64 {
+        self.market_value() - self.position * self.avg_cost
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Applies a fill of `quantity` (negative for sells) and returns the pnl it realized
+    fn fill(&mut self, quantity: f64, price: f64) -> f64 {
+        let cost = price * self.multiplier();
+        let mut realized = 0.0;
+        if self.position == 0.0 || self.position.signum() == quantity.signum() {
+            self.avg_cost =
+                (self.position * self.avg_cost + quantity * cost) / (self.position + quantity);
+        } else {
+            let closed = quantity.abs().min(self.position.abs());
+            realized = closed * (cost - self.avg_cost) * self.position.signum();
+            if quantity.abs() > self.position.abs() {
+                self.avg_cost = cost;
+            }
+        }
+        self.position += quantity;
+        if self.position == 0.0 {
+            self.avg_cost = 0.0;
+        }
+        self.market_price = price;
+        realized
+    }
+}
+
+//==================================================================================================
+#[derive(Clone, Debug)]
+struct SimOrder {
+    contract: Contract,
+    order: Order,
+    status: String,
+    filled: f64,
+    avg_fill_price: f64,
+    /// Current trigger price of a TRAIL order
+    trail_stop: f64,
+    /// A STP LMT order whose stop was hit works as a limit order
+    stop_triggered: bool,
+}
+
+impl SimOrder {
+    fn is_working(&self) -> bool {
+        self.status == "Submitted" || self.status == "PreSubmitted"
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn is_buy(&self) -> bool {
+        self.order.action.eq_ignore_ascii_case("BUY")
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn remaining(&self) -> f64 {
+        self.order.total_quantity - self.filled
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn stop_hit(&self, stop: f64, price: f64) -> bool {
+        if self.is_buy() {
+            price >= stop
+        } else {
+            price <= stop
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn limit_fill(&self, fill_model: &dyn FillModel, price: f64, gap: bool) -> Option<f64> {
+        let limit = self.order.lmt_price;
+        if !fill_model.limit_reached(self.is_buy(), limit, price) {
+            None
+        } else if gap {
+            Some(price)
+        } else {
+            Some(limit)
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Fill price of the order if it executes at `price`.  A gap price is the first price seen
+    /// (e.g. the open of a bar) and fills at that price; inside a bar the price moves
+    /// continuously, so limits and stops fill at their own level.
+    fn match_price(&mut self, fill_model: &dyn FillModel, price: f64, gap: bool) -> Option<f64> {
+        match self.order.order_type.to_uppercase().as_str() {
+            "MKT" => Some(price),
+            "LMT" => self.limit_fill(fill_model, price, gap),
+            "STP" => {
+                let stop = self.order.aux_price;
+                if self.stop_hit(stop, price) {
+                    Some(if gap { price } else { stop })
+                } else {
+                    None
+                }
+            }
+            "STP LMT" => {
+                if self.stop_triggered {
+                    return self.limit_fill(fill_model, price, gap);
+                }
+                let stop = self.order.aux_price;
+                if !self.stop_hit(stop, price) {
+                    return None;
+                }
+                self.stop_triggered = true;
+                self.limit_fill(fill_model, if gap { price } else { stop }, true)
+            }
+            "TRAIL" => {
+                let offset = if is_set(self.order.aux_price) {
+                    self.order.aux_price
+                } else {
+                    price * self.order.trailing_percent / 100.0
+                };
+                let trail_stop = if self.is_buy() {
+                    price + offset
+                } else {
+                    price - offset
+                };
+                if !is_set(self.trail_stop) {
+                    self.trail_stop = if is_set(self.order.trail_stop_price) {
+                        self.order.trail_stop_price
+                    } else {
+                        trail_stop
+                    };
+                }
+                if self.stop_hit(self.trail_stop, price) {
+                    return Some(if gap { price } else { self.trail_stop });
+                }
+                // the stop only follows the price in the favourable direction
+                self.trail_stop = if self.is_buy() {
+                    self.trail_stop.min(trail_stop)
+                } else {
+                    self.trail_stop.max(trail_stop)
+                };
+                None
+            }
+            _ => None,
+        }
+    }
+}
+
+//==================================================================================================
+/// Simulated broker that stands in for EClient on the order side.
+///
+/// Orders sent with [SimBroker::place_order] are matched against the prices passed to
+/// [SimBroker::on_tick] and [SimBroker::on_bar] for the same con_id, and the wrapper receives
+/// the callbacks TWS would send: order_status, open_order, exec_details, commission_report,
+/// position and update_portfolio.  Orders fill completely; a bar is walked open, low, high,
+/// close (open, high, low, close for down bars).  The [FillModel] decides when limits are
+/// reached and the [SlippageModel] moves the price of market-like fills.  Child orders become
+/// active when their parent fills and an order filling cancels the rest of its OCA group.
+pub struct SimBroker<T: Wrapper> {
+    wrapper: Arc<Mutex<T>>,
+    account: String,
+    client_id: i32,
+    commission_per_share: f64,
+    min_commission: f64,
+    currency: String,
+    orders: BTreeMap<i32, SimOrder>,
+    positions: BTreeMap<i32, SimPosition>,
+    last_prices: HashMap<i32, f64>,
+    time: String,
+    next_perm_id: i32,
+    next_exec_id: i64,
+    fill_model: Box<dyn FillModel>,
+    slippage: Box<dyn SlippageModel>,
+    fills: Vec<SimFill>,
+}
+
+impl<T: Wrapper> SimBroker<T> {
+    pub fn new(wrapper: Arc<Mutex<T>>, account: &str) -> Self {
+        SimBroker {
+            wrapper,
+            account: account.to_string(),
+            client_id: 0,
+            commission_per_share: 0.0,
+            min_commission: 0.0,
+            currency: "USD".to_string(),
+            orders: BTreeMap::new(),
+            positions: BTreeMap::new(),
+            last_prices: HashMap::new(),
+            time: "".to_string(),
+            next_perm_id: 1,
+            next_exec_id: 1,
+            fill_model: Box::new(TouchFill),
+            slippage: Box::new(NoSlippage),
+            fills: vec![],
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn set_client_id(&mut self, client_id: i32) {
+        self.client_id = client_id;
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Commission charged per share or contract, and per execution at least
+    pub fn set_commission(&mut self, per_share: f64, minimum: f64, currency: &str) {
+        self.commission_per_share = per_share;
+        self.min_commission = minimum;
+        self.currency = currency.to_string();
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// How limit prices are reached, [TouchFill] by default
+    pub fn set_fill_model(&mut self, fill_model: Box<dyn FillModel>) {
+        self.fill_model = fill_model;
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Slippage of market, stop and trailing stop fills, [NoSlippage] by default
+    pub fn set_slippage(&mut self, slippage: Box<dyn SlippageModel>) {
+        self.slippage = slippage;
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Time stamp of executions from now on, in the "yyyymmdd  hh:mm:ss" format of TWS
+    pub fn set_time(&mut self, time: &str) {
+        self.time = time.to_string();
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn time(&self) -> &str {
+        self.time.as_str()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn wrapper(&self) -> &Arc<Mutex<T>> {
+        &self.wrapper
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn position(&self, con_id: i32) -> Option<&SimPosition> {
+        self.positions.get(&con_id)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn positions(&self) -> impl Iterator<Item = &SimPosition> {
+        self.positions.values()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn fills(&self) -> &[SimFill] {
+        self.fills.as_slice()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Realized and unrealized pnl of all positions, net of commissions
+    pub fn total_pnl(&self) -> f64 {
+        self.positions
+            .values()
+            .map(|position| position.realized_pnl + position.unrealized_pnl())
+            .sum()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Status of an order as last reported through order_status
+    pub fn order_status(&self, order_id: i32) -> Option<&str> {
+        self.orders
+            .get(&order_id)
+            .map(|order| order.status.as_str())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn last_price(&self, con_id: i32) -> Option<f64> {
+        self.last_prices.get(&con_id).copied()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Accepts a new order or modifies a working one.  A marketable order fills at once when a
+    /// price for the contract is known.
+    pub fn place_order(
+        &mut self,
+        order_id: i32,
+        contract: &Contract,
+        order: &Order,
+    ) -> Result<(), IBKRApiLibError> {
+        validate_order(order_id, order)?;
+        let mut order = order.clone();
+        order.order_id = order_id;
+        order.client_id = self.client_id;
+        if order.account.is_empty() {
+            order.account = self.account.clone();
+        }
+
+        match self.orders.get_mut(&order_id) {
+            Some(existing) if !existing.is_working() => {
+                return Err(sim_error(
+                    order_id,
+                    format!(
+                        "Order {} can't be modified, it is {}",
+                        order_id, existing.status
+                    ),
+                ));
+            }
+            Some(existing) => {
+                order.perm_id = existing.order.perm_id;
+                existing.order = order;
+                existing.contract = contract.clone();
+                existing.trail_stop = UNSET_DOUBLE;
+                existing.stop_triggered = false;
+            }
+            None => {
+                order.perm_id = self.next_perm_id;
+                self.next_perm_id += 1;
+                let parent_active =
+                    order.parent_id == 0 || self.order_status(order.parent_id) == Some("Filled");
+                self.orders.insert(
+                    order_id,
+                    SimOrder {
+                        contract: contract.clone(),
+                        order,
+                        status: if parent_active {
+                            "Submitted".to_string()
+                        } else {
+                            "PreSubmitted".to_string()
+                        },
+                        filled: 0.0,
+                        avg_fill_price: 0.0,
+                        trail_stop: UNSET_DOUBLE,
+                        stop_triggered: false,
+                    },
+                );
+            }
+        }
+        self.report_open_order(order_id);
+        self.report_order_status(order_id, 0.0);
+
+        if let Some(price) = self.last_price(contract.con_id) {
+            self.match_order(order_id, price, true);
+        }
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Cancels a working order and its inactive children.  Like TWS, an unknown or finished
+    /// order is reported through Wrapper::error.
+    pub fn cancel_order(&mut self, order_id: i32) -> Result<(), IBKRApiLibError> {
+        let working = self
+            .orders
+            .get(&order_id)
+            .is_some_and(|order| order.is_working());
+        if !working {
+            self.wrapper.lock().unwrap().error(
+                order_id,
+                ORDER_NOT_FOUND,
+                format!(
+                    "OrderId {} that needs to be cancelled is not found.",
+                    order_id
+                )
+                .as_str(),
+            );
+            return Ok(());
+        }
+        self.set_cancelled(order_id);
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn req_global_cancel(&mut self) -> Result<(), IBKRApiLibError> {
+        let working = self
+            .orders
+            .iter()
+            .filter(|(_, order)| order.is_working())
+            .map(|(order_id, _)| *order_id)
+            .collect::<Vec<i32>>();
+        for order_id in working {
+            self.set_cancelled(order_id);
+        }
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Reports the working orders through open_order and open_order_end
+    pub fn req_open_orders(&mut self) -> Result<(), IBKRApiLibError> {
+        let working = self
+            .orders
+            .iter()
+            .filter(|(_, order)| order.is_working())
+            .map(|(order_id, _)| *order_id)
+            .collect::<Vec<i32>>();
+        for order_id in working {
+            self.report_open_order(order_id);
+        }
+        self.wrapper.lock().unwrap().open_order_end();
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Reports the positions through position and position_end
+    pub fn req_positions(&mut self) -> Result<(), IBKRApiLibError> {
+        let mut wrapper = self.wrapper.lock().unwrap();
+        for position in self.positions.values() {
+            wrapper.position(
+                self.account.as_str(),
+                position.contract.clone(),
+                position.position,
+                position.avg_cost,
+            );
+        }
+        wrapper.position_end();
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// A trade at `price`
+    pub fn on_tick(&mut self, con_id: i32, price: f64) {
+        self.on_price(con_id, price, true);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// A bar of prices.  The time of executions becomes the bar's date and the portfolio is
+    /// updated at the close.
+    pub fn on_bar(&mut self, con_id: i32, bar: &BarData) {
+        self.time = bar.date.clone();
+        let path = if bar.close >= bar.open {
+            [bar.open, bar.low, bar.high, bar.close]
+        } else {
+            [bar.open, bar.high, bar.low, bar.close]
+        };
+        for (i, price) in path.iter().enumerate() {
+            self.on_price(con_id, *price, i == 0);
+        }
+        let has_position = self
+            .positions
+            .get(&con_id)
+            .is_some_and(|position| position.position != 0.0);
+        if has_position {
+            self.report_portfolio(con_id);
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn on_price(&mut self, con_id: i32, price: f64, gap: bool) {
+        self.last_prices.insert(con_id, price);
+        if let Some(position) = self.positions.get_mut(&con_id) {
+            position.market_price = price;
+        }
+        let candidates = self
+            .orders
+            .iter()
+            .filter(|(_, order)| order.contract.con_id == con_id)
+            .map(|(order_id, _)| *order_id)
+            .collect::<Vec<i32>>();
+        for order_id in candidates {
+            self.match_order(order_id, price, gap);
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn match_order(&mut self, order_id: i32, price: f64, gap: bool) {
+        let fill_model = self.fill_model.as_ref();
+        let slippage = self.slippage.as_ref();
+        let fill_price = match self.orders.get_mut(&order_id) {
+            // orders can be cancelled by an OCA fill while prices are processed
+            Some(order) if order.status == "Submitted" => {
+                order.match_price(fill_model, price, gap).map(|fill_price| {
+                    match order.order.order_type.to_uppercase().as_str() {
+                        "LMT" | "STP LMT" => fill_price,
+                        _ => slippage.slipped_price(order.is_buy(), order.remaining(), fill_price),
+                    }
+                })
+            }
+            _ => None,
+        };
+        if let Some(fill_price) = fill_price {
+            self.fill(order_id, fill_price);
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn fill(&mut self, order_id: i32, price: f64) {
+        let sim_order = self.orders.get_mut(&order_id).unwrap();
+        let quantity = sim_order.remaining();
+        sim_order.avg_fill_price = (sim_order.avg_fill_price * sim_order.filled + price * quantity)
+            / (sim_order.filled + quantity);
+        sim_order.filled += quantity;
+        sim_order.status = "Filled".to_string();
+        let sim_order = sim_order.clone();
+        let con_id = sim_order.contract.con_id;
+
+        let exec_id = format!("sim.{:08}", self.next_exec_id);
+        self.next_exec_id += 1;
+        let execution = Execution {
+            exec_id: exec_id.clone(),
+            time: self.time.clone(),
+            acct_number: sim_order.order.account.clone(),
+            exchange: sim_order.contract.exchange.clone(),
+            side: if sim_order.is_buy() {
+                "BOT".to_string()
+            } else {
+                "SLD".to_string()
+            },
+            shares: quantity,
+            price,
+            perm_id: sim_order.order.perm_id,
+            client_id: self.client_id,
+            order_id,
+            cum_qty: sim_order.filled,
+            avg_price: sim_order.avg_fill_price,
+            order_ref: sim_order.order.order_ref.clone(),
+            ..Default::default()
+        };
+
+        let commission = (quantity * self.commission_per_share).max(self.min_commission);
+        let position = self.positions.entry(con_id).or_insert_with(|| SimPosition {
+            contract: sim_order.contract.clone(),
+            ..Default::default()
+        });
+        let signed_quantity = if sim_order.is_buy() {
+            quantity
+        } else {
+            -quantity
+        };
+        let opening =
+            position.position == 0.0 || position.position.signum() == signed_quantity.signum();
+        let realized = position.fill(signed_quantity, price) - commission;
+        position.realized_pnl += realized;
+        self.fills.push(SimFill {
+            order_id,
+            con_id,
+            time: self.time.clone(),
+            quantity: signed_quantity,
+            price,
+            commission,
+            realized_pnl: if opening { None } else { Some(realized) },
+        });
+        let commission_report = CommissionReport {
+            exec_id,
+            commission,
+            currency: self.currency.clone(),
+            realized_pnl: if opening { UNSET_DOUBLE } else { realized },
+            yield_: UNSET_DOUBLE,
+            yield_redemption_date: "".to_string(),
+        };
+
+        self.wrapper
+            .lock()
+            .unwrap()
+            .exec_details(-1, sim_order.contract.clone(), execution);
+        self.report_order_status(order_id, price);
+        self.report_open_order(order_id);
+        self.wrapper
+            .lock()
+            .unwrap()
+            .commission_report(commission_report);
+        {
+            let position = &self.positions[&con_id];
+            self.wrapper.lock().unwrap().position(
+                self.account.as_str(),
+                position.contract.clone(),
+                position.position,
+                position.avg_cost,
+            );
+        }
+        self.report_portfolio(con_id);
+
+        self.cancel_oca_group(order_id);
+        self.activate_children(order_id);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn cancel_oca_group(&mut self, order_id: i32) {
+        let oca_group = self.orders[&order_id].order.oca_group.clone();
+        if oca_group.is_empty() {
+            return;
+        }
+        let members = self
+            .orders
+            .iter()
+            .filter(|(id, order)| {
+                **id != order_id && order.is_working() && order.order.oca_group == oca_group
+            })
+            .map(|(id, _)| *id)
+            .collect::<Vec<i32>>();
+        for member in members {
+            self.set_cancelled(member);
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn activate_children(&mut self, order_id: i32) {
+        let children = self
+            .orders
+            .iter()
+            .filter(|(_, order)| order.order.parent_id == order_id && order.is_working())
+            .map(|(id, _)| *id)
+            .collect::<Vec<i32>>();
+        for child in children {
+            self.orders.get_mut(&child).unwrap().status = "Submitted".to_string();
+            self.report_order_status(child, 0.0);
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn set_cancelled(&mut self, order_id: i32) {
+        self.orders.get_mut(&order_id).unwrap().status = "Cancelled".to_string();
+        self.report_order_status(order_id, 0.0);
+        let children = self
+            .orders
+            .iter()
+            .filter(|(_, order)| order.order.parent_id == order_id && order.is_working())
+            .map(|(id, _)| *id)
+            .collect::<Vec<i32>>();
+        for child in children {
+            self.set_cancelled(child);
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn report_order_status(&self, order_id: i32, last_fill_price: f64) {
+        let sim_order = &self.orders[&order_id];
+        self.wrapper.lock().unwrap().order_status(
+            order_id,
+            sim_order.status.as_str(),
+            sim_order.filled,
+            sim_order.remaining(),
+            sim_order.avg_fill_price,
+            sim_order.order.perm_id,
+            sim_order.order.parent_id,
+            last_fill_price,
+            self.client_id,
+            "",
+            0.0,
+        );
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn report_open_order(&self, order_id: i32) {
+        let sim_order = &self.orders[&order_id];
+        let order_state = OrderState {
+            status: sim_order.status.clone(),
+            commission: UNSET_DOUBLE,
+            min_commission: UNSET_DOUBLE,
+            max_commission: UNSET_DOUBLE,
+            ..Default::default()
+        };
+        self.wrapper.lock().unwrap().open_order(
+            order_id,
+            sim_order.contract.clone(),
+            sim_order.order.clone(),
+            order_state,
+        );
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn report_portfolio(&self, con_id: i32) {
+        let position = &self.positions[&con_id];
+        self.wrapper.lock().unwrap().update_portfolio(
+            position.contract.clone(),
+            position.position,
+            position.market_price,
+            position.market_value(),
+            position.avg_cost,
+            position.unrealized_pnl(),
+            position.realized_pnl,
+            self.account.as_str(),
+        );
+    }
+}
+
+//==================================================================================================
+fn validate_order(order_id: i32, order: &Order) -> Result<(), IBKRApiLibError> {
+    let order_type = order.order_type.to_uppercase();
+    if !SUPPORTED_ORDER_TYPES.contains(&order_type.as_str()) {
+        return Err(sim_error(
+            order_id,
+            format!("Order type '{}' is not simulated", order.order_type),
+        ));
+    }
+    let action = order.action.to_uppercase();
+    if action != "BUY" && action != "SELL" && action != "SSHORT" {
+        return Err(sim_error(
+            order_id,
+            format!("Invalid action '{}'", order.action),
+        ));
+    }
+    if order.total_quantity <= 0.0 {
+        return Err(sim_error(
+            order_id,
+            format!("Invalid quantity {}", order.total_quantity),
+        ));
+    }
+    let needs_limit = order_type == "LMT" || order_type == "STP LMT";
+    let needs_stop = order_type == "STP" || order_type == "STP LMT";
+    if needs_limit && !is_set(order.lmt_price) {
+        return Err(sim_error(
+            order_id,
+            format!("{} order needs a limit price", order_type),
+        ));
+    }
+    if needs_stop && !is_set(order.aux_price) {
+        return Err(sim_error(
+            order_id,
+            format!("{} order needs a stop price", order_type),
+        ));
+    }
+    if order_type == "TRAIL" && !is_set(order.aux_price) && !is_set(order.trailing_percent) {
+        return Err(sim_error(
+            order_id,
+            "TRAIL order needs a trailing amount or percent".to_string(),
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,281 @@
+//! Parsers for the composite payloads that TWS packs into tick_string
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::common::{UNSET_DOUBLE, UNSET_INTEGER, UNSET_LONG};
+use crate::core::errors::{IBKRApiLibError, TwsApiReportableError, TwsError};
+
+/// Value TWS uses in fundamental ratio ticks when a ratio is not available
+pub const FUNDAMENTAL_RATIO_NOT_AVAILABLE: f64 = -99999.99;
+
+//==================================================================================================
+fn bad_tick_string(what: &str, value: &str) -> IBKRApiLibError {
+    IBKRApiLibError::ApiError(TwsApiReportableError::new(
+        -1,
+        TwsError::BadMessage.code().to_string(),
+        format!(
+            "{} Invalid {} tick string: '{}'",
+            TwsError::BadMessage.message(),
+            what,
+            value
+        ),
+    ))
+}
+
+//==================================================================================================
+fn parse_optional_f64(value: &str) -> Result<f64, IBKRApiLibError> {
+    if value.trim().is_empty() {
+        Ok(UNSET_DOUBLE)
+    } else {
+        Ok(value.trim().parse::<f64>()?)
+    }
+}
+
+//==================================================================================================
+/// Last trade details carried by the RtVolume (48) and RtTrdVolume (77) ticks.
+///
+/// The payload has the form `price;size;time;total_volume;vwap;single_trade_flag`.
+/// Price and size are empty when the tick only reports a volume change, in which
+/// case they are set to UNSET_DOUBLE and UNSET_INTEGER respectively.
+///
+/// * price - last trade price
+/// * size - last trade size
+/// * time - last trade time in milliseconds since the epoch
+/// * total_volume - total volume for the day
+/// * vwap - volume weighted average price for the day
+/// * single_trade - true if the trade was filled by a single market maker
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct RtVolume {
+    pub price: f64,
+    pub size: i32,
+    pub time: i64,
+    pub total_volume: i64,
+    pub vwap: f64,
+    pub single_trade: bool,
+}
+
+impl RtVolume {
+    pub fn new(
+        price: f64,
+        size: i32,
+        time: i64,
+        total_volume: i64,
+        vwap: f64,
+        single_trade: bool,
+    ) -> Self {
+        RtVolume {
+            price,
+            size,
+            time,
+            total_volume,
+            vwap,
+            single_trade,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Returns true if this tick reports an actual trade rather than only a volume update
+    pub fn has_trade(&self) -> bool {
+        self.price != UNSET_DOUBLE && self.size != UNSET_INTEGER
+    }
+}
+
+impl FromStr for RtVolume {
+    type Err = IBKRApiLibError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let parts = value.split(';').collect::<Vec<&str>>();
+        if parts.len() != 6 {
+            return Err(bad_tick_string("RTVolume", value));
+        }
+
+        let size = if parts[1].trim().is_empty() {
+            UNSET_INTEGER
+        } else {
+            // newer servers may report fractional sizes
+            parts[1].trim().parse::<f64>()? as i32
+        };
+        let time = if parts[2].trim().is_empty() {
+            UNSET_LONG
+        } else {
+            parts[2].trim().parse::<i64>()?
+        };
+        let total_volume = if parts[3].trim().is_empty() {
+            0
+        } else {
+            parts[3].trim().parse::<f64>()? as i64
+        };
+        let single_trade = match parts[5].trim() {
+            "true" | "1" => true,
+            "false" | "0" | "" => false,
+            _ => return Err(bad_tick_string("RTVolume", value)),
+        };
+
+        Ok(RtVolume::new(
+            parse_optional_f64(parts[0])?,
+            size,
+            time,
+            total_volume,
+            parse_optional_f64(parts[4])?,
+            single_trade,
+        ))
+    }
+}
+
+impl fmt::Display for RtVolume {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "price: {}, size: {}, time: {}, total_volume: {}, vwap: {}, single_trade: {}",
+            if self.price != UNSET_DOUBLE {
+                format!("{}", self.price)
+            } else {
+                "".to_string()
+            },
+            if self.size != UNSET_INTEGER {
+                format!("{}", self.size)
+            } else {
+                "".to_string()
+            },
+            self.time,
+            self.total_volume,
+            self.vwap,
+            self.single_trade
+        )
+    }
+}
+
+//==================================================================================================
+/// Fundamental ratios carried by the FundamentalRatios (47) tick.
+///
+/// The payload has the form `TAG=value;TAG=value;...`.  Most values are numeric, but a few
+/// (for example CURRENCY) are not, so the raw strings are kept and numeric access goes
+/// through [FundamentalRatios::value].
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct FundamentalRatios {
+    pub ratios: HashMap<String, String>,
+}
+
+impl FundamentalRatios {
+    pub fn new(ratios: HashMap<String, String>) -> Self {
+        FundamentalRatios { ratios }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Returns the numeric value of a ratio, or None if it is missing, not numeric or
+    /// reported as not available by TWS
+    pub fn value(&self, tag: &str) -> Option<f64> {
+        self.ratios
+            .get(tag)
+            .and_then(|val| val.parse::<f64>().ok())
+            .filter(|val| *val != FUNDAMENTAL_RATIO_NOT_AVAILABLE)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Returns the raw string value of a ratio
+    pub fn raw_value(&self, tag: &str) -> Option<&str> {
+        self.ratios.get(tag).map(|val| val.as_str())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Currency the ratios are reported in, if TWS sent one
+    pub fn currency(&self) -> Option<&str> {
+        self.raw_value("CURRENCY")
+    }
+}
+
+impl FromStr for FundamentalRatios {
+    type Err = IBKRApiLibError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut ratios = HashMap::new();
+        for pair in value.split(';').filter(|pair| !pair.trim().is_empty()) {
+            let mut key_value = pair.splitn(2, '=');
+            let key = key_value.next().unwrap_or("").trim();
+            let val = match key_value.next() {
+                Some(val) => val.trim(),
+                None => return Err(bad_tick_string("fundamental ratios", value)),
+            };
+            if key.is_empty() {
+                return Err(bad_tick_string("fundamental ratios", value));
+            }
+            ratios.insert(key.to_string(), val.to_string());
+        }
+        Ok(FundamentalRatios::new(ratios))
+    }
+}
+
+impl fmt::Display for FundamentalRatios {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut tags = self.ratios.keys().collect::<Vec<&String>>();
+        tags.sort();
+        for tag in tags {
+            write!(f, "{}={};", tag, self.ratios[tag])?;
+        }
+        Ok(())
+    }
+}
+
+//==================================================================================================
+/// Dividend information carried by the IbDividends (59) tick.
+///
+/// The payload has the form `past_12_months,next_12_months,next_date,next_amount`, e.g.
+/// `0.83,0.92,20130219,0.23`.  Missing amounts are set to UNSET_DOUBLE.
+///
+/// * past_12_months - sum of dividends paid over the past 12 months
+/// * next_12_months - expected sum of dividends for the next 12 months
+/// * next_date - date of the next dividend (YYYYMMDD)
+/// * next_amount - amount of the next dividend
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct DividendInfo {
+    pub past_12_months: f64,
+    pub next_12_months: f64,
+    pub next_date: String,
+    pub next_amount: f64,
+}
+
+impl DividendInfo {
+    pub fn new(
+        past_12_months: f64,
+        next_12_months: f64,
+        next_date: String,
+        next_amount: f64,
+    ) -> Self {
+        DividendInfo {
+            past_12_months,
+            next_12_months,
+            next_date,
+            next_amount,
+        }
+    }
+}
+
+impl FromStr for DividendInfo {
+    type Err = IBKRApiLibError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let parts = value.split(',').collect::<Vec<&str>>();
+        if parts.len() != 4 {
+            return Err(bad_tick_string("dividends", value));
+        }
+        Ok(DividendInfo::new(
+            parse_optional_f64(parts[0])?,
+            parse_optional_f64(parts[1])?,
+            parts[2].trim().to_string(),
+            parse_optional_f64(parts[3])?,
+        ))
+    }
+}
+
+impl fmt::Display for DividendInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "past_12_months: {}, next_12_months: {}, next_date: {}, next_amount: {}",
+            self.past_12_months, self.next_12_months, self.next_date, self.next_amount
+        )
+    }
+}
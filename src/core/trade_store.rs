@@ -0,0 +1,906 @@
+//! SQLite store of orders, executions, positions and account values.  Requires the
+//! `sqlite-store` feature.
+//!
+//! Forward the order, execution, position and account summary callbacks of your wrapper to a
+//! [TradeStore] to keep a durable history.  On startup, [TradeStore::replay] feeds the stored
+//! state back through a wrapper so in-memory trackers can be rebuilt, and
+//! [TradeStore::execution_filter] asks `req_executions` only for what was missed since.
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use chrono::DateTime;
+use log::*;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::core::common::{CommissionReport, UNSET_DOUBLE};
+use crate::core::contract::Contract;
+use crate::core::errors::{IBKRApiLibError, TwsApiReportableError, TwsError};
+use crate::core::execution::{Execution, ExecutionFilter};
+use crate::core::order::{Order, OrderState};
+use crate::core::wrapper::Wrapper;
+
+/// Order statuses after which TWS sends no further updates for an order
+const TERMINAL_STATUSES: &[&str] = &["Filled", "Cancelled", "ApiCancelled", "Inactive"];
+
+/// req_id passed to Wrapper::exec_details by [TradeStore::replay]
+pub const REPLAY_REQ_ID: i32 = -1;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS orders (
+    perm_id INTEGER PRIMARY KEY,
+    order_id INTEGER NOT NULL,
+    client_id INTEGER NOT NULL,
+    account TEXT,
+    con_id INTEGER,
+    symbol TEXT,
+    status TEXT NOT NULL,
+    filled REAL NOT NULL,
+    remaining REAL NOT NULL,
+    avg_fill_price REAL NOT NULL,
+    completed INTEGER NOT NULL DEFAULT 0,
+    contract TEXT,
+    order_json TEXT,
+    order_state TEXT,
+    updated INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS order_events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    time INTEGER NOT NULL,
+    perm_id INTEGER NOT NULL,
+    order_id INTEGER NOT NULL,
+    source TEXT NOT NULL,
+    status TEXT NOT NULL,
+    filled REAL NOT NULL,
+    remaining REAL NOT NULL,
+    avg_fill_price REAL NOT NULL
+);
+CREATE INDEX IF NOT EXISTS order_events_perm_id ON order_events (perm_id);
+CREATE TABLE IF NOT EXISTS executions (
+    exec_id TEXT PRIMARY KEY,
+    perm_id INTEGER NOT NULL,
+    order_id INTEGER NOT NULL,
+    account TEXT NOT NULL,
+    con_id INTEGER NOT NULL,
+    symbol TEXT NOT NULL,
+    side TEXT NOT NULL,
+    shares REAL NOT NULL,
+    price REAL NOT NULL,
+    exec_time TEXT NOT NULL,
+    recorded INTEGER NOT NULL,
+    contract TEXT NOT NULL,
+    execution TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS executions_perm_id ON executions (perm_id);
+CREATE TABLE IF NOT EXISTS commission_reports (
+    exec_id TEXT PRIMARY KEY,
+    commission REAL NOT NULL,
+    currency TEXT NOT NULL,
+    realized_pnl REAL,
+    report TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS positions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    time INTEGER NOT NULL,
+    account TEXT NOT NULL,
+    con_id INTEGER NOT NULL,
+    symbol TEXT NOT NULL,
+    position REAL NOT NULL,
+    avg_cost REAL NOT NULL,
+    contract TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS account_values (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    time INTEGER NOT NULL,
+    account TEXT NOT NULL,
+    tag TEXT NOT NULL,
+    value TEXT NOT NULL,
+    currency TEXT NOT NULL
+);
+";
+
+//==================================================================================================
+fn store_error(description: String) -> IBKRApiLibError {
+    IBKRApiLibError::ApiError(TwsApiReportableError::new(
+        -1,
+        TwsError::BadMessage.code().to_string(),
+        description,
+    ))
+}
+
+//==================================================================================================
+fn sqlite_error(err: rusqlite::Error) -> IBKRApiLibError {
+    store_error(format!("SQLite error: {}", err))
+}
+
+//==================================================================================================
+fn to_json<T: Serialize>(value: &T) -> Result<String, IBKRApiLibError> {
+    serde_json::to_string(value).map_err(|err| store_error(format!("JSON error: {}", err)))
+}
+
+//==================================================================================================
+fn from_json<T: DeserializeOwned + Default>(json: Option<String>) -> Result<T, IBKRApiLibError> {
+    match json {
+        Some(json) => serde_json::from_str(json.as_str())
+            .map_err(|err| store_error(format!("JSON error: {}", err))),
+        None => Ok(T::default()),
+    }
+}
+
+//==================================================================================================
+fn now_secs() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+//==================================================================================================
+/// Last known state of an order
+#[derive(Clone, Debug)]
+pub struct StoredOrder {
+    pub perm_id: i32,
+    pub order_id: i32,
+    pub client_id: i32,
+    /// Default if only order_status was seen for the order
+    pub contract: Contract,
+    /// Default if only order_status was seen for the order
+    pub order: Order,
+    pub order_state: OrderState,
+    pub status: String,
+    pub filled: f64,
+    pub remaining: f64,
+    pub avg_fill_price: f64,
+    /// Reported by Wrapper::completed_order
+    pub completed: bool,
+    /// Seconds since the epoch of the last change
+    pub updated: i64,
+}
+
+impl StoredOrder {
+    pub fn is_done(&self) -> bool {
+        self.completed || TERMINAL_STATUSES.contains(&self.status.as_str())
+    }
+}
+
+//==================================================================================================
+/// A change of status or filled quantity of an order
+#[derive(Clone, Debug, PartialEq)]
+pub struct OrderEvent {
+    pub time: i64,
+    pub perm_id: i32,
+    pub order_id: i32,
+    /// Callback that reported the change: open_order, order_status or completed_order
+    pub source: String,
+    pub status: String,
+    pub filled: f64,
+    pub remaining: f64,
+    pub avg_fill_price: f64,
+}
+
+//==================================================================================================
+/// An execution with its commission report, if one was received
+#[derive(Clone, Debug)]
+pub struct StoredFill {
+    pub contract: Contract,
+    pub execution: Execution,
+    pub commission_report: Option<CommissionReport>,
+    /// Seconds since the epoch when the execution was stored
+    pub recorded: i64,
+}
+
+//==================================================================================================
+#[derive(Clone, Debug)]
+pub struct PositionSnapshot {
+    pub time: i64,
+    pub account: String,
+    pub contract: Contract,
+    pub position: f64,
+    pub avg_cost: f64,
+}
+
+//==================================================================================================
+#[derive(Clone, Debug, PartialEq)]
+pub struct AccountValue {
+    pub time: i64,
+    pub account: String,
+    pub tag: String,
+    pub value: String,
+    pub currency: String,
+}
+
+//==================================================================================================
+/// SQLite database of order transitions, executions, commission reports, completed orders and
+/// position and account summary snapshots
+pub struct TradeStore {
+    connection: Connection,
+    time: Option<i64>,
+}
+
+impl TradeStore {
+    /// Opens or creates the database file
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, IBKRApiLibError> {
+        Self::with_connection(Connection::open(path).map_err(sqlite_error)?)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// A store that is lost when dropped
+    pub fn open_in_memory() -> Result<Self, IBKRApiLibError> {
+        Self::with_connection(Connection::open_in_memory().map_err(sqlite_error)?)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn with_connection(connection: Connection) -> Result<Self, IBKRApiLibError> {
+        connection.execute_batch(SCHEMA).map_err(sqlite_error)?;
+        Ok(TradeStore {
+            connection,
+            time: None,
+        })
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Fixes the time stamp of the records written from now on, in seconds since the epoch.
+    /// None uses the system clock.
+    pub fn set_time(&mut self, time: Option<i64>) {
+        self.time = time;
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn time(&self) -> i64 {
+        self.time.unwrap_or_else(now_secs)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn current_status(&self, perm_id: i32) -> Result<Option<(String, f64)>, IBKRApiLibError> {
+        self.connection
+            .query_row(
+                "SELECT status, filled FROM orders WHERE perm_id = ?1",
+                params![perm_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .map_err(sqlite_error)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Updates the order and adds an event if its status or filled quantity changed
+    #[allow(clippy::too_many_arguments)]
+    fn record_transition(
+        &self,
+        source: &str,
+        perm_id: i32,
+        order_id: i32,
+        client_id: i32,
+        status: &str,
+        filled: f64,
+        remaining: f64,
+        avg_fill_price: f64,
+    ) -> Result<bool, IBKRApiLibError> {
+        let changed = match self.current_status(perm_id)? {
+            Some((current, current_filled)) => current != status || current_filled != filled,
+            None => true,
+        };
+        let time = self.time();
+        self.connection
+            .execute(
+                "INSERT INTO orders
+                    (perm_id, order_id, client_id, status, filled, remaining, avg_fill_price, updated)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+                 ON CONFLICT (perm_id) DO UPDATE SET
+                    order_id = ?2, client_id = ?3, status = ?4, filled = ?5, remaining = ?6,
+                    avg_fill_price = ?7, updated = ?8",
+                params![
+                    perm_id,
+                    order_id,
+                    client_id,
+                    status,
+                    filled,
+                    remaining,
+                    avg_fill_price,
+                    time
+                ],
+            )
+            .map_err(sqlite_error)?;
+        if changed {
+            debug!(
+                "order {} (perm_id {}) {}: {} filled {}",
+                order_id, perm_id, source, status, filled
+            );
+            self.connection
+                .execute(
+                    "INSERT INTO order_events
+                        (time, perm_id, order_id, source, status, filled, remaining, avg_fill_price)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                    params![
+                        time,
+                        perm_id,
+                        order_id,
+                        source,
+                        status,
+                        filled,
+                        remaining,
+                        avg_fill_price
+                    ],
+                )
+                .map_err(sqlite_error)?;
+        }
+        Ok(changed)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn record_order(
+        &self,
+        source: &str,
+        order_id: i32,
+        contract: &Contract,
+        order: &Order,
+        order_state: &OrderState,
+    ) -> Result<(), IBKRApiLibError> {
+        if order.perm_id == 0 {
+            debug!("order {} has no perm_id yet, not stored", order_id);
+            return Ok(());
+        }
+        // open_order doesn't report fills; keep what order_status reported
+        let (filled, remaining, avg_fill_price) = self
+            .connection
+            .query_row(
+                "SELECT filled, remaining, avg_fill_price FROM orders WHERE perm_id = ?1",
+                params![order.perm_id],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .optional()
+            .map_err(sqlite_error)?
+            .unwrap_or_else(|| {
+                let filled = if order.filled_quantity == UNSET_DOUBLE {
+                    0.0
+                } else {
+                    order.filled_quantity
+                };
+                (filled, order.total_quantity - filled, 0.0)
+            });
+        self.record_transition(
+            source,
+            order.perm_id,
+            order_id,
+            order.client_id,
+            order_state.status.as_str(),
+            filled,
+            remaining,
+            avg_fill_price,
+        )?;
+        self.connection
+            .execute(
+                "UPDATE orders SET account = ?2, con_id = ?3, symbol = ?4, contract = ?5,
+                    order_json = ?6, order_state = ?7, completed = completed OR ?8
+                 WHERE perm_id = ?1",
+                params![
+                    order.perm_id,
+                    order.account,
+                    contract.con_id,
+                    contract.symbol,
+                    to_json(contract)?,
+                    to_json(order)?,
+                    to_json(order_state)?,
+                    source == "completed_order"
+                ],
+            )
+            .map_err(sqlite_error)?;
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Forwarded from Wrapper::open_order.  Orders without a perm_id are not stored.
+    pub fn open_order(
+        &self,
+        order_id: i32,
+        contract: &Contract,
+        order: &Order,
+        order_state: &OrderState,
+    ) -> Result<(), IBKRApiLibError> {
+        self.record_order("open_order", order_id, contract, order, order_state)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Forwarded from Wrapper::order_status.  Returns true if the status or filled quantity
+    /// changed, false for repeated updates.
+    #[allow(clippy::too_many_arguments)]
+    pub fn order_status(
+        &self,
+        order_id: i32,
+        status: &str,
+        filled: f64,
+        remaining: f64,
+        avg_fill_price: f64,
+        perm_id: i32,
+        _parent_id: i32,
+        _last_fill_price: f64,
+        client_id: i32,
+        _why_held: &str,
+        _mkt_cap_price: f64,
+    ) -> Result<bool, IBKRApiLibError> {
+        if perm_id == 0 {
+            return Ok(false);
+        }
+        self.record_transition(
+            "order_status",
+            perm_id,
+            order_id,
+            client_id,
+            status,
+            filled,
+            remaining,
+            avg_fill_price,
+        )
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Forwarded from Wrapper::completed_order
+    pub fn completed_order(
+        &self,
+        contract: &Contract,
+        order: &Order,
+        order_state: &OrderState,
+    ) -> Result<(), IBKRApiLibError> {
+        self.record_order(
+            "completed_order",
+            order.order_id,
+            contract,
+            order,
+            order_state,
+        )
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Forwarded from Wrapper::exec_details.  Returns false if the execution was already stored,
+    /// as happens when req_executions reports executions that were received live.
+    pub fn exec_details(
+        &self,
+        contract: &Contract,
+        execution: &Execution,
+    ) -> Result<bool, IBKRApiLibError> {
+        let inserted = self
+            .connection
+            .execute(
+                "INSERT OR IGNORE INTO executions
+                    (exec_id, perm_id, order_id, account, con_id, symbol, side, shares, price,
+                     exec_time, recorded, contract, execution)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+                params![
+                    execution.exec_id,
+                    execution.perm_id,
+                    execution.order_id,
+                    execution.acct_number,
+                    contract.con_id,
+                    contract.symbol,
+                    execution.side,
+                    execution.shares,
+                    execution.price,
+                    execution.time,
+                    self.time(),
+                    to_json(contract)?,
+                    to_json(execution)?
+                ],
+            )
+            .map_err(sqlite_error)?;
+        Ok(inserted > 0)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Forwarded from Wrapper::commission_report
+    pub fn commission_report(
+        &self,
+        commission_report: &CommissionReport,
+    ) -> Result<(), IBKRApiLibError> {
+        let realized_pnl = if commission_report.realized_pnl == UNSET_DOUBLE {
+            None
+        } else {
+            Some(commission_report.realized_pnl)
+        };
+        self.connection
+            .execute(
+                "INSERT OR REPLACE INTO commission_reports
+                    (exec_id, commission, currency, realized_pnl, report)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    commission_report.exec_id,
+                    commission_report.commission,
+                    commission_report.currency,
+                    realized_pnl,
+                    to_json(commission_report)?
+                ],
+            )
+            .map_err(sqlite_error)?;
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Forwarded from Wrapper::position
+    pub fn position(
+        &self,
+        account: &str,
+        contract: &Contract,
+        position: f64,
+        avg_cost: f64,
+    ) -> Result<(), IBKRApiLibError> {
+        self.connection
+            .execute(
+                "INSERT INTO positions
+                    (time, account, con_id, symbol, position, avg_cost, contract)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    self.time(),
+                    account,
+                    contract.con_id,
+                    contract.symbol,
+                    position,
+                    avg_cost,
+                    to_json(contract)?
+                ],
+            )
+            .map_err(sqlite_error)?;
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Forwarded from Wrapper::account_summary
+    pub fn account_summary(
+        &self,
+        _req_id: i32,
+        account: &str,
+        tag: &str,
+        value: &str,
+        currency: &str,
+    ) -> Result<(), IBKRApiLibError> {
+        self.connection
+            .execute(
+                "INSERT INTO account_values (time, account, tag, value, currency)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![self.time(), account, tag, value, currency],
+            )
+            .map_err(sqlite_error)?;
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn stored_order(row: &Row) -> rusqlite::Result<StoredOrderRow> {
+        Ok(StoredOrderRow {
+            perm_id: row.get(0)?,
+            order_id: row.get(1)?,
+            client_id: row.get(2)?,
+            status: row.get(3)?,
+            filled: row.get(4)?,
+            remaining: row.get(5)?,
+            avg_fill_price: row.get(6)?,
+            completed: row.get(7)?,
+            contract: row.get(8)?,
+            order: row.get(9)?,
+            order_state: row.get(10)?,
+            updated: row.get(11)?,
+        })
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn query_orders(
+        &self,
+        condition: &str,
+        parameters: &[&dyn rusqlite::ToSql],
+    ) -> Result<Vec<StoredOrder>, IBKRApiLibError> {
+        let sql = format!(
+            "SELECT perm_id, order_id, client_id, status, filled, remaining, avg_fill_price,
+                completed, contract, order_json, order_state, updated
+             FROM orders WHERE {} ORDER BY perm_id",
+            condition
+        );
+        let mut statement = self
+            .connection
+            .prepare(sql.as_str())
+            .map_err(sqlite_error)?;
+        let rows = statement
+            .query_map(parameters, Self::stored_order)
+            .map_err(sqlite_error)?
+            .collect::<rusqlite::Result<Vec<StoredOrderRow>>>()
+            .map_err(sqlite_error)?;
+        rows.into_iter().map(StoredOrderRow::into_order).collect()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn order(&self, perm_id: i32) -> Result<Option<StoredOrder>, IBKRApiLibError> {
+        Ok(self
+            .query_orders("perm_id = ?1", params![perm_id])?
+            .into_iter()
+            .next())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Orders that are neither completed nor in a terminal status
+    pub fn open_orders(&self) -> Result<Vec<StoredOrder>, IBKRApiLibError> {
+        Ok(self
+            .query_orders("completed = 0", params![])?
+            .into_iter()
+            .filter(|order| !order.is_done())
+            .collect())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Status transitions of an order, oldest first
+    pub fn order_history(&self, perm_id: i32) -> Result<Vec<OrderEvent>, IBKRApiLibError> {
+        let mut statement = self
+            .connection
+            .prepare(
+                "SELECT time, perm_id, order_id, source, status, filled, remaining, avg_fill_price
+                 FROM order_events WHERE perm_id = ?1 ORDER BY id",
+            )
+            .map_err(sqlite_error)?;
+        let events = statement
+            .query_map(params![perm_id], |row| {
+                Ok(OrderEvent {
+                    time: row.get(0)?,
+                    perm_id: row.get(1)?,
+                    order_id: row.get(2)?,
+                    source: row.get(3)?,
+                    status: row.get(4)?,
+                    filled: row.get(5)?,
+                    remaining: row.get(6)?,
+                    avg_fill_price: row.get(7)?,
+                })
+            })
+            .map_err(sqlite_error)?
+            .collect::<rusqlite::Result<Vec<OrderEvent>>>()
+            .map_err(sqlite_error);
+        events
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn query_fills(
+        &self,
+        condition: &str,
+        parameters: &[&dyn rusqlite::ToSql],
+    ) -> Result<Vec<StoredFill>, IBKRApiLibError> {
+        let sql = format!(
+            "SELECT e.contract, e.execution, c.report, e.recorded
+             FROM executions e LEFT JOIN commission_reports c ON c.exec_id = e.exec_id
+             WHERE {} ORDER BY e.recorded, e.rowid",
+            condition
+        );
+        let mut statement = self
+            .connection
+            .prepare(sql.as_str())
+            .map_err(sqlite_error)?;
+        let rows = statement
+            .query_map(parameters, |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, i64>(3)?,
+                ))
+            })
+            .map_err(sqlite_error)?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(sqlite_error)?;
+        rows.into_iter()
+            .map(|(contract, execution, report, recorded)| {
+                Ok(StoredFill {
+                    contract: from_json(Some(contract))?,
+                    execution: from_json(Some(execution))?,
+                    commission_report: match report {
+                        Some(report) => Some(from_json(Some(report))?),
+                        None => None,
+                    },
+                    recorded,
+                })
+            })
+            .collect()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Executions of an order, in the order they were stored
+    pub fn fills_for_perm_id(&self, perm_id: i32) -> Result<Vec<StoredFill>, IBKRApiLibError> {
+        self.query_fills("e.perm_id = ?1", params![perm_id])
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Executions stored at or after `time`, in seconds since the epoch
+    pub fn fills_since(&self, time: i64) -> Result<Vec<StoredFill>, IBKRApiLibError> {
+        self.query_fills("e.recorded >= ?1", params![time])
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Latest position snapshot of every contract at `time`, leaving out flat positions.  All
+    /// accounts are included if `account` is None.
+    pub fn positions_at(
+        &self,
+        account: Option<&str>,
+        time: i64,
+    ) -> Result<Vec<PositionSnapshot>, IBKRApiLibError> {
+        let mut statement = self
+            .connection
+            .prepare(
+                "SELECT p.time, p.account, p.contract, p.position, p.avg_cost
+                 FROM positions p JOIN (
+                    SELECT MAX(id) AS id FROM positions WHERE time <= ?1 GROUP BY account, con_id
+                 ) latest ON latest.id = p.id
+                 WHERE p.position != 0 AND (?2 IS NULL OR p.account = ?2)
+                 ORDER BY p.account, p.con_id",
+            )
+            .map_err(sqlite_error)?;
+        let rows = statement
+            .query_map(params![time, account], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, f64>(3)?,
+                    row.get::<_, f64>(4)?,
+                ))
+            })
+            .map_err(sqlite_error)?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(sqlite_error)?;
+        rows.into_iter()
+            .map(|(time, account, contract, position, avg_cost)| {
+                Ok(PositionSnapshot {
+                    time,
+                    account,
+                    contract: from_json(Some(contract))?,
+                    position,
+                    avg_cost,
+                })
+            })
+            .collect()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Latest value of every account summary tag of an account at `time`
+    pub fn account_summary_at(
+        &self,
+        account: &str,
+        time: i64,
+    ) -> Result<Vec<AccountValue>, IBKRApiLibError> {
+        let mut statement = self
+            .connection
+            .prepare(
+                "SELECT a.time, a.account, a.tag, a.value, a.currency
+                 FROM account_values a JOIN (
+                    SELECT MAX(id) AS id FROM account_values
+                    WHERE time <= ?1 AND account = ?2 GROUP BY tag, currency
+                 ) latest ON latest.id = a.id
+                 ORDER BY a.tag, a.currency",
+            )
+            .map_err(sqlite_error)?;
+        let values = statement
+            .query_map(params![time, account], |row| {
+                Ok(AccountValue {
+                    time: row.get(0)?,
+                    account: row.get(1)?,
+                    tag: row.get(2)?,
+                    value: row.get(3)?,
+                    currency: row.get(4)?,
+                })
+            })
+            .map_err(sqlite_error)?
+            .collect::<rusqlite::Result<Vec<AccountValue>>>()
+            .map_err(sqlite_error);
+        values
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Time the last execution was stored, in seconds since the epoch
+    pub fn last_fill_time(&self) -> Result<Option<i64>, IBKRApiLibError> {
+        self.connection
+            .query_row("SELECT MAX(recorded) FROM executions", params![], |row| {
+                row.get(0)
+            })
+            .map_err(sqlite_error)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Filter for req_executions asking for the executions since the last stored one, in UTC.
+    /// Executions that were already stored are reported again and ignored by
+    /// [TradeStore::exec_details].
+    pub fn execution_filter(&self, client_id: i32) -> Result<ExecutionFilter, IBKRApiLibError> {
+        let time = match self.last_fill_time()? {
+            Some(time) => DateTime::from_timestamp(time, 0)
+                .map(|time| time.format("%Y%m%d-%H:%M:%S").to_string())
+                .unwrap_or_default(),
+            None => "".to_string(),
+        };
+        Ok(ExecutionFilter {
+            client_id,
+            time,
+            ..Default::default()
+        })
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Feeds the stored state to a wrapper to rebuild in-memory trackers on startup: the open
+    /// orders with their last status, the executions stored at or after `fills_since` with their
+    /// commission reports, and the latest positions.
+    pub fn replay<T: Wrapper>(
+        &self,
+        wrapper: &mut T,
+        fills_since: i64,
+    ) -> Result<(), IBKRApiLibError> {
+        let open_orders = self.open_orders()?;
+        info!("replaying {} open orders", open_orders.len());
+        for stored in open_orders {
+            wrapper.open_order(
+                stored.order_id,
+                stored.contract,
+                stored.order,
+                stored.order_state,
+            );
+            wrapper.order_status(
+                stored.order_id,
+                stored.status.as_str(),
+                stored.filled,
+                stored.remaining,
+                stored.avg_fill_price,
+                stored.perm_id,
+                0,
+                0.0,
+                stored.client_id,
+                "",
+                0.0,
+            );
+        }
+        wrapper.open_order_end();
+
+        let fills = self.fills_since(fills_since)?;
+        info!("replaying {} executions", fills.len());
+        for fill in fills {
+            wrapper.exec_details(REPLAY_REQ_ID, fill.contract, fill.execution);
+            if let Some(commission_report) = fill.commission_report {
+                wrapper.commission_report(commission_report);
+            }
+        }
+        wrapper.exec_details_end(REPLAY_REQ_ID);
+
+        for snapshot in self.positions_at(None, i64::MAX)? {
+            wrapper.position(
+                snapshot.account.as_str(),
+                snapshot.contract,
+                snapshot.position,
+                snapshot.avg_cost,
+            );
+        }
+        wrapper.position_end();
+        Ok(())
+    }
+}
+
+//==================================================================================================
+struct StoredOrderRow {
+    perm_id: i32,
+    order_id: i32,
+    client_id: i32,
+    status: String,
+    filled: f64,
+    remaining: f64,
+    avg_fill_price: f64,
+    completed: bool,
+    contract: Option<String>,
+    order: Option<String>,
+    order_state: Option<String>,
+    updated: i64,
+}
+
+impl StoredOrderRow {
+    fn into_order(self) -> Result<StoredOrder, IBKRApiLibError> {
+        Ok(StoredOrder {
+            perm_id: self.perm_id,
+            order_id: self.order_id,
+            client_id: self.client_id,
+            contract: from_json(self.contract)?,
+            order: from_json(self.order)?,
+            order_state: from_json(self.order_state)?,
+            status: self.status,
+            filled: self.filled,
+            remaining: self.remaining,
+            avg_fill_price: self.avg_fill_price,
+            completed: self.completed,
+            updated: self.updated,
+        })
+    }
+}
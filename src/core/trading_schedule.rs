@@ -0,0 +1,289 @@
+//! Trading sessions parsed from the trading and liquid hours of ContractDetails
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{DateTime, Duration, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+use chrono_tz::Tz;
+
+use crate::core::contract::ContractDetails;
+use crate::core::errors::{IBKRApiLibError, TwsApiReportableError, TwsError};
+
+/// Abbreviations TWS reports in time_zone_id that are not IANA names, or that name a fixed
+/// offset in the IANA database while TWS means the zone with daylight saving time
+const TIME_ZONE_ALIASES: &[(&str, &str)] = &[
+    ("EST", "America/New_York"),
+    ("EDT", "America/New_York"),
+    ("CST", "America/Chicago"),
+    ("CDT", "America/Chicago"),
+    ("MST", "America/Denver"),
+    ("MDT", "America/Denver"),
+    ("PST", "America/Los_Angeles"),
+    ("PDT", "America/Los_Angeles"),
+    ("JST", "Asia/Tokyo"),
+    ("HKT", "Asia/Hong_Kong"),
+    ("AEST", "Australia/Sydney"),
+    ("AEDT", "Australia/Sydney"),
+];
+
+//==================================================================================================
+fn schedule_error(description: String) -> IBKRApiLibError {
+    IBKRApiLibError::ApiError(TwsApiReportableError::new(
+        -1,
+        TwsError::BadMessage.code().to_string(),
+        description,
+    ))
+}
+
+//==================================================================================================
+/// Time zone for a time_zone_id as reported by TWS.  Accepts IANA names such as "US/Eastern",
+/// the abbreviations in [TIME_ZONE_ALIASES] and the older "EST (Eastern Standard Time)" form.
+pub fn parse_time_zone(time_zone_id: &str) -> Result<Tz, IBKRApiLibError> {
+    let name = time_zone_id.split_whitespace().next().unwrap_or("");
+    let name = TIME_ZONE_ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == name)
+        .map_or(name, |(_, zone)| *zone);
+    Tz::from_str(name)
+        .map_err(|_| schedule_error(format!("Unknown time zone id '{}'", time_zone_id)))
+}
+
+//==================================================================================================
+fn parse_date(date: &str, hours: &str) -> Result<NaiveDate, IBKRApiLibError> {
+    NaiveDate::parse_from_str(date, "%Y%m%d")
+        .map_err(|_| schedule_error(format!("Invalid date '{}' in hours '{}'", date, hours)))
+}
+
+//==================================================================================================
+fn parse_time(time: &str, hours: &str) -> Result<NaiveTime, IBKRApiLibError> {
+    // 2400 is used for the end of the day
+    if time == "2400" {
+        return Ok(NaiveTime::from_hms_opt(0, 0, 0).unwrap());
+    }
+    NaiveTime::parse_from_str(time, "%H%M")
+        .map_err(|_| schedule_error(format!("Invalid time '{}' in hours '{}'", time, hours)))
+}
+
+//==================================================================================================
+/// Parses one end of a range, either "HHMM" on `date` or "YYYYMMDD:HHMM"
+fn parse_date_time(
+    value: &str,
+    date: NaiveDate,
+    hours: &str,
+) -> Result<NaiveDateTime, IBKRApiLibError> {
+    match value.split_once(':') {
+        Some((date, time)) => {
+            let date_time = parse_date(date, hours)?.and_time(parse_time(time, hours)?);
+            if time == "2400" {
+                Ok(date_time + Duration::days(1))
+            } else {
+                Ok(date_time)
+            }
+        }
+        None => Ok(date.and_time(parse_time(value, hours)?)),
+    }
+}
+
+//==================================================================================================
+/// Local time in a zone.  Times in a daylight saving gap are moved past it, ambiguous times
+/// take the earlier instant.
+fn localize(time_zone: &Tz, local: NaiveDateTime) -> DateTime<Tz> {
+    match time_zone.from_local_datetime(&local) {
+        LocalResult::Single(time) => time,
+        LocalResult::Ambiguous(earliest, _) => earliest,
+        LocalResult::None => localize(time_zone, local + Duration::minutes(30)),
+    }
+}
+
+//==================================================================================================
+/// A period during which a contract trades, in the exchange's time zone
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TradingSession {
+    pub start: DateTime<Tz>,
+    pub end: DateTime<Tz>,
+}
+
+impl TradingSession {
+    pub fn new(start: DateTime<Tz>, end: DateTime<Tz>) -> Self {
+        TradingSession { start, end }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// True from the start of the session up to, but not including, its end
+    pub fn contains<Z: TimeZone>(&self, time: &DateTime<Z>) -> bool {
+        self.start <= *time && self.end > *time
+    }
+}
+
+impl fmt::Display for TradingSession {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} - {}",
+            self.start.format("%Y%m%d %H:%M %Z"),
+            self.end.format("%Y%m%d %H:%M %Z")
+        )
+    }
+}
+
+//==================================================================================================
+/// Parses hours such as "20200101:0930-20200101:1600;20200102:CLOSED" into sessions sorted by
+/// start, with overlapping or adjacent sessions merged.  The older "20090507:0700-1830,1830-2330"
+/// form is accepted too; a range whose end is not after its start closes the next day.
+pub fn parse_sessions(hours: &str, time_zone: &Tz) -> Result<Vec<TradingSession>, IBKRApiLibError> {
+    let mut sessions = vec![];
+    for day in hours
+        .split(';')
+        .map(str::trim)
+        .filter(|day| !day.is_empty())
+    {
+        let (date, ranges) = day
+            .split_once(':')
+            .ok_or_else(|| schedule_error(format!("Invalid trading day '{}'", day)))?;
+        let date = parse_date(date, hours)?;
+        if ranges == "CLOSED" {
+            continue;
+        }
+        for range in ranges.split(',') {
+            let (start, end) = range
+                .split_once('-')
+                .ok_or_else(|| schedule_error(format!("Invalid session '{}'", range)))?;
+            let start = parse_date_time(start, date, hours)?;
+            let mut end = parse_date_time(end, start.date(), hours)?;
+            if end <= start {
+                end += Duration::days(1);
+            }
+            sessions.push(TradingSession::new(
+                localize(time_zone, start),
+                localize(time_zone, end),
+            ));
+        }
+    }
+    sessions.sort_by_key(|session| session.start);
+
+    let mut merged: Vec<TradingSession> = vec![];
+    for session in sessions {
+        match merged.last_mut() {
+            Some(last) if session.start <= last.end => {
+                if session.end > last.end {
+                    last.end = session.end;
+                }
+            }
+            _ => merged.push(session),
+        }
+    }
+    Ok(merged)
+}
+
+//==================================================================================================
+/// Trading calendar of a contract built from ContractDetails::trading_hours, liquid_hours and
+/// time_zone_id.  TWS sends only the next few days, so the schedule goes stale and should be
+/// rebuilt from fresh contract details.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TradingSchedule {
+    pub time_zone: Tz,
+    pub trading_sessions: Vec<TradingSession>,
+    pub liquid_sessions: Vec<TradingSession>,
+}
+
+impl TradingSchedule {
+    pub fn new(
+        trading_hours: &str,
+        liquid_hours: &str,
+        time_zone_id: &str,
+    ) -> Result<Self, IBKRApiLibError> {
+        let time_zone = parse_time_zone(time_zone_id)?;
+        Ok(TradingSchedule {
+            trading_sessions: parse_sessions(trading_hours, &time_zone)?,
+            liquid_sessions: parse_sessions(liquid_hours, &time_zone)?,
+            time_zone,
+        })
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn from_contract_details(details: &ContractDetails) -> Result<Self, IBKRApiLibError> {
+        TradingSchedule::new(
+            details.trading_hours.as_str(),
+            details.liquid_hours.as_str(),
+            details.time_zone_id.as_str(),
+        )
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The trading session in progress at `now`
+    pub fn current_session<Z: TimeZone>(&self, now: &DateTime<Z>) -> Option<&TradingSession> {
+        self.trading_sessions
+            .iter()
+            .find(|session| session.contains(now))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// True if the contract trades at `now`, including outside regular trading hours
+    pub fn is_open<Z: TimeZone>(&self, now: &DateTime<Z>) -> bool {
+        self.current_session(now).is_some()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// True if `now` is within the liquid (regular trading) hours.  Orders that should fill
+    /// at other times while the contract is open need outside_rth set.
+    pub fn is_rth<Z: TimeZone>(&self, now: &DateTime<Z>) -> bool {
+        self.liquid_sessions
+            .iter()
+            .any(|session| session.contains(now))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Start of the first trading session after `now`, None past the end of the schedule
+    pub fn next_open<Z: TimeZone>(&self, now: &DateTime<Z>) -> Option<DateTime<Tz>> {
+        self.trading_sessions
+            .iter()
+            .find(|session| session.start > *now)
+            .map(|session| session.start)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// End of the session in progress at `now`, or of the next one if the contract is closed
+    pub fn next_close<Z: TimeZone>(&self, now: &DateTime<Z>) -> Option<DateTime<Tz>> {
+        self.trading_sessions
+            .iter()
+            .find(|session| session.end > *now)
+            .map(|session| session.end)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Start of the first liquid session after `now`
+    pub fn next_rth_open<Z: TimeZone>(&self, now: &DateTime<Z>) -> Option<DateTime<Tz>> {
+        self.liquid_sessions
+            .iter()
+            .find(|session| session.start > *now)
+            .map(|session| session.start)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// End of the liquid session in progress at `now`, or of the next one
+    pub fn next_rth_close<Z: TimeZone>(&self, now: &DateTime<Z>) -> Option<DateTime<Tz>> {
+        self.liquid_sessions
+            .iter()
+            .find(|session| session.end > *now)
+            .map(|session| session.end)
+    }
+}
+
+impl fmt::Display for TradingSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "time_zone: {}, trading_sessions: [{}], liquid_sessions: [{}]",
+            self.time_zone.name(),
+            self.trading_sessions
+                .iter()
+                .map(|session| session.to_string())
+                .collect::<Vec<String>>()
+                .join("; "),
+            self.liquid_sessions
+                .iter()
+                .map(|session| session.to_string())
+                .collect::<Vec<String>>()
+                .join("; ")
+        )
+    }
+}
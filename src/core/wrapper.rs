@@ -13,6 +13,7 @@ use crate::core::common::{
 use crate::core::contract::{Contract, ContractDescription, ContractDetails, DeltaNeutralContract};
 use crate::core::execution::Execution;
 use crate::core::order::{Order, OrderState, SoftDollarTier};
+use crate::core::tick_parsers::{DividendInfo, FundamentalRatios, RtVolume};
 
 /// A trait that clients will implement that declares callback functions that get called when the application receives messages from the Trader WorkStation or IB Gateway
 pub trait Wrapper: Send + Sync {
@@ -56,6 +57,49 @@ pub trait Wrapper: Send + Sync {
     //----------------------------------------------------------------------------------------------
     fn tick_string(&mut self, req_id: i32, tick_type: TickType, value: &str);
 
+    //----------------------------------------------------------------------------------------------
+    /// Parsed RTVolume data.  Called instead of tick_string for the RtVolume and RtTrdVolume
+    /// tick types.  By default the raw value is passed on to tick_string.
+    ///
+    /// # Arguments
+    /// * req_id - The request's identifier.
+    /// * tick_type - Either RtVolume or RtTrdVolume.
+    /// * rt_volume - The last trade price, size and time along with the day's volume and VWAP.
+    /// * value - The tick string rt_volume was parsed from.
+    fn tick_rt_volume(
+        &mut self,
+        req_id: i32,
+        tick_type: TickType,
+        _rt_volume: RtVolume,
+        value: &str,
+    ) {
+        self.tick_string(req_id, tick_type, value);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Parsed fundamental ratios.  Called instead of tick_string for the FundamentalRatios
+    /// tick type (generic tick 258).  By default the raw value is passed on to tick_string.
+    ///
+    /// # Arguments
+    /// * req_id - The request's identifier.
+    /// * ratios - The ratios, by their TWS names.
+    /// * value - The tick string ratios was parsed from.
+    fn tick_fundamental_ratios(&mut self, req_id: i32, _ratios: FundamentalRatios, value: &str) {
+        self.tick_string(req_id, TickType::FundamentalRatios, value);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Parsed dividend information.  Called instead of tick_string for the IbDividends
+    /// tick type (generic tick 456).  By default the raw value is passed on to tick_string.
+    ///
+    /// # Arguments
+    /// * req_id - The request's identifier.
+    /// * dividends - Dividends of the past and next 12 months, and date and amount of the next one.
+    /// * value - The tick string dividends was parsed from.
+    fn tick_dividends(&mut self, req_id: i32, _dividends: DividendInfo, value: &str) {
+        self.tick_string(req_id, TickType::IbDividends, value);
+    }
+
     //----------------------------------------------------------------------------------------------
     /// market data call back for Exchange for Physical
     ///
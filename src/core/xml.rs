@@ -0,0 +1,46 @@
+//! Helpers for walking the XML documents TWS sends (fundamental data, scanner parameters, FA configuration)
+use roxmltree::Node;
+
+//==================================================================================================
+/// Element children of a node, skipping text and comments
+pub(crate) fn children<'a, 'input>(
+    node: Node<'a, 'input>,
+) -> impl Iterator<Item = Node<'a, 'input>> {
+    node.children().filter(|child| child.is_element())
+}
+
+//==================================================================================================
+/// First element child with the given tag name
+pub(crate) fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    children(node).find(|child| child.tag_name().name() == name)
+}
+
+//==================================================================================================
+/// Trimmed text of the named child, or an empty string if there is no such child
+pub(crate) fn child_text(node: Node, name: &str) -> String {
+    child(node, name).map(text).unwrap_or_default()
+}
+
+//==================================================================================================
+/// Trimmed text of a node
+pub(crate) fn text(node: Node) -> String {
+    node.text().unwrap_or("").trim().to_string()
+}
+
+//==================================================================================================
+/// Value of an attribute, or an empty string if it is missing
+pub(crate) fn attribute(node: Node, name: &str) -> String {
+    node.attribute(name).unwrap_or("").to_string()
+}
+
+//==================================================================================================
+/// Slash separated element names from the document root down to the node, used in error messages
+pub(crate) fn node_path(node: Node) -> String {
+    let mut names = node
+        .ancestors()
+        .filter(|ancestor| ancestor.is_element())
+        .map(|ancestor| ancestor.tag_name().name().to_string())
+        .collect::<Vec<String>>();
+    names.reverse();
+    names.join("/")
+}
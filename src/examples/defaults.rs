@@ -17,6 +17,7 @@ use crate::core::common::{
 use crate::core::contract::{Contract, ContractDescription, ContractDetails, DeltaNeutralContract};
 use crate::core::execution::Execution;
 use crate::core::order::{Order, OrderState, SoftDollarTier};
+use crate::core::tick_parsers::{DividendInfo, FundamentalRatios, RtVolume};
 use crate::core::wrapper::Wrapper;
 
 //==================================================================================================
@@ -91,6 +92,36 @@ impl Wrapper for DefaultWrapper {
         );
     }
 
+    //----------------------------------------------------------------------------------------------
+    fn tick_rt_volume(
+        &mut self,
+        req_id: i32,
+        tick_type: TickType,
+        rt_volume: RtVolume,
+        _value: &str,
+    ) {
+        info!(
+            "tick_rt_volume -- req_id: {}, tick_type: {}, rt_volume: {}",
+            req_id, tick_type, rt_volume
+        );
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn tick_fundamental_ratios(&mut self, req_id: i32, ratios: FundamentalRatios, _value: &str) {
+        info!(
+            "tick_fundamental_ratios -- req_id: {}, ratios: {}",
+            req_id, ratios
+        );
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn tick_dividends(&mut self, req_id: i32, dividends: DividendInfo, _value: &str) {
+        info!(
+            "tick_dividends -- req_id: {}, dividends: {}",
+            req_id, dividends
+        );
+    }
+
     //----------------------------------------------------------------------------------------------
     fn tick_efp(
         &mut self,
@@ -25,6 +25,7 @@ use crate::{
             fill_twap_params, fill_vwap_params,
         },
         streamer::Streamer,
+        tick_parsers::{DividendInfo, FundamentalRatios, RtVolume},
     },
     examples::{
         contract_samples, fa_allocation_samples, order_samples, scanner_subscription_samples,
@@ -3076,6 +3077,36 @@ where
         );
     }
 
+    //----------------------------------------------------------------------------------------------
+    fn tick_rt_volume(
+        &mut self,
+        req_id: i32,
+        tick_type: TickType,
+        rt_volume: RtVolume,
+        _value: &str,
+    ) {
+        info!(
+            "tick_rt_volume -- req_id: {}, tick_type: {}, rt_volume: {}",
+            req_id, tick_type, rt_volume
+        );
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn tick_fundamental_ratios(&mut self, req_id: i32, ratios: FundamentalRatios, _value: &str) {
+        info!(
+            "tick_fundamental_ratios -- req_id: {}, ratios: {}",
+            req_id, ratios
+        );
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn tick_dividends(&mut self, req_id: i32, dividends: DividendInfo, _value: &str) {
+        info!(
+            "tick_dividends -- req_id: {}, dividends: {}",
+            req_id, dividends
+        );
+    }
+
     //----------------------------------------------------------------------------------------------
     fn tick_efp(
         &mut self,
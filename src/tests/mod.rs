@@ -1,2 +1,28 @@
+#[cfg(test)]
+pub(crate) mod recording_wrapper;
+pub(crate) mod test_algo_strategy;
+pub(crate) mod test_backtester;
+pub(crate) mod test_client_handle;
+pub(crate) mod test_client_pool;
+pub(crate) mod test_combo;
+pub(crate) mod test_condition_expression;
+pub(crate) mod test_contract_cache;
 pub(crate) mod test_eclient;
+pub(crate) mod test_event_bus;
+pub(crate) mod test_financial_advisor;
+pub(crate) mod test_fundamentals;
+pub(crate) mod test_market_rules;
 pub(crate) mod test_messages;
+pub(crate) mod test_metrics;
+pub(crate) mod test_news;
+pub(crate) mod test_option_chain;
+pub(crate) mod test_option_pricing;
+pub(crate) mod test_order_group;
+pub(crate) mod test_request_spans;
+pub(crate) mod test_risk_gate;
+pub(crate) mod test_scanner_catalog;
+pub(crate) mod test_series_store;
+pub(crate) mod test_sim_broker;
+pub(crate) mod test_tick_parsers;
+pub(crate) mod test_trade_store;
+pub(crate) mod test_trading_schedule;
@@ -0,0 +1,693 @@
+//! Wrapper that records the callbacks it receives, for tests of components that drive a Wrapper
+use std::collections::HashSet;
+
+use bigdecimal::BigDecimal;
+
+use crate::core::common::{
+    BarData, CommissionReport, DepthMktDataDescription, FaDataType, FamilyCode, HistogramData,
+    HistoricalTick, HistoricalTickBidAsk, HistoricalTickLast, NewsProvider, PriceIncrement,
+    RealTimeBar, SmartComponent, TickAttrib, TickAttribBidAsk, TickAttribLast, TickByTickType,
+    TickType,
+};
+use crate::core::contract::{Contract, ContractDescription, ContractDetails, DeltaNeutralContract};
+use crate::core::execution::Execution;
+use crate::core::order::{Order, OrderState, SoftDollarTier};
+use crate::core::wrapper::Wrapper;
+
+//==================================================================================================
+/// Records the name of every callback in `calls` and the arguments of the commonly tested ones
+#[derive(Default)]
+pub struct RecordingWrapper {
+    /// Called from historical_data_update, e.g. to place orders like a strategy would
+    pub on_bar: Option<Box<dyn FnMut(i32, &BarData) + Send + Sync>>,
+    pub calls: Vec<String>,
+    pub errors: Vec<(i32, i32, String)>,
+    /// order_id, status, filled, remaining, avg_fill_price
+    pub order_statuses: Vec<(i32, String, f64, f64, f64)>,
+    /// order_id, order state status
+    pub open_orders: Vec<(i32, String)>,
+    pub executions: Vec<Execution>,
+    pub commission_reports: Vec<CommissionReport>,
+    /// account, con_id, position, avg_cost
+    pub positions: Vec<(String, i32, f64, f64)>,
+    /// con_id, position, market_price, realized_pnl
+    pub portfolio: Vec<(i32, f64, f64, f64)>,
+    pub tick_prices: Vec<(i32, TickType, f64)>,
+    /// Includes the composite ticks, which reach tick_string through the defaults of the typed
+    /// callbacks
+    pub tick_strings: Vec<(i32, TickType, String)>,
+    pub realtime_bars: Vec<(i32, RealTimeBar)>,
+    pub bars: Vec<(i32, BarData)>,
+    /// req_id, time, price
+    pub last_ticks: Vec<(i32, i64, f64)>,
+    /// req_id, time, bid, ask
+    pub bid_ask_ticks: Vec<(i32, i64, f64, f64)>,
+    pub current_times: Vec<i64>,
+}
+
+impl RecordingWrapper {
+    pub fn new() -> Self {
+        RecordingWrapper::default()
+    }
+
+    /// Number of times a callback was received
+    pub fn count(&self, callback: &str) -> usize {
+        self.calls.iter().filter(|call| *call == callback).count()
+    }
+}
+
+impl Wrapper for RecordingWrapper {
+    fn error(&mut self, req_id: i32, error_code: i32, error_string: &str) {
+        self.calls.push("error".to_string());
+        self.errors
+            .push((req_id, error_code, error_string.to_string()));
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn win_error(&mut self, _text: &str, _last_error: i32) {
+        self.calls.push("win_error".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn connect_ack(&mut self) {
+        self.calls.push("connect_ack".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn market_data_type(&mut self, _req_id: i32, _market_data_type: i32) {
+        self.calls.push("market_data_type".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn tick_price(&mut self, req_id: i32, tick_type: TickType, price: f64, _attrib: TickAttrib) {
+        self.calls.push("tick_price".to_string());
+        self.tick_prices.push((req_id, tick_type, price));
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn tick_size(&mut self, _req_id: i32, _tick_type: TickType, _size: i32) {
+        self.calls.push("tick_size".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn tick_snapshot_end(&mut self, _req_id: i32) {
+        self.calls.push("tick_snapshot_end".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn tick_generic(&mut self, _req_id: i32, _tick_type: TickType, _value: f64) {
+        self.calls.push("tick_generic".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn tick_string(&mut self, req_id: i32, tick_type: TickType, value: &str) {
+        self.calls.push("tick_string".to_string());
+        self.tick_strings
+            .push((req_id, tick_type, value.to_string()));
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn tick_efp(
+        &mut self,
+        _req_id: i32,
+        _tick_type: TickType,
+        _basis_points: f64,
+        _formatted_basis_points: &str,
+        _total_dividends: f64,
+        _hold_days: i32,
+        _future_last_trade_date: &str,
+        _dividend_impact: f64,
+        _dividends_to_last_trade_date: f64,
+    ) {
+        self.calls.push("tick_efp".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn order_status(
+        &mut self,
+        order_id: i32,
+        status: &str,
+        filled: f64,
+        remaining: f64,
+        avg_fill_price: f64,
+        _perm_id: i32,
+        _parent_id: i32,
+        _last_fill_price: f64,
+        _client_id: i32,
+        _why_held: &str,
+        _mkt_cap_price: f64,
+    ) {
+        self.calls.push("order_status".to_string());
+        self.order_statuses.push((
+            order_id,
+            status.to_string(),
+            filled,
+            remaining,
+            avg_fill_price,
+        ));
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn open_order(
+        &mut self,
+        order_id: i32,
+        _contract: Contract,
+        _order: Order,
+        order_state: OrderState,
+    ) {
+        self.calls.push("open_order".to_string());
+        self.open_orders.push((order_id, order_state.status));
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn open_order_end(&mut self) {
+        self.calls.push("open_order_end".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn connection_closed(&mut self) {
+        self.calls.push("connection_closed".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn update_account_value(
+        &mut self,
+        _key: &str,
+        _val: &str,
+        _currency: &str,
+        _account_name: &str,
+    ) {
+        self.calls.push("update_account_value".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn update_portfolio(
+        &mut self,
+        contract: Contract,
+        position: f64,
+        market_price: f64,
+        _market_value: f64,
+        _average_cost: f64,
+        _unrealized_pnl: f64,
+        realized_pnl: f64,
+        _account_name: &str,
+    ) {
+        self.calls.push("update_portfolio".to_string());
+        self.portfolio
+            .push((contract.con_id, position, market_price, realized_pnl));
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn update_account_time(&mut self, _time_stamp: &str) {
+        self.calls.push("update_account_time".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn account_download_end(&mut self, _account_name: &str) {
+        self.calls.push("account_download_end".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn next_valid_id(&mut self, _order_id: i32) {
+        self.calls.push("next_valid_id".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn contract_details(&mut self, _req_id: i32, _contract_details: ContractDetails) {
+        self.calls.push("contract_details".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn bond_contract_details(&mut self, _req_id: i32, _contract_details: ContractDetails) {
+        self.calls.push("bond_contract_details".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn contract_details_end(&mut self, _req_id: i32) {
+        self.calls.push("contract_details_end".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn exec_details(&mut self, _req_id: i32, _contract: Contract, execution: Execution) {
+        self.calls.push("exec_details".to_string());
+        self.executions.push(execution);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn exec_details_end(&mut self, _req_id: i32) {
+        self.calls.push("exec_details_end".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn update_mkt_depth(
+        &mut self,
+        _req_id: i32,
+        _position: i32,
+        _operation: i32,
+        _side: i32,
+        _price: f64,
+        _size: i32,
+    ) {
+        self.calls.push("update_mkt_depth".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn update_mkt_depth_l2(
+        &mut self,
+        _req_id: i32,
+        _position: i32,
+        _market_maker: &str,
+        _operation: i32,
+        _side: i32,
+        _price: f64,
+        _size: i32,
+        _is_smart_depth: bool,
+    ) {
+        self.calls.push("update_mkt_depth_l2".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn update_news_bulletin(
+        &mut self,
+        _msg_id: i32,
+        _msg_type: i32,
+        _news_message: &str,
+        _origin_exch: &str,
+    ) {
+        self.calls.push("update_news_bulletin".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn managed_accounts(&mut self, _accounts_list: &str) {
+        self.calls.push("managed_accounts".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn receive_fa(&mut self, _fa_data: FaDataType, _cxml: &str) {
+        self.calls.push("receive_fa".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn historical_data(&mut self, _req_id: i32, _bar: BarData) {
+        self.calls.push("historical_data".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn historical_data_end(&mut self, _req_id: i32, _start: &str, _end: &str) {
+        self.calls.push("historical_data_end".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn scanner_parameters(&mut self, _xml: &str) {
+        self.calls.push("scanner_parameters".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn scanner_data(
+        &mut self,
+        _req_id: i32,
+        _rank: i32,
+        _contract_details: ContractDetails,
+        _distance: &str,
+        _benchmark: &str,
+        _projection: &str,
+        _legs_str: &str,
+    ) {
+        self.calls.push("scanner_data".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn scanner_data_end(&mut self, _req_id: i32) {
+        self.calls.push("scanner_data_end".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn realtime_bar(&mut self, req_id: i32, bar: RealTimeBar) {
+        self.calls.push("realtime_bar".to_string());
+        self.realtime_bars.push((req_id, bar));
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn current_time(&mut self, time: i64) {
+        self.calls.push("current_time".to_string());
+        self.current_times.push(time);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn fundamental_data(&mut self, _req_id: i32, _data: &str) {
+        self.calls.push("fundamental_data".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn delta_neutral_validation(
+        &mut self,
+        _req_id: i32,
+        _delta_neutral_contract: DeltaNeutralContract,
+    ) {
+        self.calls.push("delta_neutral_validation".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn commission_report(&mut self, commission_report: CommissionReport) {
+        self.calls.push("commission_report".to_string());
+        self.commission_reports.push(commission_report);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn position(&mut self, account: &str, contract: Contract, position: f64, avg_cost: f64) {
+        self.calls.push("position".to_string());
+        self.positions
+            .push((account.to_string(), contract.con_id, position, avg_cost));
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn position_end(&mut self) {
+        self.calls.push("position_end".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn account_summary(
+        &mut self,
+        _req_id: i32,
+        _account: &str,
+        _tag: &str,
+        _value: &str,
+        _currency: &str,
+    ) {
+        self.calls.push("account_summary".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn account_summary_end(&mut self, _req_id: i32) {
+        self.calls.push("account_summary_end".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn verify_message_api(&mut self, _api_data: &str) {
+        self.calls.push("verify_message_api".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn verify_completed(&mut self, _is_successful: bool, _error_text: &str) {
+        self.calls.push("verify_completed".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn verify_and_auth_message_api(&mut self, _api_data: &str, _xyz_challange: &str) {
+        self.calls.push("verify_and_auth_message_api".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn verify_and_auth_completed(&mut self, _is_successful: bool, _error_text: &str) {
+        self.calls.push("verify_and_auth_completed".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn display_group_list(&mut self, _req_id: i32, _groups: &str) {
+        self.calls.push("display_group_list".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn display_group_updated(&mut self, _req_id: i32, _contract_info: &str) {
+        self.calls.push("display_group_updated".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn position_multi(
+        &mut self,
+        _req_id: i32,
+        _account: &str,
+        _model_code: &str,
+        _contract: Contract,
+        _pos: f64,
+        _avg_cost: f64,
+    ) {
+        self.calls.push("position_multi".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn position_multi_end(&mut self, _req_id: i32) {
+        self.calls.push("position_multi_end".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn account_update_multi(
+        &mut self,
+        _req_id: i32,
+        _account: &str,
+        _model_code: &str,
+        _key: &str,
+        _value: &str,
+        _currency: &str,
+    ) {
+        self.calls.push("account_update_multi".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn account_update_multi_end(&mut self, _req_id: i32) {
+        self.calls.push("account_update_multi_end".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn tick_option_computation(
+        &mut self,
+        _req_id: i32,
+        _tick_type: TickType,
+        _implied_vol: f64,
+        _delta: f64,
+        _opt_price: f64,
+        _pv_dividend: f64,
+        _gamma: f64,
+        _vega: f64,
+        _theta: f64,
+        _und_price: f64,
+    ) {
+        self.calls.push("tick_option_computation".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn security_definition_option_parameter(
+        &mut self,
+        _req_id: i32,
+        _exchange: &str,
+        _underlying_con_id: i32,
+        _trading_class: &str,
+        _multiplier: &str,
+        _expirations: HashSet<String>,
+        _strikes: HashSet<BigDecimal>,
+    ) {
+        self.calls
+            .push("security_definition_option_parameter".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn security_definition_option_parameter_end(&mut self, _req_id: i32) {
+        self.calls
+            .push("security_definition_option_parameter_end".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn soft_dollar_tiers(&mut self, _req_id: i32, _tiers: Vec<SoftDollarTier>) {
+        self.calls.push("soft_dollar_tiers".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn family_codes(&mut self, _family_codes: Vec<FamilyCode>) {
+        self.calls.push("family_codes".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn symbol_samples(&mut self, _req_id: i32, _contract_descriptions: Vec<ContractDescription>) {
+        self.calls.push("symbol_samples".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn mkt_depth_exchanges(&mut self, _depth_mkt_data_descriptions: Vec<DepthMktDataDescription>) {
+        self.calls.push("mkt_depth_exchanges".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn tick_news(
+        &mut self,
+        _ticker_id: i32,
+        _time_stamp: i32,
+        _provider_code: &str,
+        _article_id: &str,
+        _headline: &str,
+        _extra_data: &str,
+    ) {
+        self.calls.push("tick_news".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn smart_components(&mut self, _req_id: i32, _smart_components: Vec<SmartComponent>) {
+        self.calls.push("smart_components".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn tick_req_params(
+        &mut self,
+        _ticker_id: i32,
+        _min_tick: f64,
+        _bbo_exchange: &str,
+        _snapshot_permissions: i32,
+    ) {
+        self.calls.push("tick_req_params".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn news_providers(&mut self, _news_providers: Vec<NewsProvider>) {
+        self.calls.push("news_providers".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn news_article(&mut self, _request_id: i32, _article_type: i32, _article_text: &str) {
+        self.calls.push("news_article".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn historical_news(
+        &mut self,
+        _request_id: i32,
+        _time: &str,
+        _provider_code: &str,
+        _article_id: &str,
+        _headline: &str,
+    ) {
+        self.calls.push("historical_news".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn historical_news_end(&mut self, _request_id: i32, _has_more: bool) {
+        self.calls.push("historical_news_end".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn head_timestamp(&mut self, _req_id: i32, _head_timestamp: &str) {
+        self.calls.push("head_timestamp".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn histogram_data(&mut self, _req_id: i32, _items: Vec<HistogramData>) {
+        self.calls.push("histogram_data".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn historical_data_update(&mut self, req_id: i32, bar: BarData) {
+        self.calls.push("historical_data_update".to_string());
+        if let Some(on_bar) = self.on_bar.as_mut() {
+            on_bar(req_id, &bar);
+        }
+        self.bars.push((req_id, bar));
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn reroute_mkt_data_req(&mut self, _req_id: i32, _con_id: i32, _exchange: &str) {
+        self.calls.push("reroute_mkt_data_req".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn reroute_mkt_depth_req(&mut self, _req_id: i32, _con_id: i32, _exchange: &str) {
+        self.calls.push("reroute_mkt_depth_req".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn market_rule(&mut self, _market_rule_id: i32, _price_increments: Vec<PriceIncrement>) {
+        self.calls.push("market_rule".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn pnl(&mut self, _req_id: i32, _daily_pn_l: f64, _unrealized_pn_l: f64, _realized_pn_l: f64) {
+        self.calls.push("pnl".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn pnl_single(
+        &mut self,
+        _req_id: i32,
+        _pos: i32,
+        _daily_pn_l: f64,
+        _unrealized_pn_l: f64,
+        _realized_pn_l: f64,
+        _value: f64,
+    ) {
+        self.calls.push("pnl_single".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn historical_ticks(&mut self, _req_id: i32, _ticks: Vec<HistoricalTick>, _done: bool) {
+        self.calls.push("historical_ticks".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn historical_ticks_bid_ask(
+        &mut self,
+        _req_id: i32,
+        _ticks: Vec<HistoricalTickBidAsk>,
+        _done: bool,
+    ) {
+        self.calls.push("historical_ticks_bid_ask".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn historical_ticks_last(
+        &mut self,
+        _req_id: i32,
+        _ticks: Vec<HistoricalTickLast>,
+        _done: bool,
+    ) {
+        self.calls.push("historical_ticks_last".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn tick_by_tick_all_last(
+        &mut self,
+        req_id: i32,
+        _tick_type: TickByTickType,
+        time: i64,
+        price: f64,
+        _size: i32,
+        _tick_attrib_last: TickAttribLast,
+        _exchange: &str,
+        _special_conditions: &str,
+    ) {
+        self.calls.push("tick_by_tick_all_last".to_string());
+        self.last_ticks.push((req_id, time, price));
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn tick_by_tick_bid_ask(
+        &mut self,
+        req_id: i32,
+        time: i64,
+        bid_price: f64,
+        ask_price: f64,
+        _bid_size: i32,
+        _ask_size: i32,
+        _tick_attrib_bid_ask: TickAttribBidAsk,
+    ) {
+        self.calls.push("tick_by_tick_bid_ask".to_string());
+        self.bid_ask_ticks
+            .push((req_id, time, bid_price, ask_price));
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn tick_by_tick_mid_point(&mut self, _req_id: i32, _time: i64, _mid_point: f64) {
+        self.calls.push("tick_by_tick_mid_point".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn order_bound(&mut self, _req_id: i32, _api_client_id: i32, _api_order_id: i32) {
+        self.calls.push("order_bound".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn completed_order(&mut self, _contract: Contract, _order: Order, _order_state: OrderState) {
+        self.calls.push("completed_order".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn completed_orders_end(&mut self) {
+        self.calls.push("completed_orders_end".to_string());
+    }
+}
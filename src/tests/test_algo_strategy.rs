@@ -0,0 +1,121 @@
+#[cfg(test)]
+mod tests {
+    use crate::core::algo_params::{fill_arrival_price_params, fill_vwap_params};
+    use crate::core::algo_strategy::{
+        AdaptivePriority, AlgoStrategy, RiskAversion, TwapStrategyType,
+    };
+    use crate::core::errors::IBKRApiLibError;
+    use crate::core::order::Order;
+
+    #[test]
+    fn test_apply_and_decode() -> Result<(), IBKRApiLibError> {
+        let strategy = AlgoStrategy::Vwap {
+            max_pct_vol: 0.2,
+            start_time: "09:00:00 US/Eastern".to_string(),
+            end_time: "20261020-16:00:00 US/Eastern".to_string(),
+            allow_past_end_time: true,
+            no_take_liq: false,
+            monetary_value: 100000.0,
+        };
+        let mut order = Order::default();
+        strategy.apply(&mut order)?;
+        assert_eq!("Vwap", order.algo_strategy);
+        assert_eq!(6, order.algo_params.len());
+        assert_eq!("maxPctVol", order.algo_params[0].tag);
+        assert_eq!("0.2", order.algo_params[0].value);
+        assert_eq!(Some(strategy), AlgoStrategy::from_order(&order)?);
+
+        let strategy = AlgoStrategy::Twap {
+            strategy_type: TwapStrategyType::MatchingMidpoint,
+            start_time: String::new(),
+            end_time: String::new(),
+            allow_past_end_time: false,
+            monetary_value: 0.0,
+        };
+        strategy.apply(&mut order)?;
+        assert_eq!("Twap", order.algo_strategy);
+        assert_eq!("Matching Midpoint", order.algo_params[0].value);
+        assert_eq!(Some(strategy), AlgoStrategy::from_order(&order)?);
+
+        assert_eq!(None, AlgoStrategy::from_order(&Order::default())?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_fill_params() -> Result<(), IBKRApiLibError> {
+        let mut order = Order::default();
+        fill_arrival_price_params(
+            &mut order,
+            0.1,
+            "Aggressive",
+            "09:00:00 CET",
+            "16:00:00 CET",
+            true,
+            true,
+            100000,
+        );
+        match AlgoStrategy::from_order(&order)? {
+            Some(AlgoStrategy::ArrivalPx {
+                max_pct_vol,
+                risk_aversion,
+                force_completion,
+                allow_past_end_time,
+                monetary_value,
+                ..
+            }) => {
+                assert_eq!(0.1, max_pct_vol);
+                assert_eq!(RiskAversion::Aggressive, risk_aversion);
+                assert!(force_completion);
+                assert!(allow_past_end_time);
+                assert_eq!(100000.0, monetary_value);
+            }
+            other => panic!("unexpected strategy {:?}", other),
+        }
+
+        fill_vwap_params(&mut order, 0.2, "09:00:00 CET", "", true, true, 0.0);
+        let strategy = AlgoStrategy::from_order(&order)?.unwrap();
+        strategy.validate()?;
+        assert_eq!("Vwap", strategy.name());
+
+        order.algo_strategy = "Adaptive".to_string();
+        order.algo_params.clear();
+        // the priority is required
+        assert!(AlgoStrategy::from_order(&order).is_err());
+        order.algo_strategy = "Unknown".to_string();
+        assert!(AlgoStrategy::from_order(&order).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_validation() -> Result<(), IBKRApiLibError> {
+        let mut order = Order::default();
+        let strategy = AlgoStrategy::MinImpact { max_pct_vol: 0.6 };
+        assert!(strategy.apply(&mut order).is_err());
+        assert!(order.algo_strategy.is_empty());
+
+        let strategy = AlgoStrategy::PctVol {
+            pct_vol: 0.1,
+            start_time: "9am".to_string(),
+            end_time: String::new(),
+            no_take_liq: false,
+            monetary_value: 0.0,
+        };
+        assert!(strategy.validate().is_err());
+
+        let strategy = AlgoStrategy::DarkIce {
+            display_size: 0,
+            start_time: String::new(),
+            end_time: String::new(),
+            allow_past_end_time: false,
+            monetary_value: 0.0,
+        };
+        assert!(strategy.validate().is_err());
+
+        let strategy = AlgoStrategy::Adaptive {
+            priority: AdaptivePriority::Patient,
+        };
+        strategy.apply(&mut order)?;
+        assert_eq!("Adaptive: [adaptivePriority=Patient]", strategy.to_string());
+        Ok(())
+    }
+}
@@ -0,0 +1,153 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use crate::core::backtester::{bar_time, Backtester, BarDelivery, TickDelivery};
+    use crate::core::common::{
+        BarData, HistoricalTickBidAsk, HistoricalTickLast, TickAttribBidAsk, TickAttribLast,
+    };
+    use crate::core::errors::IBKRApiLibError;
+    use crate::core::sim_broker::FixedSlippage;
+    use crate::examples::contract_samples;
+    use crate::examples::order_samples;
+    use crate::tests::recording_wrapper::RecordingWrapper;
+
+    fn bar(date: &str, close: f64) -> BarData {
+        BarData::new(
+            date.to_string(),
+            close,
+            close + 1.0,
+            close - 1.0,
+            close,
+            1000,
+            10,
+            close,
+        )
+    }
+
+    #[test]
+    fn test_bar_time() -> Result<(), IBKRApiLibError> {
+        assert_eq!(1760832000, bar_time("20251019")?);
+        assert_eq!(1760866200, bar_time("20251019  09:30:00")?);
+        assert_eq!(1760866200, bar_time("20251019 09:30:00 US/Eastern")?);
+        assert_eq!(1760866200, bar_time("1760866200")?);
+        assert!(bar_time("2025-10-19").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_strategy_replay() -> Result<(), IBKRApiLibError> {
+        let mut contract = contract_samples::usstock();
+        contract.con_id = 3691937;
+        let wrapper = Arc::new(Mutex::new(RecordingWrapper::new()));
+        let mut backtester = Backtester::new(wrapper.clone(), "DU123456", 100000.0);
+        backtester.broker_mut().set_commission(0.0, 1.0, "USD");
+        backtester
+            .broker_mut()
+            .set_slippage(Box::new(FixedSlippage { amount: 0.1 }));
+
+        // buy after the second bar, sell after the fourth
+        let client = backtester.client();
+        let strategy_contract = contract.clone();
+        let mut count = 0;
+        wrapper.lock().unwrap().on_bar = Some(Box::new(move |_, _| {
+            count += 1;
+            client.req_current_time().unwrap();
+            if count == 2 {
+                let order = order_samples::market_order("BUY", 100.0);
+                client.place_order(1, &strategy_contract, &order).unwrap();
+            } else if count == 4 {
+                let order = order_samples::market_order("SELL", 100.0);
+                client.place_order(2, &strategy_contract, &order).unwrap();
+            }
+        }));
+
+        let bars = vec![
+            bar("20251020  09:30:00", 100.0),
+            bar("20251020  09:31:00", 101.0),
+            bar("20251020  09:32:00", 103.0),
+            bar("20251020  09:33:00", 105.0),
+            bar("20251020  09:34:00", 104.0),
+        ];
+        let path = std::env::temp_dir().join(format!("backtest_bars_{}.json", std::process::id()));
+        std::fs::write(&path, serde_json::to_string(&bars).unwrap())?;
+        backtester.load_bars(7, &contract, &path, BarDelivery::HistoricalDataUpdate)?;
+        std::fs::remove_file(&path)?;
+        let report = backtester.run()?;
+
+        assert_eq!(5, report.equity_curve.len());
+        assert_eq!(2, report.fills.len());
+        assert_eq!(101.1, report.fills[0].price);
+        assert_eq!(104.9, report.fills[1].price);
+        assert_eq!(1, report.trades);
+        assert_eq!(1, report.winning_trades);
+        assert_eq!(2.0, report.total_commission);
+        assert!((report.final_equity - (100000.0 + 380.0 - 2.0)).abs() < 1e-6);
+        assert!((report.equity_curve[2].1 - (100000.0 + 190.0 - 1.0)).abs() < 1e-6);
+        assert_eq!(1.0, report.win_rate());
+        assert!(report.to_string().contains("trades: 1 (1 won, 0 lost"));
+
+        let wrapper = wrapper.lock().unwrap();
+        assert_eq!(5, wrapper.bars.len());
+        assert_eq!(
+            bar_time("20251020  09:34:00")?,
+            *wrapper.current_times.last().unwrap()
+        );
+        assert_eq!("20251020  09:31:00", wrapper.executions[0].time);
+        Ok(())
+    }
+
+    #[test]
+    fn test_tick_replay_order() -> Result<(), IBKRApiLibError> {
+        let contract = contract_samples::usstock();
+        let wrapper = Arc::new(Mutex::new(RecordingWrapper::new()));
+        let mut backtester = Backtester::new(wrapper.clone(), "DU123456", 0.0);
+        let last = vec![
+            HistoricalTickLast::new(
+                10,
+                TickAttribLast::default(),
+                100.0,
+                5,
+                "ISLAND".to_string(),
+                "".to_string(),
+            ),
+            HistoricalTickLast::new(
+                30,
+                TickAttribLast::default(),
+                101.0,
+                5,
+                "ISLAND".to_string(),
+                "".to_string(),
+            ),
+        ];
+        let bid_ask = vec![
+            HistoricalTickBidAsk::new(20, TickAttribBidAsk::default(), 99.9, 100.1, 1, 1),
+            HistoricalTickBidAsk::new(30, TickAttribBidAsk::default(), 100.9, 101.1, 1, 1),
+        ];
+        backtester.add_last_ticks(1, &contract, last, TickDelivery::TickByTick);
+        backtester.add_bid_ask_ticks(2, &contract, bid_ask, TickDelivery::TickPrice);
+        let report = backtester.run()?;
+        assert_eq!(
+            vec![10, 20, 30],
+            report
+                .equity_curve
+                .iter()
+                .map(|(time, _)| *time)
+                .collect::<Vec<i64>>()
+        );
+        let wrapper = wrapper.lock().unwrap();
+        assert_eq!(
+            vec![
+                "tick_by_tick_all_last",
+                "tick_price",
+                "tick_price",
+                "tick_by_tick_all_last",
+                "tick_price",
+                "tick_price"
+            ],
+            wrapper.calls
+        );
+        assert_eq!(vec![(1, 10, 100.0), (1, 30, 101.0)], wrapper.last_ticks);
+        Ok(())
+    }
+}
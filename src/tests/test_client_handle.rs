@@ -0,0 +1,165 @@
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+    use std::sync::mpsc::channel;
+    use std::sync::{Arc, Mutex};
+
+    use crate::core::client::{ConnStatus, EClient, POISONED_MUTEX};
+    use crate::core::client_handle::ClientHandle;
+    use crate::core::decoder::Decoder;
+    use crate::core::errors::{IBKRApiLibError, TwsError};
+    use crate::core::messages::{read_fields, read_msg, OutgoingMessageIds};
+    use crate::core::streamer::{Streamer, TestStreamer};
+    use crate::examples::contract_samples;
+    use crate::examples::order_samples;
+    use crate::tests::recording_wrapper::RecordingWrapper;
+
+    fn connect_test(client: &mut EClient<RecordingWrapper>) {
+        *client.conn_state.lock().expect(POISONED_MUTEX) = ConnStatus::CONNECTED;
+        client.set_streamer(Some(Box::new(TestStreamer::new()) as Box<dyn Streamer>));
+        client.server_version = 151;
+    }
+
+    fn message(fields: &[&str]) -> Vec<String> {
+        fields.iter().map(|field| field.to_string()).collect()
+    }
+
+    fn bar_update(req_id: &str) -> Vec<String> {
+        message(&[
+            "90", req_id, "1", "20200101", "1", "2", "0.5", "1.5", "1.2", "100",
+        ])
+    }
+
+    //------------------------------------------------------------------------------------------------
+    #[test]
+    fn test_order_from_exclusive_handler() -> Result<(), IBKRApiLibError> {
+        let (handle, queue) = ClientHandle::<RecordingWrapper>::channel();
+        let contract = contract_samples::usstock();
+        let mut handler = RecordingWrapper::new();
+        handler.on_bar = Some(Box::new(move |_, _| {
+            let order = order_samples::limit_order("BUY", 100.0, 50.0);
+            handle.place_order(7, &contract, &order).unwrap();
+        }));
+
+        let (_tx, rx) = channel::<String>();
+        let mut decoder = Decoder::with_handler(
+            handler,
+            rx,
+            151,
+            Arc::new(Mutex::new(ConnStatus::CONNECTED)),
+        );
+        decoder.interpret(&bar_update("4001"))?;
+
+        let mut client = EClient::with_handler(RecordingWrapper::new());
+        connect_test(&mut client);
+        assert_eq!(1, queue.run_pending(&mut client));
+        assert_eq!(0, queue.run_pending(&mut client));
+
+        let mut buf = Vec::<u8>::new();
+        client.stream.as_mut().unwrap().read_to_end(&mut buf)?;
+        let (_size, msg, _remaining) = read_msg(buf.as_slice())?;
+        let fields = read_fields(&msg);
+        assert_eq!(
+            OutgoingMessageIds::PlaceOrder as i32,
+            fields[0].parse::<i32>().unwrap()
+        );
+        assert_eq!("7", fields[1]);
+
+        let handler = decoder.into_handler().unwrap();
+        assert_eq!(1, handler.count("historical_data_update"));
+        Ok(())
+    }
+
+    //------------------------------------------------------------------------------------------------
+    #[test]
+    fn test_failed_command_is_reported() -> Result<(), IBKRApiLibError> {
+        let (handle, queue) = ClientHandle::<RecordingWrapper>::channel();
+        let (tx, rx) = channel::<String>();
+        let mut client = EClient::with_handler(RecordingWrapper::new());
+        client.msg_tx = Some(tx);
+
+        handle.cancel_order(8)?;
+        handle.execute(9, |_| {
+            Err(IBKRApiLibError::Io(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                "broken pipe",
+            )))
+        })?;
+        assert_eq!(2, queue.run_pending(&mut client));
+        drop(client);
+
+        let mut decoder = Decoder::with_handler(
+            RecordingWrapper::new(),
+            rx,
+            151,
+            Arc::new(Mutex::new(ConnStatus::CONNECTED)),
+        );
+        decoder.run()?;
+        let handler = decoder.into_handler().unwrap();
+        assert_eq!(2, handler.errors.len());
+        assert_eq!(
+            (8, TwsError::NotConnected.code()),
+            (handler.errors[0].0, handler.errors[0].1)
+        );
+        assert_eq!(
+            (9, TwsError::BadMessage.code(), "IO error: broken pipe"),
+            (
+                handler.errors[1].0,
+                handler.errors[1].1,
+                handler.errors[1].2.as_str()
+            )
+        );
+
+        drop(queue);
+        assert!(handle.req_current_time().is_err());
+        Ok(())
+    }
+
+    //------------------------------------------------------------------------------------------------
+    #[test]
+    fn test_spawned_queue_and_shared_wrapper() -> Result<(), IBKRApiLibError> {
+        let wrapper = Arc::new(Mutex::new(RecordingWrapper::new()));
+        let mut client = EClient::new(wrapper.clone());
+        connect_test(&mut client);
+        let client = Arc::new(Mutex::new(client));
+
+        let (handle, queue) = ClientHandle::channel();
+        let worker = queue.spawn(&client);
+        handle.clone().req_current_time()?;
+        handle.req_ids(1)?;
+        drop(handle);
+        worker.join().unwrap();
+
+        let mut buf = Vec::<u8>::new();
+        client
+            .lock()
+            .unwrap()
+            .stream
+            .as_mut()
+            .unwrap()
+            .read_to_end(&mut buf)?;
+        let (_size, msg, remaining) = read_msg(buf.as_slice())?;
+        assert_eq!(
+            OutgoingMessageIds::ReqCurrentTime as i32,
+            read_fields(&msg)[0].parse::<i32>().unwrap()
+        );
+        let (_size, msg, _remaining) = read_msg(remaining.as_slice())?;
+        assert_eq!(
+            OutgoingMessageIds::ReqIds as i32,
+            read_fields(&msg)[0].parse::<i32>().unwrap()
+        );
+
+        // the decoder of a shared wrapper still locks it for every callback
+        let (_tx, rx) = channel::<String>();
+        let mut decoder = Decoder::new(
+            wrapper.clone(),
+            rx,
+            151,
+            Arc::new(Mutex::new(ConnStatus::CONNECTED)),
+        );
+        decoder.interpret(&bar_update("4001"))?;
+        assert!(decoder.into_handler().is_none());
+        assert_eq!(1, wrapper.lock().unwrap().count("historical_data_update"));
+        Ok(())
+    }
+}
@@ -0,0 +1,232 @@
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+    use std::sync::mpsc::channel;
+    use std::sync::{Arc, Mutex};
+
+    use crate::core::client::{ConnStatus, EClient, POISONED_MUTEX};
+    use crate::core::client_pool::{ClientPool, ClientPoolConfig, RequestCategory};
+    use crate::core::decoder::Decoder;
+    use crate::core::errors::IBKRApiLibError;
+    use crate::core::event_bus::{Backpressure, Event, EventBus, EventFilter};
+    use crate::core::messages::{read_fields, read_msg, OutgoingMessageIds};
+    use crate::core::streamer::{Streamer, TestStreamer};
+    use crate::examples::contract_samples;
+
+    fn config() -> ClientPoolConfig {
+        ClientPoolConfig::new("127.0.0.1", 4002)
+            .connection(1, &[RequestCategory::MarketData])
+            .connection(0, &[RequestCategory::Orders])
+            .connection(2, &[RequestCategory::Account])
+    }
+
+    fn connect_test(pool: &ClientPool) {
+        for connection in pool.config().connections.iter() {
+            let mut client = pool
+                .client_by_id(connection.client_id)
+                .unwrap()
+                .lock()
+                .expect(POISONED_MUTEX);
+            *client.conn_state.lock().expect(POISONED_MUTEX) = ConnStatus::CONNECTED;
+            client.set_streamer(Some(Box::new(TestStreamer::new()) as Box<dyn Streamer>));
+            client.server_version = 151;
+        }
+    }
+
+    fn decoder(pool: &ClientPool, client_id: i32) -> Decoder<EventBus> {
+        let (_tx, rx) = channel::<String>();
+        Decoder::with_handler(
+            pool.connection_events(client_id).unwrap(),
+            rx,
+            151,
+            Arc::new(Mutex::new(ConnStatus::CONNECTED)),
+        )
+    }
+
+    /// Ids of the messages a client has sent
+    fn sent(client: &Arc<Mutex<EClient<EventBus>>>) -> Result<Vec<i32>, IBKRApiLibError> {
+        let mut buf = Vec::<u8>::new();
+        let mut client = client.lock().expect(POISONED_MUTEX);
+        client.stream.as_mut().unwrap().read_to_end(&mut buf)?;
+        let mut ids = vec![];
+        while !buf.is_empty() {
+            let (_size, msg, remaining) = read_msg(&buf)?;
+            ids.push(read_fields(&msg)[0].parse().unwrap());
+            buf = remaining;
+        }
+        Ok(ids)
+    }
+
+    #[test]
+    fn test_invalid_configs() {
+        let no_orders = ClientPoolConfig::new("127.0.0.1", 4002)
+            .connection(1, &[RequestCategory::MarketData, RequestCategory::Account]);
+        let twice = config().connection(3, &[RequestCategory::Account]);
+        let same_id = ClientPoolConfig::new("127.0.0.1", 4002)
+            .connection(1, &[RequestCategory::MarketData])
+            .connection(1, &[RequestCategory::Orders, RequestCategory::Account]);
+        let mut too_many = config();
+        for client_id in 3..33 {
+            too_many = too_many.connection(client_id, &[]);
+        }
+        let unknown_master = config().master_client_id(7);
+        let bind_without_0 = ClientPoolConfig::new("127.0.0.1", 4002)
+            .connection(1, &RequestCategory::ALL)
+            .bind_manual_orders(true);
+
+        for config in vec![
+            ClientPoolConfig::new("127.0.0.1", 4002),
+            no_orders,
+            twice,
+            same_id,
+            too_many,
+            unknown_master,
+            bind_without_0,
+        ] {
+            assert!(ClientPool::new(config).is_err());
+        }
+    }
+
+    #[test]
+    fn test_requests_are_routed_by_category() -> Result<(), IBKRApiLibError> {
+        let pool = ClientPool::new(config().bind_manual_orders(true))?;
+        connect_test(&pool);
+        assert!(pool.is_connected());
+
+        let contract = contract_samples::usstock();
+        let req_id = pool.next_req_id();
+        pool.execute(RequestCategory::MarketData, |client| {
+            client.req_mkt_data(req_id, &contract, "", false, false, vec![])
+        })?;
+        pool.execute(RequestCategory::Account, |client| client.req_positions())?;
+        pool.execute(RequestCategory::Orders, |client| client.req_ids(1))?;
+        pool.bind_manual_orders()?;
+        pool.req_open_orders()?;
+        assert_ne!(req_id, pool.next_req_id());
+
+        assert_eq!(
+            vec![OutgoingMessageIds::ReqMktData as i32],
+            sent(pool.client(RequestCategory::MarketData))?
+        );
+        assert_eq!(
+            vec![OutgoingMessageIds::ReqPositions as i32],
+            sent(pool.client_by_id(2).unwrap())?
+        );
+        assert_eq!(
+            vec![
+                OutgoingMessageIds::ReqIds as i32,
+                OutgoingMessageIds::ReqAutoOpenOrders as i32,
+                OutgoingMessageIds::ReqOpenOrders as i32,
+            ],
+            sent(pool.client_by_id(0).unwrap())?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_master_client_sees_all_open_orders() -> Result<(), IBKRApiLibError> {
+        let pool = ClientPool::new(config().master_client_id(2))?;
+        connect_test(&pool);
+        pool.bind_manual_orders()?;
+        pool.req_open_orders()?;
+
+        assert!(sent(pool.client(RequestCategory::Orders))?.is_empty());
+        assert_eq!(
+            vec![OutgoingMessageIds::ReqAllOpenOrders as i32],
+            sent(pool.open_orders_client())?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_events_of_all_connections() -> Result<(), IBKRApiLibError> {
+        let pool = ClientPool::new(config())?;
+        let receiver = pool
+            .events()
+            .subscribe(EventFilter::new(), 10, Backpressure::Block);
+
+        // what each connection's decoder does with the messages it receives
+        for (client_id, req_id, text) in [(1, "4001", "quotes"), (0, "4002", "orders")].iter() {
+            let mut decoder = decoder(&pool, *client_id);
+            decoder.interpret_fields(&["4", "2", req_id, "200", text])?;
+        }
+
+        let errors: Vec<(i32, String)> = receiver
+            .take(2)
+            .map(|event| match event {
+                Event::Error {
+                    req_id,
+                    error_string,
+                    ..
+                } => (req_id, error_string),
+                _ => panic!("unexpected event"),
+            })
+            .collect();
+        assert_eq!(
+            vec![(4001, "quotes".to_string()), (4002, "orders".to_string())],
+            errors
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_connection_wide_events_are_published_once() -> Result<(), IBKRApiLibError> {
+        let pool = ClientPool::new(config().master_client_id(2))?;
+        let receiver = pool
+            .events()
+            .subscribe(EventFilter::new(), 100, Backpressure::Block);
+
+        // every connection receives these
+        for client_id in 0..3 {
+            let mut decoder = decoder(&pool, client_id);
+            decoder.interpret_fields(&["9", "1", "100"])?;
+            decoder.interpret_fields(&["15", "1", "DU123"])?;
+            decoder.interpret_fields(&["4", "2", "-1", "2104", "Market data farm is OK"])?;
+            decoder.interpret_fields(&[
+                "3",
+                "7",
+                "Submitted",
+                "0",
+                "100",
+                "0",
+                "555",
+                "0",
+                "0",
+                "0",
+                "",
+                "0",
+            ])?;
+            decoder.interpret_fields(&["4", "2", "-1", "326", "Client id is in use"])?;
+        }
+        assert!(pool.connection_events(7).is_none());
+
+        let mut received = vec![];
+        while let Some(event) = receiver.try_recv() {
+            received.push(match event {
+                Event::NextValidId { order_id } => format!("next_valid_id {}", order_id),
+                Event::ManagedAccounts { accounts_list } => {
+                    format!("managed_accounts {}", accounts_list)
+                }
+                Event::Error { error_code, .. } => format!("error {}", error_code),
+                Event::OrderStatus { order_id, .. } => format!("order_status {}", order_id),
+                other => panic!("unexpected event {:?}", other),
+            });
+        }
+        assert_eq!(
+            vec![
+                // the Orders connection
+                "next_valid_id 100",
+                "managed_accounts DU123",
+                "error 2104",
+                "error 326",
+                // the MarketData connection
+                "error 326",
+                // the master connection
+                "order_status 7",
+                "error 326",
+            ],
+            received
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,192 @@
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::core::combo::{ComboBuilder, ComboLegSpec};
+    use crate::core::common::{Right, UNSET_DOUBLE};
+    use crate::core::contract::{Contract, ContractDetails};
+    use crate::core::contract_cache::ContractCache;
+    use crate::core::errors::IBKRApiLibError;
+    use crate::core::order::Order;
+    use crate::examples::contract_samples;
+
+    fn option_template() -> Contract {
+        let mut contract = Contract::default();
+        contract.symbol = "SPY".to_string();
+        contract.sec_type = "OPT".to_string();
+        contract.exchange = "SMART".to_string();
+        contract.currency = "USD".to_string();
+        contract.multiplier = "100".to_string();
+        contract
+    }
+
+    fn details(contract: &Contract, con_id: i32, valid_exchanges: &str) -> ContractDetails {
+        let mut details = ContractDetails::default();
+        details.contract = contract.clone();
+        details.contract.con_id = con_id;
+        details.valid_exchanges = valid_exchanges.to_string();
+        details
+    }
+
+    #[test]
+    fn test_vertical_resolution() -> Result<(), IBKRApiLibError> {
+        let mut builder = ComboBuilder::new(500).vertical(
+            &option_template(),
+            "20201218",
+            Right::Call,
+            330.0,
+            340.0,
+        );
+        assert!(builder.build().is_err());
+
+        let requests = builder.contract_detail_requests();
+        assert_eq!(
+            vec![500, 501],
+            requests.iter().map(|r| r.0).collect::<Vec<i32>>()
+        );
+        assert_eq!(330.0, requests[0].1.strike);
+        assert_eq!("C", requests[1].1.right);
+        assert!(builder.contract_detail_requests().is_empty());
+
+        assert!(builder.contract_details(500, &details(&requests[0].1, 1001, "SMART,CBOE")));
+        assert!(builder.contract_details_end(500)?);
+        // a spec matching two contracts can't be used as a leg
+        builder.contract_details(501, &details(&requests[1].1, 1002, "SMART,CBOE"));
+        builder.contract_details(501, &details(&requests[1].1, 1003, "SMART,CBOE"));
+        match builder.contract_details_end(501) {
+            Err(IBKRApiLibError::ApiError(err)) => {
+                assert_eq!(501, err.req_id);
+                assert!(err.description.contains("1002,1003"));
+            }
+            _ => panic!("expected an ambiguous leg"),
+        }
+        assert!(!builder.is_resolved());
+
+        let requests = builder.contract_detail_requests();
+        assert_eq!(502, requests[0].0);
+        builder.contract_details(502, &details(&requests[0].1, 1002, "SMART,CBOE"));
+        builder.contract_details_end(502)?;
+        assert!(!builder.contract_details_end(502)?);
+
+        let combo = builder.build()?;
+        assert_eq!("BAG", combo.sec_type);
+        assert_eq!("SPY", combo.symbol);
+        assert_eq!("SMART", combo.exchange);
+        assert_eq!("USD", combo.currency);
+        assert_eq!(2, combo.combo_legs.len());
+        assert_eq!(1001, combo.combo_legs[0].con_id);
+        assert_eq!("BUY", combo.combo_legs[0].action);
+        assert_eq!("SELL", combo.combo_legs[1].action);
+        assert_eq!("SMART", combo.combo_legs[1].exchange);
+
+        // directed to an exchange a leg doesn't trade on
+        assert!(builder.clone().exchange("ISE").build().is_err());
+        assert_eq!(
+            "CBOE",
+            builder.exchange("CBOE").build()?.combo_legs[0].exchange
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_templates() {
+        let template = option_template();
+        let butterfly =
+            ComboBuilder::new(1).butterfly(&template, "20201218", Right::Put, 300.0, 310.0, 320.0);
+        assert_eq!(
+            vec![("BUY", 1.0), ("SELL", 2.0), ("BUY", 1.0)],
+            butterfly
+                .legs()
+                .iter()
+                .map(|leg| (leg.action.as_str(), leg.ratio))
+                .collect::<Vec<(&str, f64)>>()
+        );
+        assert_eq!(
+            "SELL 2 SPY OPT 20201218 310P",
+            butterfly.legs()[1].to_string()
+        );
+
+        let calendar =
+            ComboBuilder::new(1).calendar(&template, Right::Call, 330.0, "20201120", "20201218");
+        assert_eq!("SELL", calendar.legs()[0].action);
+        assert_eq!(
+            "20201218",
+            calendar.legs()[1]
+                .contract
+                .last_trade_date_or_contract_month
+        );
+
+        let straddle = ComboBuilder::new(1).straddle(&template, "20201218", 330.0);
+        assert_eq!("C", straddle.legs()[0].contract.right);
+        assert_eq!("P", straddle.legs()[1].contract.right);
+        let strangle = ComboBuilder::new(1).strangle(&template, "20201218", 320.0, 340.0);
+        assert_eq!(320.0, strangle.legs()[0].contract.strike);
+    }
+
+    #[test]
+    fn test_stock_vs_option_from_cache() -> Result<(), IBKRApiLibError> {
+        let mut stock = contract_samples::usstock();
+        stock.exchange = "SMART".to_string();
+        let mut call = option_template();
+        call.symbol = stock.symbol.clone();
+        call.last_trade_date_or_contract_month = "20201218".to_string();
+        call.strike = 3200.0;
+        call.right = "C".to_string();
+
+        let mut cache = ContractCache::new(Duration::from_secs(60));
+        cache.insert(&stock, vec![details(&stock, 3691937, "")]);
+        cache.insert(&call, vec![details(&call, 4001, "")]);
+
+        let mut builder = ComboBuilder::new(1)
+            .stock_vs_option(&stock, 100.0, &call, "SELL")
+            .symbol("USD");
+        builder.resolve_from_cache(&cache)?;
+        assert!(builder.is_resolved());
+        assert!(builder.contract_detail_requests().is_empty());
+
+        let combo = builder.build()?;
+        assert_eq!("USD", combo.symbol);
+        assert_eq!(100.0, combo.combo_legs[0].ratio);
+        assert_eq!(4001, combo.combo_legs[1].con_id);
+
+        let mut order = Order::default();
+        builder.apply_leg_prices(&mut order);
+        assert!(order.order_combo_legs.is_empty());
+        builder.set_leg_price(1, 12.5);
+        builder.apply_leg_prices(&mut order);
+        assert_eq!(UNSET_DOUBLE, order.order_combo_legs[0].price);
+        assert_eq!(12.5, order.order_combo_legs[1].price);
+        Ok(())
+    }
+
+    #[test]
+    fn test_combo_validation() {
+        let template = option_template();
+        let mut euro = template.clone();
+        euro.currency = "EUR".to_string();
+        let mut builder = ComboBuilder::new(1)
+            .buy(&template, 1.0)
+            .leg(ComboLegSpec::new(euro.clone(), "SELL", 1.0));
+        builder.resolve_leg(0, &details(&template, 1, ""));
+        builder.resolve_leg(1, &details(&euro, 2, ""));
+        match builder.build() {
+            Err(IBKRApiLibError::ApiError(err)) => assert!(err.description.contains("EUR")),
+            _ => panic!("expected a currency mismatch"),
+        }
+
+        let mut builder = ComboBuilder::new(1)
+            .buy(&template, 1.0)
+            .sell(&template, 1.0);
+        builder.resolve_leg(0, &details(&template, 1, ""));
+        builder.resolve_leg(1, &details(&template, 1, ""));
+        assert!(builder.build().is_err());
+
+        let mut builder = ComboBuilder::new(1)
+            .buy(&template, 0.0)
+            .sell(&template, 1.0);
+        builder.resolve_leg(0, &details(&template, 1, ""));
+        builder.resolve_leg(1, &details(&template, 2, ""));
+        assert!(builder.build().is_err());
+        assert!(ComboBuilder::new(1).buy(&template, 1.0).build().is_err());
+    }
+}
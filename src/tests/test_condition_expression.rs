@@ -0,0 +1,144 @@
+#[cfg(test)]
+mod tests {
+    use crate::core::condition_expression::{
+        condition_to_string, conditions_to_string, parse_condition, parse_conditions,
+    };
+    use crate::core::errors::IBKRApiLibError;
+    use crate::core::order_condition::{
+        create_condition, Condition, ConditionType, OperatorCondition, OrderConditionEnum,
+        PriceCondition, TriggerMethod,
+    };
+    use crate::examples::order_samples;
+
+    #[test]
+    fn test_operator_condition() -> Result<(), IBKRApiLibError> {
+        let mut operator = OperatorCondition::new(ConditionType::Margin, false);
+        operator.set_value_from_string(">")?;
+        assert!(operator.is_more);
+        assert_eq!(">=", operator.value_to_string());
+        operator.set_value_from_string(" <= ")?;
+        assert!(!operator.is_more);
+        assert!(operator.set_value_from_string("=").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_conditions() -> Result<(), IBKRApiLibError> {
+        let conditions = parse_conditions(
+            "price(265598@SMART, last) >= 150 and time > 20261020 14:00:00 or margin < 30",
+        )?;
+        assert_eq!(3, conditions.len());
+        match &conditions[0] {
+            OrderConditionEnum::Price(price) => {
+                assert_eq!(265598, price.contract_condition.con_id);
+                assert_eq!("SMART", price.contract_condition.exchange);
+                assert_eq!(150.0, price.price);
+                assert_eq!(TriggerMethod::Last as i32, price.trigger_method as i32);
+                assert!(price.contract_condition.operator_condition.is_more);
+                assert!(
+                    price
+                        .contract_condition
+                        .operator_condition
+                        .order_condition
+                        .is_conjunction_connection
+                );
+            }
+            _ => panic!("expected a price condition"),
+        }
+        match &conditions[1] {
+            OrderConditionEnum::Time(time) => {
+                assert_eq!("20261020 14:00:00", time.time);
+                assert!(
+                    !time
+                        .operator_condition
+                        .order_condition
+                        .is_conjunction_connection
+                );
+            }
+            _ => panic!("expected a time condition"),
+        }
+        assert_eq!(
+            ConditionType::Margin as i32,
+            conditions[2].get_type() as i32
+        );
+
+        // > and < are written back as >= and <=
+        assert_eq!(
+            "price(265598@SMART, last) >= 150 and time >= 20261020 14:00:00 or margin <= 30",
+            conditions_to_string(&conditions)
+        );
+        assert!(parse_conditions("")?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_round_trip() -> Result<(), IBKRApiLibError> {
+        let text = "price(265598@SMART) <= 150.25 or volume(8314@ISLAND) >= 100000 and \
+                    percent_change(8314@ISLAND) <= -2.5 and execution(AAPL@SMART, STK) or \
+                    time <= 20261020 09:30:00 US/Eastern";
+        let conditions = parse_conditions(text)?;
+        assert_eq!(text, conditions_to_string(&conditions));
+        let reparsed = parse_conditions(conditions_to_string(&conditions).as_str())?;
+        for (condition, parsed) in conditions.iter().zip(reparsed.iter()) {
+            assert_eq!(condition.make_fields()?, parsed.make_fields()?);
+        }
+
+        // conditions built the old way render too
+        let price = OrderConditionEnum::Price(order_samples::price_condition(
+            TriggerMethod::DoubleBidAsk as i32,
+            208813720,
+            "SMART",
+            600.0,
+            false,
+            false,
+        ));
+        assert_eq!(
+            "price(208813720@SMART, double_bid_ask) <= 600",
+            condition_to_string(&price)
+        );
+        assert_eq!(
+            price.make_fields()?,
+            parse_condition(condition_to_string(&price).as_str())?.make_fields()?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for text in [
+            "price(265598@SMART) 150",
+            "price(SMART) >= 150",
+            "price(265598@SMART, sometimes) >= 150",
+            "price(265598@SMART >= 150",
+            "margin >= lots",
+            "volume(8314@ISLAND) >= 1.5",
+            "weather > 20",
+            "margin >= 30 and",
+            "or margin >= 30",
+            "execution(AAPL@SMART) >= 1",
+            "time >=",
+        ]
+        .iter()
+        {
+            assert!(parse_conditions(text).is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_decode_owned_fields() -> Result<(), IBKRApiLibError> {
+        let fields = ["150.25", "o", "1", "265598", "SMART", "2", "next"]
+            .iter()
+            .map(|field| field.to_string())
+            .collect::<Vec<String>>();
+        let mut fields_iter = fields.iter();
+        let mut price: PriceCondition = create_condition(ConditionType::Price).into();
+        price.decode_owned(&mut fields_iter)?;
+
+        assert_eq!(150.25, price.price);
+        assert_eq!(265598, price.contract_condition.con_id);
+        assert_eq!("SMART", price.contract_condition.exchange);
+        assert!(price.contract_condition.operator_condition.is_more);
+        assert_eq!(Some("next"), fields_iter.next().map(String::as_str));
+        Ok(())
+    }
+}
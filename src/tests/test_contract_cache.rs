@@ -0,0 +1,169 @@
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+    use std::time::Duration;
+
+    use crate::core::client::{ConnStatus, EClient, POISONED_MUTEX};
+    use crate::core::contract::{Contract, ContractDetails};
+    use crate::core::contract_cache::{ContractCache, ContractSpec};
+    use crate::core::errors::IBKRApiLibError;
+    use crate::core::streamer::{Streamer, TestStreamer};
+    use crate::examples::contract_samples;
+    use crate::tests::recording_wrapper::RecordingWrapper;
+
+    const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+    fn details(con_id: i32, contract: &Contract) -> ContractDetails {
+        let mut details = ContractDetails::default();
+        details.contract = contract.clone();
+        details.contract.con_id = con_id;
+        details.long_name = format!("contract {}", con_id);
+        details
+    }
+
+    #[test]
+    fn test_contract_spec() {
+        let mut contract = contract_samples::usstock();
+        let spec = ContractSpec::from_contract(&contract);
+        contract.symbol = format!(" {} ", contract.symbol.to_lowercase());
+        assert_eq!(spec, ContractSpec::from_contract(&contract));
+        contract.currency = "EUR".to_string();
+        assert_ne!(spec, ContractSpec::from_contract(&contract));
+    }
+
+    #[test]
+    fn test_contract_cache_lookup() -> Result<(), IBKRApiLibError> {
+        let mut cache = ContractCache::new(DAY);
+        let stock = contract_samples::usstock();
+        assert!(cache.lookup(&stock)?.is_none());
+
+        cache.track(1, &stock);
+        assert!(!cache.contract_details(2, &details(3691937, &stock)));
+        assert!(cache.contract_details(1, &details(3691937, &stock)));
+        assert!(cache.contract_details_end(1));
+        assert!(!cache.contract_details_end(1));
+
+        assert_eq!(3691937, cache.lookup(&stock)?.unwrap().contract.con_id);
+        assert_eq!("contract 3691937", cache.get(3691937).unwrap().long_name);
+        let mut by_con_id = Contract::default();
+        by_con_id.con_id = 3691937;
+        assert!(cache.lookup(&by_con_id)?.is_some());
+
+        // a spec that matched several contracts is an error
+        let mut future = Contract::default();
+        future.symbol = "ES".to_string();
+        future.sec_type = "FUT".to_string();
+        future.exchange = "GLOBEX".to_string();
+        future.currency = "USD".to_string();
+        cache.insert(
+            &future,
+            vec![details(495512551, &future), details(495512552, &future)],
+        );
+        assert_eq!(2, cache.lookup_all(&future).unwrap().len());
+        match cache.lookup(&future) {
+            Err(IBKRApiLibError::ApiError(err)) => {
+                assert!(err.description.contains("Ambiguous contract"));
+                assert!(err.description.contains("495512551,495512552"));
+            }
+            _ => panic!("expected an ambiguous contract error"),
+        }
+        future.last_trade_date_or_contract_month = "202012".to_string();
+        assert!(cache.lookup(&future)?.is_none());
+
+        // nothing is fresh with a zero time to live
+        cache.set_ttl(Duration::from_secs(0));
+        assert!(cache.lookup(&stock)?.is_none());
+        cache.purge_expired();
+        assert!(cache.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_contract_cache_persistence() -> Result<(), IBKRApiLibError> {
+        let path =
+            std::env::temp_dir().join(format!("twsapi_contract_cache_{}.json", std::process::id()));
+        let missing = ContractCache::load(&path, DAY)?;
+        assert!(missing.is_empty());
+
+        let stock = contract_samples::usstock();
+        let mut cache = ContractCache::new(DAY);
+        cache.insert(&stock, vec![details(3691937, &stock)]);
+        cache.save(&path)?;
+
+        let loaded = ContractCache::load(&path, DAY)?;
+        assert_eq!(1, loaded.len());
+        assert_eq!(
+            "contract 3691937",
+            loaded.lookup(&stock)?.unwrap().long_name
+        );
+
+        std::fs::write(&path, "not json")?;
+        assert!(ContractCache::load(&path, DAY).is_err());
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_req_contract_details_through_cache() -> Result<(), IBKRApiLibError> {
+        let mut cache = ContractCache::new(DAY);
+        let mut wrapper = RecordingWrapper::new();
+        let mut client = EClient::with_handler(RecordingWrapper::new());
+        let stock = contract_samples::usstock();
+
+        // a request that could not be sent is not waited for
+        assert!(cache
+            .req_contract_details(&mut client, &mut wrapper, 1, &stock)
+            .is_err());
+        assert!(!cache.contract_details(1, &details(3691937, &stock)));
+
+        *client.conn_state.lock().expect(POISONED_MUTEX) = ConnStatus::CONNECTED;
+        client.set_streamer(Some(Box::new(TestStreamer::new()) as Box<dyn Streamer>));
+        client.server_version = 151;
+
+        // nor is one TWS answered with an error
+        cache.req_contract_details(&mut client, &mut wrapper, 2, &stock)?;
+        assert!(cache.error(2));
+        assert!(!cache.contract_details(2, &details(3691937, &stock)));
+
+        cache.req_contract_details(&mut client, &mut wrapper, 3, &stock)?;
+        assert!(cache.contract_details(3, &details(3691937, &stock)));
+        assert!(cache.contract_details_end(3));
+        assert!(wrapper.calls.is_empty());
+        let mut buf = Vec::<u8>::new();
+        client.stream.as_mut().unwrap().read_to_end(&mut buf)?;
+        assert!(!buf.is_empty());
+
+        // served from the cache through the same callbacks, without sending anything
+        cache.req_contract_details(&mut client, &mut wrapper, 4, &stock)?;
+        assert_eq!(
+            vec!["contract_details", "contract_details_end"],
+            wrapper.calls
+        );
+        buf.clear();
+        client.stream.as_mut().unwrap().read_to_end(&mut buf)?;
+        assert!(buf.is_empty());
+
+        let mut future = Contract::default();
+        future.symbol = "ES".to_string();
+        future.sec_type = "FUT".to_string();
+        cache.insert(
+            &future,
+            vec![details(495512551, &future), details(495512552, &future)],
+        );
+        // an ambiguous spec is answered with every match, like the request that cached it
+        wrapper.calls.clear();
+        cache.req_contract_details(&mut client, &mut wrapper, 5, &future)?;
+        assert_eq!(
+            vec![
+                "contract_details",
+                "contract_details",
+                "contract_details_end"
+            ],
+            wrapper.calls
+        );
+        buf.clear();
+        client.stream.as_mut().unwrap().read_to_end(&mut buf)?;
+        assert!(buf.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,189 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc::channel;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::Duration;
+
+    use crate::core::client::ConnStatus;
+    use crate::core::common::{TickAttrib, TickType};
+    use crate::core::contract::Contract;
+    use crate::core::decoder::Decoder;
+    use crate::core::errors::IBKRApiLibError;
+    use crate::core::event_bus::{
+        Backpressure, Event, EventBus, EventFilter, EventKind, EventReceiver,
+    };
+    use crate::core::execution::Execution;
+    use crate::core::wrapper::Wrapper;
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    fn tick_price(bus: &mut EventBus, req_id: i32, price: f64) {
+        bus.tick_price(req_id, TickType::Last, price, TickAttrib::default());
+    }
+
+    fn price(event: Event) -> f64 {
+        match event {
+            Event::TickPrice { price, .. } => price,
+            other => panic!("unexpected event {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_filters() {
+        let mut bus = EventBus::new();
+        let all = bus.subscribe(EventFilter::new(), 100, Backpressure::Block);
+        let req_1 = bus.subscribe(EventFilter::new().req_id(1), 100, Backpressure::Block);
+        let errors = bus.subscribe(
+            EventFilter::new().kind(EventKind::Error),
+            100,
+            Backpressure::Block,
+        );
+        let order_7 = bus.subscribe(EventFilter::new().order_id(7), 100, Backpressure::Block);
+        let account = bus.subscribe(EventFilter::new().account("DU1"), 100, Backpressure::Block);
+
+        tick_price(&mut bus, 1, 10.0);
+        tick_price(&mut bus, 2, 20.0);
+        bus.error(1, 354, "Not subscribed");
+        bus.order_status(7, "Filled", 100.0, 0.0, 50.0, 555, 0, 50.0, 0, "", 0.0);
+        let mut execution = Execution::default();
+        execution.order_id = 7;
+        execution.acct_number = "DU1".to_string();
+        bus.exec_details(-1, Contract::default(), execution);
+        bus.position("DU2", Contract::default(), 100.0, 50.0);
+        bus.update_account_value("NetLiquidation", "1000", "USD", "DU1");
+
+        let kinds = |receiver: &EventReceiver| {
+            std::iter::from_fn(|| receiver.try_recv())
+                .map(|event| event.kind().as_str())
+                .collect::<Vec<&str>>()
+        };
+        assert_eq!(7, all.len());
+        assert_eq!(vec!["tick_price", "error"], kinds(&req_1));
+        assert_eq!(vec!["error"], kinds(&errors));
+        assert_eq!(vec!["order_status", "exec_details"], kinds(&order_7));
+        assert_eq!(
+            vec!["exec_details", "update_account_value"],
+            kinds(&account)
+        );
+
+        // criteria are combined
+        let both = bus.subscribe(
+            EventFilter::new().req_id(1).kind(EventKind::TickPrice),
+            100,
+            Backpressure::Block,
+        );
+        bus.error(1, 354, "Not subscribed");
+        tick_price(&mut bus, 1, 11.0);
+        assert_eq!(vec!["tick_price"], kinds(&both));
+    }
+
+    #[test]
+    fn test_backpressure() {
+        let mut bus = EventBus::new();
+        let newest = bus.subscribe(EventFilter::new(), 2, Backpressure::DropNewest);
+        let oldest = bus.subscribe(EventFilter::new(), 2, Backpressure::DropOldest);
+        for i in 0..4 {
+            tick_price(&mut bus, 1, i as f64);
+        }
+        assert_eq!(2, newest.dropped());
+        assert_eq!(2, oldest.dropped());
+        assert_eq!(
+            vec![0.0, 1.0],
+            std::iter::from_fn(|| newest.try_recv())
+                .map(price)
+                .collect::<Vec<f64>>()
+        );
+        assert_eq!(
+            vec![2.0, 3.0],
+            std::iter::from_fn(|| oldest.try_recv())
+                .map(price)
+                .collect::<Vec<f64>>()
+        );
+    }
+
+    #[test]
+    fn test_block_waits_for_the_subscriber() {
+        let bus = EventBus::new();
+        let receiver = bus.subscribe(EventFilter::new(), 1, Backpressure::Block);
+        let mut publisher = bus.clone();
+        let handle = thread::spawn(move || {
+            for i in 0..5 {
+                tick_price(&mut publisher, 1, i as f64);
+            }
+        });
+
+        let prices = receiver.take(5).map(price).collect::<Vec<f64>>();
+        handle.join().unwrap();
+        assert_eq!(vec![0.0, 1.0, 2.0, 3.0, 4.0], prices);
+    }
+
+    #[test]
+    fn test_handlers_and_unsubscribe() {
+        let mut bus = EventBus::new();
+        let (tx, rx) = channel::<f64>();
+        let id = bus.subscribe_fn(
+            EventFilter::new().kind(EventKind::TickPrice),
+            10,
+            Backpressure::Block,
+            move |event| {
+                if let Event::TickPrice { price, .. } = event {
+                    tx.send(*price).unwrap();
+                }
+            },
+        );
+        tick_price(&mut bus, 1, 1.5);
+        bus.tick_size(1, TickType::LastSize, 100);
+        assert_eq!(1.5, rx.recv_timeout(TIMEOUT).unwrap());
+
+        assert!(bus.unsubscribe(id));
+        assert!(!bus.unsubscribe(id));
+        tick_price(&mut bus, 1, 2.5);
+        // the handler thread exits and drops the sender
+        assert!(rx.recv_timeout(TIMEOUT).is_err());
+
+        // dropped receivers unsubscribe, and dropping the bus ends the remaining subscriptions
+        let dropped = bus.subscribe(EventFilter::new(), 10, Backpressure::Block);
+        let kept = bus.subscribe(EventFilter::new(), 10, Backpressure::Block);
+        assert_eq!(2, bus.subscriber_count());
+        drop(dropped);
+        assert_eq!(1, bus.subscriber_count());
+        tick_price(&mut bus, 1, 3.5);
+        drop(bus);
+        assert_eq!(vec![3.5], kept.map(price).collect::<Vec<f64>>());
+    }
+
+    #[test]
+    fn test_bus_as_decoder_wrapper() -> Result<(), IBKRApiLibError> {
+        let bus = EventBus::new();
+        let receiver = bus.subscribe(EventFilter::new().req_id(100), 10, Backpressure::Block);
+        let (tx, rx) = channel::<String>();
+        let mut decoder = Decoder::new(
+            Arc::new(Mutex::new(bus.clone())),
+            rx,
+            151,
+            Arc::new(Mutex::new(ConnStatus::CONNECTED)),
+        );
+        tx.send("4\x002\x00100\x00200\x00No security definition\x00".to_string())
+            .unwrap();
+        tx.send("4\x002\x00-1\x002104\x00Market data farm OK\x00".to_string())
+            .unwrap();
+        drop(tx);
+        decoder.run()?;
+
+        match receiver.recv_timeout(TIMEOUT) {
+            Some(Event::Error {
+                req_id,
+                error_code,
+                error_string,
+            }) => {
+                assert_eq!(100, req_id);
+                assert_eq!(200, error_code);
+                assert_eq!("No security definition", error_string);
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+        assert!(receiver.try_recv().is_none());
+        Ok(())
+    }
+}
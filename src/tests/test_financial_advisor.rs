@@ -0,0 +1,232 @@
+#[cfg(all(test, feature = "financial-advisor"))]
+mod tests {
+
+    use crate::core::common::{FaDataType, Method};
+    use crate::core::errors::IBKRApiLibError;
+    use crate::core::financial_advisor::{
+        aliases_from_xml, groups_from_xml, groups_to_xml, profiles_from_xml, profiles_to_xml,
+        FaAlias, FaAllocation, FaChange, FaConfiguration, FaGroup, FaProfile, FaProfileType,
+    };
+    use crate::examples::fa_allocation_samples;
+
+    const MANAGED_ACCOUNTS: &str = "DU119915,DU119916,DU119917,";
+
+    // Like fa_allocation_samples::FA_TWO_PROFILES, but with Ratios_2_1 typed as ratios so the
+    // configuration validates
+    const PROFILES: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\
+         <ListOfAllocationProfiles>\
+         <AllocationProfile>\
+         <name>Percent_60_40</name>\
+         <type>1</type>\
+         <ListOfAllocations varName=\"listOfAllocations\">\
+         <Allocation><acct>DU119915</acct><amount>60.0</amount></Allocation>\
+         <Allocation><acct>DU119916</acct><amount>40.0</amount></Allocation>\
+         </ListOfAllocations>\
+         </AllocationProfile>\
+         <AllocationProfile>\
+         <name>Ratios_2_1</name>\
+         <type>2</type>\
+         <ListOfAllocations varName=\"listOfAllocations\">\
+         <Allocation><acct>DU119915</acct><amount>2.0</amount></Allocation>\
+         <Allocation><acct>DU119916</acct><amount>1.0</amount></Allocation>\
+         </ListOfAllocations>\
+         </AllocationProfile>\
+         </ListOfAllocationProfiles>";
+
+    fn configuration() -> Result<FaConfiguration, IBKRApiLibError> {
+        let mut configuration = FaConfiguration::new();
+        configuration.receive_fa(FaDataType::GROUPS, fa_allocation_samples::FA_TWO_GROUPS)?;
+        configuration.receive_fa(FaDataType::PROFILES, PROFILES)?;
+        configuration.receive_fa(
+            FaDataType::ALIASES,
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\
+             <ListOfAccountAliases>\
+             <AccountAlias><account>DU119915</account><alias>Main</alias></AccountAlias>\
+             </ListOfAccountAliases>",
+        )?;
+        Ok(configuration)
+    }
+
+    #[test]
+    fn test_fa_parsing() -> Result<(), IBKRApiLibError> {
+        let configuration = configuration()?;
+        assert_eq!(2, configuration.groups.len());
+        assert_eq!(
+            &FaGroup::new(
+                "Pct_Change",
+                vec!["DU119915".to_string(), "DU119916".to_string()],
+                Method::PctChange
+            ),
+            configuration.group("Pct_Change").unwrap()
+        );
+        let profile = configuration.profile("Percent_60_40").unwrap();
+        assert_eq!(FaProfileType::Percentages, profile.profile_type);
+        assert_eq!(Some(40.0), profile.amount("DU119916"));
+        assert_eq!(Some("Main"), configuration.alias("DU119915"));
+
+        assert!(groups_from_xml(fa_allocation_samples::FA_ONE_PROFILE).is_err());
+        assert!(groups_from_xml(
+            "<ListOfGroups><Group><name>g</name><defaultMethod>Random</defaultMethod></Group></ListOfGroups>"
+        )
+        .is_err());
+        assert!(profiles_from_xml(
+            "<ListOfAllocationProfiles><AllocationProfile><name>p</name><type>9</type></AllocationProfile></ListOfAllocationProfiles>"
+        )
+        .is_err());
+        assert!(aliases_from_xml("<ListOfAccountAliases><Alias/></ListOfAccountAliases>").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_fa_round_trip() -> Result<(), IBKRApiLibError> {
+        let configuration = configuration()?;
+        for fa_data in [
+            FaDataType::GROUPS,
+            FaDataType::PROFILES,
+            FaDataType::ALIASES,
+        ]
+        .iter()
+        {
+            let xml = configuration.to_xml(fa_data.clone())?;
+            let mut parsed = FaConfiguration::new();
+            parsed.receive_fa(fa_data.clone(), xml.as_str())?;
+            match fa_data {
+                FaDataType::GROUPS => assert_eq!(configuration.groups, parsed.groups),
+                FaDataType::PROFILES => assert_eq!(configuration.profiles, parsed.profiles),
+                _ => assert_eq!(configuration.aliases, parsed.aliases),
+            }
+        }
+        assert!(configuration.to_xml(FaDataType::NA).is_err());
+
+        // names are escaped on the way out
+        let groups = vec![FaGroup::new(
+            "R&D <west>",
+            vec!["DU119915".to_string()],
+            Method::NetLiq,
+        )];
+        let xml = groups_to_xml(groups.as_slice());
+        assert!(xml.contains("<name>R&amp;D &lt;west&gt;</name>"));
+        assert_eq!(groups, groups_from_xml(xml.as_str())?);
+
+        let profiles = vec![FaProfile::new(
+            "Shares",
+            FaProfileType::Shares,
+            vec![FaAllocation::new("DU119915", 100.0)],
+        )];
+        let xml = profiles_to_xml(profiles.as_slice());
+        assert!(xml.contains("<type>3</type>"));
+        assert!(xml.contains("<amount>100.0</amount>"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_fa_validation() -> Result<(), IBKRApiLibError> {
+        let mut configuration = configuration()?;
+        configuration.validate(MANAGED_ACCOUNTS)?;
+
+        configuration.profiles[0].allocations[1].amount = 30.0;
+        configuration.groups[0]
+            .accounts
+            .push("DU999999".to_string());
+        configuration
+            .aliases
+            .push(FaAlias::new("DU119915", "Other"));
+        match configuration.validate(MANAGED_ACCOUNTS) {
+            Err(IBKRApiLibError::ApiError(err)) => {
+                assert!(err
+                    .description
+                    .contains("profile 'Percent_60_40': percentages add up to 90 instead of 100"));
+                assert!(err
+                    .description
+                    .contains("group 'Equal_Quantity': account DU999999 is not managed"));
+                assert!(err
+                    .description
+                    .contains("account DU119915 has more than one alias"));
+            }
+            _ => panic!("expected a validation error"),
+        }
+
+        // ratio profiles don't need to add up to 100
+        let mut configuration = FaConfiguration::new();
+        configuration.profiles.push(FaProfile::new(
+            "Ratios",
+            FaProfileType::Ratios,
+            vec![
+                FaAllocation::new("DU119915", 2.0),
+                FaAllocation::new("DU119916", 1.0),
+            ],
+        ));
+        configuration.validate(MANAGED_ACCOUNTS)?;
+        configuration
+            .groups
+            .push(FaGroup::new("Empty", vec![], Method::EqualQuantity));
+        assert!(configuration.validate(MANAGED_ACCOUNTS).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_fa_diff() -> Result<(), IBKRApiLibError> {
+        let current = configuration()?;
+        assert!(current.diff(&current).is_empty());
+
+        let mut new = current.clone();
+        new.groups[0]
+            .accounts
+            .retain(|account| account != "DU119916");
+        new.groups[0].accounts.push("DU119917".to_string());
+        new.groups[1].default_method = Method::NetLiq;
+        new.groups.push(FaGroup::new(
+            "Net_Liq",
+            vec!["DU119917".to_string()],
+            Method::NetLiq,
+        ));
+        new.profiles.remove(1);
+        new.profiles[0].allocations[0].amount = 50.0;
+        new.profiles[0].allocations[1].amount = 50.0;
+        new.aliases[0].alias = "Primary".to_string();
+
+        let changes = current.diff(&new);
+        assert_eq!(
+            vec![
+                FaChange::GroupAccountAdded {
+                    group: "Equal_Quantity".to_string(),
+                    account: "DU119917".to_string()
+                },
+                FaChange::GroupAccountRemoved {
+                    group: "Equal_Quantity".to_string(),
+                    account: "DU119916".to_string()
+                },
+                FaChange::GroupMethodChanged {
+                    group: "Pct_Change".to_string(),
+                    from: Method::PctChange,
+                    to: Method::NetLiq
+                },
+                FaChange::GroupAdded(new.groups[2].clone()),
+                FaChange::AllocationChanged {
+                    profile: "Percent_60_40".to_string(),
+                    account: "DU119915".to_string(),
+                    from: Some(60.0),
+                    to: Some(50.0)
+                },
+                FaChange::AllocationChanged {
+                    profile: "Percent_60_40".to_string(),
+                    account: "DU119916".to_string(),
+                    from: Some(40.0),
+                    to: Some(50.0)
+                },
+                FaChange::ProfileRemoved("Ratios_2_1".to_string()),
+                FaChange::AliasChanged {
+                    account: "DU119915".to_string(),
+                    from: "Main".to_string(),
+                    to: "Primary".to_string()
+                },
+            ],
+            changes
+        );
+        assert_eq!(
+            "group Pct_Change: default method PctChange -> NetLiq",
+            changes[2].to_string()
+        );
+        Ok(())
+    }
+}
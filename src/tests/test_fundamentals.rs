@@ -0,0 +1,208 @@
+#[cfg(all(test, feature = "fundamentals"))]
+mod tests {
+
+    use crate::core::common::FundamentalType;
+    use crate::core::errors::IBKRApiLibError;
+    use crate::core::fundamentals::{
+        parse_fundamental_data, AnalystEstimates, CompanyCalendar, CompanySnapshot,
+        FinancialStatements, FinancialSummary, FundamentalReport, RatioReport, StatementType,
+    };
+
+    const SNAPSHOT: &str = include_str!("fixtures/fundamentals/report_snapshot.xml");
+    const FIN_SUMMARY: &str = include_str!("fixtures/fundamentals/fin_summary.xml");
+    const FIN_STATEMENTS: &str = include_str!("fixtures/fundamentals/fin_statements.xml");
+    const RESC: &str = include_str!("fixtures/fundamentals/resc.xml");
+    const CALENDAR: &str = include_str!("fixtures/fundamentals/calendar.xml");
+    const RATIOS: &str = include_str!("fixtures/fundamentals/ratios.xml");
+
+    #[test]
+    fn test_company_snapshot() -> Result<(), IBKRApiLibError> {
+        let snapshot = CompanySnapshot::from_xml(SNAPSHOT)?;
+        assert_eq!(
+            "International Business Machines Corp.",
+            snapshot.company_name
+        );
+        assert_eq!("05680", snapshot.company_ids["RepNo"]);
+        assert_eq!("IBM", snapshot.ticker);
+        assert_eq!("NYSE", snapshot.exchange);
+        assert_eq!(Some(352600.0), snapshot.employees);
+        assert_eq!(Some(887110455.0), snapshot.shares_outstanding);
+        assert_eq!("USD", snapshot.reporting_currency);
+        assert!(snapshot
+            .business_summary
+            .starts_with("International Business"));
+        assert_eq!("https://www.ibm.com/", snapshot.web_site);
+        assert_eq!(2, snapshot.industries.len());
+        assert_eq!(
+            "IT Services & Consulting - NEC",
+            snapshot.industries[0].description
+        );
+        assert_eq!(2, snapshot.officers.len());
+        assert_eq!("Krishna", snapshot.officers[0].last_name);
+        assert_eq!("58", snapshot.officers[0].age);
+        assert_eq!(2, snapshot.ratios.len());
+        assert_eq!(
+            Some(8.81),
+            snapshot
+                .ratio("TTMEPSXCLX")
+                .and_then(|ratio| ratio.numeric_value())
+        );
+        assert_eq!(None, snapshot.ratio("PDATE").unwrap().numeric_value());
+        assert_eq!("136.58333", snapshot.forecasts[1].value);
+        Ok(())
+    }
+
+    #[test]
+    fn test_financial_summary() -> Result<(), IBKRApiLibError> {
+        let summary = FinancialSummary::from_xml(FIN_SUMMARY)?;
+        assert_eq!("USD", summary.currency);
+        assert_eq!(3, summary.earnings_per_share.len());
+        assert_eq!("12M", summary.earnings_per_share[2].period);
+        assert_eq!(10.56, summary.earnings_per_share[2].value);
+        assert_eq!(2, summary.dividends_per_share.len());
+        assert_eq!(18123000000.0, summary.total_revenues[0].value);
+        assert_eq!("2020-08-07", summary.dividends[0].ex_date);
+        assert_eq!(1.63, summary.dividends[0].value);
+        Ok(())
+    }
+
+    #[test]
+    fn test_financial_statements() -> Result<(), IBKRApiLibError> {
+        let statements = FinancialStatements::from_xml(FIN_STATEMENTS)?;
+        assert_eq!(Some("Revenue"), statements.describe("SREV"));
+        assert_eq!(
+            StatementType::BalanceSheet,
+            statements.coa_map["ATOT"].statement_type
+        );
+        assert_eq!(2, statements.annual_periods.len());
+        assert_eq!(1, statements.interim_periods.len());
+
+        let fy2019 = &statements.annual_periods[0];
+        assert_eq!(2019, fy2019.fiscal_year);
+        assert_eq!(3, fy2019.statements.len());
+        assert_eq!(
+            Some(77147.0),
+            fy2019.line_item(StatementType::Income, "SREV")
+        );
+        assert_eq!(
+            Some(152186.0),
+            fy2019.line_item(StatementType::BalanceSheet, "ATOT")
+        );
+        assert_eq!(None, fy2019.line_item(StatementType::BalanceSheet, "SREV"));
+        let income = fy2019.statement(StatementType::Income).unwrap();
+        assert_eq!(Some(12), income.period_length);
+        assert_eq!("M", income.period_unit);
+        assert_eq!("10-K", income.source);
+
+        let q2 = &statements.interim_periods[0];
+        assert_eq!(Some(2), q2.fiscal_period_number);
+        assert_eq!(Some(1361.0), q2.line_item(StatementType::Income, "NINC"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_analyst_estimates() -> Result<(), IBKRApiLibError> {
+        let estimates = AnalystEstimates::from_xml(RESC)?;
+        assert_eq!(
+            "International Business Machines Corp.",
+            estimates.company_name
+        );
+        assert_eq!(2, estimates.actuals.len());
+        assert_eq!(12.81, estimates.actuals[0].value);
+        assert_eq!(Some(2), estimates.actuals[1].period_number);
+
+        assert_eq!(1, estimates.estimates.len());
+        let eps = estimates.annual_estimate("EPS", 2020).unwrap();
+        assert_eq!(Some(11.36), eps.high);
+        assert_eq!(Some(10.60), eps.low);
+        assert_eq!(Some(11.01), eps.mean);
+        assert_eq!(Some(11.00), eps.median);
+        assert_eq!(Some(0.21), eps.std_dev);
+        assert_eq!(Some(12.0), eps.number_of_estimates);
+        assert!(estimates.annual_estimate("EPS", 2021).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_ratios_and_calendar() -> Result<(), IBKRApiLibError> {
+        let ratios = RatioReport::from_xml(RATIOS)?;
+        assert_eq!(2, ratios.groups.len());
+        assert_eq!(
+            Some(5.28),
+            ratios
+                .ratio("DIVYIELD")
+                .and_then(|ratio| ratio.numeric_value())
+        );
+
+        let calendar = CompanyCalendar::from_xml(CALENDAR)?;
+        assert_eq!(2, calendar.events.len());
+        let earnings = calendar.events_of_kind("Earnings").collect::<Vec<_>>();
+        assert_eq!(1, earnings.len());
+        assert_eq!("10/19/2020", earnings[0].fields["Date"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_fundamental_data() -> Result<(), IBKRApiLibError> {
+        match parse_fundamental_data(&FundamentalType::ReportsFinStatements, FIN_STATEMENTS)? {
+            FundamentalReport::FinancialStatements(statements) => {
+                assert_eq!(2, statements.annual_periods.len())
+            }
+            other => panic!("unexpected report {:?}", other),
+        }
+        match parse_fundamental_data(&FundamentalType::RESC, RESC)? {
+            FundamentalReport::Estimates(estimates) => assert_eq!(1, estimates.estimates.len()),
+            other => panic!("unexpected report {:?}", other),
+        }
+        assert!(parse_fundamental_data(&FundamentalType::ReportsOwnership, SNAPSHOT).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_fundamentals_errors() {
+        let error = |result: Result<FundamentalReport, IBKRApiLibError>| match result {
+            Err(IBKRApiLibError::ApiError(err)) => err.description,
+            other => panic!("expected an error, got {:?}", other),
+        };
+
+        // report type doesn't match the document
+        let description = error(parse_fundamental_data(
+            &FundamentalType::ReportSnapshot,
+            FIN_SUMMARY,
+        ));
+        assert!(description.contains("expected root element <ReportSnapshot>"));
+        assert!(description.contains("<FinancialSummary>"));
+
+        // unknown statement type
+        let xml = FIN_STATEMENTS.replace("Statement Type=\"CAS\"", "Statement Type=\"XYZ\"");
+        let description = error(parse_fundamental_data(
+            &FundamentalType::ReportsFinStatements,
+            xml.as_str(),
+        ));
+        assert!(description.contains("unknown statement type 'XYZ'"));
+        assert!(description.contains("AnnualPeriods/FiscalPeriod/Statement"));
+
+        // element that doesn't belong in a statement
+        let xml = FIN_STATEMENTS.replace(
+            "<lineItem coaCode=\"ATOT\">152186.0</lineItem>",
+            "<bogus>1</bogus>",
+        );
+        let description = error(parse_fundamental_data(
+            &FundamentalType::ReportsFinStatements,
+            xml.as_str(),
+        ));
+        assert!(description.contains("unexpected element <bogus>"));
+
+        // non-numeric value
+        let xml = FIN_SUMMARY.replace(">1.52<", ">n/a<");
+        let description = error(parse_fundamental_data(
+            &FundamentalType::ReportsFinSummary,
+            xml.as_str(),
+        ));
+        assert!(description.contains("'n/a' is not a number"));
+        assert!(description.contains("FinancialSummary/EPSs/EPS"));
+
+        // not XML at all
+        assert!(parse_fundamental_data(&FundamentalType::RESC, "not xml").is_err());
+    }
+}
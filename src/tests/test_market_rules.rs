@@ -0,0 +1,171 @@
+#[cfg(test)]
+mod tests {
+    use crate::core::common::{PriceIncrement, UNSET_DOUBLE};
+    use crate::core::contract::ContractDetails;
+    use crate::core::errors::IBKRApiLibError;
+    use crate::core::market_rules::{
+        is_on_grid, round_to_increments, MarketRules, PriceCheck, RoundingDirection,
+    };
+    use crate::core::order::Order;
+    use crate::examples::contract_samples;
+
+    fn stock_details() -> ContractDetails {
+        let mut details = ContractDetails::default();
+        details.contract = contract_samples::usstock();
+        details.contract.con_id = 8314;
+        details.contract.exchange = "SMART".to_string();
+        details.valid_exchanges = "SMART,AMEX,NYSE,ISLAND".to_string();
+        details.market_rule_ids = "26,26,26,239".to_string();
+        details
+    }
+
+    fn tiered() -> Vec<PriceIncrement> {
+        vec![
+            PriceIncrement::new(0.0, 0.0001),
+            PriceIncrement::new(1.0, 0.01),
+        ]
+    }
+
+    #[test]
+    fn test_rounding() {
+        let increments = tiered();
+        assert_eq!(
+            Some(101.23),
+            round_to_increments(&increments, 101.2345, RoundingDirection::Down)
+        );
+        assert_eq!(
+            Some(101.24),
+            round_to_increments(&increments, 101.2345, RoundingDirection::Up)
+        );
+        assert_eq!(
+            Some(101.23),
+            round_to_increments(&increments, 101.2345, RoundingDirection::Nearest)
+        );
+        assert_eq!(
+            Some(0.1235),
+            round_to_increments(&increments, 0.12345, RoundingDirection::Up)
+        );
+        // prices already on the grid are not moved by floating point noise
+        assert_eq!(
+            Some(0.3),
+            round_to_increments(&increments, 0.1 + 0.2, RoundingDirection::Up)
+        );
+        assert!(is_on_grid(&increments, 101.23));
+        assert!(!is_on_grid(&increments, 101.235));
+        assert!(is_on_grid(&increments, -2.5));
+        assert_eq!(None, round_to_increments(&[], 1.0, RoundingDirection::Up));
+
+        assert_eq!(RoundingDirection::Down, RoundingDirection::passive("BUY"));
+        assert_eq!(RoundingDirection::Up, RoundingDirection::passive("SELL"));
+    }
+
+    #[test]
+    fn test_market_rules() -> Result<(), IBKRApiLibError> {
+        let mut rules = MarketRules::new();
+        let details = stock_details();
+        assert_eq!(vec![26, 239], rules.add_contract_details(&details)?);
+        let contract = &details.contract;
+        assert_eq!(Some(26), rules.rule_id(contract, ""));
+        assert_eq!(Some(239), rules.rule_id(contract, "island"));
+        assert!(rules
+            .round_price(contract, "", 10.0, RoundingDirection::Up)
+            .is_err());
+
+        assert!(rules.market_rule(26, vec![PriceIncrement::new(0.0, 0.01)]));
+        assert!(!rules.market_rule(
+            7,
+            vec![
+                PriceIncrement::new(0.0, 0.0001),
+                PriceIncrement::new(1.0, 0.01)
+            ]
+        ));
+        assert_eq!(
+            10.13,
+            rules.round_price(contract, "SMART", 10.125, RoundingDirection::Up)?
+        );
+
+        // unknown exchanges fall back to the primary exchange
+        let mut routed = contract.clone();
+        routed.exchange = "ARCA".to_string();
+        routed.primary_exchange = "NYSE".to_string();
+        assert_eq!(Some(26), rules.rule_id(&routed, ""));
+        routed.primary_exchange = "".to_string();
+        assert!(rules
+            .round_price(&routed, "", 10.0, RoundingDirection::Up)
+            .is_err());
+
+        let mut inconsistent = stock_details();
+        inconsistent.market_rule_ids = "26".to_string();
+        assert!(rules.add_contract_details(&inconsistent).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_order_price_check() -> Result<(), IBKRApiLibError> {
+        let mut rules = MarketRules::new();
+        rules.add_contract_details(&stock_details())?;
+        rules.market_rule(26, vec![PriceIncrement::new(0.0, 0.01)]);
+        let contract = stock_details().contract;
+
+        let mut order = Order::default();
+        order.order_id = 12;
+        order.action = "BUY".to_string();
+        order.lmt_price = 10.127;
+        assert!(rules
+            .check_order(&contract, &mut order.clone(), PriceCheck::Reject)
+            .is_err());
+        rules.check_order(&contract, &mut order, PriceCheck::Snap)?;
+        assert_eq!(10.12, order.lmt_price);
+        assert_eq!(UNSET_DOUBLE, order.aux_price);
+
+        order.action = "SELL".to_string();
+        order.order_type = "STP LMT".to_string();
+        order.aux_price = 9.991;
+        match rules.check_order(&contract, &mut order, PriceCheck::Reject) {
+            Err(IBKRApiLibError::ApiError(err)) => {
+                assert_eq!(12, err.req_id);
+                assert!(err.description.contains("aux_price 9.991"));
+            }
+            _ => panic!("expected a rejected order"),
+        }
+        assert_eq!(9.991, order.aux_price);
+        rules.check_order(&contract, &mut order, PriceCheck::Snap)?;
+        assert_eq!(10.0, order.aux_price);
+
+        // the aux_price of a trailing order is an offset, not a price on the grid
+        let mut trail = Order::default();
+        trail.action = "SELL".to_string();
+        trail.order_type = "TRAIL".to_string();
+        trail.aux_price = 0.125;
+        rules.check_order(&contract, &mut trail, PriceCheck::Reject)?;
+        rules.check_order(&contract, &mut trail, PriceCheck::Snap)?;
+        assert_eq!(0.125, trail.aux_price);
+        Ok(())
+    }
+
+    #[test]
+    fn test_unusable_rules_are_errors() -> Result<(), IBKRApiLibError> {
+        let contract = stock_details().contract;
+        let mut order = Order::default();
+        order.action = "BUY".to_string();
+        order.lmt_price = 10.127;
+
+        for increments in vec![
+            vec![],
+            vec![PriceIncrement::new(0.0, 0.0)],
+            vec![
+                PriceIncrement::new(std::f64::NAN, 0.01),
+                PriceIncrement::new(0.0, 0.0),
+            ],
+        ] {
+            let mut rules = MarketRules::new();
+            rules.add_contract_details(&stock_details())?;
+            rules.market_rule(26, increments);
+            for check in [PriceCheck::Snap, PriceCheck::Reject].iter() {
+                assert!(rules.check_order(&contract, &mut order, *check).is_err());
+                assert_eq!(10.127, order.lmt_price);
+            }
+        }
+        Ok(())
+    }
+}
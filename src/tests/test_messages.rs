@@ -1,13 +1,22 @@
 #[cfg(test)]
 mod tests {
+    use std::io::Write;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::mpsc::channel;
+    use std::sync::{Arc, Mutex};
 
-    use crate::core::common::{TickByTickType, UNSET_DOUBLE, UNSET_INTEGER};
-    use crate::core::errors::IBKRApiLibError;
+    use crate::core::client::ConnStatus;
+    use crate::core::common::{TickByTickType, NO_VALID_ID, UNSET_DOUBLE, UNSET_INTEGER};
+    use crate::core::decoder::Decoder;
+    use crate::core::errors::{IBKRApiLibError, TwsError};
     use crate::core::messages::{
-        make_field, make_field_handle_empty, make_message, read_fields, read_msg,
-        OutgoingMessageIds,
+        make_field, make_field_handle_empty, make_message, read_fields, read_msg, read_msg_slice,
+        with_fields, OutgoingMessageIds,
     };
+    use crate::core::reader::Reader;
+    use crate::core::streamer::TestStreamer;
     use crate::examples::contract_samples;
+    use crate::tests::recording_wrapper::RecordingWrapper;
     #[test]
     fn test_make_field() -> Result<(), IBKRApiLibError> {
         assert_eq!("1\u{0}", make_field(&true)?);
@@ -92,4 +101,62 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_read_msg_slice() -> Result<(), IBKRApiLibError> {
+        let mut packet = make_message("49\u{0}2\u{0}")?;
+        packet.extend(make_message("4\u{0}2\u{0}-1\u{0}2104\u{0}OK\u{0}")?);
+        packet.extend_from_slice(&[0, 0, 0, 9, 52]);
+
+        let (first, remaining) = read_msg_slice(&packet)?.unwrap();
+        assert_eq!("49\u{0}2\u{0}", first);
+        let (second, remaining) = read_msg_slice(remaining)?.unwrap();
+        assert_eq!("4\u{0}2\u{0}-1\u{0}2104\u{0}OK\u{0}", second);
+        // only part of the third message has arrived
+        assert_eq!(None, read_msg_slice(remaining)?);
+        assert_eq!(None, read_msg_slice(&remaining[..3])?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_reader_skips_malformed_message() -> Result<(), IBKRApiLibError> {
+        let mut stream = TestStreamer::new();
+        // a whole frame that isn't UTF-8, then a valid message
+        stream.write_all(&[0, 0, 0, 3, 0xff, 0xfe, 0])?;
+        stream.write_all(&make_message("49\u{0}1\u{0}1596974400\u{0}")?)?;
+        let (tx, rx) = channel::<String>();
+        let mut reader = Reader::new(Box::new(stream), tx, Arc::new(AtomicBool::new(false)));
+        reader.run();
+        drop(reader);
+
+        let mut decoder = Decoder::with_handler(
+            RecordingWrapper::new(),
+            rx,
+            151,
+            Arc::new(Mutex::new(ConnStatus::CONNECTED)),
+        );
+        decoder.run()?;
+        let wrapper = decoder.into_handler().unwrap();
+        assert_eq!(1, wrapper.errors.len());
+        assert_eq!(NO_VALID_ID, wrapper.errors[0].0);
+        assert_eq!(TwsError::BadMessage.code(), wrapper.errors[0].1);
+        assert_eq!(1, wrapper.count("current_time"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_with_fields() {
+        for msg in [
+            "",
+            "1\u{0}",
+            "4\u{0}2\u{0}-1\u{0}\u{0}OK\u{0}",
+            &"7\u{0}".repeat(63),
+            &"7\u{0}".repeat(64),
+            &"7\u{0}".repeat(65),
+            &"7\u{0}".repeat(200),
+        ] {
+            let expected = read_fields(msg);
+            with_fields(msg, |fields| assert_eq!(expected, fields));
+        }
+    }
 }
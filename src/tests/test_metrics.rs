@@ -0,0 +1,228 @@
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::sync::atomic::AtomicBool;
+    use std::sync::mpsc::channel;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use crate::core::client::{ConnStatus, EClient, POISONED_MUTEX};
+    use crate::core::decoder::Decoder;
+    use crate::core::errors::IBKRApiLibError;
+    use crate::core::messages::make_message;
+    use crate::core::metrics::{MetricsSink, SubscriptionKind};
+    use crate::core::reader::Reader;
+    use crate::core::streamer::{Streamer, TestStreamer};
+    use crate::examples::contract_samples;
+    use crate::tests::recording_wrapper::RecordingWrapper;
+
+    /// Records the events it receives
+    #[derive(Default)]
+    struct EventLog {
+        events: Mutex<Vec<String>>,
+    }
+
+    impl EventLog {
+        fn push(&self, event: String) {
+            self.events.lock().unwrap().push(event);
+        }
+
+        fn events(&self) -> Vec<String> {
+            self.events.lock().unwrap().clone()
+        }
+    }
+
+    impl MetricsSink for EventLog {
+        fn message_dequeued(&self) {
+            self.push("dequeued".to_string());
+        }
+
+        fn message_received(&self, msg_id: i32, _decode_time: Duration) {
+            self.push(format!("received {}", msg_id));
+        }
+
+        fn message_sent(&self, msg_id: i32) {
+            self.push(format!("sent {}", msg_id));
+        }
+
+        fn bytes_sent(&self, count: usize) {
+            self.push(format!("bytes_sent {}", count));
+        }
+
+        fn disconnected(&self) {
+            self.push("disconnected".to_string());
+        }
+
+        fn error_received(&self, req_id: i32, code: i32) {
+            self.push(format!("error {} {}", req_id, code));
+        }
+
+        fn subscribed(&self, kind: SubscriptionKind, req_id: i32) {
+            self.push(format!("subscribed {} {}", kind, req_id));
+        }
+
+        fn unsubscribed(&self, kind: SubscriptionKind, req_id: i32) {
+            self.push(format!("unsubscribed {} {}", kind, req_id));
+        }
+    }
+
+    fn connected_client(log: &Arc<EventLog>) -> EClient<RecordingWrapper> {
+        let mut client = EClient::new(Arc::new(Mutex::new(RecordingWrapper::new())));
+        *client.conn_state.lock().expect(POISONED_MUTEX) = ConnStatus::CONNECTED;
+        client.set_streamer(Some(Box::new(TestStreamer::new()) as Box<dyn Streamer>));
+        client.server_version = 151;
+        client.set_metrics(Some(log.clone()));
+        client
+    }
+
+    #[test]
+    fn test_client_reports_requests_and_subscriptions() -> Result<(), IBKRApiLibError> {
+        let log = Arc::new(EventLog::default());
+        let mut client = connected_client(&log);
+        let contract = contract_samples::usstock();
+
+        client.req_mkt_data(100, &contract, "", false, false, vec![])?;
+        let mut buf = vec![];
+        client.stream.as_mut().unwrap().read_to_end(&mut buf)?;
+        assert_eq!(
+            vec![
+                format!("bytes_sent {}", buf.len()),
+                "sent 1".to_string(),
+                "subscribed mkt_data 100".to_string(),
+            ],
+            log.events()
+        );
+
+        // snapshots end by themselves and are not subscriptions
+        client.req_mkt_data(101, &contract, "", true, false, vec![])?;
+        client.cancel_mkt_data(100)?;
+        client.req_account_updates(true, "DU123")?;
+        client.req_account_updates(false, "DU123")?;
+        // reported by the reader, which the test client doesn't have
+        client.disconnect()?;
+        let events = log.events();
+        let tracked = events
+            .iter()
+            .filter(|event| !event.starts_with("bytes_sent"))
+            .skip(2)
+            .cloned()
+            .collect::<Vec<String>>();
+        assert_eq!(
+            vec![
+                "sent 1",
+                "sent 2",
+                "unsubscribed mkt_data 100",
+                "sent 6",
+                "subscribed account_updates -1",
+                "sent 6",
+                "unsubscribed account_updates -1",
+            ],
+            tracked
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_decoder_reports_messages_and_errors() -> Result<(), IBKRApiLibError> {
+        let log = Arc::new(EventLog::default());
+        let wrapper = Arc::new(Mutex::new(RecordingWrapper::new()));
+        let (tx, rx) = channel::<String>();
+        let mut decoder = Decoder::new(
+            wrapper.clone(),
+            rx,
+            151,
+            Arc::new(Mutex::new(ConnStatus::CONNECTED)),
+        );
+        decoder.set_metrics(Some(log.clone()));
+
+        // error message: id, version, req_id, code, text
+        tx.send("4\x002\x00100\x00200\x00No security definition\x00".to_string())
+            .unwrap();
+        drop(tx);
+        decoder.run()?;
+
+        assert_eq!(
+            vec!["dequeued", "error 100 200", "received 4"],
+            log.events()
+        );
+        let wrapper = wrapper.lock().unwrap();
+        assert_eq!(1, wrapper.errors.len());
+        assert_eq!(1, wrapper.count("connection_closed"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_reader_reports_disconnect_once() {
+        let log = Arc::new(EventLog::default());
+        let mut stream = TestStreamer::new();
+        stream
+            .write_all(&make_message("4\x002\x00-1\x002104\x00OK\x00").unwrap())
+            .unwrap();
+        let (tx, rx) = channel::<String>();
+        let mut reader = Reader::new(Box::new(stream), tx, Arc::new(AtomicBool::new(false)));
+        reader.set_metrics(Some(log.clone()));
+
+        // the message, then 0 bytes once the peer has closed the connection
+        reader.run();
+        assert_eq!(1, rx.try_iter().count());
+        assert_eq!(vec!["disconnected"], log.events());
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn test_prometheus_scrape() -> Result<(), IBKRApiLibError> {
+        use std::net::TcpStream;
+
+        use crate::core::prometheus_metrics::PrometheusMetrics;
+
+        let metrics = PrometheusMetrics::new()?;
+        let gateway = metrics.connection("gw1");
+        let other = metrics.connection("gw2");
+        gateway.connected();
+        gateway.disconnected();
+        gateway.connected();
+        gateway.message_queued();
+        gateway.message_queued();
+        gateway.message_dequeued();
+        gateway.message_received(4, Duration::from_micros(20));
+        gateway.message_sent(1);
+        gateway.bytes_received(120);
+        gateway.bytes_sent(40);
+        gateway.error_received(100, 200);
+        gateway.subscribed(SubscriptionKind::MktData, 100);
+        gateway.subscribed(SubscriptionKind::MktData, 100);
+        gateway.subscribed(SubscriptionKind::MktData, 101);
+        gateway.unsubscribed(SubscriptionKind::MktData, 101);
+        // TWS ends a subscription with an error, but not with a warning
+        gateway.subscribed(SubscriptionKind::MktData, 102);
+        gateway.error_received(102, 354);
+        gateway.error_received(100, 10167);
+        other.subscribed(SubscriptionKind::TickByTick, 7);
+
+        let mut server = metrics.serve("127.0.0.1:0")?;
+        let mut stream = TcpStream::connect(server.local_addr())?;
+        stream.write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n")?;
+        let mut response = String::new();
+        stream.read_to_string(&mut response)?;
+        server.stop();
+
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        for line in &[
+            "twsapi_connects_total{connection=\"gw1\"} 2",
+            "twsapi_reconnects_total{connection=\"gw1\"} 1",
+            "twsapi_disconnects_total{connection=\"gw1\"} 1",
+            "twsapi_queue_depth{connection=\"gw1\"} 1",
+            "twsapi_messages_received_total{connection=\"gw1\",message=\"ErrMsg\"} 1",
+            "twsapi_messages_sent_total{connection=\"gw1\",message=\"ReqMktData\"} 1",
+            "twsapi_bytes_received_total{connection=\"gw1\"} 120",
+            "twsapi_bytes_sent_total{connection=\"gw1\"} 40",
+            "twsapi_errors_total{code=\"200\",connection=\"gw1\"} 1",
+            "twsapi_subscriptions{connection=\"gw1\",kind=\"mkt_data\"} 1",
+            "twsapi_subscriptions{connection=\"gw2\",kind=\"tick_by_tick\"} 1",
+            "twsapi_decode_seconds_count{connection=\"gw1\"} 1",
+        ] {
+            assert!(response.contains(line), "{} not in\n{}", line, response);
+        }
+        Ok(())
+    }
+}
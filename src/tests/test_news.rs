@@ -0,0 +1,194 @@
+#[cfg(test)]
+mod tests {
+
+    use crate::core::common::NewsProvider;
+    use crate::core::errors::IBKRApiLibError;
+    use crate::core::news::{
+        HistoricalNewsQuery, NewsArticle, NewsClient, ARTICLE_TYPE_BINARY, ARTICLE_TYPE_TEXT,
+    };
+
+    fn news_client() -> NewsClient {
+        let mut news = NewsClient::new(100);
+        news.news_providers(vec![
+            NewsProvider::new(
+                "BRFG".to_string(),
+                "Briefing.com General Market Columns".to_string(),
+            ),
+            NewsProvider::new("DJNL".to_string(), "Dow Jones Newsletters".to_string()),
+        ]);
+        news
+    }
+
+    #[test]
+    fn test_historical_news_paging() {
+        let mut news = news_client();
+        let req_id = news.start_historical(HistoricalNewsQuery::new(
+            8314,
+            vec!["BRFG".to_string(), "DJNL".to_string()],
+            "",
+            "2020-08-10 00:00:00.0",
+            2,
+        ));
+        assert_eq!(100, req_id);
+        assert!(news.owns_request(req_id));
+        assert_eq!(1, news.next_page_requests().len());
+        assert!(news.next_page_requests().is_empty());
+
+        assert!(!news.historical_news(1, "2020-08-09 10:00:00.0", "BRFG", "BRFG$1", "x"));
+        news.historical_news(
+            req_id,
+            "2020-08-09 10:00:00.0",
+            "BRFG",
+            "BRFG$1",
+            "IBM beats",
+        );
+        news.historical_news(
+            req_id,
+            "2020-08-08 09:00:00.0",
+            "BRFG",
+            "BRFG$2",
+            "Market wrap",
+        );
+        assert!(news.historical_news_end(req_id, true));
+        assert!(!news.historical_complete(req_id));
+
+        // the next page ends at the oldest headline received so far
+        let requests = news.next_page_requests();
+        assert_eq!(1, requests.len());
+        assert_eq!("2020-08-08 09:00:00.0", requests[0].1.end_date_time);
+
+        // the boundary article is repeated and the same story arrives from another provider
+        news.historical_news(
+            req_id,
+            "2020-08-08 09:00:00.0",
+            "BRFG",
+            "BRFG$2",
+            "Market wrap",
+        );
+        news.historical_news(
+            req_id,
+            "2020-08-08 08:59:00.0",
+            "DJNL",
+            "DJNL$7",
+            "IBM  Beats",
+        );
+        news.historical_news(
+            req_id,
+            "2020-08-07 16:00:00.0",
+            "DJNL",
+            "DJNL$8",
+            "Oil slides",
+        );
+        news.historical_news_end(req_id, false);
+        assert!(news.historical_complete(req_id));
+        assert!(news.next_page_requests().is_empty());
+        assert_eq!(2, news.historical_pages(req_id));
+
+        let headlines = news.historical_headlines(req_id).unwrap();
+        assert_eq!(
+            vec!["IBM beats", "Market wrap", "Oil slides"],
+            headlines
+                .iter()
+                .map(|headline| headline.headline.as_str())
+                .collect::<Vec<&str>>()
+        );
+        assert_eq!(
+            "Briefing.com General Market Columns",
+            headlines[0].provider_name
+        );
+        assert_eq!(
+            vec![("DJNL".to_string(), "DJNL$7".to_string())],
+            headlines[0].other_sources
+        );
+    }
+
+    #[test]
+    fn test_historical_news_stops_without_progress() {
+        let mut news = news_client();
+        let req_id = news.start_historical(HistoricalNewsQuery::new(
+            8314,
+            vec!["BRFG".to_string()],
+            "",
+            "",
+            10,
+        ));
+        news.historical_news(
+            req_id,
+            "2020-08-09 10:00:00.0",
+            "BRFG",
+            "BRFG$1",
+            "IBM beats",
+        );
+        news.historical_news_end(req_id, true);
+        assert_eq!(1, news.next_page_requests().len());
+        // TWS claims more but sends nothing new
+        news.historical_news_end(req_id, true);
+        assert!(news.historical_complete(req_id));
+    }
+
+    #[test]
+    fn test_news_articles() -> Result<(), IBKRApiLibError> {
+        assert_eq!(
+            NewsArticle::Text("<html>hi</html>".to_string()),
+            NewsArticle::from_message(ARTICLE_TYPE_TEXT, "<html>hi</html>")?
+        );
+        assert_eq!(
+            NewsArticle::Pdf(b"%PDF-1.4".to_vec()),
+            NewsArticle::from_message(ARTICLE_TYPE_BINARY, "JVBERi0xLjQ=")?
+        );
+        assert!(NewsArticle::from_message(ARTICLE_TYPE_BINARY, "not base64!").is_err());
+        assert!(NewsArticle::from_message(7, "").is_err());
+
+        let mut news = news_client();
+        assert!(!news.news_article(5, ARTICLE_TYPE_TEXT, "text")?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_live_headlines() {
+        let mut news = news_client();
+        let stream = news.headline_stream();
+        assert!(!news.tick_news(7, 1596974400, "BRFG", "BRFG$1", "IBM beats", ""));
+
+        news.track_live(7);
+        news.track_live(8);
+        assert!(news.tick_news(7, 1596974400, "BRFG", "BRFG$1", "IBM beats", "A:800015"));
+        assert!(news.tick_news(8, 1596974401, "DJNL", "DJNL$7", "IBM beats", ""));
+        assert!(news.tick_news(8, 1596974402, "DJNL", "DJNL$8", "Oil slides", ""));
+
+        let first = stream.try_recv().unwrap();
+        assert_eq!("Briefing.com General Market Columns", first.provider_name);
+        assert_eq!("A:800015", first.extra_data);
+        assert_eq!("Oil slides", stream.try_recv().unwrap().headline);
+        assert!(stream.try_recv().is_err());
+
+        // dropped receivers don't stop delivery to others
+        drop(stream);
+        let stream = news.headline_stream();
+        news.tick_news(8, 1596974403, "DJNL", "DJNL$9", "Gold rallies", "");
+        assert_eq!("Gold rallies", stream.try_recv().unwrap().headline);
+    }
+
+    #[test]
+    fn test_live_headlines_are_forgotten_at_capacity() {
+        let mut news = news_client().with_live_seen_capacity(2);
+        let stream = news.headline_stream();
+        news.track_live(7);
+        news.tick_news(7, 1596974400, "BRFG", "BRFG$1", "IBM beats", "");
+        news.tick_news(7, 1596974401, "BRFG", "BRFG$2", "Oil slides", "");
+        news.tick_news(7, 1596974402, "DJNL", "DJNL$1", "IBM beats", "");
+        news.tick_news(7, 1596974403, "BRFG", "BRFG$3", "Gold rallies", "");
+        // "IBM beats" was the oldest headline and has been forgotten
+        news.tick_news(7, 1596974404, "DJNL", "DJNL$2", "IBM beats", "");
+        news.tick_news(7, 1596974405, "DJNL", "DJNL$3", "Gold rallies", "");
+
+        let headlines = stream
+            .try_iter()
+            .map(|headline| headline.headline)
+            .collect::<Vec<String>>();
+        assert_eq!(
+            vec!["IBM beats", "Oil slides", "Gold rallies", "IBM beats"],
+            headlines
+        );
+    }
+}
@@ -0,0 +1,235 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use bigdecimal::{BigDecimal, FromPrimitive};
+    use chrono::NaiveDate;
+
+    use crate::core::common::{Right, TickType, UNSET_DOUBLE};
+    use crate::core::contract::{Contract, ContractDetails};
+    use crate::core::option_chain::{OptionChain, OptionChainFilter};
+
+    fn underlying() -> Contract {
+        let mut contract = Contract::default();
+        contract.con_id = 265598;
+        contract.symbol = "AAPL".to_string();
+        contract.sec_type = "STK".to_string();
+        contract.exchange = "SMART".to_string();
+        contract.currency = "USD".to_string();
+        contract
+    }
+
+    fn option_details(con_id: i32, expiry: &str, strike: f64, right: &str) -> ContractDetails {
+        let mut details = ContractDetails::default();
+        details.contract.con_id = con_id;
+        details.contract.symbol = "AAPL".to_string();
+        details.contract.sec_type = "OPT".to_string();
+        details.contract.last_trade_date_or_contract_month = expiry.to_string();
+        details.contract.strike = strike;
+        details.contract.right = right.to_string();
+        details
+    }
+
+    fn loaded_chain() -> OptionChain {
+        let mut chain = OptionChain::new(underlying(), 100);
+        let expirations = vec!["20261120".to_string(), "20261218".to_string()]
+            .into_iter()
+            .collect::<HashSet<String>>();
+        let strikes = vec![140.0, 145.0, 150.0, 155.0, 160.0]
+            .into_iter()
+            .map(|strike| BigDecimal::from_f64(strike).unwrap())
+            .collect::<HashSet<BigDecimal>>();
+        assert!(chain.security_definition_option_parameter(
+            100,
+            "SMART",
+            265598,
+            "AAPL",
+            "100",
+            expirations,
+            strikes
+        ));
+        assert!(chain.security_definition_option_parameter_end(100));
+
+        let expirations = chain.params()[0].expirations.clone();
+        let requests = chain.contract_detail_requests("SMART", "AAPL", &expirations, 145.0, 155.0);
+        assert_eq!(2, requests.len());
+        assert_eq!("OPT", requests[0].1.sec_type);
+        assert_eq!("100", requests[0].1.multiplier);
+
+        let mut con_id = 1;
+        for (req_id, contract) in requests.iter() {
+            for strike in &[140.0, 145.0, 150.0, 155.0, 160.0] {
+                for right in &["C", "P"] {
+                    let details = option_details(
+                        con_id,
+                        contract.last_trade_date_or_contract_month.as_ref(),
+                        *strike,
+                        right,
+                    );
+                    assert!(chain.contract_details(*req_id, &details));
+                    con_id += 1;
+                }
+            }
+            assert!(!chain.contracts_complete());
+            assert!(chain.contract_details_end(*req_id));
+        }
+        chain
+    }
+
+    #[test]
+    fn test_params_are_sorted() {
+        let chain = loaded_chain();
+        let params = chain.params_for("SMART", "AAPL").unwrap();
+        assert_eq!(vec!["20261120", "20261218"], params.expirations);
+        assert_eq!(vec![140.0, 145.0, 150.0, 155.0, 160.0], params.strikes);
+        assert_eq!(
+            vec![145.0, 150.0, 155.0, 160.0],
+            params.strikes_around(152.0, 2)
+        );
+    }
+
+    #[test]
+    fn test_strike_window() {
+        let chain = loaded_chain();
+        assert!(chain.contracts_complete());
+        // 2 expiries * 3 strikes * 2 rights
+        assert_eq!(12, chain.quotes().len());
+        assert!(chain.quote("20261120", 140.0, Right::Call).is_none());
+        assert!(chain.quote("20261120", 150.0, Right::Put).is_some());
+    }
+
+    #[test]
+    fn test_failed_contract_request() {
+        let mut chain = OptionChain::new(underlying(), 100);
+        let expirations = vec!["20261120".to_string(), "20261218".to_string()];
+        let requests = chain.contract_detail_requests("SMART", "AAPL", &expirations, 145.0, 155.0);
+        let (first, second) = (requests[0].0, requests[1].0);
+
+        assert!(chain.contract_details(first, &option_details(1, "20261120", 150.0, "C")));
+        assert!(chain.contract_details(first, &option_details(2, "20261120", 150.0, "P")));
+        assert!(chain.contract_details_end(first));
+        assert!(!chain.contracts_complete());
+
+        assert!(!chain.error(9999, 200, "No security definition has been found"));
+        assert!(chain.error(second, 200, "No security definition has been found"));
+        assert!(chain.contracts_complete());
+        assert!(!chain.owns_request(second));
+        assert_eq!(
+            vec![(
+                second,
+                200,
+                "No security definition has been found".to_string()
+            )],
+            chain.failed_contract_requests()
+        );
+        assert_eq!(Right::Put, chain.quotes()[0].right);
+        assert_eq!(2, chain.quotes().len());
+    }
+
+    #[test]
+    fn test_all_contract_requests_failed() {
+        let mut chain = OptionChain::new(underlying(), 100);
+        let requests =
+            chain.contract_detail_requests("SMART", "AAPL", &["20261120".to_string()], 0.0, 1e9);
+        assert!(chain.error(requests[0].0, 200, "No security definition has been found"));
+        assert!(chain.contracts_complete());
+        assert!(chain.quotes().is_empty());
+    }
+
+    #[test]
+    fn test_nan_strike_does_not_panic() {
+        let mut chain = OptionChain::new(underlying(), 100);
+        let requests =
+            chain.contract_detail_requests("SMART", "AAPL", &["20261120".to_string()], 0.0, 1e9);
+        let details = option_details(1, "20261120", f64::NAN, "C");
+        assert!(chain.contract_details(requests[0].0, &details));
+        let details = option_details(2, "20261120", 150.0, "C");
+        assert!(chain.contract_details(requests[0].0, &details));
+        assert!(chain.contract_details_end(requests[0].0));
+        assert_eq!(150.0, chain.quotes()[0].strike);
+    }
+
+    #[test]
+    fn test_greeks_and_filters() {
+        let mut chain = loaded_chain();
+        let requests = chain.market_data_requests();
+        assert_eq!(12, requests.len());
+        assert!(chain.market_data_requests().is_empty());
+
+        let call_150 = chain
+            .quote("20261120", 150.0, Right::Call)
+            .unwrap()
+            .market_data_req_id
+            .unwrap();
+        assert!(chain.tick_option_computation(
+            call_150,
+            TickType::ModelOption,
+            0.25,
+            0.52,
+            4.1,
+            0.0,
+            0.04,
+            0.18,
+            -0.07,
+            150.5
+        ));
+        assert!(!chain.tick_option_computation(
+            9999,
+            TickType::ModelOption,
+            0.25,
+            0.52,
+            4.1,
+            0.0,
+            0.04,
+            0.18,
+            -0.07,
+            150.5
+        ));
+
+        let quote = chain.quote("20261120", 150.0, Right::Call).unwrap();
+        assert_eq!(Some(0.25), quote.implied_vol());
+        assert_eq!(Some(0.52), quote.delta());
+        assert_eq!(Some(-0.07), quote.theta());
+        assert_eq!(Some(150.5), chain.underlying_price());
+
+        let today = NaiveDate::from_ymd_opt(2026, 10, 18).unwrap();
+        let near_calls = chain.filter(
+            &OptionChainFilter::new()
+                .right(Right::Call)
+                .moneyness(0.98, 1.02)
+                .days_to_expiry(0, 40),
+            today,
+        );
+        assert_eq!(1, near_calls.len());
+        assert_eq!(150.0, near_calls[0].strike);
+
+        let all_dec = chain.filter(&OptionChainFilter::new().days_to_expiry(41, 90), today);
+        assert_eq!(6, all_dec.len());
+    }
+
+    #[test]
+    fn test_unset_greeks_are_none() {
+        let mut chain = loaded_chain();
+        chain.market_data_requests();
+        let put_145 = chain
+            .quote("20261218", 145.0, Right::Put)
+            .unwrap()
+            .market_data_req_id
+            .unwrap();
+        chain.tick_option_computation(
+            put_145,
+            TickType::ModelOption,
+            UNSET_DOUBLE,
+            UNSET_DOUBLE,
+            UNSET_DOUBLE,
+            UNSET_DOUBLE,
+            UNSET_DOUBLE,
+            UNSET_DOUBLE,
+            UNSET_DOUBLE,
+            UNSET_DOUBLE,
+        );
+        let quote = chain.quote("20261218", 145.0, Right::Put).unwrap();
+        assert_eq!(None, quote.implied_vol());
+        assert_eq!(None, chain.underlying_price());
+    }
+}
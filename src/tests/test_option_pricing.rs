@@ -0,0 +1,113 @@
+#[cfg(test)]
+mod tests {
+    use crate::core::common::Right;
+    use crate::core::errors::IBKRApiLibError;
+    use crate::core::option_pricing::{
+        binomial, black_scholes, implied_volatility, norm_cdf, price_option, ExerciseStyle,
+        OptionPricingInput,
+    };
+
+    fn input(right: Right, style: ExerciseStyle) -> OptionPricingInput {
+        OptionPricingInput::new(right, style, 100.0, 100.0, 1.0, 0.05, 0.0, 0.2)
+    }
+
+    fn assert_close(expected: f64, actual: f64, tolerance: f64) {
+        assert!(
+            (expected - actual).abs() < tolerance,
+            "expected {} but got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn test_norm_cdf() {
+        assert_close(0.5, norm_cdf(0.0), 1e-15);
+        assert_close(0.975_002_104_851_780, norm_cdf(1.96), 1e-12);
+        assert_close(0.024_997_895_148_220, norm_cdf(-1.96), 1e-12);
+    }
+
+    #[test]
+    fn test_black_scholes() -> Result<(), IBKRApiLibError> {
+        let call = black_scholes(&input(Right::Call, ExerciseStyle::European))?;
+        let put = black_scholes(&input(Right::Put, ExerciseStyle::European))?;
+        assert_close(10.450_583_572_185, call.price, 1e-9);
+        assert_close(5.573_526_022_256, put.price, 1e-9);
+        assert_close(0.636_830_651_175, call.delta, 1e-9);
+        assert_close(call.delta - 1.0, put.delta, 1e-12);
+        assert_close(0.018_762_017_346, call.gamma, 1e-9);
+        assert_close(0.375_240_346_917, call.vega, 1e-9);
+        assert_close(-6.414_027_546_438 / 365.0, call.theta, 1e-9);
+
+        // put-call parity
+        assert_close(
+            call.price - put.price,
+            100.0 - 100.0 * (-0.05_f64).exp(),
+            1e-9,
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_binomial_converges_to_black_scholes() -> Result<(), IBKRApiLibError> {
+        let european = input(Right::Call, ExerciseStyle::European);
+        let tree = binomial(&european, 1000)?;
+        let closed_form = black_scholes(&european)?;
+        assert_close(closed_form.price, tree.price, 1e-2);
+        assert_close(closed_form.delta, tree.delta, 1e-2);
+        assert_close(closed_form.gamma, tree.gamma, 1e-3);
+        assert_close(closed_form.vega, tree.vega, 1e-2);
+        assert_close(closed_form.theta, tree.theta, 1e-3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_american_put_early_exercise() -> Result<(), IBKRApiLibError> {
+        let american = binomial(&input(Right::Put, ExerciseStyle::American), 1000)?;
+        let european = black_scholes(&input(Right::Put, ExerciseStyle::European))?;
+        assert_close(6.09, american.price, 1e-2);
+        assert!(american.price > european.price);
+
+        // without dividends an American call is never exercised early
+        let american_call = binomial(&input(Right::Call, ExerciseStyle::American), 1000)?;
+        let european_call = binomial(&input(Right::Call, ExerciseStyle::European), 1000)?;
+        assert_close(european_call.price, american_call.price, 1e-9);
+        Ok(())
+    }
+
+    #[test]
+    fn test_implied_volatility_round_trip() -> Result<(), IBKRApiLibError> {
+        for style in &[ExerciseStyle::European, ExerciseStyle::American] {
+            let mut priced = input(Right::Put, *style);
+            priced.volatility = 0.37;
+            priced.dividend_yield = 0.02;
+            let price = price_option(&priced)?.price;
+
+            let mut solve = priced;
+            solve.volatility = 0.0;
+            assert_close(0.37, implied_volatility(&solve, price)?, 1e-6);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        assert!(black_scholes(&input(Right::None, ExerciseStyle::European)).is_err());
+        let mut negative = input(Right::Call, ExerciseStyle::European);
+        negative.strike = -1.0;
+        assert!(black_scholes(&negative).is_err());
+        // below intrinsic value
+        assert!(implied_volatility(&input(Right::Call, ExerciseStyle::European), 0.01).is_err());
+    }
+
+    #[test]
+    fn test_to_option_greeks() -> Result<(), IBKRApiLibError> {
+        let mut with_dividends = input(Right::Call, ExerciseStyle::European);
+        with_dividends.dividend_yield = 0.03;
+        let greeks = black_scholes(&with_dividends)?.to_option_greeks(&with_dividends);
+        assert_close(0.2, greeks.implied_vol, 1e-12);
+        assert_close(100.0, greeks.und_price, 1e-12);
+        assert_close(100.0 * (1.0 - (-0.03_f64).exp()), greeks.pv_dividend, 1e-12);
+        Ok(())
+    }
+}
@@ -0,0 +1,146 @@
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use crate::core::client::{ConnStatus, EClient, POISONED_MUTEX};
+    use crate::core::common::UNSET_DOUBLE;
+    use crate::core::errors::IBKRApiLibError;
+    use crate::core::order::{Order, OrderState};
+    use crate::core::order_group::{HedgeType, OcaType, OrderGroup, OrderGroupState};
+    use crate::core::streamer::{Streamer, TestStreamer};
+    use crate::examples::contract_samples;
+    use crate::tests::recording_wrapper::RecordingWrapper;
+
+    fn status(group: &mut OrderGroup, order_id: i32, status: &str, filled: f64) -> bool {
+        group.order_status(order_id, status, filled, 0.0, 10.0, 0, 0, 10.0, 0, "", 0.0)
+    }
+
+    #[test]
+    fn test_bracket_ids_and_transmit() -> Result<(), IBKRApiLibError> {
+        let contract = contract_samples::usstock();
+        let mut group = OrderGroup::bracket(&contract, "BUY", 100.0, 10.0, 11.0, 9.0);
+        let trailing = group.attach_trailing_stop(0, 5.0, UNSET_DOUBLE, 9.5)?;
+        assert_eq!(OrderGroupState::Staged, group.state());
+
+        assert_eq!(24, group.assign_ids(20));
+        let orders = group.orders();
+        assert_eq!(
+            vec![
+                (20, 0, false),
+                (21, 20, false),
+                (22, 20, false),
+                (23, 20, true)
+            ],
+            orders
+                .iter()
+                .map(|o| (o.order.order_id, o.order.parent_id, o.order.transmit))
+                .collect::<Vec<(i32, i32, bool)>>()
+        );
+        assert_eq!("SELL", orders[1].order.action);
+        assert_eq!("STP", orders[2].order.order_type);
+        assert_eq!(9.0, orders[2].order.aux_price);
+        let trailing = group.order(trailing).unwrap();
+        assert_eq!("TRAIL", trailing.order.order_type);
+        assert_eq!(100.0, trailing.order.total_quantity);
+        assert_eq!(5.0, trailing.order.trailing_percent);
+
+        assert!(group
+            .attach_trailing_stop(4, 5.0, UNSET_DOUBLE, 9.5)
+            .is_err());
+        assert!(group.attach(4, &contract, Order::default()).is_err());
+        assert_eq!(4, group.orders().len());
+        Ok(())
+    }
+
+    #[test]
+    fn test_oca_and_hedges() -> Result<(), IBKRApiLibError> {
+        let stock = contract_samples::usstock();
+        let mut buy = Order::default();
+        buy.action = "BUY".to_string();
+        buy.order_type = "LMT".to_string();
+        let mut sell = buy.clone();
+        sell.action = "SELL".to_string();
+        let mut group = OrderGroup::oca(
+            "oca-1",
+            OcaType::ReduceWithBlock,
+            vec![(stock.clone(), buy.clone()), (stock.clone(), sell)],
+        );
+        group.assign_ids(1);
+        for order in group.orders() {
+            assert_eq!("oca-1", order.order.oca_group);
+            assert_eq!(2, order.order.oca_type);
+            // unrelated orders each transmit themselves
+            assert!(order.order.transmit);
+        }
+
+        let mut group = OrderGroup::new();
+        let parent = group.add(&contract_samples::european_stock(), buy);
+        let hedge = group.attach_hedge(
+            parent,
+            &contract_samples::eur_gbp_fx(),
+            "BUY",
+            HedgeType::Fx,
+            "",
+        )?;
+        group.assign_ids(30);
+        let hedge = group.order(hedge).unwrap();
+        assert_eq!("F", hedge.order.hedge_type);
+        assert_eq!(0.0, hedge.order.total_quantity);
+        assert_eq!(30, hedge.order.parent_id);
+        assert!(hedge.order.transmit);
+        assert!(!group.order(parent).unwrap().order.transmit);
+        Ok(())
+    }
+
+    #[test]
+    fn test_group_state() {
+        let contract = contract_samples::usstock();
+        let mut group = OrderGroup::bracket(&contract, "SELL", 10.0, 10.0, 9.0, 11.0);
+        group.assign_ids(1);
+        assert!(!status(&mut group, 1, "Submitted", 0.0));
+        group.mark_placed();
+        assert!(group.owns_order(3));
+        assert!(!group.owns_order(4));
+        assert_eq!(OrderGroupState::Working, group.state());
+
+        assert!(status(&mut group, 1, "Filled", 10.0));
+        assert!(status(&mut group, 2, "Filled", 10.0));
+        assert!(!status(&mut group, 7, "Filled", 10.0));
+        assert_eq!(OrderGroupState::Working, group.state());
+        let mut state = OrderState::default();
+        state.status = "Cancelled".to_string();
+        assert!(group.open_order(3, &state));
+        assert_eq!(OrderGroupState::Completed, group.state());
+        assert_eq!(10.0, group.order(1).unwrap().avg_fill_price);
+
+        let mut group = OrderGroup::bracket(&contract, "BUY", 10.0, 10.0, 11.0, 9.0);
+        group.assign_ids(1);
+        group.mark_placed();
+        for order_id in 1..4 {
+            status(&mut group, order_id, "ApiCancelled", 0.0);
+        }
+        assert_eq!(OrderGroupState::Cancelled, group.state());
+    }
+
+    #[test]
+    fn test_place_once() -> Result<(), IBKRApiLibError> {
+        let mut client = EClient::with_handler(RecordingWrapper::new());
+        *client.conn_state.lock().expect(POISONED_MUTEX) = ConnStatus::CONNECTED;
+        client.set_streamer(Some(Box::new(TestStreamer::new()) as Box<dyn Streamer>));
+        client.server_version = 151;
+
+        let contract = contract_samples::usstock();
+        let mut group = OrderGroup::bracket(&contract, "BUY", 10.0, 10.0, 11.0, 9.0);
+        assert_eq!(4, group.place(&mut client, 1)?);
+        let mut buf = Vec::<u8>::new();
+        client.stream.as_mut().unwrap().read_to_end(&mut buf)?;
+        assert!(!buf.is_empty());
+
+        assert!(group.place(&mut client, 4).is_err());
+        buf.clear();
+        client.stream.as_mut().unwrap().read_to_end(&mut buf)?;
+        assert!(buf.is_empty());
+        assert_eq!(1, group.order(0).unwrap().order.order_id);
+        Ok(())
+    }
+}
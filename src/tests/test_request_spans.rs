@@ -0,0 +1,407 @@
+#[cfg(all(test, feature = "tracing"))]
+mod tests {
+    use std::collections::HashMap;
+    use std::fmt;
+    use std::io::{self, Read, Write};
+    use std::net::{Shutdown, SocketAddr};
+    use std::sync::mpsc::channel;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id};
+    use tracing::{Event, Subscriber};
+    use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+    use tracing_subscriber::registry::LookupSpan;
+
+    use crate::core::client::{ConnStatus, EClient, POISONED_MUTEX};
+    use crate::core::decoder::Decoder;
+    use crate::core::errors::IBKRApiLibError;
+    use crate::core::request_spans::RequestSpans;
+    use crate::core::streamer::{Streamer, TestStreamer};
+    use crate::examples::contract_samples;
+    use crate::examples::order_samples;
+    use crate::tests::recording_wrapper::RecordingWrapper;
+
+    #[derive(Clone, Default)]
+    struct Fields(HashMap<String, String>);
+
+    impl Visit for Fields {
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            self.0
+                .insert(field.name().to_string(), format!("{:?}", value));
+        }
+
+        fn record_str(&mut self, field: &Field, value: &str) {
+            self.0.insert(field.name().to_string(), value.to_string());
+        }
+    }
+
+    /// Records each event with the fields of the span it happened in, and each closed span
+    #[derive(Clone, Default)]
+    struct Capture {
+        lines: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Capture {
+        fn lines(&self) -> Vec<String> {
+            self.lines.lock().unwrap().clone()
+        }
+    }
+
+    impl<S> Layer<S> for Capture
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+            let mut fields = Fields::default();
+            attrs.record(&mut fields);
+            ctx.span(id).unwrap().extensions_mut().insert(fields);
+        }
+
+        fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+            let mut fields = Fields::default();
+            event.record(&mut fields);
+            let span = ctx
+                .event_span(event)
+                .and_then(|span| span.extensions().get::<Fields>().cloned())
+                .map(|span| format!("{} {}", span.0["message"], span.0["req_id"]))
+                .unwrap_or_else(|| "none".to_string());
+            self.lines
+                .lock()
+                .unwrap()
+                .push(format!("[{}] {}", span, fields.0["message"]));
+        }
+
+        fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+            let span = ctx.span(&id).unwrap();
+            let extensions = span.extensions();
+            let fields = extensions.get::<Fields>().unwrap();
+            self.lines.lock().unwrap().push(format!(
+                "closed {} {} {} {}",
+                fields.0["message"], fields.0["req_id"], fields.0["symbol"], fields.0["client_id"]
+            ));
+        }
+    }
+
+    fn connected_client() -> EClient<RecordingWrapper> {
+        let mut client = EClient::new(Arc::new(Mutex::new(RecordingWrapper::new())));
+        *client.conn_state.lock().expect(POISONED_MUTEX) = ConnStatus::CONNECTED;
+        client.set_streamer(Some(Box::new(TestStreamer::new()) as Box<dyn Streamer>));
+        client.server_version = 151;
+        client
+    }
+
+    fn decoder(request_spans: &Arc<RequestSpans>) -> Decoder<RecordingWrapper> {
+        let (_tx, rx) = channel::<String>();
+        let mut decoder = Decoder::new(
+            Arc::new(Mutex::new(RecordingWrapper::new())),
+            rx,
+            151,
+            Arc::new(Mutex::new(ConnStatus::CONNECTED)),
+        );
+        decoder.set_request_spans(Some(request_spans.clone()));
+        decoder
+    }
+
+    /// Connection whose writes fail
+    struct BrokenStreamer;
+
+    impl Streamer for BrokenStreamer {
+        fn shutdown(&mut self, _how: Shutdown) -> io::Result<()> {
+            Ok(())
+        }
+
+        fn connect(&mut self, _addr: &SocketAddr) {}
+    }
+
+    impl Read for BrokenStreamer {
+        fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+            Ok(0)
+        }
+    }
+
+    impl Write for BrokenStreamer {
+        fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+            Err(io::Error::new(io::ErrorKind::BrokenPipe, "broken pipe"))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn message(fields: &[&str]) -> Vec<String> {
+        fields.iter().map(|field| field.to_string()).collect()
+    }
+
+    fn order_status(order_id: &str, status: &str, filled: &str) -> Vec<String> {
+        message(&[
+            "3", order_id, status, filled, "0", "0", "555", "0", "0", "0", "", "0",
+        ])
+    }
+
+    #[test]
+    fn test_historical_data_trace() -> Result<(), IBKRApiLibError> {
+        let capture = Capture::default();
+        let subscriber = tracing_subscriber::registry().with(capture.clone());
+        tracing::subscriber::with_default(subscriber, || -> Result<(), IBKRApiLibError> {
+            let mut client = connected_client();
+            let mut decoder = decoder(client.request_spans());
+            let contract = contract_samples::usstock();
+            client.req_historical_data(
+                4001,
+                &contract,
+                "",
+                "1 D",
+                "1 hour",
+                "TRADES",
+                1,
+                1,
+                false,
+                vec![],
+            )?;
+            client.req_head_time_stamp(4002, &contract, "TRADES", 1, 1)?;
+            assert_eq!(vec![4001, 4002], client.request_spans().open_ids());
+
+            // messages of other requests, or of none, are not traced
+            decoder.interpret(&message(&["4", "2", "-1", "2104", "Market data farm OK"]))?;
+            decoder.interpret(&message(&["88", "4999", "20200101"]))?;
+            decoder.interpret(&message(&[
+                "17", "4001", "20200101", "20200102", "1", "20200101", "1", "2", "0.5", "1.5",
+                "100", "1.2", "10",
+            ]))?;
+            decoder.interpret(&message(&["4", "2", "4002", "162", "No head time stamp"]))?;
+            assert!(client.request_spans().open_ids().is_empty());
+            Ok(())
+        })?;
+
+        assert_eq!(
+            vec![
+                "[req_historical_data 4001] request sent",
+                "[req_head_time_stamp 4002] request sent",
+                "[req_historical_data 4001] historical_data",
+                "closed req_historical_data 4001 AMZN 0",
+                "[req_head_time_stamp 4002] error: No head time stamp",
+                "closed req_head_time_stamp 4002 AMZN 0",
+            ],
+            capture.lines()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_keep_up_to_date_stays_open() -> Result<(), IBKRApiLibError> {
+        let mut client = connected_client();
+        let mut decoder = decoder(client.request_spans());
+        let contract = contract_samples::usstock();
+        client.req_historical_data(
+            4001,
+            &contract,
+            "",
+            "1 D",
+            "1 hour",
+            "TRADES",
+            1,
+            1,
+            true,
+            vec![],
+        )?;
+        decoder.interpret(&message(&["17", "4001", "20200101", "20200102", "0"]))?;
+        assert_eq!(vec![4001], client.request_spans().open_ids());
+
+        client.cancel_historical_data(4001)?;
+        assert!(client.request_spans().open_ids().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_order_trace() -> Result<(), IBKRApiLibError> {
+        let capture = Capture::default();
+        let subscriber = tracing_subscriber::registry().with(capture.clone());
+        tracing::subscriber::with_default(subscriber, || -> Result<(), IBKRApiLibError> {
+            let mut client = connected_client();
+            let mut decoder = decoder(client.request_spans());
+            let contract = contract_samples::usstock();
+            let order = order_samples::limit_order("BUY", 100.0, 50.0);
+            client.place_order(7, &contract, &order)?;
+            client.place_order(7, &contract, &order)?;
+
+            decoder.interpret(&order_status("7", "Submitted", "0"))?;
+            // req_id, order_id, contract, exec_id, then the rest of the execution
+            let mut execution = message(
+                &"11|-1|7|265598|IBKR|STK||0|||SMART|USD|IBKR|NMS|0001f4e8.1|20200101 10:00:00|DU123|ISLAND|BOT|100|50|555|0|0|100|50"
+                    .split('|')
+                    .collect::<Vec<&str>>(),
+            );
+            execution.resize(40, "".to_string());
+            decoder.interpret(&execution)?;
+            decoder.interpret(&order_status("7", "Filled", "100"))?;
+            assert!(client.request_spans().open_ids().is_empty());
+
+            // the commission report only carries the exec_id and arrives after the fill
+            decoder.interpret(&message(&[
+                "59",
+                "1",
+                "0001f4e8.1",
+                "1.0",
+                "USD",
+                "0",
+                "0",
+                "0",
+            ]))?;
+
+            // orders placed after the span closed get a new one
+            client.place_order(8, &contract, &order)?;
+            client.cancel_order(8)?;
+            decoder.interpret(&order_status("8", "Cancelled", "0"))?;
+            Ok(())
+        })?;
+
+        assert_eq!(
+            vec![
+                "[place_order 7] request sent",
+                "[place_order 7] order modified",
+                "[place_order 7] order_status",
+                "[place_order 7] exec_details",
+                "[place_order 7] order_status",
+                "[place_order 7] commission_report",
+                "closed place_order 7 AMZN 0",
+                "[place_order 8] request sent",
+                "[place_order 8] cancel_order",
+                "[place_order 8] order_status",
+                "closed place_order 8 AMZN 0",
+            ],
+            capture.lines()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_historical_ticks_close_when_done() -> Result<(), IBKRApiLibError> {
+        let mut client = connected_client();
+        let mut decoder = decoder(client.request_spans());
+        let contract = contract_samples::usstock();
+        for (req_id, what_to_show) in
+            [(4001, "TRADES"), (4002, "BID_ASK"), (4003, "MIDPOINT")].iter()
+        {
+            client.req_historical_ticks(
+                *req_id,
+                &contract,
+                "20200101 10:00:00",
+                "",
+                10,
+                what_to_show,
+                1,
+                false,
+                vec![],
+            )?;
+        }
+
+        // ticks come in batches until one has the done flag set
+        decoder.interpret(&message(&[
+            "96",
+            "4003",
+            "1",
+            "1577872800",
+            "0",
+            "1.5",
+            "0",
+            "0",
+        ]))?;
+        assert_eq!(vec![4001, 4002, 4003], client.request_spans().open_ids());
+        decoder.interpret(&message(&["96", "4003", "0", "1"]))?;
+        decoder.interpret(&message(&[
+            "97",
+            "4002",
+            "1",
+            "1577872800",
+            "0",
+            "1.4",
+            "1.6",
+            "100",
+            "200",
+            "1",
+        ]))?;
+        decoder.interpret(&message(&[
+            "98",
+            "4001",
+            "1",
+            "1577872800",
+            "0",
+            "1.5",
+            "100",
+            "ISLAND",
+            "",
+            "1",
+        ]))?;
+        assert!(client.request_spans().open_ids().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_executions_without_commission_report_expire() {
+        let request_spans = RequestSpans::with_execution_ttl(Duration::from_secs(0));
+        let contract = contract_samples::usstock();
+        request_spans.open(7, "place_order", Some(&contract), 0);
+        let execution = |exec_id: &str| {
+            let mut execution = format!(
+                "11|-1|7|265598|IBKR|STK||0|||SMART|USD|IBKR|NMS|{}|20200101 10:00:00|DU123|ISLAND",
+                exec_id
+            )
+            .split('|')
+            .map(|field| field.to_string())
+            .collect::<Vec<String>>();
+            execution.resize(40, "".to_string());
+            execution
+        };
+        let commission_report = |exec_id: &str| {
+            let report = ["59", "1", exec_id, "1.0", "USD", "0", "0", "0"];
+            request_spans.received(59, &report, 151).is_some()
+        };
+
+        for exec_id in ["0001f4e8.1", "0001f4e8.2"].iter() {
+            let execution = execution(exec_id);
+            let fields = execution.iter().map(String::as_str).collect::<Vec<&str>>();
+            let received = request_spans.received(11, &fields, 151).unwrap();
+            request_spans.handled(received);
+        }
+
+        // the first one was dropped when the second arrived
+        assert!(!commission_report("0001f4e8.1"));
+        assert!(commission_report("0001f4e8.2"));
+        assert!(!commission_report("0001f4e8.2"));
+    }
+
+    #[test]
+    fn test_span_closed_when_request_not_sent() -> Result<(), IBKRApiLibError> {
+        let mut client = connected_client();
+        let contract = contract_samples::usstock();
+        let order = order_samples::limit_order("BUY", 100.0, 50.0);
+        client.place_order(7, &contract, &order)?;
+
+        client.set_streamer(Some(Box::new(BrokenStreamer) as Box<dyn Streamer>));
+        assert!(client
+            .req_mkt_data(4001, &contract, "", false, false, vec![])
+            .is_err());
+        assert!(client
+            .req_historical_data(
+                4002,
+                &contract,
+                "",
+                "1 D",
+                "1 hour",
+                "TRADES",
+                1,
+                1,
+                true,
+                vec![],
+            )
+            .is_err());
+        // a modification that failed leaves the span of the order it modifies open
+        assert!(client.place_order(7, &contract, &order).is_err());
+        assert!(client.place_order(8, &contract, &order).is_err());
+        assert_eq!(vec![7], client.request_spans().open_ids());
+        Ok(())
+    }
+}
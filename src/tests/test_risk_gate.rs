@@ -0,0 +1,255 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::sync::{Arc, Mutex};
+
+    use crate::core::client::EClient;
+    use crate::core::common::{TickAttrib, TickType};
+    use crate::core::errors::IBKRApiLibError;
+    use crate::core::risk_gate::{RiskGate, RiskLimits, RiskViolation};
+    use crate::examples::contract_samples;
+    use crate::examples::defaults::DefaultWrapper;
+    use crate::examples::order_samples;
+
+    fn violation(result: Result<(), IBKRApiLibError>) -> RiskViolation {
+        match result {
+            Err(IBKRApiLibError::RiskRejected(violation)) => violation,
+            other => panic!("expected a risk rejection, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_limits() -> Result<(), IBKRApiLibError> {
+        let mut contract = contract_samples::usstock();
+        contract.con_id = 3691937;
+        let gate = RiskGate::new(RiskLimits {
+            max_order_notional: Some(50000.0),
+            max_position: Some(300.0),
+            max_price_deviation: Some(0.05),
+            max_daily_loss: Some(1000.0),
+            allowed_symbols: Some(vec!["AMZN".to_string()].into_iter().collect()),
+            duplicate_window_secs: None,
+        });
+
+        // notional of a market order needs the last price
+        let order = order_samples::market_order("BUY", 100.0);
+        assert_eq!(
+            RiskViolation::NoPrice { con_id: 3691937 },
+            violation(gate.check(1, &contract, &order))
+        );
+        gate.track_market_data(7, contract.con_id);
+        assert!(gate.tick_price(7, TickType::Last, 180.0, &TickAttrib::default()));
+        assert!(!gate.tick_price(8, TickType::Last, 1.0, &TickAttrib::default()));
+        gate.check(1, &contract, &order)?;
+
+        let order = order_samples::limit_order("BUY", 100.0, 200.0);
+        match violation(gate.check(2, &contract, &order)) {
+            RiskViolation::PriceDeviation { last_price, .. } => assert_eq!(180.0, last_price),
+            other => panic!("unexpected violation {:?}", other),
+        }
+
+        let order = order_samples::limit_order("BUY", 300.0, 181.0);
+        match violation(gate.check(3, &contract, &order)) {
+            RiskViolation::OrderNotional { notional, .. } => assert_eq!(54300.0, notional),
+            other => panic!("unexpected violation {:?}", other),
+        }
+
+        gate.position("", &contract, 250.0, 175.0);
+        let order = order_samples::limit_order("BUY", 100.0, 181.0);
+        match violation(gate.check(4, &contract, &order)) {
+            RiskViolation::Position { position, .. } => assert_eq!(350.0, position),
+            other => panic!("unexpected violation {:?}", other),
+        }
+        let order = order_samples::limit_order("SELL", 100.0, 181.0);
+        gate.check(4, &contract, &order)?;
+
+        gate.pnl(9, -600.0, 0.0, 0.0);
+        gate.pnl(10, -500.0, 0.0, 0.0);
+        match violation(gate.check(5, &contract, &order)) {
+            RiskViolation::DailyLoss { daily_pnl, .. } => assert_eq!(-1100.0, daily_pnl),
+            other => panic!("unexpected violation {:?}", other),
+        }
+
+        let mut other = contract_samples::usstock();
+        other.symbol = "IBM".to_string();
+        assert_eq!(
+            RiskViolation::SymbolNotAllowed {
+                symbol: "IBM".to_string()
+            },
+            violation(gate.check(6, &other, &order))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_duplicates_and_reload() -> Result<(), IBKRApiLibError> {
+        let contract = contract_samples::usstock();
+        let gate = RiskGate::new(RiskLimits {
+            duplicate_window_secs: Some(60),
+            ..Default::default()
+        });
+        let order = order_samples::limit_order("BUY", 100.0, 180.0);
+        gate.check(1, &contract, &order)?;
+        // orders count as duplicates once they were sent
+        gate.check(2, &contract, &order)?;
+        gate.record_order(1, &contract, &order);
+        // modifying the same order is not a duplicate
+        gate.check(1, &contract, &order)?;
+        assert_eq!(
+            RiskViolation::DuplicateOrder {
+                previous_order_id: 1
+            },
+            violation(gate.check(2, &contract, &order))
+        );
+
+        let path = std::env::temp_dir().join(format!("risk_limits_{}.json", std::process::id()));
+        std::fs::write(&path, r#"{"allowed_symbols": ["IBM"]}"#)?;
+        gate.load_limits(&path)?;
+        std::fs::remove_file(&path)?;
+        let mut allowed = HashSet::new();
+        allowed.insert("IBM".to_string());
+        assert_eq!(Some(allowed), gate.limits().allowed_symbols);
+        assert_eq!(None, gate.limits().duplicate_window_secs);
+        match violation(gate.check(2, &contract, &order)) {
+            RiskViolation::SymbolNotAllowed { .. } => (),
+            other => panic!("unexpected violation {:?}", other),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_client_place_order() -> Result<(), IBKRApiLibError> {
+        let wrapper = Arc::new(Mutex::new(DefaultWrapper::new()));
+        let mut client = EClient::new(wrapper);
+        let gate = Arc::new(RiskGate::new(RiskLimits {
+            max_order_notional: Some(10000.0),
+            ..Default::default()
+        }));
+        client.set_risk_gate(Some(gate.clone()));
+        let contract = contract_samples::usstock();
+
+        // rejected before the connection is even looked at
+        let order = order_samples::limit_order("BUY", 100.0, 180.0);
+        violation(client.place_order(1, &contract, &order));
+
+        // accepted orders go on to the connection check
+        let order = order_samples::limit_order("BUY", 10.0, 180.0);
+        match client.place_order(2, &contract, &order) {
+            Err(IBKRApiLibError::ApiError(_)) => (),
+            other => panic!("expected not connected, got {:?}", other),
+        }
+
+        // an order that was not sent can be retried with another id
+        gate.set_limits(RiskLimits {
+            duplicate_window_secs: Some(60),
+            ..Default::default()
+        });
+        for order_id in 3..5 {
+            match client.place_order(order_id, &contract, &order) {
+                Err(IBKRApiLibError::ApiError(_)) => (),
+                other => panic!("expected not connected, got {:?}", other),
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_contract_without_con_id() -> Result<(), IBKRApiLibError> {
+        let contract = contract_samples::usstock();
+        let gate = RiskGate::new(RiskLimits {
+            max_position: Some(300.0),
+            ..Default::default()
+        });
+        let mut held = contract.clone();
+        held.con_id = 3691937;
+        gate.position("", &held, 250.0, 175.0);
+
+        let order = order_samples::limit_order("BUY", 100.0, 180.0);
+        assert_eq!(
+            RiskViolation::MissingConId {
+                symbol: contract.symbol.clone()
+            },
+            violation(gate.check(1, &contract, &order))
+        );
+
+        // the order price is enough for the notional limit
+        gate.set_limits(RiskLimits {
+            max_order_notional: Some(20000.0),
+            ..Default::default()
+        });
+        gate.set_last_price(0, 1000.0);
+        gate.check(2, &contract, &order)?;
+        let order = order_samples::market_order("BUY", 100.0);
+        assert_eq!(
+            RiskViolation::NoPrice { con_id: 0 },
+            violation(gate.check(3, &contract, &order))
+        );
+        Ok(())
+    }
+
+    fn status(gate: &RiskGate, order_id: i32, status: &str, remaining: f64) -> bool {
+        gate.order_status(order_id, status, 0.0, remaining, 0.0, 0, 0, 0.0, 0, "", 0.0)
+    }
+
+    #[test]
+    fn test_position_limit_counts_accounts_and_working_orders() -> Result<(), IBKRApiLibError> {
+        let mut contract = contract_samples::usstock();
+        contract.con_id = 3691937;
+        let gate = RiskGate::new(RiskLimits {
+            max_position: Some(300.0),
+            ..Default::default()
+        });
+        gate.position("DU119915", &contract, 150.0, 175.0);
+        gate.position("DU119916", &contract, 100.0, 175.0);
+
+        // without an account the order is checked against all accounts
+        let order = order_samples::limit_order("BUY", 100.0, 180.0);
+        match violation(gate.check(1, &contract, &order)) {
+            RiskViolation::Position { position, .. } => assert_eq!(350.0, position),
+            other => panic!("unexpected violation {:?}", other),
+        }
+        let mut order = order;
+        order.account = "DU119915".to_string();
+        gate.check(1, &contract, &order)?;
+        gate.record_order(1, &contract, &order);
+        assert_eq!(1, gate.working_orders());
+
+        // the working order counts until it is done, but not against its own modification
+        match violation(gate.check(2, &contract, &order)) {
+            RiskViolation::Position {
+                account, position, ..
+            } => {
+                assert_eq!("DU119915", account);
+                assert_eq!(350.0, position);
+            }
+            other => panic!("unexpected violation {:?}", other),
+        }
+        gate.check(1, &contract, &order)?;
+        assert!(status(&gate, 1, "Submitted", 40.0));
+        gate.check(2, &contract, &order)?;
+        assert!(status(&gate, 1, "Cancelled", 40.0));
+        assert!(!status(&gate, 1, "Cancelled", 40.0));
+        assert_eq!(0, gate.working_orders());
+        Ok(())
+    }
+
+    #[test]
+    fn test_price_deviation_needs_last_price() -> Result<(), IBKRApiLibError> {
+        let mut contract = contract_samples::usstock();
+        contract.con_id = 3691937;
+        let gate = RiskGate::new(RiskLimits {
+            max_price_deviation: Some(0.05),
+            ..Default::default()
+        });
+        // nothing to compare for a market order
+        gate.check(1, &contract, &order_samples::market_order("BUY", 100.0))?;
+        let order = order_samples::limit_order("BUY", 100.0, 180.0);
+        assert_eq!(
+            RiskViolation::NoPrice { con_id: 3691937 },
+            violation(gate.check(2, &contract, &order))
+        );
+        gate.set_last_price(contract.con_id, 179.0);
+        gate.check(2, &contract, &order)?;
+        Ok(())
+    }
+}
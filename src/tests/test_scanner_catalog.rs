@@ -0,0 +1,164 @@
+#[cfg(all(test, feature = "scanner-catalog"))]
+mod tests {
+
+    use crate::core::common::TagValue;
+    use crate::core::contract::ContractDetails;
+    use crate::core::errors::IBKRApiLibError;
+    use crate::core::scanner_catalog::{MarketScanner, ScannerCatalog, ScannerFieldType};
+    use crate::examples::scanner_subscription_samples;
+
+    const SCANNER_PARAMETERS: &str = include_str!("fixtures/scanner_parameters.xml");
+
+    fn filter(tag: &str, value: &str) -> TagValue {
+        TagValue::new(tag.to_string(), value.to_string())
+    }
+
+    fn contract_details(con_id: i32) -> ContractDetails {
+        let mut details = ContractDetails::default();
+        details.contract.con_id = con_id;
+        details
+    }
+
+    #[test]
+    fn test_scanner_catalog_parsing() -> Result<(), IBKRApiLibError> {
+        let catalog = ScannerCatalog::from_xml(SCANNER_PARAMETERS)?;
+        assert_eq!(2, catalog.instruments.len());
+        assert_eq!(
+            vec!["PRICE", "VOLUME", "STKTYPES", "CHANGEPERC"],
+            catalog.instrument("STK").unwrap().filters
+        );
+        assert_eq!(2, catalog.locations.len());
+        assert_eq!(2, catalog.locations[0].children.len());
+        assert_eq!(
+            "Listed/NASDAQ",
+            catalog.location("STK.US.MAJOR").unwrap().display_name
+        );
+        assert!(catalog.location("STK.EU").is_none());
+        assert_eq!(2, catalog.scan_types_for("STK").len());
+        assert_eq!(1, catalog.scan_types_for("FUT.US").len());
+
+        let (price, price_above) = catalog.filter_field("priceAbove").unwrap();
+        assert_eq!("PRICE", price.id);
+        assert!(price.range);
+        assert_eq!(ScannerFieldType::Double, price_above.field_type);
+        let (_, stk_types) = catalog.filter_field("stkTypes").unwrap();
+        assert_eq!(vec!["inc:CORP", "inc:ADR"], stk_types.values);
+
+        assert!(ScannerCatalog::from_xml("<FinancialSummary/>").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_scanner_subscription_validation() -> Result<(), IBKRApiLibError> {
+        let catalog = ScannerCatalog::from_xml(SCANNER_PARAMETERS)?;
+        let subscription = scanner_subscription_samples::hot_usstk_by_volume();
+
+        // HOT_BY_VOLUME is not in the catalog
+        assert!(catalog.validate(1, &subscription, &[]).is_err());
+
+        let mut subscription = subscription;
+        subscription.scan_code = "TOP_PERC_GAIN".to_string();
+        catalog.validate(1, &subscription, &[])?;
+        catalog.validate(
+            1,
+            &subscription,
+            &[
+                filter("priceAbove", "5"),
+                filter("volumeAbove", "100000"),
+                filter("changePercAbove", "-2.5"),
+                filter("stkTypes", "inc:CORP"),
+            ],
+        )?;
+
+        // bad filter values and unknown filters
+        assert!(catalog
+            .validate(1, &subscription, &[filter("priceAbove", "cheap")])
+            .is_err());
+        assert!(catalog
+            .validate(1, &subscription, &[filter("priceAbove", "-1")])
+            .is_err());
+        assert!(catalog
+            .validate(1, &subscription, &[filter("volumeAbove", "1.5")])
+            .is_err());
+        assert!(catalog
+            .validate(1, &subscription, &[filter("stkTypes", "inc:ETF")])
+            .is_err());
+        assert!(catalog
+            .validate(1, &subscription, &[filter("marketCapAbove", "1")])
+            .is_err());
+
+        // too many rows
+        subscription.number_of_rows = 100;
+        assert!(catalog.validate(1, &subscription, &[]).is_err());
+        subscription.number_of_rows = 50;
+
+        // location inherits its instruments from STK.US, so futures are rejected there
+        subscription.instrument = "FUT.US".to_string();
+        subscription.scan_code = "MOST_ACTIVE".to_string();
+        assert!(catalog.validate(1, &subscription, &[]).is_err());
+        subscription.location_code = "FUT.CME".to_string();
+        catalog.validate(1, &subscription, &[filter("priceAbove", "10")])?;
+
+        // STKTYPES does not apply to futures
+        assert!(catalog
+            .validate(1, &subscription, &[filter("stkTypes", "inc:CORP")])
+            .is_err());
+
+        // the error names the problem
+        subscription.instrument = "BOND".to_string();
+        match catalog.validate(7, &subscription, &[]) {
+            Err(IBKRApiLibError::ApiError(err)) => {
+                assert_eq!(7, err.req_id);
+                assert!(err.description.contains("unknown instrument 'BOND'"));
+            }
+            _ => panic!("expected an error"),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_market_scanner_results() -> Result<(), IBKRApiLibError> {
+        let mut scanner = MarketScanner::new();
+        let subscription = scanner_subscription_samples::hot_usstk_by_volume();
+        assert!(scanner.validate(1, &subscription, &[]).is_err());
+        scanner.scanner_parameters(SCANNER_PARAMETERS)?;
+        assert!(scanner.catalog().is_some());
+
+        scanner.track(1);
+        assert!(!scanner.scanner_data(2, 0, contract_details(10), "", "", "", ""));
+        assert!(scanner.scanner_data(1, 1, contract_details(20), "", "", "", ""));
+        assert!(scanner.scanner_data(1, 0, contract_details(10), "", "", "", ""));
+        assert!(scanner.scanner_data(1, 2, contract_details(30), "", "", "", ""));
+        // nothing is published until scanner_data_end
+        assert_eq!(0, scanner.results(1).unwrap().rows.len());
+        assert!(scanner.scanner_data_end(1));
+
+        let results = scanner.results(1).unwrap();
+        assert_eq!(1, results.update_count);
+        assert_eq!(
+            vec![10, 20, 30],
+            results
+                .rows
+                .iter()
+                .map(|row| row.contract.contract.con_id)
+                .collect::<Vec<i32>>()
+        );
+        assert_eq!(vec![10, 20, 30], results.added);
+
+        scanner.scanner_data(1, 0, contract_details(30), "", "", "", "");
+        scanner.scanner_data(1, 1, contract_details(10), "", "", "", "");
+        scanner.scanner_data(1, 2, contract_details(40), "", "", "", "");
+        scanner.scanner_data_end(1);
+
+        let results = scanner.results(1).unwrap();
+        assert_eq!(2, results.update_count);
+        assert_eq!(Some(0), results.rank(30));
+        assert_eq!(Some(2), results.rank_change(30));
+        assert_eq!(Some(-1), results.rank_change(10));
+        assert_eq!(None, results.rank_change(40));
+        assert_eq!(vec![40], results.added);
+        assert_eq!(vec![20], results.removed);
+        assert!(!scanner.scanner_data_end(2));
+        Ok(())
+    }
+}
@@ -0,0 +1,183 @@
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use chrono::NaiveDate;
+
+    use crate::core::common::{
+        BarData, HistoricalTick, HistoricalTickBidAsk, HistoricalTickLast, RealTimeBar,
+        TickAttribBidAsk, TickAttribLast,
+    };
+    use crate::core::errors::IBKRApiLibError;
+    use crate::core::series_csv;
+    use crate::core::series_store::{SeriesRecord, SeriesStore, StorageFormat};
+
+    fn store_root(name: &str) -> PathBuf {
+        let root =
+            std::env::temp_dir().join(format!("twsapi_series_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        root
+    }
+
+    fn bar(date: &str, close: f64) -> BarData {
+        BarData::new(
+            date.to_string(),
+            close,
+            close + 1.0,
+            close - 1.0,
+            close,
+            1000,
+            10,
+            close,
+        )
+    }
+
+    fn last_tick(time: i32, price: f64) -> HistoricalTickLast {
+        HistoricalTickLast::new(
+            time,
+            TickAttribLast::new(false, true),
+            price,
+            100,
+            "ISLAND, ARCA".to_string(),
+            "\"T\"".to_string(),
+        )
+    }
+
+    fn csv_round_trip<T: SeriesRecord>(records: &[T]) -> Result<Vec<T>, IBKRApiLibError> {
+        let mut buffer = vec![];
+        series_csv::write(&mut buffer, records, true)?;
+        series_csv::read(buffer.as_slice())
+    }
+
+    #[test]
+    fn test_csv_round_trip() -> Result<(), IBKRApiLibError> {
+        let bars = csv_round_trip(&[bar("20251020  09:30:00", 100.5)])?;
+        assert_eq!("20251020  09:30:00", bars[0].date);
+        assert_eq!(101.5, bars[0].high);
+        assert_eq!(1000, bars[0].volume);
+
+        let realtime = csv_round_trip(&[RealTimeBar::new(
+            "1760952600".to_string(),
+            1.0,
+            2.0,
+            0.5,
+            1.5,
+            300,
+            1.25,
+            7,
+        )])?;
+        assert_eq!("1760952600", realtime[0].date_time);
+        assert_eq!(1.25, realtime[0].wap);
+        assert_eq!(7, realtime[0].count);
+
+        let midpoints = csv_round_trip(&[HistoricalTick::new(1760952600, 99.75, 0)])?;
+        assert_eq!(99.75, midpoints[0].price);
+
+        let quotes = csv_round_trip(&[HistoricalTickBidAsk::new(
+            1760952600,
+            TickAttribBidAsk::new(true, false),
+            99.5,
+            100.0,
+            200,
+            300,
+        )])?;
+        assert!(quotes[0].tick_attrib_bid_ask.bid_past_low);
+        assert!(!quotes[0].tick_attrib_bid_ask.ask_past_high);
+        assert_eq!(300, quotes[0].size_ask);
+
+        // quoting keeps commas and quotes in text columns
+        let trades = csv_round_trip(&[last_tick(1760952600, 99.8)])?;
+        assert!(trades[0].tick_attrib_last.unreported);
+        assert_eq!("ISLAND, ARCA", trades[0].exchange);
+        assert_eq!("\"T\"", trades[0].special_conditions);
+        Ok(())
+    }
+
+    #[test]
+    fn test_csv_rejects_other_schema() {
+        let mut buffer = vec![];
+        series_csv::write(&mut buffer, &[HistoricalTick::new(1, 1.0, 1)], true).unwrap();
+        let result = series_csv::read::<HistoricalTickLast, _>(buffer.as_slice());
+        assert!(result.is_err());
+
+        let result = series_csv::read::<HistoricalTick, _>("time,price,size\n1,abc,1\n".as_bytes());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_store_partitions_by_symbol_and_date() -> Result<(), IBKRApiLibError> {
+        let root = store_root("csv");
+        let store = SeriesStore::new(root.as_path(), StorageFormat::Csv);
+
+        let paths = store.append(
+            "BRK/B",
+            &[
+                bar("20251020  09:30:00", 100.0),
+                bar("20251020  09:31:00", 101.0),
+                bar("20251021  09:30:00", 102.0),
+            ],
+        )?;
+        assert_eq!(
+            vec![
+                root.join("BRK_B").join("bars").join("20251020.csv"),
+                root.join("BRK_B").join("bars").join("20251021.csv"),
+            ],
+            paths
+        );
+
+        // appending to an existing partition doesn't repeat the header
+        store.append("BRK/B", &[bar("20251020  09:32:00", 100.5)])?;
+        let day = NaiveDate::from_ymd_opt(2025, 10, 20).unwrap();
+        let bars = store.read::<BarData>("BRK/B", day)?;
+        assert_eq!(3, bars.len());
+        assert_eq!(100.5, bars[2].close);
+
+        let next = day.succ_opt().unwrap();
+        assert_eq!(vec![day, next], store.dates::<BarData>("BRK/B")?);
+        assert_eq!(4, store.read_range::<BarData>("BRK/B", day, next)?.len());
+        assert_eq!(1, store.read_range::<BarData>("BRK/B", next, next)?.len());
+
+        // other kinds and symbols are kept apart
+        store.append("AMZN", &[last_tick(1760952600, 99.8)])?;
+        assert!(store.read::<BarData>("AMZN", day)?.is_empty());
+        assert_eq!(1, store.read::<HistoricalTickLast>("AMZN", day)?.len());
+        assert!(store.dates::<HistoricalTickLast>("BRK/B")?.is_empty());
+
+        fs::remove_dir_all(root)?;
+        Ok(())
+    }
+
+    #[cfg(feature = "parquet-storage")]
+    #[test]
+    fn test_parquet_store() -> Result<(), IBKRApiLibError> {
+        let root = store_root("parquet");
+        let store = SeriesStore::new(root.as_path(), StorageFormat::Parquet);
+
+        store.append("AMZN", &[last_tick(1760952600, 99.8)])?;
+        store.append("AMZN", &[last_tick(1760952601, 99.9)])?;
+        let day = NaiveDate::from_ymd_opt(2025, 10, 20).unwrap();
+        assert_eq!(
+            root.join("AMZN")
+                .join("ticks_last")
+                .join("20251020.parquet"),
+            store.partition_path::<HistoricalTickLast>("AMZN", day)
+        );
+        let ticks = store.read::<HistoricalTickLast>("AMZN", day)?;
+        assert_eq!(2, ticks.len());
+        assert_eq!(99.9, ticks[1].price);
+        assert_eq!("ISLAND, ARCA", ticks[1].exchange);
+        assert!(ticks[1].tick_attrib_last.unreported);
+
+        assert!(StorageFormat::Parquet
+            .read::<BarData>(
+                store
+                    .partition_path::<HistoricalTickLast>("AMZN", day)
+                    .as_path()
+            )
+            .is_err());
+
+        fs::remove_dir_all(root)?;
+        Ok(())
+    }
+}
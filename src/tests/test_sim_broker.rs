@@ -0,0 +1,164 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use crate::core::common::BarData;
+    use crate::core::contract::Contract;
+    use crate::core::errors::IBKRApiLibError;
+    use crate::core::sim_broker::SimBroker;
+    use crate::examples::contract_samples;
+    use crate::examples::order_samples;
+    use crate::tests::recording_wrapper::RecordingWrapper;
+
+    fn bar(date: &str, open: f64, high: f64, low: f64, close: f64) -> BarData {
+        BarData::new(date.to_string(), open, high, low, close, 1000, 10, close)
+    }
+
+    fn setup() -> (SimBroker<RecordingWrapper>, Contract) {
+        let wrapper = Arc::new(Mutex::new(RecordingWrapper::new()));
+        let mut broker = SimBroker::new(wrapper, "DU123456");
+        broker.set_commission(0.01, 1.0, "USD");
+        let mut contract = contract_samples::usstock();
+        contract.con_id = 3691937;
+        (broker, contract)
+    }
+
+    #[test]
+    fn test_limit_round_trip() -> Result<(), IBKRApiLibError> {
+        let (mut broker, contract) = setup();
+        broker.place_order(
+            1,
+            &contract,
+            &order_samples::limit_order("BUY", 200.0, 99.0),
+        )?;
+        assert_eq!(Some("Submitted"), broker.order_status(1));
+
+        broker.on_bar(
+            contract.con_id,
+            &bar("20261019  09:30:00", 100.0, 101.0, 99.5, 100.5),
+        );
+        assert_eq!(Some("Submitted"), broker.order_status(1));
+        broker.on_bar(
+            contract.con_id,
+            &bar("20261019  09:31:00", 100.0, 100.5, 98.0, 98.5),
+        );
+        assert_eq!(Some("Filled"), broker.order_status(1));
+        {
+            let wrapper = broker.wrapper().lock().unwrap();
+            assert_eq!(1, wrapper.executions.len());
+            assert_eq!(99.0, wrapper.executions[0].price);
+            assert_eq!("BOT", wrapper.executions[0].side);
+            assert_eq!("20261019  09:31:00", wrapper.executions[0].time);
+            assert_eq!(2.0, wrapper.commission_reports[0].commission);
+            assert_eq!(
+                ("DU123456".to_string(), 3691937, 200.0, 99.0),
+                wrapper.positions[0]
+            );
+            let (order_id, status, filled, remaining, avg_fill_price) =
+                wrapper.order_statuses.last().unwrap().clone();
+            assert_eq!(
+                (1, "Filled", 200.0, 0.0, 99.0),
+                (order_id, status.as_str(), filled, remaining, avg_fill_price)
+            );
+            // portfolio after the fill and at the close of the bar
+            assert_eq!(2, wrapper.count("update_portfolio"));
+            assert_eq!(98.5, wrapper.portfolio[1].2);
+        }
+
+        // a marketable sell fills at once at the last price
+        broker.place_order(
+            2,
+            &contract,
+            &order_samples::limit_order("SELL", 200.0, 98.0),
+        )?;
+        assert_eq!(Some("Filled"), broker.order_status(2));
+        let position = broker.position(contract.con_id).unwrap();
+        assert_eq!(0.0, position.position);
+        assert_eq!(200.0 * -0.5 - 4.0, position.realized_pnl);
+        let wrapper = broker.wrapper().lock().unwrap();
+        assert_eq!(98.5, wrapper.executions[1].price);
+        assert_eq!(
+            200.0 * -0.5 - 2.0,
+            wrapper.commission_reports[1].realized_pnl
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_stops() -> Result<(), IBKRApiLibError> {
+        let (mut broker, contract) = setup();
+        broker.on_tick(contract.con_id, 100.0);
+        broker.place_order(1, &contract, &order_samples::market_order("BUY", 100.0))?;
+        assert_eq!(Some("Filled"), broker.order_status(1));
+
+        broker.place_order(2, &contract, &order_samples::stop("SELL", 50.0, 95.0))?;
+        broker.place_order(
+            3,
+            &contract,
+            &order_samples::trailing_stop("SELL", 50.0, 5.0, 90.0),
+        )?;
+        broker.on_tick(contract.con_id, 110.0);
+        // trail stop moved up to 104.5
+        broker.on_tick(contract.con_id, 104.0);
+        assert_eq!(Some("Filled"), broker.order_status(3));
+        assert_eq!(Some("Submitted"), broker.order_status(2));
+        // a gap through the stop fills at the gap price
+        broker.on_tick(contract.con_id, 93.0);
+        assert_eq!(Some("Filled"), broker.order_status(2));
+        let wrapper = broker.wrapper().lock().unwrap();
+        let prices = wrapper
+            .executions
+            .iter()
+            .map(|execution| execution.price)
+            .collect::<Vec<f64>>();
+        assert_eq!(vec![100.0, 104.0, 93.0], prices);
+        assert_eq!(0.0, broker.position(contract.con_id).unwrap().position);
+        Ok(())
+    }
+
+    #[test]
+    fn test_bracket_and_cancel() -> Result<(), IBKRApiLibError> {
+        let (mut broker, contract) = setup();
+        let parent = order_samples::limit_order("BUY", 100.0, 99.0);
+        let mut take_profit = order_samples::limit_order("SELL", 100.0, 105.0);
+        take_profit.parent_id = 1;
+        take_profit.oca_group = "bracket".to_string();
+        let mut stop_loss = order_samples::stop("SELL", 100.0, 95.0);
+        stop_loss.parent_id = 1;
+        stop_loss.oca_group = "bracket".to_string();
+        broker.place_order(1, &contract, &parent)?;
+        broker.place_order(2, &contract, &take_profit)?;
+        broker.place_order(3, &contract, &stop_loss)?;
+        assert_eq!(Some("PreSubmitted"), broker.order_status(2));
+
+        // children stay inactive until the parent fills
+        broker.on_bar(contract.con_id, &bar("1", 100.0, 101.0, 98.0, 99.0));
+        assert_eq!(Some("Filled"), broker.order_status(1));
+        assert_eq!(Some("Submitted"), broker.order_status(2));
+        broker.on_bar(contract.con_id, &bar("2", 104.0, 106.0, 103.0, 105.5));
+        assert_eq!(Some("Filled"), broker.order_status(2));
+        assert_eq!(Some("Cancelled"), broker.order_status(3));
+        assert_eq!(105.0, broker.wrapper().lock().unwrap().executions[1].price);
+
+        broker.place_order(4, &contract, &order_samples::limit_order("BUY", 10.0, 50.0))?;
+        broker.place_order(5, &contract, &order_samples::limit_order("BUY", 10.0, 51.0))?;
+        broker.cancel_order(4)?;
+        broker.cancel_order(4)?;
+        broker.req_global_cancel()?;
+        assert_eq!(Some("Cancelled"), broker.order_status(5));
+        assert!(broker
+            .place_order(5, &contract, &order_samples::limit_order("BUY", 10.0, 52.0))
+            .is_err());
+        assert!(broker
+            .place_order(
+                6,
+                &contract,
+                &order_samples::market_if_touched("BUY", 10.0, 52.0)
+            )
+            .is_err());
+        let wrapper = broker.wrapper().lock().unwrap();
+        assert_eq!(1, wrapper.errors.len());
+        assert_eq!(10147, wrapper.errors[0].1);
+        Ok(())
+    }
+}
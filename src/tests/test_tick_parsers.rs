@@ -0,0 +1,113 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc::channel;
+    use std::sync::{Arc, Mutex};
+
+    use crate::core::client::ConnStatus;
+    use crate::core::common::{TickType, UNSET_DOUBLE, UNSET_INTEGER};
+    use crate::core::decoder::Decoder;
+    use crate::core::errors::IBKRApiLibError;
+    use crate::core::tick_parsers::{DividendInfo, FundamentalRatios, RtVolume};
+    use crate::tests::recording_wrapper::RecordingWrapper;
+
+    #[test]
+    fn test_parse_rt_volume() -> Result<(), IBKRApiLibError> {
+        let rt_volume = "701.28;1;1348075471534;67854;701.46918464;true".parse::<RtVolume>()?;
+        assert_eq!(
+            RtVolume::new(701.28, 1, 1348075471534, 67854, 701.46918464, true),
+            rt_volume
+        );
+        assert!(rt_volume.has_trade());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_rt_volume_volume_only() -> Result<(), IBKRApiLibError> {
+        let rt_volume = ";;1348075471534;67854;701.46918464;false".parse::<RtVolume>()?;
+        assert_eq!(UNSET_DOUBLE, rt_volume.price);
+        assert_eq!(UNSET_INTEGER, rt_volume.size);
+        assert_eq!(67854, rt_volume.total_volume);
+        assert!(!rt_volume.has_trade());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_rt_volume_bad_payload() {
+        assert!("701.28;1;1348075471534".parse::<RtVolume>().is_err());
+        assert!("abc;1;1348075471534;67854;701.4;true"
+            .parse::<RtVolume>()
+            .is_err());
+    }
+
+    #[test]
+    fn test_parse_fundamental_ratios() -> Result<(), IBKRApiLibError> {
+        let ratios = "TTMNPMGN=16.1298;NLOW=101.3;CURRENCY=USD;QCURRATIO=-99999.99;"
+            .parse::<FundamentalRatios>()?;
+        assert_eq!(Some(16.1298), ratios.value("TTMNPMGN"));
+        assert_eq!(Some(101.3), ratios.value("NLOW"));
+        assert_eq!(None, ratios.value("QCURRATIO"));
+        assert_eq!(None, ratios.value("CURRENCY"));
+        assert_eq!(Some("USD"), ratios.currency());
+        assert!("NLOW".parse::<FundamentalRatios>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_dividends() -> Result<(), IBKRApiLibError> {
+        let dividends = "0.83,0.92,20130219,0.23".parse::<DividendInfo>()?;
+        assert_eq!(
+            DividendInfo::new(0.83, 0.92, "20130219".to_string(), 0.23),
+            dividends
+        );
+
+        let no_dividends = ",,,".parse::<DividendInfo>()?;
+        assert_eq!(UNSET_DOUBLE, no_dividends.next_amount);
+        assert!("0.83,0.92".parse::<DividendInfo>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_composite_ticks_reach_tick_string_by_default() -> Result<(), IBKRApiLibError> {
+        let (_tx, rx) = channel::<String>();
+        let mut decoder = Decoder::with_handler(
+            RecordingWrapper::new(),
+            rx,
+            151,
+            Arc::new(Mutex::new(ConnStatus::CONNECTED)),
+        );
+        let ticks = [
+            (
+                TickType::RtVolume,
+                "48",
+                "701.28;1;1348075471534;67854;701.46918464;true",
+            ),
+            (
+                TickType::FundamentalRatios,
+                "47",
+                "NLOW=101.3;CURRENCY=USD;",
+            ),
+            (TickType::IbDividends, "59", "0.83,0.92,20130219,0.23"),
+        ];
+        for (_, tick_type, value) in ticks.iter() {
+            decoder.interpret_fields(&["46", "6", "1001", tick_type, value])?;
+        }
+
+        let wrapper = decoder.into_handler().unwrap();
+        assert_eq!(
+            ticks
+                .iter()
+                .map(|(tick_type, _, value)| (1001, tick_type.clone() as i32, value.to_string()))
+                .collect::<Vec<(i32, i32, String)>>(),
+            wrapper
+                .tick_strings
+                .iter()
+                .map(|(req_id, tick_type, value)| (
+                    *req_id,
+                    tick_type.clone() as i32,
+                    value.clone()
+                ))
+                .collect::<Vec<(i32, i32, String)>>()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,187 @@
+#[cfg(all(test, feature = "sqlite-store"))]
+mod tests {
+    use std::fs;
+
+    use crate::core::common::{CommissionReport, UNSET_DOUBLE};
+    use crate::core::errors::IBKRApiLibError;
+    use crate::core::execution::Execution;
+    use crate::core::order::{Order, OrderState};
+    use crate::core::trade_store::TradeStore;
+    use crate::examples::contract_samples;
+    use crate::examples::order_samples;
+    use crate::tests::recording_wrapper::RecordingWrapper;
+
+    fn order(perm_id: i32) -> Order {
+        let mut order = order_samples::limit_order("BUY", 100.0, 50.0);
+        order.order_id = 7;
+        order.client_id = 1;
+        order.perm_id = perm_id;
+        order.account = "DU123".to_string();
+        order
+    }
+
+    fn order_state(status: &str) -> OrderState {
+        OrderState {
+            status: status.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn execution(exec_id: &str, perm_id: i32, shares: f64) -> Execution {
+        Execution {
+            exec_id: exec_id.to_string(),
+            time: "20251020  09:30:00".to_string(),
+            acct_number: "DU123".to_string(),
+            side: "BOT".to_string(),
+            shares,
+            price: 50.0,
+            perm_id,
+            order_id: 7,
+            ..Default::default()
+        }
+    }
+
+    fn commission(exec_id: &str, realized_pnl: f64) -> CommissionReport {
+        CommissionReport {
+            exec_id: exec_id.to_string(),
+            commission: 1.0,
+            currency: "USD".to_string(),
+            realized_pnl,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_order_transitions() -> Result<(), IBKRApiLibError> {
+        let mut store = TradeStore::open_in_memory()?;
+        let contract = contract_samples::usstock();
+        store.set_time(Some(100));
+        store.open_order(7, &contract, &order(1001), &order_state("Submitted"))?;
+        // statuses repeating what is known are not transitions
+        assert!(!store.order_status(7, "Submitted", 0.0, 100.0, 0.0, 1001, 0, 0.0, 1, "", 0.0)?);
+        store.set_time(Some(110));
+        assert!(store.order_status(7, "Submitted", 40.0, 60.0, 50.0, 1001, 0, 50.0, 1, "", 0.0)?);
+        assert_eq!(1, store.open_orders()?.len());
+
+        store.set_time(Some(120));
+        store.order_status(7, "Filled", 100.0, 0.0, 50.0, 1001, 0, 50.0, 1, "", 0.0)?;
+        assert!(store.open_orders()?.is_empty());
+
+        let history = store.order_history(1001)?;
+        assert_eq!(
+            vec![
+                ("open_order", "Submitted", 0.0),
+                ("order_status", "Submitted", 40.0),
+                ("order_status", "Filled", 100.0),
+            ],
+            history
+                .iter()
+                .map(|event| (event.source.as_str(), event.status.as_str(), event.filled))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(120, history[2].time);
+
+        // open_order after the fill keeps the filled quantity order_status reported
+        store.completed_order(&contract, &order(1001), &order_state("Filled"))?;
+        let stored = store.order(1001)?.unwrap();
+        assert!(stored.completed);
+        assert_eq!(100.0, stored.filled);
+        assert_eq!("AMZN", stored.contract.symbol);
+        assert_eq!(50.0, stored.order.lmt_price);
+        assert_eq!(3, store.order_history(1001)?.len());
+
+        // orders TWS hasn't given a perm_id are left out
+        store.open_order(8, &contract, &order(0), &order_state("PendingSubmit"))?;
+        assert!(store.order(0)?.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_fills_and_snapshots() -> Result<(), IBKRApiLibError> {
+        let mut store = TradeStore::open_in_memory()?;
+        let contract = contract_samples::usstock();
+        store.set_time(Some(1760952600));
+        assert!(store.exec_details(&contract, &execution("e1", 1001, 40.0))?);
+        store.commission_report(&commission("e1", UNSET_DOUBLE))?;
+        store.set_time(Some(1760952660));
+        assert!(store.exec_details(&contract, &execution("e2", 1001, 60.0))?);
+        assert!(store.exec_details(&contract, &execution("e3", 1002, 10.0))?);
+        // executions reported again by req_executions are ignored
+        assert!(!store.exec_details(&contract, &execution("e1", 1001, 40.0))?);
+
+        let fills = store.fills_for_perm_id(1001)?;
+        assert_eq!(2, fills.len());
+        assert_eq!("e1", fills[0].execution.exec_id);
+        assert_eq!(1.0, fills[0].commission_report.as_ref().unwrap().commission);
+        assert!(fills[1].commission_report.is_none());
+        assert_eq!(2, store.fills_since(1760952660)?.len());
+        assert_eq!(
+            "20251020-09:31:00",
+            store.execution_filter(1)?.time.as_str()
+        );
+
+        store.set_time(Some(100));
+        store.position("DU123", &contract, 40.0, 50.0)?;
+        store.account_summary(9001, "DU123", "NetLiquidation", "100000", "USD")?;
+        store.set_time(Some(200));
+        store.position("DU123", &contract, 100.0, 50.0)?;
+        store.account_summary(9001, "DU123", "NetLiquidation", "99000", "USD")?;
+        store.set_time(Some(300));
+        store.position("DU123", &contract, 0.0, 0.0)?;
+
+        assert!(store.positions_at(None, 50)?.is_empty());
+        assert_eq!(40.0, store.positions_at(Some("DU123"), 150)?[0].position);
+        assert_eq!(100.0, store.positions_at(None, 250)?[0].position);
+        assert!(store.positions_at(None, 300)?.is_empty());
+        assert!(store.positions_at(Some("DU999"), 250)?.is_empty());
+
+        let summary = store.account_summary_at("DU123", 150)?;
+        assert_eq!(1, summary.len());
+        assert_eq!("100000", summary[0].value);
+        assert_eq!("99000", store.account_summary_at("DU123", 250)?[0].value);
+        Ok(())
+    }
+
+    #[test]
+    fn test_replay_after_restart() -> Result<(), IBKRApiLibError> {
+        let path = std::env::temp_dir().join(format!("trade_store_{}.db", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let contract = contract_samples::usstock();
+        {
+            let mut store = TradeStore::open(&path)?;
+            store.set_time(Some(100));
+            store.open_order(7, &contract, &order(1001), &order_state("Submitted"))?;
+            store.order_status(7, "Submitted", 40.0, 60.0, 50.0, 1001, 0, 50.0, 1, "", 0.0)?;
+            store.open_order(9, &contract, &order(1003), &order_state("Cancelled"))?;
+            store.exec_details(&contract, &execution("e1", 1001, 40.0))?;
+            store.commission_report(&commission("e1", 0.0))?;
+            store.position("DU123", &contract, 40.0, 50.0)?;
+        }
+
+        let store = TradeStore::open(&path)?;
+        let mut wrapper = RecordingWrapper::new();
+        store.replay(&mut wrapper, 0)?;
+        assert_eq!(vec![(7, "Submitted".to_string())], wrapper.open_orders);
+        assert_eq!(
+            vec![(7, "Submitted".to_string(), 40.0, 60.0, 50.0)],
+            wrapper.order_statuses
+        );
+        assert_eq!("e1", wrapper.executions[0].exec_id);
+        assert_eq!(1, wrapper.commission_reports.len());
+        assert_eq!(
+            vec![("DU123".to_string(), 0, 40.0, 50.0)],
+            wrapper.positions
+        );
+        assert_eq!(1, wrapper.count("open_order_end"));
+        assert_eq!(1, wrapper.count("exec_details_end"));
+        assert_eq!(1, wrapper.count("position_end"));
+
+        let mut wrapper = RecordingWrapper::new();
+        store.replay(&mut wrapper, 101)?;
+        assert!(wrapper.executions.is_empty());
+
+        drop(store);
+        fs::remove_file(path)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,136 @@
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use chrono_tz::America::{Chicago, New_York};
+
+    use crate::core::contract::ContractDetails;
+    use crate::core::errors::IBKRApiLibError;
+    use crate::core::trading_schedule::{parse_sessions, parse_time_zone, TradingSchedule};
+
+    #[test]
+    fn test_time_zones() -> Result<(), IBKRApiLibError> {
+        assert_eq!(New_York, parse_time_zone("EST (Eastern Standard Time)")?);
+        assert_eq!(Chicago, parse_time_zone("CST")?);
+        assert_eq!("US/Eastern", parse_time_zone("US/Eastern")?.name());
+        assert_eq!("Asia/Tokyo", parse_time_zone("JST")?.name());
+        assert!(parse_time_zone("Nowhere/Special").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_stock_schedule() -> Result<(), IBKRApiLibError> {
+        let mut details = ContractDetails::default();
+        details.time_zone_id = "US/Eastern".to_string();
+        details.trading_hours =
+            "20200102:0400-20200102:2000;20200103:0400-20200103:2000;20200104:CLOSED".to_string();
+        details.liquid_hours =
+            "20200102:0930-20200102:1600;20200103:0930-20200103:1600;20200104:CLOSED".to_string();
+        let schedule = TradingSchedule::from_contract_details(&details)?;
+        assert_eq!(2, schedule.trading_sessions.len());
+
+        // 10:00 in New York
+        let morning = Utc.with_ymd_and_hms(2020, 1, 2, 15, 0, 0).unwrap();
+        assert!(schedule.is_open(&morning));
+        assert!(schedule.is_rth(&morning));
+        assert_eq!(
+            New_York.with_ymd_and_hms(2020, 1, 2, 20, 0, 0).unwrap(),
+            schedule.next_close(&morning).unwrap()
+        );
+        assert_eq!(
+            New_York.with_ymd_and_hms(2020, 1, 3, 4, 0, 0).unwrap(),
+            schedule.next_open(&morning).unwrap()
+        );
+
+        // 17:00 in New York is after hours
+        let evening = New_York.with_ymd_and_hms(2020, 1, 2, 17, 0, 0).unwrap();
+        assert!(schedule.is_open(&evening));
+        assert!(!schedule.is_rth(&evening));
+        assert_eq!(
+            New_York.with_ymd_and_hms(2020, 1, 3, 9, 30, 0).unwrap(),
+            schedule.next_rth_open(&evening).unwrap()
+        );
+
+        // the close itself is outside the session
+        let close = New_York.with_ymd_and_hms(2020, 1, 3, 20, 0, 0).unwrap();
+        assert!(!schedule.is_open(&close));
+        assert_eq!(None, schedule.next_open(&close));
+        assert_eq!(None, schedule.next_close(&close));
+        Ok(())
+    }
+
+    #[test]
+    fn test_overnight_sessions() -> Result<(), IBKRApiLibError> {
+        let schedule = TradingSchedule::new(
+            "20200105:1700-20200106:1600;20200106:1700-20200107:1600",
+            "20200106:0830-20200106:1500;20200107:0830-20200107:1500",
+            "CST (Central Standard Time)",
+        )?;
+        let midnight = Chicago.with_ymd_and_hms(2020, 1, 6, 0, 30, 0).unwrap();
+        assert!(schedule.is_open(&midnight));
+        assert!(!schedule.is_rth(&midnight));
+        assert_eq!(
+            Chicago.with_ymd_and_hms(2020, 1, 5, 17, 0, 0).unwrap(),
+            schedule.current_session(&midnight).unwrap().start
+        );
+        assert_eq!(
+            Chicago.with_ymd_and_hms(2020, 1, 6, 16, 0, 0).unwrap(),
+            schedule.next_close(&midnight).unwrap()
+        );
+
+        // the daily maintenance break
+        let break_time = Chicago.with_ymd_and_hms(2020, 1, 6, 16, 30, 0).unwrap();
+        assert!(!schedule.is_open(&break_time));
+        assert_eq!(
+            Chicago.with_ymd_and_hms(2020, 1, 6, 17, 0, 0).unwrap(),
+            schedule.next_open(&break_time).unwrap()
+        );
+        assert_eq!(
+            Chicago.with_ymd_and_hms(2020, 1, 7, 16, 0, 0).unwrap(),
+            schedule.next_close(&break_time).unwrap()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_sessions() -> Result<(), IBKRApiLibError> {
+        // older format, adjacent ranges are merged and an end before the start is the next day
+        let sessions = parse_sessions(
+            "20090507:0700-1830,1830-2330;20090508:CLOSED;20090510:1700-0800",
+            &New_York,
+        )?;
+        assert_eq!(2, sessions.len());
+        assert_eq!(
+            New_York.with_ymd_and_hms(2009, 5, 7, 7, 0, 0).unwrap(),
+            sessions[0].start
+        );
+        assert_eq!(
+            New_York.with_ymd_and_hms(2009, 5, 7, 23, 30, 0).unwrap(),
+            sessions[0].end
+        );
+        assert_eq!(
+            New_York.with_ymd_and_hms(2009, 5, 11, 8, 0, 0).unwrap(),
+            sessions[1].end
+        );
+
+        // sessions keep their local times across a daylight saving change
+        let sessions = parse_sessions(
+            "20200306:0930-20200306:1600;20200309:0930-20200309:1600",
+            &New_York,
+        )?;
+        assert_eq!(
+            Utc.with_ymd_and_hms(2020, 3, 6, 14, 30, 0).unwrap(),
+            sessions[0].start
+        );
+        assert_eq!(
+            Utc.with_ymd_and_hms(2020, 3, 9, 13, 30, 0).unwrap(),
+            sessions[1].start
+        );
+
+        assert!(parse_sessions("", &New_York)?.is_empty());
+        assert!(parse_sessions("20200101", &New_York).is_err());
+        assert!(parse_sessions("20200101:0930", &New_York).is_err());
+        assert!(parse_sessions("2020010x:0930-1600", &New_York).is_err());
+        assert!(parse_sessions("20200101:0930-1660", &New_York).is_err());
+        Ok(())
+    }
+}